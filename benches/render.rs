@@ -0,0 +1,203 @@
+//! Render and transfer benchmarks
+//!
+//! ```bash
+//! cargo bench --bench render              # all of them
+//! cargo bench --bench render -- transfer  # one group
+//! ```
+//!
+//! Budget per update on the Pi Zero 2 W (Cortex-A53 at 1 GHz), with what a
+//! desktop x86-64 measured when they were set; expect the Pi to be about
+//! ten times slower:
+//!
+//! | Benchmark                   | Pi Zero 2 W budget | Desktop  |
+//! |-----------------------------|--------------------|----------|
+//! | `render/render_chart`       | 100 ms             | 4.5 ms   |
+//! | `render/frame`              | 200 ms             | 9 ms     |
+//! | `set_pixel/*` (whole panel) | 20 ms              | 0.5 ms   |
+//! | `text/*` (whole panel)      | 10 ms              | 0.2 ms   |
+//! | `transfer/full_window`      | 10 ms              | 0.03 ms  |
+//! | `transfer/dirty`            | 1 ms               | 0.02 ms  |
+//!
+//! The transfer budget is set against the bus: the two planes take about
+//! 30 ms on the wire at 8 MHz, and preparing the bytes shouldn't add more
+//! than a third of that. Check a change against a saved baseline:
+//!
+//! ```bash
+//! cargo bench --bench render -- --save-baseline main
+//! cargo bench --bench render -- --baseline main
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use embedded_graphics::mono_font::{ascii::FONT_6X10, MonoTextStyle};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+use tide_clock_lib::config::Config;
+use tide_clock_lib::eink_renderer::EinkTideRenderer;
+use tide_clock_lib::epd4in2b_v2::{
+    Color, DisplayBuffer, Epd4in2bV2, EpdError, GpioPin, InputPin, SoftwareSpi, EPD_HEIGHT,
+    EPD_WIDTH,
+};
+use tide_clock_lib::fallback;
+use tide_clock_lib::renderer::TideRenderer;
+
+/// Bus and lines that go nowhere
+struct NullBus;
+
+impl SoftwareSpi for NullBus {
+    fn write_byte(&mut self, data: u8) -> Result<(), EpdError> {
+        // Without black_box the whole send loop folds into one addition
+        black_box(data);
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> Result<u8, EpdError> {
+        Ok(0)
+    }
+}
+
+impl GpioPin for NullBus {
+    fn set_high(&mut self) -> Result<(), EpdError> {
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), EpdError> {
+        Ok(())
+    }
+}
+
+impl InputPin for NullBus {
+    fn is_high(&self) -> Result<bool, EpdError> {
+        Ok(false)
+    }
+}
+
+type NullEpd = Epd4in2bV2<NullBus, NullBus, NullBus, NullBus, NullBus>;
+
+fn null_epd() -> NullEpd {
+    let mut epd = Epd4in2bV2::new(NullBus, None, NullBus, NullBus, NullBus);
+    // Outside the measurements: init() waits out the reset pulses
+    epd.init().unwrap();
+    epd
+}
+
+fn render(c: &mut Criterion) {
+    let epd = null_epd();
+    let renderer = EinkTideRenderer::new();
+    let series = fallback::approximate(None);
+    let mut config = Config::default();
+    config.display.use_red = true;
+    let mut buffer = DisplayBuffer::new(EPD_WIDTH, EPD_HEIGHT);
+    let now = chrono::Utc::now();
+
+    let mut group = c.benchmark_group("render");
+    group.bench_function("render_chart", |b| {
+        b.iter(|| {
+            buffer.clear(Color::White);
+            renderer.render_chart(&epd, &mut buffer, black_box(&series), &config, now);
+        })
+    });
+    group.bench_function("frame", |b| {
+        b.iter(|| renderer.render(black_box(&series), &config, now))
+    });
+    group.finish();
+}
+
+fn set_pixel(c: &mut Criterion) {
+    let mut buffer = DisplayBuffer::new(EPD_WIDTH, EPD_HEIGHT);
+
+    let mut group = c.benchmark_group("set_pixel");
+    for (name, color) in [("black", Color::Black), ("red", Color::Red)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for y in 0..EPD_HEIGHT {
+                    for x in 0..EPD_WIDTH {
+                        buffer.set_pixel(black_box(x), y, color);
+                    }
+                }
+            })
+        });
+    }
+    // Every other pixel, so each byte flips between colors on every pass
+    group.bench_function("checkerboard", |b| {
+        b.iter(|| {
+            for y in 0..EPD_HEIGHT {
+                for x in 0..EPD_WIDTH {
+                    let color = if (x + y) % 2 == 0 {
+                        Color::Black
+                    } else {
+                        Color::White
+                    };
+                    buffer.set_pixel(black_box(x), y, color);
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+fn text(c: &mut Criterion) {
+    let mut buffer = DisplayBuffer::new(EPD_WIDTH, EPD_HEIGHT);
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let line = "12:34 High 9.8 ft  Low -0.4 ft  Portland, ME 8418150";
+    // One glyph of the font's atlas: 6 pixels in a byte per row
+    let glyph = [0x70, 0x88, 0x98, 0xA8, 0xC8, 0x88, 0x70, 0x00, 0x00, 0x00];
+
+    let mut group = c.benchmark_group("text");
+    // A panel full of FONT_6X10, through embedded-graphics' glyph pixels
+    group.bench_function("page", |b| {
+        b.iter(|| {
+            for row in 0..30 {
+                Text::with_baseline(line, Point::new(0, row * 10), style, Baseline::Top)
+                    .draw(&mut buffer)
+                    .unwrap();
+            }
+        })
+    });
+    group.bench_function("blit_glyph", |b| {
+        b.iter(|| {
+            for row in 0..30 {
+                for col in 0..66 {
+                    buffer.blit_glyph(col * 6, row * 10, 6, black_box(&glyph), Color::Black);
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+fn transfer(c: &mut Criterion) {
+    let mut epd = null_epd();
+    let renderer = EinkTideRenderer::new();
+    let mut config = Config::default();
+    config.display.use_red = true;
+    let tide_clock_lib::renderer::Frame::Bitmap(mut frame) =
+        renderer.render(&fallback::approximate(None), &config, chrono::Utc::now())
+    else {
+        unreachable!("the e-ink renderer draws bitmaps");
+    };
+
+    let mut group = c.benchmark_group("transfer");
+    // Both planes, every byte: the serialization a full refresh does,
+    // without the settle delays around it
+    group.bench_function("full_window", |b| {
+        b.iter(|| {
+            epd.display_partial(0, 0, EPD_WIDTH, EPD_HEIGHT, black_box(&frame))
+                .unwrap()
+        })
+    });
+    // The minute tick: the "now" marker moves a few pixels
+    group.bench_function("dirty", |b| {
+        let mut x = 0;
+        b.iter(|| {
+            x = (x + 1) % EPD_WIDTH;
+            frame.set_pixel(x, 150, Color::Red);
+            frame.set_pixel(x, 151, Color::Red);
+            epd.display_dirty(black_box(&mut frame)).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, render, set_pixel, text, transfer);
+criterion_main!(benches);
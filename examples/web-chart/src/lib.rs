@@ -0,0 +1,90 @@
+//! # Web Chart
+//!
+//! The panel frame for a `tide-config.toml`, drawn in the browser so a
+//! station, layout or theme can be tried out before it goes on the Pi. The
+//! data comes from the offline prediction (`[station.harmonics]` given
+//! inline, else the model), since the page can't reach NOAA or read files.
+//!
+//! Built for `wasm32-unknown-unknown` and loaded by `index.html`, see the
+//! "Browser Preview" section of the README.
+
+use chrono::Utc;
+use embedded_graphics::prelude::*;
+use tide_clock_lib::config_check::{self, DEFAULT_CONFIG_TOML};
+use tide_clock_lib::stations::StationSeries;
+use tide_clock_lib::{fallback, panel};
+use wasm_bindgen::prelude::*;
+
+/// A drawn frame and what `config validate` says about its configuration
+#[wasm_bindgen]
+pub struct Preview {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    findings: String,
+}
+
+#[wasm_bindgen]
+impl Preview {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA bytes row by row, for `new ImageData(...)`
+    pub fn pixels(&self) -> Vec<u8> {
+        self.pixels.clone()
+    }
+
+    /// Warnings about the configuration, one per line
+    #[wasm_bindgen(getter)]
+    pub fn findings(&self) -> String {
+        self.findings.clone()
+    }
+}
+
+/// The annotated configuration `config init` writes, to start editing from
+#[wasm_bindgen]
+pub fn default_config() -> String {
+    DEFAULT_CONFIG_TOML.to_string()
+}
+
+/// Draw the panel for the configuration in `config_toml`, turned and sized
+/// as the configured panel would show it
+///
+/// Fails with the `config validate` errors when the configuration can't be
+/// used.
+#[wasm_bindgen]
+pub fn render(config_toml: &str) -> Result<Preview, JsError> {
+    let (config, findings) = config_check::check(config_toml);
+    let findings = findings
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let config = config.ok_or_else(|| JsError::new(&findings))?;
+
+    let now = Utc::now();
+    let displayed: Vec<StationSeries> = config
+        .all_stations()
+        .into_iter()
+        .map(|station| StationSeries {
+            station: station.clone(),
+            series: fallback::for_station(&config, station, Some(now)),
+        })
+        .collect();
+    let (width, height) = config.display.hardware.panel.size();
+    let frame = panel::compose_frame(&displayed, &config, Size::new(width, height), now);
+    let size = frame.size();
+    Ok(Preview {
+        width: size.width,
+        height: size.height,
+        pixels: panel::to_rgba(&frame),
+        findings,
+    })
+}
@@ -0,0 +1,86 @@
+#ifndef TIDE_TRACKER_H
+#define TIDE_TRACKER_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Fetched tide data for the primary station, freed with [`tide_free`]
+typedef struct TideData TideData;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Fetch the primary station's series
+//
+// `config_path` names the configuration file, or is NULL to find it the way
+// `tide-tracker` does. Returns NULL when `config_path` isn't UTF-8 or the
+// async runtime can't start, or on a panic.
+//
+// # Safety
+//
+// `config_path` must be NULL or point to a NUL-terminated string.
+TideData *tide_fetch(const char *config_path);
+
+// Number of samples, 0 for NULL or on a panic
+//
+// # Safety
+//
+// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed.
+uintptr_t tide_sample_count(const TideData *data);
+
+// Sample `index`: minutes relative to [`tide_base_time`] and height in feet
+//
+// Returns false, leaving the outputs alone, when `index` is out of range or
+// on a panic.
+//
+// # Safety
+//
+// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed;
+// `mins_rel` and `tide_ft` must be valid for writes.
+bool tide_sample_at(const TideData *data, uintptr_t index, int16_t *mins_rel, float *tide_ft);
+
+// The instant `mins_rel` 0 refers to, in Unix seconds (0 when unknown or
+// on a panic)
+//
+// # Safety
+//
+// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed.
+int64_t tide_base_time(const TideData *data);
+
+// Whether the series comes from the offline model rather than NOAA (false
+// on a panic)
+//
+// # Safety
+//
+// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed.
+bool tide_is_offline(const TideData *data);
+
+// Save the panel frame `tide-tracker --png` would draw for this data at
+// `path` (BMP when it ends in `.bmp`)
+//
+// Returns 0 on success, -1 for bad arguments, -2 when the image can't be
+// written and -3 on a panic.
+//
+// # Safety
+//
+// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed;
+// `path` must be NULL or point to a NUL-terminated string.
+int tide_render_png(const TideData *data, const char *path);
+
+// Free data from [`tide_fetch`]; NULL is ignored
+//
+// # Safety
+//
+// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed.
+void tide_free(TideData *data);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TIDE_TRACKER_H */
@@ -0,0 +1,228 @@
+//! # Prediction Accuracy Report
+//!
+//! Compares NOAA's published predictions for a station with the water levels
+//! actually observed there over the last few days, and summarizes the error as
+//! RMSE and bias. Run it with:
+//!
+//! ```text
+//! tide-tracker report accuracy --days 30
+//! ```
+//!
+//! A large bias means the curve is consistently high or low (often a datum or
+//! `msl_offset` problem); a large RMSE with a small bias means timing or range
+//! is off, which usually calls for a subordinate-station correction or a
+//! closer reference station.
+//!
+//! Observations come from the six-minute `water_level` product, which
+//! includes the preliminary data of the last few weeks (`hourly_height` only
+//! has verified data, weeks to months behind), and are compared with hourly
+//! predictions at the top of each hour. Both are requested in GMT, so they
+//! line up on the same timestamps regardless of the Pi's locale. Stations
+//! without a water level gauge only publish predictions and are reported as
+//! having no observations.
+
+use crate::config::StationConfig;
+use crate::tide_data::TideError;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Bias above which the summary suggests a correction, in feet
+const BIAS_WARN_FT: f32 = 0.3;
+
+/// RMSE above which the summary suggests a correction, in feet
+const RMSE_WARN_FT: f32 = 0.5;
+
+/// Longest span of six-minute data NOAA returns in one request
+const MAX_REQUEST_DAYS: u32 = 30;
+
+/// Start of NOAA's message for a product a station doesn't offer (no gauge)
+/// or has nothing for in the requested range
+const NO_DATA: &str = "No data was found";
+
+/// Error statistics for one station
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccuracyStats {
+    /// Number of hours with both a prediction and an observation
+    pub samples: usize,
+    /// Root-mean-square of (observed - predicted), in feet
+    pub rmse_ft: f32,
+    /// Mean of (observed - predicted), in feet; positive = water ran higher
+    pub bias_ft: f32,
+    /// Largest absolute error, in feet
+    pub max_error_ft: f32,
+}
+
+impl AccuracyStats {
+    /// Whether the error is large enough to warrant a correction
+    pub fn needs_correction(&self) -> bool {
+        self.bias_ft.abs() > BIAS_WARN_FT || self.rmse_ft > RMSE_WARN_FT
+    }
+}
+
+impl fmt::Display for AccuracyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RMSE {:.2} ft, bias {:+.2} ft, max {:.2} ft over {} h",
+            self.rmse_ft, self.bias_ft, self.max_error_ft, self.samples
+        )
+    }
+}
+
+/// Compare predictions with observations taken at the same timestamps
+///
+/// Timestamps present in only one series are ignored. Returns `None` when
+/// the two series have nothing in common.
+pub fn compare(
+    predictions: &[(NaiveDateTime, f32)],
+    observations: &[(NaiveDateTime, f32)],
+) -> Option<AccuracyStats> {
+    let predicted: HashMap<NaiveDateTime, f32> = predictions.iter().copied().collect();
+    let errors: Vec<f32> = observations
+        .iter()
+        .filter_map(|(t, observed)| predicted.get(t).map(|p| observed - p))
+        .collect();
+    if errors.is_empty() {
+        return None;
+    }
+
+    let n = errors.len() as f32;
+    Some(AccuracyStats {
+        samples: errors.len(),
+        rmse_ft: (errors.iter().map(|e| e * e).sum::<f32>() / n).sqrt(),
+        bias_ft: errors.iter().sum::<f32>() / n,
+        max_error_ft: errors.iter().fold(0.0, |m, e| m.max(e.abs())),
+    })
+}
+
+/// Fetch the last `days` of predictions and observations and compare them
+///
+/// `Ok(None)` means the station has no observations for the period,
+/// usually because it has no gauge.
+pub async fn station_accuracy(
+    station: &StationConfig,
+    days: u32,
+) -> Result<Option<AccuracyStats>, TideError> {
+    let client = reqwest::Client::builder()
+        .timeout(station.fetch_deadline())
+        .build()?;
+    let end = Utc::now();
+    let mut predictions = Vec::new();
+    let mut observations = Vec::new();
+    // Newest first, each request at most `MAX_REQUEST_DAYS` long
+    let mut done = 0;
+    while done < days {
+        let span = (days - done).min(MAX_REQUEST_DAYS);
+        let to = end - Duration::days(done as i64);
+        let from = to - Duration::days(span as i64);
+        predictions.extend(fetch(&client, station, "predictions&interval=h", from, to).await?);
+        observations.extend(fetch(&client, station, "water_level", from, to).await?);
+        done += span;
+    }
+    Ok(compare(&predictions, &observations))
+}
+
+/// Request one NOAA product between `begin` and `end`, in GMT
+async fn fetch(
+    client: &reqwest::Client,
+    station: &StationConfig,
+    product: &str,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<(NaiveDateTime, f32)>, TideError> {
+    let url = format!(
+        "https://api.tidesandcurrents.noaa.gov/api/prod/datagetter?\
+        product={}&station={}&begin_date={}&end_date={}&\
+        datum={}&time_zone=gmt&units=english&format=json",
+        product,
+        station.id,
+        begin.format("%Y%m%d %H:%M"),
+        end.format("%Y%m%d %H:%M"),
+        station.datum.api_code()
+    );
+    let response = client.get(&url).send().await?.text().await?;
+    parse_points(&response)
+}
+
+/// Extract `(t, v)` pairs from a NOAA JSON response
+///
+/// Predictions come under `predictions`, observations under `data`. Hours
+/// with an empty value (gauge outage) are skipped. NOAA's "No data was
+/// found" error, what a station without a gauge answers for observations,
+/// yields an empty list; any other `error` object is a [`TideError::Api`].
+pub(crate) fn parse_points(body: &str) -> Result<Vec<(NaiveDateTime, f32)>, TideError> {
+    let json: serde_json::Value = serde_json::from_str(body).map_err(|_| TideError::Scrape)?;
+    if let Some(error) = json.get("error") {
+        let message = error["message"].as_str().unwrap_or_default().trim();
+        if message.starts_with(NO_DATA) {
+            return Ok(Vec::new());
+        }
+        return Err(TideError::Api(message.to_string()));
+    }
+    let points = json["predictions"]
+        .as_array()
+        .or_else(|| json["data"].as_array())
+        .ok_or(TideError::Scrape)?;
+
+    let mut parsed = Vec::with_capacity(points.len());
+    for point in points {
+        let (Some(t), Some(v)) = (point["t"].as_str(), point["v"].as_str()) else {
+            return Err(TideError::Scrape);
+        };
+        let Ok(ft) = v.parse::<f32>() else {
+            continue;
+        };
+        let time =
+            NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M").map_err(|_| TideError::Scrape)?;
+        parsed.push((time, ft));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2025, 7, 1)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_compare_rmse_and_bias() {
+        let predictions = vec![(at(0), 1.0), (at(1), 2.0), (at(2), 3.0), (at(3), 4.0)];
+        // Hour 3 has no observation, hour 4 has no prediction
+        let observations = vec![(at(0), 1.5), (at(1), 2.5), (at(2), 2.5), (at(4), 9.0)];
+
+        let stats = compare(&predictions, &observations).unwrap();
+        assert_eq!(stats.samples, 3);
+        assert!((stats.bias_ft - 0.5 / 3.0).abs() < 1e-6);
+        assert!((stats.rmse_ft - 0.5).abs() < 1e-6);
+        assert_eq!(stats.max_error_ft, 0.5);
+        assert!(!stats.needs_correction());
+
+        assert!(compare(&predictions, &[]).is_none());
+    }
+
+    #[test]
+    fn test_parse_points_skips_gaps() {
+        let body = r#"{"data":[
+            {"t":"2025-07-01 00:00","v":"1.234","s":"0.01","f":"0,0"},
+            {"t":"2025-07-01 01:00","v":"","s":"","f":""}
+        ]}"#;
+        assert_eq!(parse_points(body).unwrap(), vec![(at(0), 1.234)]);
+
+        let no_gauge = r#"{"error":{"message":"No data was found. This product may not be offered at this station at the requested time."}}"#;
+        assert!(parse_points(no_gauge).unwrap().is_empty());
+
+        // Anything else NOAA complains about is an error, not "no gauge"
+        let bad_range = r#"{"error":{"message":"The size limit for data retrieval for this product is 31 days"}}"#;
+        assert!(matches!(
+            parse_points(bad_range),
+            Err(TideError::Api(m)) if m.contains("31 days")
+        ));
+    }
+}
@@ -0,0 +1,614 @@
+//! # Alert Scheduling
+//!
+//! Every notification the tracker sends goes through one [`AlertScheduler`],
+//! which decides *whether* an alert should go out and then hands it to each
+//! configured [`Notifier`]. Producers only describe what happened; the
+//! scheduler applies the policy from the `[alerts]` section:
+//!
+//! - **Quiet hours**: nothing is sent inside the window (which may wrap
+//!   midnight), in the station's timezone. Suppressed alerts are not
+//!   recorded, so a condition that still holds on the first refresh after
+//!   quiet hours fires then.
+//! - **Cooldowns**: an alert key that fired recently is not repeated until its
+//!   cooldown has passed (a global default plus per-key overrides).
+//! - **Dedupe across restarts**: the last-fired time of every key is persisted
+//!   next to the cache file, so the one-shot process started by the systemd
+//!   timer doesn't re-send the same alert every refresh.
+//!
+//! ```toml
+//! [alerts]
+//! enabled = true
+//! cooldown_minutes = 60
+//! quiet_hours = { start = "22:00", end = "07:00" }
+//!
+//! [alerts.cooldowns]
+//! "offline:8418150" = 360
+//!
+//! [[alerts.sinks]]
+//! kind = "ntfy"
+//! topic = "my-tide-tracker"
+//!
+//! [[alerts.sinks]]
+//! kind = "webhook"
+//! url = "http://homeassistant.local:8123/api/webhook/tides"
+//! ```
+//!
+//! Other transports (MQTT, a GPIO buzzer, ...) plug in by implementing
+//! [`Notifier`] and registering with [`AlertScheduler::with_notifier`].
+
+use crate::config::Config;
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Errors a notifier can report when delivering an alert
+#[derive(Error, Debug)]
+pub enum AlertError {
+    #[cfg(feature = "net")]
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("{0} rejected the alert with status {1}")]
+    Rejected(String, u16),
+}
+
+/// Something worth telling the user about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Alert {
+    /// Stable identifier used for cooldowns and dedupe, e.g. "offline:8418150"
+    pub key: String,
+    /// Short headline
+    pub title: String,
+    /// Full message body
+    pub message: String,
+}
+
+impl Alert {
+    pub fn new(
+        key: impl Into<String>,
+        title: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            title: title.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Boxed future returned by [`Notifier::send`]
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AlertError>> + Send + 'a>>;
+
+/// A transport that delivers alerts (push service, webhook, buzzer, ...)
+pub trait Notifier: Send + Sync {
+    /// Short name used in log messages
+    fn name(&self) -> &str;
+
+    /// Deliver a single alert
+    fn send<'a>(&'a self, alert: &'a Alert) -> SendFuture<'a>;
+}
+
+/// `[alerts]` configuration section
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AlertsConfig {
+    /// Master switch; nothing is sent while false (default)
+    pub enabled: bool,
+    /// Minimum minutes between two sends of the same alert key
+    pub cooldown_minutes: u64,
+    /// Per-key cooldown overrides in minutes
+    pub cooldowns: BTreeMap<String, u64>,
+    /// Station-local time window during which no alerts are sent
+    pub quiet_hours: Option<QuietHours>,
+    /// Where last-fired times are persisted (default: `tide_alerts.json`
+    /// next to the cache file)
+    pub state_path: Option<PathBuf>,
+    /// Built-in transports to deliver alerts through
+    pub sinks: Vec<SinkConfig>,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cooldown_minutes: 60,
+            cooldowns: BTreeMap::new(),
+            quiet_hours: None,
+            state_path: None,
+            sinks: Vec::new(),
+        }
+    }
+}
+
+impl AlertsConfig {
+    /// Cooldown for `key`, falling back to `cooldown_minutes`
+    pub fn cooldown(&self, key: &str) -> chrono::Duration {
+        let minutes = self
+            .cooldowns
+            .get(key)
+            .copied()
+            .unwrap_or(self.cooldown_minutes);
+        chrono::Duration::minutes(minutes as i64)
+    }
+}
+
+/// Daily window, in local time, during which alerts are held back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QuietHours {
+    /// Start of the window, e.g. "22:00"
+    pub start: NaiveTime,
+    /// End of the window (exclusive), e.g. "07:00"
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Whether `time` falls inside the window; windows may wrap midnight
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// A built-in alert transport
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SinkConfig {
+    /// Push notification through an ntfy server
+    Ntfy {
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        topic: String,
+    },
+    /// HTTP POST of the alert as JSON (`key`, `title`, `message`)
+    Webhook { url: String },
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+/// Timeout for a single delivery attempt
+#[cfg(feature = "net")]
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ntfy.sh (or self-hosted ntfy) push notifications
+#[cfg(feature = "net")]
+pub struct NtfyNotifier {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "net")]
+impl NtfyNotifier {
+    pub fn new(server: &str, topic: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/{}", server.trim_end_matches('/'), topic),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &str {
+        "ntfy"
+    }
+
+    fn send<'a>(&'a self, alert: &'a Alert) -> SendFuture<'a> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .post(&self.url)
+                .header("Title", &alert.title)
+                .body(alert.message.clone())
+                .timeout(SEND_TIMEOUT)
+                .send()
+                .await?;
+            check_status(self.name(), response.status())
+        })
+    }
+}
+
+/// Generic JSON webhook
+#[cfg(feature = "net")]
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "net")]
+impl WebhookNotifier {
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn send<'a>(&'a self, alert: &'a Alert) -> SendFuture<'a> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .post(&self.url)
+                .json(alert)
+                .timeout(SEND_TIMEOUT)
+                .send()
+                .await?;
+            check_status(self.name(), response.status())
+        })
+    }
+}
+
+#[cfg(feature = "net")]
+fn check_status(name: &str, status: reqwest::StatusCode) -> Result<(), AlertError> {
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(AlertError::Rejected(name.to_string(), status.as_u16()))
+    }
+}
+
+/// Notifier delivering to a configured sink
+#[cfg(feature = "net")]
+fn notifier_for(sink: &SinkConfig) -> Option<Box<dyn Notifier>> {
+    Some(match sink {
+        SinkConfig::Ntfy { server, topic } => Box::new(NtfyNotifier::new(server, topic)),
+        SinkConfig::Webhook { url } => Box::new(WebhookNotifier::new(url)),
+    })
+}
+
+#[cfg(not(feature = "net"))]
+fn notifier_for(sink: &SinkConfig) -> Option<Box<dyn Notifier>> {
+    warn!("Ignoring alert sink {sink:?}: built without the net feature");
+    None
+}
+
+/// Why the scheduler did or didn't send an alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Send,
+    /// Alerts are disabled in configuration
+    Disabled,
+    /// Inside the configured quiet hours
+    QuietHours,
+    /// The same key fired less than its cooldown ago
+    Cooldown,
+}
+
+/// Last-fired time of every alert key, persisted between runs
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct AlertState {
+    last_fired: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// Applies quiet hours, cooldowns and dedupe, then fans alerts out to notifiers
+pub struct AlertScheduler {
+    config: AlertsConfig,
+    state_path: PathBuf,
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl AlertScheduler {
+    /// Scheduler with the notifiers listed under `[[alerts.sinks]]`
+    ///
+    /// Without the `net` feature the built-in sinks can't be sent to and are
+    /// skipped with a warning.
+    pub fn from_config(config: &Config) -> Self {
+        let alerts = config.alerts.clone();
+        let state_path = alerts
+            .state_path
+            .clone()
+            .unwrap_or_else(|| config.display.cache_path.with_file_name("tide_alerts.json"));
+        let notifiers = alerts.sinks.iter().filter_map(notifier_for).collect();
+        Self {
+            config: alerts,
+            state_path,
+            notifiers,
+        }
+    }
+
+    /// Register an additional transport
+    pub fn with_notifier(mut self, notifier: Box<dyn Notifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Decide whether `alert` may be sent at `now`, with quiet hours in
+    /// `timezone` (`None` = the system's)
+    fn decide(
+        &self,
+        state: &AlertState,
+        alert: &Alert,
+        now: DateTime<Utc>,
+        timezone: Option<Tz>,
+    ) -> Decision {
+        if !self.config.enabled {
+            return Decision::Disabled;
+        }
+        if let Some(quiet) = &self.config.quiet_hours {
+            if quiet.contains(crate::config::local_time(timezone, now).time()) {
+                return Decision::QuietHours;
+            }
+        }
+        match state.last_fired.get(&alert.key) {
+            Some(&last) if now - last < self.config.cooldown(&alert.key) => Decision::Cooldown,
+            _ => Decision::Send,
+        }
+    }
+
+    /// Send every alert that passes the policy at `now`; returns how many
+    /// went out
+    ///
+    /// Quiet hours are read on the clock of `timezone`, the station's
+    /// (`None` = the system's). An alert counts as fired (and starts its
+    /// cooldown) once at least one notifier accepted it. Delivery failures
+    /// are logged, never returned.
+    pub async fn dispatch(
+        &self,
+        alerts: &[Alert],
+        now: DateTime<Utc>,
+        timezone: Option<Tz>,
+    ) -> usize {
+        if !self.config.enabled || alerts.is_empty() {
+            return 0;
+        }
+        let mut state = self.load_state();
+        let mut sent = 0;
+
+        for alert in alerts {
+            let decision = self.decide(&state, alert, now, timezone);
+            if decision != Decision::Send {
+                info!("Alert '{}' held back: {:?}", alert.key, decision);
+                continue;
+            }
+
+            let mut delivered = false;
+            for notifier in &self.notifiers {
+                match notifier.send(alert).await {
+                    Ok(()) => delivered = true,
+                    Err(e) => warn!(
+                        "Alert '{}' via {} failed: {}",
+                        alert.key,
+                        notifier.name(),
+                        e
+                    ),
+                }
+            }
+            if delivered {
+                state.last_fired.insert(alert.key.clone(), now);
+                sent += 1;
+            }
+        }
+
+        if sent > 0 {
+            self.save_state(&state);
+        }
+        sent
+    }
+
+    fn load_state(&self) -> AlertState {
+        fs::read_to_string(&self.state_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_state(&self, state: &AlertState) {
+        if let Some(parent) = self.state_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        // Failing to persist only means an alert may repeat after a restart
+        if let Ok(json) = serde_json::to_string(state) {
+            let _ = fs::write(&self.state_path, json);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::{Arc, Mutex};
+
+    /// Records every alert it is asked to deliver
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Notifier for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn send<'a>(&'a self, alert: &'a Alert) -> SendFuture<'a> {
+            self.0.lock().unwrap().push(alert.key.clone());
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn scheduler(
+        dir: &std::path::Path,
+        alerts: AlertsConfig,
+    ) -> (AlertScheduler, Arc<Mutex<Vec<String>>>) {
+        let config = Config {
+            alerts: AlertsConfig {
+                state_path: Some(dir.join("alerts.json")),
+                ..alerts
+            },
+            ..Config::default()
+        };
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let scheduler =
+            AlertScheduler::from_config(&config).with_notifier(Box::new(Recorder(log.clone())));
+        (scheduler, log)
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 7, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_quiet_hours_wrap_midnight() {
+        let quiet = QuietHours {
+            start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+        };
+        assert!(quiet.contains(NaiveTime::from_hms_opt(23, 30, 0).unwrap()));
+        assert!(quiet.contains(NaiveTime::from_hms_opt(3, 0, 0).unwrap()));
+        assert!(!quiet.contains(NaiveTime::from_hms_opt(7, 0, 0).unwrap()));
+        assert!(!quiet.contains(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_alerts_config_parses() {
+        let config: AlertsConfig = toml::from_str(
+            r#"
+            enabled = true
+            quiet_hours = { start = "22:00", end = "07:00" }
+            [cooldowns]
+            offline = 360
+            [[sinks]]
+            kind = "ntfy"
+            topic = "tides"
+            [[sinks]]
+            kind = "webhook"
+            url = "http://example.invalid/hook"
+            "#,
+        )
+        .unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.cooldown("offline"), chrono::Duration::minutes(360));
+        assert_eq!(config.cooldown("other"), chrono::Duration::minutes(60));
+        assert_eq!(
+            config.sinks[0],
+            SinkConfig::Ntfy {
+                server: "https://ntfy.sh".to_string(),
+                topic: "tides".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_dedupes_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let enabled = AlertsConfig {
+            enabled: true,
+            ..AlertsConfig::default()
+        };
+        let alert = Alert::new("offline:1", "Offline", "NOAA unreachable");
+
+        let (first, log) = scheduler(dir.path(), enabled.clone());
+        assert_eq!(
+            first
+                .dispatch(
+                    std::slice::from_ref(&alert),
+                    at(12, 0),
+                    Some(chrono_tz::UTC)
+                )
+                .await,
+            1
+        );
+
+        // A new process inside the cooldown sends nothing
+        let (second, log2) = scheduler(dir.path(), enabled.clone());
+        assert_eq!(
+            second
+                .dispatch(
+                    std::slice::from_ref(&alert),
+                    at(12, 30),
+                    Some(chrono_tz::UTC)
+                )
+                .await,
+            0
+        );
+        assert!(log2.lock().unwrap().is_empty());
+
+        // After the cooldown it fires again
+        assert_eq!(
+            second
+                .dispatch(&[alert], at(13, 0), Some(chrono_tz::UTC))
+                .await,
+            1
+        );
+        assert_eq!(log.lock().unwrap().len(), 1);
+        assert_eq!(log2.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_hold_back_without_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let (scheduler, log) = scheduler(
+            dir.path(),
+            AlertsConfig {
+                enabled: true,
+                quiet_hours: Some(QuietHours {
+                    start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                    end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+                }),
+                ..AlertsConfig::default()
+            },
+        );
+        let alert = Alert::new("low-water", "Low water", "Below intake");
+
+        assert_eq!(
+            scheduler
+                .dispatch(std::slice::from_ref(&alert), at(2, 0), Some(chrono_tz::UTC))
+                .await,
+            0
+        );
+        // Not recorded during quiet hours, so it fires as soon as they end
+        assert_eq!(
+            scheduler
+                .dispatch(&[alert], at(7, 5), Some(chrono_tz::UTC))
+                .await,
+            1
+        );
+        assert_eq!(*log.lock().unwrap(), vec!["low-water".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_follow_the_station_timezone() {
+        let dir = tempfile::tempdir().unwrap();
+        let (scheduler, log) = scheduler(
+            dir.path(),
+            AlertsConfig {
+                enabled: true,
+                quiet_hours: Some(QuietHours {
+                    start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                    end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+                }),
+                ..AlertsConfig::default()
+            },
+        );
+        let alert = Alert::new("low-water", "Low water", "Below intake");
+        let eastern = Some(chrono_tz::America::New_York);
+
+        // 06:30 EDT is still quiet in Portland, whatever the Pi's clock says
+        assert_eq!(
+            scheduler
+                .dispatch(std::slice::from_ref(&alert), at(10, 30), eastern)
+                .await,
+            0
+        );
+        // 23:30 UTC is only 19:30 EDT
+        assert_eq!(scheduler.dispatch(&[alert], at(23, 30), eastern).await, 1);
+        assert_eq!(log.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,384 @@
+//! # Tide Archive
+//!
+//! A record of the predicted heights the chart has shown and, for stations
+//! with a surge threshold, the water levels NOAA observed there (see
+//! [`crate::surge`]), kept so today can be put next to yesterday. Built
+//! with the `archive` feature and turned on by giving it a file:
+//!
+//! ```toml
+//! [archive]
+//! path = "/var/lib/tide-tracker/archive.csv"
+//! overlay_yesterday = true
+//! ```
+//!
+//! Each update appends the main station's samples, and the observed ones,
+//! that have come due since the last row from the same kind of data, one
+//! `time,height_ft,source` row each, to a file per station
+//! ([`Config::archive_path_for`](crate::config::Config::archive_path_for)).
+//! Observations are tagged `SENSOR`:
+//!
+//! ```text
+//! time,height_ft,source
+//! 2025-07-01T16:00:00Z,5.23,NOAA
+//! 2025-07-01T15:50:00Z,6.41,SENSOR
+//! 2025-07-01T16:10:00Z,5.61,CACHE
+//! ```
+//!
+//! Times are UTC and heights in the station's datum, without its
+//! `[station.reference]`, so the file reads the same whatever the display
+//! settings. The file is only ever appended to and can be read, or trimmed
+//! from the front, by other tools while the tracker runs. The offline model
+//! and samples it backfilled ([`Sample::modeled`](crate::Sample::modeled))
+//! aren't recorded: those are the model's guesses, not the station's.
+//!
+//! With `overlay_yesterday`, [`yesterday`] draws the rows from 24 hours
+//! before the window over the chart as a faint curve: what the water did,
+//! where yesterday's observations were archived, and otherwise only
+//! yesterday's prediction, which differs from today's by the tide's daily
+//! shift and can't show a surge.
+
+use crate::config::StationConfig;
+use crate::grid::{self, Window};
+use crate::stations::StationSeries;
+use crate::{DataSource, TideSeries};
+use chrono::{DateTime, Duration, Utc};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// First line of every archive file
+const HEADER: &str = "time,height_ft,source";
+
+/// How much of the end of the file [`yesterday`] reads: two days of rows
+/// even at one a minute
+const TAIL_BYTES: u64 = 256 * 1024;
+
+/// Append the samples of `station`'s series from after the last row of the
+/// same kind (observed or predicted) up to `now`, returning how many were
+/// written
+///
+/// `station` is the main station's predictions or its observed water
+/// levels ([`DataSource::Sensor`], from
+/// [`fetch_observed`](crate::surge::fetch_observed)). Nothing is written
+/// for offline model data or a series without a base time.
+pub fn record(path: &Path, station: &StationSeries, now: DateTime<Utc>) -> io::Result<usize> {
+    let series = &station.series;
+    let Some(base) = series.base_time.filter(|_| !series.is_offline()) else {
+        return Ok(0);
+    };
+    let observed = is_observed(series.source);
+    let last = read_tail(path, TAIL_BYTES)?
+        .into_iter()
+        .filter(|row| row.observed == observed)
+        .map(|row| row.time)
+        .next_back();
+    let level = station
+        .station
+        .reference
+        .as_ref()
+        .map_or(0.0, |r| r.elevation_ft);
+    let rows: Vec<String> = series
+        .samples
+        .iter()
+        .filter(|s| !s.modeled)
+        .map(|s| {
+            (
+                base + Duration::minutes(s.mins_rel as i64),
+                s.tide_ft + level,
+            )
+        })
+        // `None < Some(_)`, so everything is new to an empty archive
+        .filter(|&(t, _)| t <= now && Some(t) > last)
+        .map(|(t, ft)| {
+            format!(
+                "{},{:.2},{}",
+                t.format("%Y-%m-%dT%H:%M:%SZ"),
+                ft,
+                series.source.label()
+            )
+        })
+        .collect();
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut text = String::new();
+    if file.metadata()?.len() == 0 {
+        text.push_str(HEADER);
+        text.push('\n');
+    }
+    for row in &rows {
+        text.push_str(row);
+        text.push('\n');
+    }
+    // One write, so a reader never sees half the rows of an update
+    file.write_all(text.as_bytes())?;
+    Ok(rows.len())
+}
+
+/// The main station's archived heights from 24 hours before its window,
+/// moved forward a day to line up with it, as an overlay labeled `label`
+///
+/// The observed rows when there are at least two in that window, otherwise
+/// the predicted ones. `None` when the archive has fewer than two rows of
+/// either (or doesn't exist yet), or the series has no base time.
+pub fn yesterday(
+    path: &Path,
+    main: &StationSeries,
+    label: &str,
+) -> io::Result<Option<StationSeries>> {
+    let series = &main.series;
+    let (Some(center), Some(half_width), Some(interval)) =
+        (series.base_time, series.half_width(), series.interval())
+    else {
+        return Ok(None);
+    };
+    let day = Duration::hours(24);
+    let earlier = Window::around(center - day, half_width);
+    let rows: Vec<Row> = read_tail(path, TAIL_BYTES)?
+        .into_iter()
+        .filter(|row| earlier.contains(row.time))
+        .collect();
+    let observed = rows.iter().filter(|row| row.observed).count() >= 2;
+    let points: Vec<(DateTime<Utc>, f32)> = rows
+        .into_iter()
+        .filter(|row| row.observed == observed)
+        .map(|row| (row.time + day, row.height_ft))
+        .collect();
+    let (Some(&(first, _)), Some(&(last, _))) = (points.first(), points.last()) else {
+        return Ok(None);
+    };
+    let window = Window::around(center, half_width);
+    let Some(mut samples) = grid::resample(&points, window, interval) else {
+        return Ok(None);
+    };
+    // Only where there are rows, rather than held flat out to the edges
+    let mins = |t: DateTime<Utc>| (t - center).num_minutes();
+    samples.retain(|s| (mins(first)..=mins(last)).contains(&(s.mins_rel as i64)));
+
+    let archived = TideSeries {
+        samples,
+        source: DataSource::File,
+        base_time: Some(center),
+        extremes: None,
+        fetched_at: None,
+    };
+    let archived = match &main.station.reference {
+        Some(reference) => archived.relative_to(reference.elevation_ft),
+        None => archived,
+    };
+    Ok(Some(StationSeries {
+        station: StationConfig {
+            name: label.to_string(),
+            ..main.station.clone()
+        },
+        series: archived,
+    }))
+}
+
+/// One archived height
+#[derive(Debug, Clone, Copy)]
+struct Row {
+    time: DateTime<Utc>,
+    height_ft: f32,
+    /// Tagged with [`DataSource::Sensor`]'s label: an observation rather
+    /// than a prediction
+    observed: bool,
+}
+
+/// Whether rows from data of this source are observations
+fn is_observed(source: DataSource) -> bool {
+    source == DataSource::Sensor
+}
+
+/// Rows in the last `max_bytes` of the file, oldest first (predictions and
+/// observations each in time order); none when it doesn't exist
+///
+/// Lines that don't parse (the header, one cut short by a full disk, or
+/// the partial first line of the tail) are skipped.
+fn read_tail(path: &Path, max_bytes: u64) -> io::Result<Vec<Row>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(max_bytes)))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let mut rows: Vec<_> = String::from_utf8_lossy(&bytes)
+        .lines()
+        .filter_map(parse_row)
+        .collect();
+    rows.sort_by_key(|row| (row.time, row.observed));
+    rows.dedup_by_key(|row| (row.time, row.observed));
+    Ok(rows)
+}
+
+/// One `time,height_ft,source` row
+fn parse_row(line: &str) -> Option<Row> {
+    let mut fields = line.split(',');
+    let time = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+    let height_ft = fields.next()?.parse().ok()?;
+    let observed = fields.next()? == DataSource::Sensor.label();
+    Some(Row {
+        time: time.with_timezone(&Utc),
+        height_ft,
+        observed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ReferenceLevel};
+    use chrono::TimeZone;
+
+    fn station_series(center: DateTime<Utc>, height: impl Fn(i16) -> f32) -> StationSeries {
+        let spec = grid::SeriesSpec::standard(center);
+        StationSeries {
+            station: Config::default().station,
+            series: TideSeries::builder(spec.generate(height))
+                .base_time(center)
+                .build()
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_record_appends_only_new_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive_8418150.csv");
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 16, 0, 0).unwrap();
+
+        // The past half of the window, then the next update's one new sample
+        let first = station_series(now, |m| 5.0 + m as f32 / 100.0);
+        assert_eq!(record(&path, &first, now).unwrap(), 73);
+        let later = now + Duration::minutes(10);
+        let second = station_series(later, |m| 5.1 + m as f32 / 100.0);
+        assert_eq!(record(&path, &second, later).unwrap(), 1);
+        assert_eq!(record(&path, &second, later).unwrap(), 0);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 75);
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(lines[1], "2025-07-01T04:00:00Z,-2.20,NOAA");
+        assert_eq!(lines[73], "2025-07-01T16:00:00Z,5.00,NOAA");
+        assert_eq!(lines[74], "2025-07-01T16:10:00Z,5.10,NOAA");
+
+        // Heights go in relative to the datum, not the reference level
+        let mut relative = station_series(later + Duration::minutes(10), |_| 1.0);
+        relative.station.reference = Some(ReferenceLevel {
+            name: "Intake".to_string(),
+            elevation_ft: 2.5,
+            alert_below: false,
+            alert_lead_minutes: 0,
+        });
+        record(&path, &relative, later + Duration::minutes(10)).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.ends_with("2025-07-01T16:20:00Z,3.50,NOAA\n"), "{text}");
+    }
+
+    #[test]
+    fn test_record_skips_model_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.csv");
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 16, 0, 0).unwrap();
+
+        let mut offline = station_series(now, |_| 1.0);
+        offline.series.source = DataSource::Fallback;
+        assert_eq!(record(&path, &offline, now).unwrap(), 0);
+        assert!(!path.exists());
+
+        let mut backfilled = station_series(now, |_| 1.0);
+        for sample in &mut backfilled.series.samples[..70] {
+            sample.modeled = true;
+        }
+        assert_eq!(record(&path, &backfilled, now).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_observations_are_archived_and_preferred() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.csv");
+        let now = Utc.with_ymd_and_hms(2025, 7, 2, 16, 0, 0).unwrap();
+        let observed = |at: DateTime<Utc>, height: f32| {
+            let mut series = station_series(at, |_| height);
+            series.series.source = DataSource::Sensor;
+            // Observations only reach the latest report, 20 minutes back
+            series.series.samples.retain(|s| s.mins_rel <= -20);
+            series
+        };
+
+        // Yesterday: predicted 2 ft, the water ran 1.5 ft higher
+        let then = now - Duration::hours(24);
+        record(&path, &station_series(then, |_| 2.0), then).unwrap();
+        assert_eq!(record(&path, &observed(then, 3.5), then).unwrap(), 71);
+        // Each kind picks up after its own last row
+        let later = then + Duration::minutes(10);
+        assert_eq!(
+            record(&path, &station_series(later, |_| 2.0), later).unwrap(),
+            1
+        );
+        assert_eq!(record(&path, &observed(later, 3.5), later).unwrap(), 1);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            text.ends_with("2025-07-01T15:50:00Z,3.50,SENSOR\n"),
+            "{text}"
+        );
+
+        let today = station_series(now, |_| 2.0);
+        let overlay = yesterday(&path, &today, "Yesterday").unwrap().unwrap();
+        assert!(overlay.series.samples.iter().all(|s| s.tide_ft == 3.5));
+
+        // Without observations it falls back to yesterday's prediction
+        let predicted = dir.path().join("predicted.csv");
+        record(&predicted, &station_series(then, |_| 2.0), then).unwrap();
+        let overlay = yesterday(&predicted, &today, "Yesterday").unwrap().unwrap();
+        assert!(overlay.series.samples.iter().all(|s| s.tide_ft == 2.0));
+    }
+
+    #[test]
+    fn test_yesterday_lines_up_with_today() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.csv");
+        let now = Utc.with_ymd_and_hms(2025, 7, 2, 16, 0, 0).unwrap();
+        let today = station_series(now, |_| 2.0);
+        assert!(yesterday(&path, &today, "Yesterday").unwrap().is_none());
+
+        // A day and a half of updates every 10 minutes, each showing the
+        // same rising line: 1 ft at 06:00 yesterday, up 0.1 ft an hour
+        let start = now - Duration::hours(34);
+        let height = |t: DateTime<Utc>| 1.0 + (t - start).num_minutes() as f32 / 600.0;
+        for step in 0..=34 * 6 {
+            let at = start + Duration::minutes(step * 10);
+            let shown = station_series(at, |m| height(at + Duration::minutes(m as i64)));
+            record(&path, &shown, at).unwrap();
+        }
+
+        let overlay = yesterday(&path, &today, "Yesterday").unwrap().unwrap();
+        assert_eq!(overlay.station.name, "Yesterday");
+        assert_eq!(overlay.series.base_time, Some(now));
+        let samples = &overlay.series.samples;
+        assert_eq!(samples.len(), 145);
+        for sample in samples {
+            let then = now + Duration::minutes(sample.mins_rel as i64) - Duration::hours(24);
+            assert!((sample.tide_ft - height(then)).abs() < 0.01, "{sample:?}");
+        }
+
+        // Rows for only part of yesterday's window, the 12 hours before one
+        // update 20 hours ago, give part of a curve
+        let recent = dir.path().join("recent.csv");
+        let at = now - Duration::hours(20);
+        record(&recent, &station_series(at, |_| 3.0), at).unwrap();
+        let overlay = yesterday(&recent, &today, "Yesterday").unwrap().unwrap();
+        let samples = &overlay.series.samples;
+        assert_eq!(samples.first().unwrap().mins_rel, -480);
+        assert_eq!(samples.last().unwrap().mins_rel, 240);
+    }
+}
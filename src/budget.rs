@@ -0,0 +1,160 @@
+//! # Refresh Budget
+//!
+//! One refresh (fetch every station, send alerts, draw) must finish within
+//! `display.refresh_budget_secs`. Per-request timeouts alone don't guarantee
+//! that: a hung TLS handshake per station, or a slow notifier after a slow
+//! fetch, still add up to minutes with a stale panel. Each stage runs through
+//! [`RefreshBudget::run`] against the one shared deadline; a stage that
+//! overruns is abandoned, the caller degrades (stale cache, fallback model,
+//! skipped alerts) and the refresh carries on to the render.
+//!
+//! Overruns are logged and appended to `tide_overruns.log` next to the cache
+//! file, so intermittently slow networks show up after the fact:
+//!
+//! ```text
+//! 2025-07-01T12:00:30Z fetch Portland, ME: budget of 30s spent after 30.0s
+//! ```
+
+use crate::config::Config;
+use chrono::{SecondsFormat, Utc};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Overrun records kept in the log; older lines are dropped
+const MAX_RECORDS: usize = 100;
+
+/// Shared deadline for the stages of one refresh
+#[derive(Debug, Clone)]
+pub struct RefreshBudget {
+    started: Instant,
+    limit: Duration,
+    log_path: Option<PathBuf>,
+}
+
+impl RefreshBudget {
+    /// Start the clock with the configured budget, logging overruns next to the cache
+    pub fn start(config: &Config) -> Self {
+        Self {
+            log_path: Some(
+                config
+                    .display
+                    .cache_path
+                    .with_file_name("tide_overruns.log"),
+            ),
+            ..Self::with_limit(config.display.refresh_budget())
+        }
+    }
+
+    /// A budget of `limit` that only logs overruns to stderr
+    pub fn with_limit(limit: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            limit,
+            log_path: None,
+        }
+    }
+
+    /// Time left before the deadline (zero once spent)
+    pub fn remaining(&self) -> Duration {
+        self.limit.saturating_sub(self.started.elapsed())
+    }
+
+    /// Run `stage` until it completes or the budget is spent
+    ///
+    /// Returns `None` on overrun, after recording it. Once the budget is gone
+    /// every later stage returns `None` right away.
+    pub async fn run<F: Future>(&self, stage: &str, fut: F) -> Option<F::Output> {
+        match tokio::time::timeout_at(self.started + self.limit, fut).await {
+            Ok(output) => Some(output),
+            Err(_) => {
+                self.record_overrun(stage);
+                None
+            }
+        }
+    }
+
+    fn record_overrun(&self, stage: &str) {
+        let line = format!(
+            "{} {}: budget of {}s spent after {:.1}s",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            stage,
+            self.limit.as_secs(),
+            self.started.elapsed().as_secs_f32()
+        );
+        warn!("Refresh budget overrun: {}", line);
+        if let Some(path) = &self.log_path {
+            if let Err(e) = append_record(path, &line) {
+                warn!("Could not record overrun in {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Append `line` to the log at `path`, keeping the last [`MAX_RECORDS`] lines
+fn append_record(path: &Path, line: &str) -> std::io::Result<()> {
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    let lines: Vec<&str> = existing.lines().collect();
+    if lines.len() < MAX_RECORDS {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        return writeln!(file, "{}", line);
+    }
+    let mut kept = lines[lines.len() + 1 - MAX_RECORDS..].join("\n");
+    kept.push('\n');
+    kept.push_str(line);
+    kept.push('\n');
+    std::fs::write(path, kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stages_share_one_deadline() {
+        let budget = RefreshBudget::with_limit(Duration::from_secs(30));
+
+        let fast = budget.run("fast", tokio::time::sleep(Duration::from_secs(20)));
+        assert_eq!(fast.await, Some(()));
+        assert_eq!(budget.remaining(), Duration::from_secs(10));
+
+        // 20s more would fit a fresh 30s timeout, but not the shared budget
+        let slow = budget.run("slow", async {
+            tokio::time::sleep(Duration::from_secs(20)).await;
+            1
+        });
+        assert_eq!(slow.await, None);
+        assert_eq!(budget.remaining(), Duration::ZERO);
+
+        // Once spent, later stages give up immediately
+        let started = Instant::now();
+        assert_eq!(budget.run("late", std::future::pending::<()>()).await, None);
+        assert_eq!(started.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_overruns_are_recorded_and_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tide_overruns.log");
+        let budget = RefreshBudget {
+            log_path: Some(path.clone()),
+            ..RefreshBudget::with_limit(Duration::ZERO)
+        };
+
+        for i in 0..MAX_RECORDS + 5 {
+            let stage = format!("stage{}", i);
+            budget.run(&stage, std::future::pending::<()>()).await;
+        }
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), MAX_RECORDS);
+        assert!(lines[0].contains(" stage5: budget of 0s"));
+        assert!(lines[MAX_RECORDS - 1].contains(&format!(" stage{}:", MAX_RECORDS + 4)));
+    }
+}
@@ -0,0 +1,155 @@
+//! # Front-Panel Button
+//!
+//! An optional push button (`[display.hardware] button_pin`, wired to
+//! ground) read by `tide-tracker --daemon` between updates:
+//!
+//! - **Short press**: show the next layout of `[display] button_layouts`,
+//!   redrawn from the data already fetched
+//! - **Long press** (held [`LONG_PRESS`]): fetch again, ignoring the cache,
+//!   and redraw with a full refresh
+//!
+//! [`Button`] turns raw samples of the line into presses. Contact bounce is
+//! filtered by only accepting a level that held for [`DEBOUNCE`], and a long
+//! press is reported as soon as it is long enough, not on release, so the
+//! panel starts updating while the button is still held.
+
+use std::time::{Duration, Instant};
+
+/// How long a level must hold before it counts
+pub const DEBOUNCE: Duration = Duration::from_millis(30);
+
+/// Hold time that turns a press into a long press
+pub const LONG_PRESS: Duration = Duration::from_secs(1);
+
+/// How often the daemon samples the button line
+pub const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A completed press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Press {
+    Short,
+    Long,
+}
+
+/// Debounced press detection from periodic samples of the button line
+#[derive(Debug)]
+pub struct Button {
+    /// Debounced level
+    pressed: bool,
+    /// Raw level last seen, and since when
+    raw: bool,
+    raw_since: Instant,
+    /// When the debounced press began
+    pressed_at: Instant,
+    /// The current press was already reported as long
+    long_reported: bool,
+}
+
+impl Button {
+    /// Start out released
+    pub fn new(now: Instant) -> Self {
+        Self {
+            pressed: false,
+            raw: false,
+            raw_since: now,
+            pressed_at: now,
+            long_reported: false,
+        }
+    }
+
+    /// Feed one sample of the line (`true` while held down), returning a
+    /// press when one completes
+    pub fn update(&mut self, raw: bool, now: Instant) -> Option<Press> {
+        if raw != self.raw {
+            self.raw = raw;
+            self.raw_since = now;
+        }
+        if self.raw != self.pressed && now - self.raw_since >= DEBOUNCE {
+            self.pressed = self.raw;
+            if self.pressed {
+                self.pressed_at = self.raw_since;
+                self.long_reported = false;
+            } else if !self.long_reported {
+                return Some(Press::Short);
+            }
+        }
+        if self.pressed && !self.long_reported && now - self.pressed_at >= LONG_PRESS {
+            self.long_reported = true;
+            return Some(Press::Long);
+        }
+        None
+    }
+}
+
+/// Layout after `current` in `cycle`, wrapping around; the first one when
+/// `current` isn't in it
+pub fn next_layout(current: &str, cycle: &[String]) -> Option<String> {
+    let next = match cycle.iter().position(|name| name == current) {
+        Some(i) => (i + 1) % cycle.len(),
+        None => 0,
+    };
+    cycle.get(next).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `(level, milliseconds)` steps, sampled every `POLL_INTERVAL`,
+    /// and collect the presses
+    fn presses(steps: &[(bool, u64)]) -> Vec<Press> {
+        let start = Instant::now();
+        let mut button = Button::new(start);
+        let mut at = Duration::ZERO;
+        let mut out = Vec::new();
+        for &(level, ms) in steps {
+            let until = at + Duration::from_millis(ms);
+            while at < until {
+                out.extend(button.update(level, start + at));
+                at += POLL_INTERVAL;
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_short_and_long_presses() {
+        assert_eq!(
+            presses(&[(false, 100), (true, 200), (false, 100)]),
+            [Press::Short]
+        );
+        // Reported once while held, and not again as a short press on release
+        assert_eq!(presses(&[(true, 3000), (false, 100)]), [Press::Long]);
+        assert_eq!(
+            presses(&[(true, 200), (false, 100), (true, 1500), (false, 100)]),
+            [Press::Short, Press::Long]
+        );
+    }
+
+    #[test]
+    fn test_bounce_is_ignored() {
+        // Chatter shorter than DEBOUNCE on press and release is one press
+        let bouncy = [
+            (true, 10),
+            (false, 10),
+            (true, 10),
+            (false, 10),
+            (true, 200),
+            (false, 10),
+            (true, 10),
+            (false, 100),
+        ];
+        assert_eq!(presses(&bouncy), [Press::Short]);
+        // A lone glitch is no press at all
+        assert!(presses(&[(false, 50), (true, 20), (false, 100)]).is_empty());
+    }
+
+    #[test]
+    fn test_next_layout_wraps() {
+        let cycle: Vec<String> = ["chart", "table", "split"].map(String::from).to_vec();
+        assert_eq!(next_layout("chart", &cycle).as_deref(), Some("table"));
+        assert_eq!(next_layout("split", &cycle).as_deref(), Some("chart"));
+        assert_eq!(next_layout("large", &cycle).as_deref(), Some("chart"));
+        assert_eq!(next_layout("chart", &[]), None);
+    }
+}
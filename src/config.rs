@@ -3,9 +3,22 @@
 /// This module handles loading and parsing configuration from the tide-config.toml file.
 /// It provides a centralized way to configure NOAA station settings, display options,
 /// and other runtime parameters.
+use crate::alerts::AlertsConfig;
+use crate::eink_renderer::ChartStyle;
+use crate::fallback::ModelConstants;
+use crate::harmonics::HarmonicsConfig;
+use crate::nmea::SensorConfig;
+use crate::provider::ProviderKind;
+use crate::theme::Theme;
+use chrono::{DateTime, FixedOffset, Local, LocalResult, NaiveDateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use thiserror::Error;
+use tracing::{info, warn};
 
 /// Application configuration loaded from tide-config.toml
 #[derive(Debug, Deserialize, Serialize)]
@@ -14,10 +27,64 @@ pub struct Config {
     pub station: StationConfig,
     /// Display and UI configuration  
     pub display: DisplayConfig,
+    /// Additional stations shown alongside (or in turn with) `station`
+    #[serde(default)]
+    pub stations: Vec<StationConfig>,
+    /// Rendering theme (strokes, patterns, colors, fonts)
+    #[serde(default)]
+    pub theme: Theme,
+    /// Notification policy and transports
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    /// Shared offline prediction sources
+    #[serde(default)]
+    pub fallback: FallbackConfig,
+    /// NMEA depth sounder drawn over the predictions
+    #[serde(default)]
+    pub sensor: SensorConfig,
+    /// Record of past heights, see [`crate::archive`] (`archive` feature)
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+}
+
+/// `[fallback]` configuration section
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FallbackConfig {
+    /// XTide harmonics file (`harmonics-*.txt`) to look stations up in by
+    /// name when they have no `[station.harmonics]` of their own
+    pub harmonics_file: Option<PathBuf>,
+    /// Offline model constants for stations without their own `[station.model]`
+    #[serde(flatten)]
+    pub model: ModelConstants,
+}
+
+/// `[archive]` configuration section
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// File to append the heights shown to, one per station (see
+    /// [`Config::archive_path_for`]); no archive when unset
+    pub path: Option<PathBuf>,
+    /// Draw the main station's heights from 24 hours earlier over the chart,
+    /// observed where the archive has observations
+    pub overlay_yesterday: bool,
+    /// Legend label of that curve
+    pub label: String,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            overlay_yesterday: false,
+            label: "Yesterday".to_string(),
+        }
+    }
 }
 
 /// NOAA tide station configuration
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StationConfig {
     /// NOAA station ID (e.g., "8418150" for Portland, ME)
     pub id: String,
@@ -28,26 +95,400 @@ pub struct StationConfig {
     /// Whether to show heights relative to MSL (true) or MLLW (false)
     /// Default false shows traditional MLLW tide chart values (0-9+ feet)
     /// Set true to show heights relative to mean sea level (-5 to +5 feet)
+    /// `msl_offset` is measured from MLLW, so this only makes sense with `datum = "MLLW"`
     pub show_msl: bool,
+    /// Vertical datum the NOAA predictions are referenced to (default: MLLW)
+    #[serde(default)]
+    pub datum: Datum,
+    /// IANA timezone of the station, e.g. "America/New_York" (default: the Pi's)
+    ///
+    /// Used to interpret NOAA's station-local times, for the ±12h window and
+    /// for every time shown on the display, so a Pi left on UTC still shows
+    /// the station's wall-clock times.
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+    /// Custom zero reference (e.g. an intake pipe); heights are shown relative to it
+    #[serde(default)]
+    pub reference: Option<ReferenceLevel>,
+    /// Height in feet, in the station's datum, above which the water floods
+    /// something (a dock, a road); the chart flags upcoming water above it
+    #[serde(default)]
+    pub flood_threshold_ft: Option<f32>,
+    /// Feet the observed water may run above or below the predictions
+    /// before the panel warns of a surge; observations are only fetched
+    /// when set, see [`crate::surge`]
+    #[serde(default)]
+    pub surge_threshold_ft: Option<f32>,
+    /// Harmonic constants for accurate offline predictions, see [`crate::harmonics`]
+    #[serde(default)]
+    pub harmonics: Option<HarmonicsConfig>,
+    /// Offline model constants for this station (default: `[fallback]`'s)
+    #[serde(default)]
+    pub model: Option<ModelConstants>,
+    /// Overall deadline in seconds for fetching fresh data (cache read + NOAA request)
+    /// before giving up and falling back to the offline model
+    #[serde(default = "default_fetch_timeout_secs")]
+    pub fetch_timeout_secs: u64,
+    /// Data sources in order of preference (default: `["noaa"]`); with more
+    /// than one they are raced, see [`crate::provider`]
+    #[serde(default = "default_providers")]
+    pub providers: Vec<ProviderKind>,
+    /// Head start in milliseconds the first provider gets over the others
+    #[serde(default = "default_race_head_start_ms")]
+    pub race_head_start_ms: u64,
+    /// Predictions file the `file` provider reads, see [`crate::file_source`]
+    #[serde(default)]
+    pub data_file: Option<PathBuf>,
+}
+
+impl StationConfig {
+    /// Fetch deadline as a `Duration`
+    pub fn fetch_deadline(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.fetch_timeout_secs)
+    }
+
+    /// Head start of the preferred provider as a `Duration`
+    pub fn race_head_start(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.race_head_start_ms)
+    }
+
+    /// Wall-clock time at the station for the instant `t`
+    pub fn local_time(&self, t: DateTime<Utc>) -> DateTime<FixedOffset> {
+        local_time(self.timezone, t)
+    }
+
+    /// Instant of a station-local wall-clock time; `None` if it doesn't exist
+    /// (skipped by a DST change). Ambiguous times resolve to the earlier one.
+    pub fn from_local(&self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        self.from_local_after(naive, None)
+    }
+
+    /// [`from_local`](Self::from_local) for times read in order, `previous`
+    /// being the instant of the one before
+    ///
+    /// A time repeated when the clocks go back resolves to the later instant
+    /// once the earlier one has been read, so the second 1:00 AM of a
+    /// fall-back night comes an hour after the first instead of landing on it.
+    pub fn from_local_after(
+        &self,
+        naive: NaiveDateTime,
+        previous: Option<DateTime<Utc>>,
+    ) -> Option<DateTime<Utc>> {
+        let resolve = |local: LocalResult<DateTime<Utc>>| match local {
+            LocalResult::Single(t) => Some(t),
+            LocalResult::Ambiguous(earlier, later) => match previous {
+                Some(previous) if earlier <= previous => Some(later),
+                _ => Some(earlier),
+            },
+            LocalResult::None => None,
+        };
+        match self.timezone {
+            Some(tz) => resolve(naive.and_local_timezone(tz).map(|t| t.with_timezone(&Utc))),
+            None => resolve(
+                naive
+                    .and_local_timezone(Local)
+                    .map(|t| t.with_timezone(&Utc)),
+            ),
+        }
+    }
+}
+
+/// `path` with `_suffix` appended to its file stem, keeping the extension
+pub fn with_stem_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "tide_cache".to_string());
+    let name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, suffix, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, suffix),
+    };
+    path.with_file_name(name)
+}
+
+/// `t` in `timezone`, or in the system's local timezone when unset
+pub fn local_time(timezone: Option<Tz>, t: DateTime<Utc>) -> DateTime<FixedOffset> {
+    match timezone {
+        Some(tz) => t.with_timezone(&tz).fixed_offset(),
+        None => t.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+/// A site-specific zero level such as a saltwater intake or pump inlet
+///
+/// Heights are displayed relative to `elevation_ft`, so the chart reads
+/// "water above the intake" directly, and an alert can fire when the water is
+/// or soon will be below it.
+///
+/// ```toml
+/// [station.reference]
+/// name = "intake"
+/// elevation_ft = 1.5      # in the station's datum
+/// alert_below = true
+/// alert_lead_minutes = 60
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReferenceLevel {
+    /// Short name shown on the Y-axis and in alerts
+    pub name: String,
+    /// Elevation of the reference in feet, in the station's datum
+    pub elevation_ft: f32,
+    /// Send an alert when the water drops below the reference
+    #[serde(default)]
+    pub alert_below: bool,
+    /// How far ahead to look for the water dropping below, in minutes
+    #[serde(default = "default_alert_lead_minutes")]
+    pub alert_lead_minutes: i16,
+}
+
+/// Vertical datum for NOAA predictions, passed straight to the API
+///
+/// Engineering users can pick the datum their survey data uses so readings
+/// line up without manual offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Datum {
+    /// Mean Lower Low Water (chart datum)
+    #[default]
+    Mllw,
+    /// Mean Sea Level
+    Msl,
+    /// Mean Tide Level
+    Mtl,
+    /// North American Vertical Datum of 1988
+    Navd88,
+    /// Station Datum
+    Stnd,
+}
+
+impl Datum {
+    /// Value of the `datum` query parameter in the NOAA CO-OPS API
+    pub fn api_code(self) -> &'static str {
+        match self {
+            Datum::Mllw => "MLLW",
+            Datum::Msl => "MSL",
+            Datum::Mtl => "MTL",
+            Datum::Navd88 => "NAVD",
+            Datum::Stnd => "STND",
+        }
+    }
+
+    /// Short label for axis annotations
+    pub fn label(self) -> &'static str {
+        match self {
+            Datum::Navd88 => "NAVD88",
+            other => other.api_code(),
+        }
+    }
 }
 
 /// Display and visualization configuration
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DisplayConfig {
-    /// Time window in hours (shows -window to +window from current time)
+    /// Time window in hours (shows -window to +window from current time),
+    /// 1 to 36; 24 shows two full tide days
     pub time_window_hours: i64,
+    /// Minutes between samples, one of 1, 2, 3, 4, 5, 6, 10 (default), 12,
+    /// 15, 20, 30 or 60; others round down to the nearest of those. 5 suits
+    /// fast-changing rivers, 15 saves memory on multi-day windows
+    #[serde(default = "default_sample_interval_minutes")]
+    pub sample_interval_minutes: i64,
     /// Cache TTL in minutes
     pub cache_ttl_minutes: u64,
-    /// E-ink display width in pixels
+    /// Cache file location (default: `/tmp/tide_cache.bin`, cleared on reboot)
+    ///
+    /// Each station's file has its ID appended, see [`Config::cache_path_for`].
+    /// Point this at a persistent directory such as `/var/cache/tide-tracker/`
+    /// so off-grid installs keep their last good data across reboots. Missing
+    /// parent directories are created on first write.
+    #[serde(default = "default_cache_path")]
+    pub cache_path: PathBuf,
+    /// E-ink display width in pixels (informational; frames are drawn at
+    /// the size of `hardware.panel`)
     pub width: i32,
-    /// E-ink display height in pixels
+    /// E-ink display height in pixels (informational, like `width`)
     pub height: i32,
     /// Font size for e-ink display (affects text rendering)
     pub font_height: i32,
+    /// Clock style for every rendered time: "12h" (3:45PM, default) or "24h" (15:45)
+    #[serde(default)]
+    pub time_format: TimeFormat,
+    /// How multiple stations share the panel: "rotate" (one per refresh, default)
+    /// or "overlay" (all curves on one chart with a legend)
+    #[serde(default)]
+    pub multi_station: MultiStationMode,
+    /// Layout drawn on the panel: "chart" (default), "large" (large-print),
+    /// "table" (tide table) or "split" (chart beside a table)
+    #[serde(default = "default_layout")]
+    pub layout: String,
+    /// Time budget in seconds for a whole refresh (all fetches plus alerts);
+    /// stages still running when it is spent are abandoned and the display is
+    /// drawn from stale cache or the offline model instead, see [`crate::budget`]
+    #[serde(default = "default_refresh_budget_secs")]
+    pub refresh_budget_secs: u64,
+    /// Draw the moon phase and age in a corner of the chart
+    #[serde(default = "default_show_moon_phase")]
+    pub show_moon_phase: bool,
+    /// Tag the chart with where the tides are between neap and spring
+    #[serde(default = "default_show_spring_neap")]
+    pub show_spring_neap: bool,
+    /// Draw red theme elements in red on the tri-color panel; turn off for
+    /// black-and-white panels, where they are drawn black
+    #[serde(default = "default_use_red")]
+    pub use_red: bool,
+    /// Turn the picture clockwise by 0, 90, 180 or 270 degrees before it is
+    /// sent to the panel, for displays mounted sideways or upside down
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// Flip the picture left to right (before `rotation`), e.g. when the
+    /// panel is viewed through a mirror
+    #[serde(default)]
+    pub mirror: bool,
+    /// Minutes between full (flashing) refreshes; runs in between only
+    /// partially update what changed, mainly the clock and the now marker
+    /// sliding along the last full chart. 0 (the default) always refreshes
+    /// fully, skipping frames identical to the one on the panel.
+    #[serde(default)]
+    pub full_refresh_minutes: u32,
+    /// Force a full refresh after this many partial updates in a row, even
+    /// inside `full_refresh_minutes`, so ghosting of the partially redrawn
+    /// parts is cleared regularly. 0 sets no limit.
+    #[serde(default = "default_max_partial_refreshes")]
+    pub max_partial_refreshes: u32,
+    /// Power the panel down (POWER_OFF + DEEP_SLEEP) once each update is on
+    /// screen; the next run wakes it with a reset. Saves idle current and
+    /// panel life, so only turn it off while debugging the driver.
+    #[serde(default = "default_deep_sleep_after_update")]
+    pub deep_sleep_after_update: bool,
+    /// Below this panel temperature (°C, from the controller's sensor) full
+    /// refreshes use the slow cold-weather waveform, for outdoor winter
+    /// installs where the ink is sluggish. Unset never does. Needs wiring
+    /// that can read the sensor, see
+    /// [`Epd4in2bV2::read_temperature`](crate::epd4in2b_v2::Epd4in2bV2::read_temperature).
+    #[serde(default)]
+    pub cold_refresh_below_c: Option<f32>,
+    /// Draw a "DATA >2h OLD" banner once the NOAA data shown was fetched
+    /// more than this many hours ago (the fetch keeps failing and the last
+    /// cache is all there is). 0 never does.
+    #[serde(default = "default_stale_after_hours")]
+    pub stale_after_hours: u32,
+    /// Minutes between updates when running as a daemon (`--daemon`)
+    /// instead of from the systemd timer
+    #[serde(default = "default_update_interval_minutes")]
+    pub update_interval_minutes: u64,
+    /// Layouts a short press of the button cycles through, see
+    /// [`crate::button`]
+    #[serde(default = "default_button_layouts")]
+    pub button_layouts: Vec<String>,
+    /// Chart margins, insets, ticks and label offsets (`[display.chart]`)
+    #[serde(default)]
+    pub chart: ChartStyle,
     /// Hardware GPIO pin configuration
     pub hardware: HardwareConfig,
 }
 
+/// How several configured stations are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiStationMode {
+    /// Show one station per refresh, cycling through the list
+    #[default]
+    Rotate,
+    /// Draw every station's curve on the same chart
+    Overlay,
+}
+
+/// Clockwise turn applied to the picture before it reaches the panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl Rotation {
+    /// Number of clockwise quarter turns
+    pub fn quarter_turns(self) -> u8 {
+        match self {
+            Rotation::Deg0 => 0,
+            Rotation::Deg90 => 1,
+            Rotation::Deg180 => 2,
+            Rotation::Deg270 => 3,
+        }
+    }
+
+    /// Size to draw at so that, once turned, the picture fills a panel of
+    /// `width` × `height`
+    pub fn drawing_size(self, width: u32, height: u32) -> (u32, u32) {
+        if self.quarter_turns() % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+}
+
+impl TryFrom<u16> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u16) -> Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Rotation::Deg0),
+            90 => Ok(Rotation::Deg90),
+            180 => Ok(Rotation::Deg180),
+            270 => Ok(Rotation::Deg270),
+            other => Err(format!("rotation must be 0, 90, 180 or 270, not {other}")),
+        }
+    }
+}
+
+impl From<Rotation> for u16 {
+    fn from(rotation: Rotation) -> u16 {
+        rotation.quarter_turns() as u16 * 90
+    }
+}
+
+/// 12-hour vs 24-hour clock display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum TimeFormat {
+    /// 3:45PM
+    #[default]
+    #[serde(rename = "12h")]
+    H12,
+    /// 15:45
+    #[serde(rename = "24h")]
+    H24,
+}
+
+impl TimeFormat {
+    /// chrono format string for a time of day
+    pub fn clock(self) -> &'static str {
+        match self {
+            TimeFormat::H12 => "%-I:%M%p",
+            TimeFormat::H24 => "%H:%M",
+        }
+    }
+
+    /// chrono format string for a whole hour on the time axis ("3PM", "15:00")
+    pub fn hour(self) -> &'static str {
+        match self {
+            TimeFormat::H12 => "%-I%p",
+            TimeFormat::H24 => "%H:%M",
+        }
+    }
+
+    /// chrono format string for a short date plus time of day
+    pub fn date_time(self) -> &'static str {
+        match self {
+            TimeFormat::H12 => "%-m/%-d %-I:%M%p",
+            TimeFormat::H24 => "%-m/%-d %H:%M",
+        }
+    }
+}
+
 /// Hardware GPIO pin configuration for e-ink display
 ///
 /// Default pin mapping for Waveshare 4.2" e-ink display on Raspberry Pi Zero 2 W:
@@ -68,12 +509,292 @@ pub struct HardwareConfig {
     pub rst_pin: u32,
     /// Busy status pin (default: GPIO 24, Pin 18)
     pub busy_pin: u32,
+    /// SPI bus number, i.e. the `N` in `/dev/spidevN.M` (default: 0)
+    #[serde(default)]
+    pub spi_bus: u8,
+    /// SPI mode (0-3) used for the display (default: 0)
+    #[serde(default)]
+    pub spi_mode: u8,
+    /// SPI clock speed in Hz used for the display (default: 8 MHz, SSD1683 max)
+    #[serde(default = "default_spi_speed_hz")]
+    pub spi_speed_hz: u32,
+    /// Which Waveshare panel is wired up: "4in2b_v2" (default), "4in2_v2"
+    /// (plain black/white 4.2") or "7in5_v2"
+    #[serde(default)]
+    pub panel: PanelModel,
+    /// GPIO character device with the header lines, for the "cdev" backend;
+    /// unset finds the chip whose lines are named `GPIO<n>` (gpiochip4 on a
+    /// Pi 5), falling back to `/dev/gpiochip0`
+    #[serde(default)]
+    pub gpiochip: Option<PathBuf>,
+    /// How GPIO and SPI are reached: "cdev" (gpio-cdev + spidev, default) or
+    /// "rppal" (needs the `rppal` build feature)
+    #[serde(default)]
+    pub backend: GpioBackend,
+    /// Push button between this GPIO and ground, read by `--daemon` (see
+    /// [`crate::button`]). rppal turns the internal pull-up on; with cdev
+    /// GPIO 2-8 have one at boot and others need `gpio=<n>=pu` in config.txt
+    #[serde(default)]
+    pub button_pin: Option<u32>,
+    /// Other peripherals sharing an SPI bus with the display (SD card,
+    /// sensor, ...), each with its own chip select, mode and speed; see
+    /// [`crate::spi_bus`]
+    #[serde(default)]
+    pub spi_devices: Vec<SpiDeviceConfig>,
+}
+
+/// Library used to drive the display's GPIO lines and SPI bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpioBackend {
+    /// Character-device GPIO (`/dev/gpiochip0`) and `/dev/spidev*`
+    #[default]
+    Cdev,
+    /// rppal, which maps the header pins itself; only available when built
+    /// with `--features rppal`, and without `spi_devices`
+    Rppal,
+}
+
+/// Supported e-ink panels, see [`crate::epd`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum PanelModel {
+    /// Waveshare 4.2" black/white/red V2, 400x300
+    #[default]
+    #[serde(rename = "4in2b_v2")]
+    Epd4in2bV2,
+    /// Waveshare 4.2" black/white V2, 400x300: the same driver without the
+    /// red plane
+    #[serde(rename = "4in2_v2")]
+    Epd4in2V2,
+    /// Waveshare 7.5" black/white V2, 800x480
+    #[serde(rename = "7in5_v2")]
+    Epd7in5V2,
+}
+
+impl PanelModel {
+    /// Panel width and height in pixels, as its driver reports them
+    pub fn size(self) -> (u32, u32) {
+        match self {
+            PanelModel::Epd4in2bV2 | PanelModel::Epd4in2V2 => (
+                crate::epd4in2b_v2::EPD_WIDTH,
+                crate::epd4in2b_v2::EPD_HEIGHT,
+            ),
+            PanelModel::Epd7in5V2 => (crate::epd7in5_v2::EPD_WIDTH, crate::epd7in5_v2::EPD_HEIGHT),
+        }
+    }
+}
+
+/// Another SPI peripheral wired to a bus the display is on
+///
+/// ```toml
+/// [[display.hardware.spi_devices]]
+/// name = "sd-card"
+/// cs_pin = 7          # CE1 (kernel-controlled), any other pin is manual CS
+/// spi_mode = 0
+/// spi_speed_hz = 20000000
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpiDeviceConfig {
+    /// Human-readable name used in log messages
+    pub name: String,
+    /// SPI bus number (default: the display's `spi_bus`)
+    #[serde(default)]
+    pub spi_bus: Option<u8>,
+    /// Chip Select GPIO (8 = CE0, 7 = CE1, anything else is toggled manually)
+    pub cs_pin: u32,
+    /// SPI mode (0-3) the peripheral expects
+    #[serde(default)]
+    pub spi_mode: u8,
+    /// SPI clock speed in Hz the peripheral expects
+    #[serde(default = "default_spi_speed_hz")]
+    pub spi_speed_hz: u32,
+}
+
+impl DisplayConfig {
+    /// Cache TTL as a `Duration`
+    pub fn cache_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.cache_ttl_minutes * 60)
+    }
+
+    /// The configured chart window around `now`
+    pub fn window(&self, now: DateTime<Utc>) -> crate::grid::Window {
+        self.series_spec(now).window
+    }
+
+    /// The configured window around `now` and the step between its samples
+    pub fn series_spec(&self, now: DateTime<Utc>) -> crate::grid::SeriesSpec {
+        crate::grid::SeriesSpec::hours(now, self.time_window_hours, self.sample_interval_minutes)
+    }
+
+    /// Refresh budget as a `Duration`
+    pub fn refresh_budget(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.refresh_budget_secs)
+    }
+}
+
+/// Highest BCM GPIO on the 40-pin header
+pub const MAX_HEADER_GPIO: u32 = 27;
+
+/// Why the `[display.hardware]` pins can't work together
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PinError {
+    #[error("{name} = {pin}: the header only has GPIO 0-{MAX_HEADER_GPIO}")]
+    OutOfRange { name: String, pin: u32 },
+    #[error("{name} = {pin}: GPIO {pin} is {reason}")]
+    Reserved {
+        name: String,
+        pin: u32,
+        reason: &'static str,
+    },
+    #[error("{first} and {second} are both GPIO {pin}")]
+    Duplicate {
+        first: String,
+        second: String,
+        pin: u32,
+    },
+}
+
+impl HardwareConfig {
+    /// Check the pins before any are requested, so a typo is reported as
+    /// such rather than as a GPIO error from deep inside the backend
+    ///
+    /// Every pin must be on the header, not on the HAT EEPROM lines or the
+    /// MISO/MOSI/SCLK of any bus in use, and used only once. SPI0's CE0
+    /// (GPIO 8) and CE1 (GPIO 7) belong to the kernel driver and can only be
+    /// chip selects.
+    pub fn check_pins(&self) -> Result<(), PinError> {
+        let mut pins: Vec<(String, u32, bool)> = vec![
+            ("cs_pin".into(), self.cs_pin, true),
+            ("dc_pin".into(), self.dc_pin, false),
+            ("rst_pin".into(), self.rst_pin, false),
+            ("busy_pin".into(), self.busy_pin, false),
+        ];
+        pins.extend(self.button_pin.map(|pin| ("button_pin".into(), pin, false)));
+        pins.extend(
+            self.spi_devices
+                .iter()
+                .map(|d| (format!("spi_devices \"{}\" cs_pin", d.name), d.cs_pin, true)),
+        );
+
+        let buses: Vec<u8> = std::iter::once(self.spi_bus)
+            .chain(self.spi_devices.iter().filter_map(|d| d.spi_bus))
+            .collect();
+        let bus_lines: Vec<u32> = buses
+            .iter()
+            .flat_map(|bus| match bus {
+                0 => &[9, 10, 11][..],
+                1 => &[19, 20, 21][..],
+                _ => &[][..],
+            })
+            .copied()
+            .collect();
+        for (i, (name, pin, is_cs)) in pins.iter().enumerate() {
+            let reserved = |reason| PinError::Reserved {
+                name: name.clone(),
+                pin: *pin,
+                reason,
+            };
+            if *pin > MAX_HEADER_GPIO {
+                return Err(PinError::OutOfRange {
+                    name: name.clone(),
+                    pin: *pin,
+                });
+            }
+            if matches!(pin, 0 | 1) {
+                return Err(reserved("the HAT ID EEPROM bus (ID_SD/ID_SC)"));
+            }
+            if bus_lines.contains(pin) {
+                return Err(reserved("one of the SPI bus's MISO/MOSI/SCLK lines"));
+            }
+            if buses.contains(&0) && matches!(pin, 7 | 8) && !is_cs {
+                return Err(reserved(
+                    "an SPI0 chip select owned by the kernel driver; only a cs_pin can use it",
+                ));
+            }
+            if let Some((first, ..)) = pins[..i].iter().find(|(_, other, _)| other == pin) {
+                return Err(PinError::Duplicate {
+                    first: first.clone(),
+                    second: name.clone(),
+                    pin: *pin,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_layout() -> String {
+    crate::layout::DEFAULT_LAYOUT.to_string()
+}
+
+fn default_cache_path() -> PathBuf {
+    PathBuf::from("/tmp/tide_cache.bin")
+}
+
+fn default_fetch_timeout_secs() -> u64 {
+    20
+}
+
+fn default_providers() -> Vec<ProviderKind> {
+    vec![ProviderKind::Noaa]
+}
+
+fn default_race_head_start_ms() -> u64 {
+    2000
+}
+
+fn default_show_moon_phase() -> bool {
+    true
+}
+
+fn default_show_spring_neap() -> bool {
+    true
+}
+
+fn default_max_partial_refreshes() -> u32 {
+    10
+}
+
+fn default_sample_interval_minutes() -> i64 {
+    crate::grid::INTERVAL_MINS
+}
+
+fn default_stale_after_hours() -> u32 {
+    2
+}
+
+fn default_update_interval_minutes() -> u64 {
+    10
+}
+
+fn default_button_layouts() -> Vec<String> {
+    ["chart", "table", "split"].map(String::from).to_vec()
+}
+
+fn default_deep_sleep_after_update() -> bool {
+    true
+}
+
+fn default_use_red() -> bool {
+    true
+}
+
+fn default_refresh_budget_secs() -> u64 {
+    30
+}
+
+fn default_alert_lead_minutes() -> i16 {
+    60
 }
 
 fn default_cs_pin() -> u32 {
     8
 }
 
+fn default_spi_speed_hz() -> u32 {
+    8_000_000
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -82,62 +803,350 @@ impl Default for Config {
                 name: "Portland, ME".to_string(),
                 msl_offset: 4.9,
                 show_msl: false, // Default to traditional MLLW display
+                datum: Datum::default(),
+                timezone: None,
+                reference: None,
+                flood_threshold_ft: None,
+                surge_threshold_ft: None,
+                harmonics: None,
+                model: None,
+                fetch_timeout_secs: default_fetch_timeout_secs(),
+                providers: default_providers(),
+                race_head_start_ms: default_race_head_start_ms(),
+                data_file: None,
             },
             display: DisplayConfig {
                 time_window_hours: 12,
+                sample_interval_minutes: default_sample_interval_minutes(),
                 cache_ttl_minutes: 30,
+                cache_path: default_cache_path(),
                 width: 400,      // Waveshare 4.2" display
                 height: 300,     // Waveshare 4.2" display
                 font_height: 20, // FONT_10X20 height
+                time_format: TimeFormat::default(),
+                multi_station: MultiStationMode::default(),
+                layout: default_layout(),
+                refresh_budget_secs: default_refresh_budget_secs(),
+                show_moon_phase: default_show_moon_phase(),
+                show_spring_neap: default_show_spring_neap(),
+                use_red: default_use_red(),
+                rotation: Rotation::default(),
+                mirror: false,
+                full_refresh_minutes: 0,
+                max_partial_refreshes: default_max_partial_refreshes(),
+                deep_sleep_after_update: default_deep_sleep_after_update(),
+                cold_refresh_below_c: None,
+                stale_after_hours: default_stale_after_hours(),
+                update_interval_minutes: default_update_interval_minutes(),
+                button_layouts: default_button_layouts(),
+                chart: ChartStyle::default(),
                 hardware: HardwareConfig {
                     cs_pin: 8,    // GPIO 8 (Pin 24) - SPI Chip Select
                     dc_pin: 25,   // GPIO 25 (Pin 22) - Data/Command
                     rst_pin: 17,  // GPIO 17 (Pin 11) - Reset
                     busy_pin: 24, // GPIO 24 (Pin 18) - Busy status
+                    spi_bus: 0,
+                    spi_mode: 0,
+                    spi_speed_hz: default_spi_speed_hz(),
+                    panel: PanelModel::default(),
+                    backend: GpioBackend::default(),
+                    gpiochip: None,
+                    button_pin: None,
+                    spi_devices: Vec::new(),
                 },
             },
+            stations: Vec::new(),
+            theme: Theme::default(),
+            alerts: AlertsConfig::default(),
+            fallback: FallbackConfig::default(),
+            sensor: SensorConfig::default(),
+            archive: ArchiveConfig::default(),
         }
     }
 }
 
+/// Configuration file name, looked up in each of [`Config::search_paths`]
+pub const CONFIG_FILE: &str = "tide-config.toml";
+
+/// Environment variable naming the configuration file, like `--config`
+pub const CONFIG_ENV: &str = "TIDE_TRACKER_CONFIG";
+
+/// Configuration file given on the command line
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
 impl Config {
-    /// Load configuration from tide-config.toml file
+    /// Load configuration from the file [`Config::path`] finds
     /// Falls back to default configuration if file doesn't exist or is invalid
     pub fn load() -> Self {
-        Self::load_from_path("tide-config.toml")
+        Self::load_from_path(Self::path())
+    }
+
+    /// Use `path` as the configuration file from now on (`--config`),
+    /// ahead of [`CONFIG_ENV`] and the search
+    pub fn set_path(path: PathBuf) {
+        if CONFIG_PATH.set(path).is_err() {
+            warn!("Configuration file already chosen, ignoring another --config");
+        }
+    }
+
+    /// Where a configuration file is looked for, in order, when none is given
+    /// with `--config` or [`CONFIG_ENV`]: the current directory, then
+    /// `$XDG_CONFIG_HOME/tide-tracker/` (`~/.config/tide-tracker/` when
+    /// unset), then `/etc/tide-tracker/`
+    pub fn search_paths() -> Vec<PathBuf> {
+        search_paths_in(
+            std::env::var_os("XDG_CONFIG_HOME"),
+            std::env::var_os("HOME"),
+        )
+    }
+
+    /// The configuration file: the one given explicitly whether or not it
+    /// exists, else the first of [`Config::search_paths`] that does, else
+    /// [`CONFIG_FILE`] in the current directory
+    pub fn path() -> PathBuf {
+        let explicit = CONFIG_PATH.get().cloned().or_else(|| {
+            std::env::var_os(CONFIG_ENV)
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        });
+        explicit
+            .or_else(|| Self::search_paths().into_iter().find(|p| p.is_file()))
+            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE))
+    }
+
+    /// The primary station followed by any additional `[[stations]]`
+    pub fn all_stations(&self) -> Vec<&StationConfig> {
+        std::iter::once(&self.station)
+            .chain(self.stations.iter())
+            .collect()
+    }
+
+    /// Harmonic constants to predict `station` from, if any
+    ///
+    /// The station's own `[station.harmonics]` win; an XTide station named
+    /// there without a file, or a station with no harmonics at all, is looked
+    /// up in `[fallback] harmonics_file` (by the station's name unless
+    /// `xtide_station` is given).
+    pub fn harmonics_for(&self, station: &StationConfig) -> Option<HarmonicsConfig> {
+        let shared = self.fallback.harmonics_file.as_ref();
+        let mut harmonics = match (&station.harmonics, shared) {
+            (Some(harmonics), _) => harmonics.clone(),
+            (None, Some(_)) => HarmonicsConfig::default(),
+            (None, None) => return None,
+        };
+        if harmonics.xtide_file.is_none()
+            && (harmonics.xtide_station.is_some() || station.harmonics.is_none())
+        {
+            harmonics.xtide_file = shared.cloned();
+        }
+        if harmonics.xtide_file.is_some() && harmonics.xtide_station.is_none() {
+            harmonics.xtide_station = Some(station.name.clone());
+        }
+        Some(harmonics)
+    }
+
+    /// Offline model constants for `station`
+    pub fn model_for(&self, station: &StationConfig) -> ModelConstants {
+        station.model.unwrap_or(self.fallback.model)
+    }
+
+    /// Cache file for `station_id`'s data from `provider`
+    ///
+    /// `display.cache_path` with the station ID appended to the file stem,
+    /// and the provider name too unless it is NOAA:
+    /// `/tmp/tide_cache_8418150.bin`, `/tmp/tide_cache_8418150_file.bin`.
+    /// Stations and providers never overwrite each other, and editing the
+    /// station doesn't serve the old one's data.
+    pub fn cache_path_for(&self, station_id: &str, provider: ProviderKind) -> PathBuf {
+        let key = match provider {
+            ProviderKind::Noaa => station_id.to_string(),
+            other => format!("{}_{}", station_id, other.name()),
+        };
+        with_stem_suffix(&self.display.cache_path, &key)
+    }
+
+    /// Archive file of `station_id`, `[archive] path` with the station ID
+    /// appended to the file stem (`/var/lib/tide-tracker/archive_8418150.csv`);
+    /// `None` when there is no archive
+    pub fn archive_path_for(&self, station_id: &str) -> Option<PathBuf> {
+        let path = self.archive.path.as_ref()?;
+        Some(with_stem_suffix(path, station_id))
+    }
+
+    /// Whether a configuration file exists at all (false on a fresh install)
+    pub fn file_exists() -> bool {
+        Self::path().exists()
     }
 
     /// Load configuration from specified path
     /// Falls back to default configuration if file doesn't exist or is invalid
+    ///
+    /// `TIDE_*` environment variables then override single values, see
+    /// [`env_override_key`].
     pub fn load_from_path<P: AsRef<Path>>(path: P) -> Self {
-        match fs::read_to_string(&path) {
-            Ok(contents) => match toml::from_str::<Config>(&contents) {
-                Ok(config) => {
-                    println!("Loaded configuration for station: {}", config.station.name);
-                    config
-                }
+        let file = match fs::read_to_string(&path) {
+            Ok(contents) => match contents.parse::<toml::Table>() {
+                Ok(table) => Some(table),
                 Err(e) => {
-                    eprintln!("Warning: Invalid config file format: {}", e);
-                    eprintln!("Using default configuration (Portland, ME)");
-                    Self::default()
+                    warn!(
+                        "Invalid config file format, using the default configuration (Portland, ME): {}",
+                        e
+                    );
+                    None
                 }
             },
             Err(_) => {
-                eprintln!("Info: No config file found, using default configuration (Portland, ME)");
+                info!(
+                    "No config file at {}, using the default configuration (Portland, ME)",
+                    path.as_ref().display()
+                );
+                None
+            }
+        };
+        let overrides: Vec<(String, String, String)> = std::env::vars()
+            .filter_map(|(var, raw)| Some((env_override_key(&var)?, var, raw)))
+            .collect();
+        if file.is_none() && overrides.is_empty() {
+            return Self::default();
+        }
+
+        let mut table = file.unwrap_or_else(default_table);
+        apply_overrides(&mut table, &overrides);
+        match toml::Value::Table(table).try_into::<Config>() {
+            Ok(config) => {
+                info!("Loaded configuration for station: {}", config.station.name);
+                config
+            }
+            Err(e) => {
+                warn!(
+                    "Invalid config file format, using the default configuration (Portland, ME): {}",
+                    e
+                );
                 Self::default()
             }
         }
     }
 
-    /// Save current configuration to tide-config.toml
+    /// Save current configuration to [`Config::path`]
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let contents = toml::to_string_pretty(self)?;
-        fs::write("tide-config.toml", contents)?;
-        println!("Configuration saved to tide-config.toml");
+        let path = Self::path();
+        fs::write(&path, contents)?;
+        info!("Configuration saved to {}", path.display());
         Ok(())
     }
 }
 
+/// Environment variables with a short name for a commonly changed value
+pub const ENV_SHORTCUTS: &[(&str, &str)] = &[
+    ("TIDE_STATION_ID", "station.id"),
+    ("TIDE_STATION_NAME", "station.name"),
+    ("TIDE_CACHE_TTL", "display.cache_ttl_minutes"),
+    ("TIDE_CACHE_PATH", "display.cache_path"),
+    ("TIDE_LAYOUT", "display.layout"),
+    ("TIDE_PANEL", "display.hardware.panel"),
+    ("TIDE_CS_PIN", "display.hardware.cs_pin"),
+    ("TIDE_DC_PIN", "display.hardware.dc_pin"),
+    ("TIDE_RST_PIN", "display.hardware.rst_pin"),
+    ("TIDE_BUSY_PIN", "display.hardware.busy_pin"),
+];
+
+/// Dotted configuration key the environment variable `name` overrides
+///
+/// Besides [`ENV_SHORTCUTS`], any value can be set with its path in capitals
+/// after `TIDE__`, separated by double underscores, e.g.
+/// `TIDE__DISPLAY__TIME_FORMAT=24h` for `[display] time_format`. Values are
+/// read as TOML (`false`, `5`, `["noaa", "model"]`), or as plain text where
+/// that fails or the setting is text anyway.
+pub fn env_override_key(name: &str) -> Option<String> {
+    if let Some((_, key)) = ENV_SHORTCUTS.iter().find(|(var, _)| *var == name) {
+        return Some(key.to_string());
+    }
+    let path = name.strip_prefix("TIDE__")?;
+    let parts: Vec<String> = path.split("__").map(str::to_ascii_lowercase).collect();
+    (!parts.iter().any(String::is_empty)).then(|| parts.join("."))
+}
+
+/// `Config::default()` as a table, the base layer when there is no file
+fn default_table() -> toml::Table {
+    match toml::Value::try_from(Config::default()) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => toml::Table::new(),
+    }
+}
+
+/// Set `(key, variable, raw value)` overrides in `table`
+///
+/// Text settings (judged by the file's value, else the default's) keep the
+/// raw value as is, so `TIDE_STATION_ID=8418150` stays a string.
+fn apply_overrides(table: &mut toml::Table, overrides: &[(String, String, String)]) {
+    let defaults = default_table();
+    for (key, var, raw) in overrides {
+        let parts: Vec<&str> = key.split('.').collect();
+        let Some((last, parents)) = parts.split_last() else {
+            continue;
+        };
+        let existing = |table: &toml::Table| {
+            parents
+                .iter()
+                .try_fold(table, |at, part| at.get(*part)?.as_table())?
+                .get(*last)
+                .map(toml::Value::is_str)
+        };
+        let value = if existing(table).or_else(|| existing(&defaults)) == Some(true) {
+            toml::Value::String(raw.clone())
+        } else {
+            format!("v = {raw}")
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|mut t| t.remove("v"))
+                .unwrap_or_else(|| toml::Value::String(raw.clone()))
+        };
+
+        let Some(at) = table_at(table, Some(&defaults), parents) else {
+            warn!("{} ignored: {} is not a table", var, parents.join("."));
+            continue;
+        };
+        if at.get(*last).is_some_and(toml::Value::is_table) {
+            warn!("{} ignored: {} is a table", var, key);
+            continue;
+        }
+        info!("{} overrides {}", var, key);
+        at.insert(last.to_string(), value);
+    }
+}
+
+/// The table at `path` in `table`; one the file leaves out starts as a copy
+/// of the default's, so setting a single key doesn't drop its required
+/// neighbours
+fn table_at<'a>(
+    table: &'a mut toml::Table,
+    defaults: Option<&toml::Table>,
+    path: &[&str],
+) -> Option<&'a mut toml::Table> {
+    let Some((first, rest)) = path.split_first() else {
+        return Some(table);
+    };
+    let defaults = defaults.and_then(|d| d.get(*first)?.as_table());
+    let next = table
+        .entry(first.to_string())
+        .or_insert_with(|| toml::Value::Table(defaults.cloned().unwrap_or_default()))
+        .as_table_mut()?;
+    table_at(next, defaults, rest)
+}
+
+fn search_paths_in(xdg_config_home: Option<OsString>, home: Option<OsString>) -> Vec<PathBuf> {
+    // An empty or relative XDG_CONFIG_HOME is invalid and ignored, per the spec
+    let user = xdg_config_home
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home.map(|home| Path::new(&home).join(".config")));
+    let mut paths = vec![PathBuf::from(CONFIG_FILE)];
+    paths.extend(user.map(|dir| dir.join("tide-tracker").join(CONFIG_FILE)));
+    paths.push(Path::new("/etc/tide-tracker").join(CONFIG_FILE));
+    paths
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,6 +1159,12 @@ mod tests {
         assert_eq!(config.station.msl_offset, 4.9);
         assert_eq!(config.display.time_window_hours, 12);
         assert_eq!(config.display.cache_ttl_minutes, 30);
+        assert_eq!(config.station.fetch_timeout_secs, 20);
+        let spec = config.display.series_spec(chrono::Utc::now());
+        assert_eq!(
+            (spec.sample_count(), spec.interval.num_minutes()),
+            (145, 10)
+        );
     }
 
     #[test]
@@ -161,10 +1176,435 @@ mod tests {
         assert_eq!(config.station.name, parsed.station.name);
     }
 
+    #[test]
+    fn test_hardware_parse() {
+        let toml_str = r#"
+            [station]
+            id = "8418150"
+            name = "Portland, ME"
+            msl_offset = 4.9
+            show_msl = false
+
+            [display]
+            time_window_hours = 12
+            cache_ttl_minutes = 30
+            width = 400
+            height = 300
+            font_height = 20
+
+            [display.hardware]
+            cs_pin = 7
+            dc_pin = 25
+            rst_pin = 17
+            busy_pin = 24
+            panel = "7in5_v2"
+            backend = "rppal"
+            gpiochip = "/dev/gpiochip4"
+            spi_mode = 3
+
+            [[display.hardware.spi_devices]]
+            name = "sd-card"
+            cs_pin = 8
+            spi_speed_hz = 20000000
+
+            [[display.hardware.spi_devices]]
+            name = "second"
+            spi_bus = 1
+            cs_pin = 16
+            spi_mode = 2
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let hw = &config.display.hardware;
+        assert_eq!(hw.spi_mode, 3);
+        assert_eq!(hw.spi_speed_hz, 8_000_000);
+        let sd = &hw.spi_devices[0];
+        assert_eq!(
+            (sd.spi_bus, sd.spi_mode, sd.spi_speed_hz),
+            (None, 0, 20_000_000)
+        );
+        let second = &hw.spi_devices[1];
+        assert_eq!(
+            (second.spi_bus, second.spi_mode, second.spi_speed_hz),
+            (Some(1), 2, 8_000_000)
+        );
+        assert_eq!(hw.panel, PanelModel::Epd7in5V2);
+        assert_eq!(hw.panel.size(), (800, 480));
+        assert_eq!(hw.backend, GpioBackend::Rppal);
+        assert_eq!(hw.gpiochip.as_deref(), Some(Path::new("/dev/gpiochip4")));
+        assert!(Config::default().display.hardware.gpiochip.is_none());
+        assert_eq!(Config::default().display.hardware.panel.size(), (400, 300));
+    }
+
+    #[test]
+    fn test_check_pins() {
+        assert_eq!(Config::default().display.hardware.check_pins(), Ok(()));
+        let with = |edit: fn(&mut HardwareConfig)| {
+            let mut hw = Config::default().display.hardware;
+            edit(&mut hw);
+            hw.check_pins().map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            with(|hw| hw.busy_pin = hw.dc_pin),
+            Err("dc_pin and busy_pin are both GPIO 25".to_string())
+        );
+        assert_eq!(
+            with(|hw| hw.rst_pin = 40),
+            Err("rst_pin = 40: the header only has GPIO 0-27".to_string())
+        );
+        assert!(matches!(
+            with(|hw| hw.dc_pin = 10),
+            Err(e) if e.contains("MISO/MOSI/SCLK")
+        ));
+        assert!(matches!(with(|hw| hw.rst_pin = 1), Err(e) if e.contains("EEPROM")));
+        // CE1 can be the display's chip select, but not its reset line
+        assert_eq!(with(|hw| hw.cs_pin = 7), Ok(()));
+        assert!(matches!(with(|hw| hw.rst_pin = 7), Err(e) if e.contains("SPI0 chip select")));
+        // On SPI1 GPIO 10 is free, but its own SCLK isn't
+        assert_eq!(
+            with(|hw| {
+                hw.spi_bus = 1;
+                hw.cs_pin = 16;
+                hw.dc_pin = 10;
+            }),
+            Ok(())
+        );
+        assert!(with(|hw| {
+            hw.spi_bus = 1;
+            hw.busy_pin = 21;
+        })
+        .is_err());
+
+        // Peripherals sharing the bus can't reuse the display's chip select
+        let sd = SpiDeviceConfig {
+            name: "sd".into(),
+            spi_bus: None,
+            cs_pin: 8,
+            spi_mode: 0,
+            spi_speed_hz: 1_000_000,
+        };
+        let mut hw = Config::default().display.hardware;
+        hw.spi_devices.push(sd.clone());
+        assert_eq!(
+            hw.check_pins().map_err(|e| e.to_string()),
+            Err("cs_pin and spi_devices \"sd\" cs_pin are both GPIO 8".to_string())
+        );
+        // A device on SPI1 reserves that bus's lines too
+        hw.spi_devices[0] = SpiDeviceConfig {
+            spi_bus: Some(1),
+            cs_pin: 16,
+            ..sd
+        };
+        assert_eq!(hw.check_pins(), Ok(()));
+        hw.busy_pin = 21;
+        assert!(hw.check_pins().is_err());
+    }
+
+    #[test]
+    fn test_time_format() {
+        use chrono::TimeZone;
+        let t = chrono::Utc.with_ymd_and_hms(2025, 7, 23, 15, 5, 0).unwrap();
+        assert_eq!(t.format(TimeFormat::H12.clock()).to_string(), "3:05PM");
+        assert_eq!(t.format(TimeFormat::H24.clock()).to_string(), "15:05");
+        assert_eq!(
+            t.format(TimeFormat::H24.date_time()).to_string(),
+            "7/23 15:05"
+        );
+        #[derive(Deserialize)]
+        struct Wrapper {
+            f: TimeFormat,
+        }
+        let parsed: Wrapper = toml::from_str("f = \"24h\"").unwrap();
+        assert_eq!(parsed.f, TimeFormat::H24);
+    }
+
+    #[test]
+    fn test_datum_parse_and_api_code() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            d: Datum,
+        }
+        let parsed: Wrapper = toml::from_str("d = \"NAVD88\"").unwrap();
+        assert_eq!(parsed.d, Datum::Navd88);
+        assert_eq!(parsed.d.api_code(), "NAVD");
+        assert_eq!(parsed.d.label(), "NAVD88");
+        assert_eq!(Config::default().station.datum, Datum::Mllw);
+    }
+
+    #[test]
+    fn test_rotation_parse() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            r: Rotation,
+        }
+        let parsed: Wrapper = toml::from_str("r = 270").unwrap();
+        assert_eq!(parsed.r, Rotation::Deg270);
+        assert_eq!(parsed.r.drawing_size(400, 300), (300, 400));
+        assert_eq!(Rotation::Deg180.drawing_size(400, 300), (400, 300));
+        let err = toml::from_str::<Wrapper>("r = 45").err().unwrap();
+        assert!(err.to_string().contains("0, 90, 180 or 270"), "{err}");
+        assert!(toml::to_string(&Config::default())
+            .unwrap()
+            .contains("rotation = 0"));
+    }
+
+    #[test]
+    fn test_reference_level_parse() {
+        let config: Config = toml::from_str(
+            r#"
+            [station]
+            id = "8418150"
+            name = "Portland, ME"
+            msl_offset = 4.9
+            show_msl = false
+            [station.reference]
+            name = "intake"
+            elevation_ft = 1.5
+            alert_below = true
+            [display]
+            time_window_hours = 12
+            cache_ttl_minutes = 30
+            width = 400
+            height = 300
+            font_height = 20
+            [display.hardware]
+            dc_pin = 25
+            rst_pin = 17
+            busy_pin = 24
+            "#,
+        )
+        .unwrap();
+        let reference = config.station.reference.unwrap();
+        assert_eq!(reference.name, "intake");
+        assert_eq!(reference.elevation_ft, 1.5);
+        assert!(reference.alert_below);
+        assert_eq!(reference.alert_lead_minutes, 60);
+    }
+
+    #[test]
+    fn test_station_timezone() {
+        use chrono::TimeZone;
+        let mut station = Config::default().station;
+        station.timezone = Some("America/New_York".parse().unwrap());
+
+        let t = Utc.with_ymd_and_hms(2025, 7, 23, 19, 5, 0).unwrap();
+        assert_eq!(station.local_time(t).format("%H:%M").to_string(), "15:05");
+        let naive = NaiveDateTime::parse_from_str("2025-07-23 15:05", "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(station.from_local(naive), Some(t));
+
+        // Clocks going forward skip 2:00-2:59, going back repeat 1:00-1:59
+        let local = |text| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(station.from_local(local("2025-03-09 02:30")), None);
+        let first = Utc.with_ymd_and_hms(2025, 11, 2, 5, 30, 0).unwrap();
+        let second = Utc.with_ymd_and_hms(2025, 11, 2, 6, 30, 0).unwrap();
+        let repeated = local("2025-11-02 01:30");
+        assert_eq!(station.from_local(repeated), Some(first));
+        assert_eq!(
+            station.from_local_after(repeated, Some(first - chrono::Duration::minutes(6))),
+            Some(first)
+        );
+        assert_eq!(
+            station.from_local_after(repeated, Some(first)),
+            Some(second)
+        );
+        assert_eq!(
+            station.from_local_after(repeated, Some(second)),
+            Some(second)
+        );
+
+        #[derive(Deserialize)]
+        struct Wrapper {
+            tz: Tz,
+        }
+        let parsed: Wrapper = toml::from_str("tz = \"Europe/London\"").unwrap();
+        assert_eq!(parsed.tz, chrono_tz::Europe::London);
+    }
+
+    #[test]
+    fn test_additional_station_cache_paths() {
+        let mut config = Config::default();
+        let mut boothbay = config.station.clone();
+        boothbay.id = "8414612".to_string();
+        config.stations.push(boothbay);
+
+        assert_eq!(config.all_stations().len(), 2);
+        assert_eq!(
+            config.cache_path_for("8418150", ProviderKind::Noaa),
+            PathBuf::from("/tmp/tide_cache_8418150.bin")
+        );
+        assert_eq!(
+            config.cache_path_for("8414612", ProviderKind::Noaa),
+            PathBuf::from("/tmp/tide_cache_8414612.bin")
+        );
+        assert_eq!(
+            config.cache_path_for("8414612", ProviderKind::File),
+            PathBuf::from("/tmp/tide_cache_8414612_file.bin")
+        );
+        config.display.cache_path = PathBuf::from("/var/cache/tide-tracker/tides");
+        assert_eq!(
+            config.cache_path_for("8418150", ProviderKind::Noaa),
+            PathBuf::from("/var/cache/tide-tracker/tides_8418150")
+        );
+    }
+
+    #[test]
+    fn test_harmonics_from_shared_xtide_file() {
+        let mut config = Config::default();
+        assert_eq!(config.harmonics_for(&config.station), None);
+
+        let file = PathBuf::from("/usr/share/xtide/harmonics.txt");
+        config.fallback.harmonics_file = Some(file.clone());
+        let shared = config.harmonics_for(&config.station).unwrap();
+        assert_eq!(shared.xtide_file.as_ref(), Some(&file));
+        assert_eq!(shared.xtide_station.as_deref(), Some("Portland, ME"));
+
+        // An explicit XTide name picks up the shared file
+        config.station.harmonics = Some(HarmonicsConfig {
+            xtide_station: Some("Portland, Casco Bay".to_string()),
+            ..HarmonicsConfig::default()
+        });
+        let named = config.harmonics_for(&config.station).unwrap();
+        assert_eq!(named.xtide_file.as_ref(), Some(&file));
+        assert_eq!(named.xtide_station.as_deref(), Some("Portland, Casco Bay"));
+
+        // Station-specific constants aren't mixed with the shared file
+        config.station.harmonics = Some(HarmonicsConfig {
+            mean_level_ft: Some(5.0),
+            ..HarmonicsConfig::default()
+        });
+        let own = config.harmonics_for(&config.station).unwrap();
+        assert_eq!(own, config.station.harmonics.clone().unwrap());
+    }
+
+    #[test]
+    fn test_fallback_model_constants() {
+        let config: Config = toml::from_str(
+            r#"
+            [station]
+            id = "9414290"
+            name = "San Francisco, CA"
+            msl_offset = 3.1
+            show_msl = false
+
+            [display]
+            time_window_hours = 12
+            cache_ttl_minutes = 30
+            width = 400
+            height = 300
+            font_height = 20
+            [display.hardware]
+            dc_pin = 25
+            rst_pin = 17
+            busy_pin = 24
+
+            [fallback]
+            mean_level_ft = 3.1
+            m2_amplitude_ft = 1.8
+
+            [[stations]]
+            id = "8418150"
+            name = "Portland, ME"
+            msl_offset = 4.9
+            show_msl = false
+            model = { lunitidal_offset_hours = 11.2 }
+            "#,
+        )
+        .unwrap();
+        let shared = config.model_for(&config.station);
+        assert_eq!(shared.mean_level_ft, 3.1);
+        assert_eq!(shared.m2_amplitude_ft, 1.8);
+        assert_eq!(
+            shared.s2_amplitude_ft,
+            ModelConstants::default().s2_amplitude_ft
+        );
+        let own = config.model_for(&config.stations[0]);
+        assert_eq!(own.lunitidal_offset_hours, 11.2);
+        assert_eq!(own.mean_level_ft, ModelConstants::default().mean_level_ft);
+    }
+
     #[test]
     fn test_load_nonexistent_file() {
         let config = Config::load_from_path("/nonexistent/path");
         // Should fallback to default
         assert_eq!(config.station.id, "8418150");
     }
+
+    #[test]
+    fn test_search_paths() {
+        let os = |s: &str| Some(OsString::from(s));
+        let paths = |xdg, home| -> Vec<String> {
+            search_paths_in(xdg, home)
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect()
+        };
+        assert_eq!(
+            paths(os("/xdg"), os("/home/pi")),
+            [
+                "tide-config.toml",
+                "/xdg/tide-tracker/tide-config.toml",
+                "/etc/tide-tracker/tide-config.toml"
+            ]
+        );
+        // Unset or relative XDG_CONFIG_HOME means ~/.config
+        assert_eq!(
+            paths(os("relative"), os("/home/pi"))[1],
+            "/home/pi/.config/tide-tracker/tide-config.toml"
+        );
+        assert_eq!(
+            paths(None, None),
+            ["tide-config.toml", "/etc/tide-tracker/tide-config.toml"]
+        );
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        assert_eq!(
+            env_override_key("TIDE_DC_PIN").as_deref(),
+            Some("display.hardware.dc_pin")
+        );
+        assert_eq!(
+            env_override_key("TIDE__DISPLAY__TIME_FORMAT").as_deref(),
+            Some("display.time_format")
+        );
+        assert_eq!(env_override_key("TIDE_TRACKER_CONFIG"), None);
+        assert_eq!(env_override_key("TIDE__DISPLAY__"), None);
+        assert_eq!(env_override_key("PATH"), None);
+
+        // No [display] at all: it starts from the defaults
+        let mut table: toml::Table = r#"
+            [station]
+            id = "8418150"
+            name = "Portland, ME"
+            msl_offset = 4.9
+            show_msl = false
+        "#
+        .parse()
+        .unwrap();
+        let overrides: Vec<(String, String, String)> = [
+            ("TIDE_STATION_ID", "8443970"),
+            ("TIDE_DC_PIN", "22"),
+            ("TIDE__DISPLAY__USE_RED", "false"),
+            ("TIDE__STATION__TIMEZONE", "America/New_York"),
+            ("TIDE__STATION__PROVIDERS", r#"["noaa", "model"]"#),
+        ]
+        .into_iter()
+        .map(|(var, raw)| (env_override_key(var).unwrap(), var.into(), raw.into()))
+        .collect();
+        apply_overrides(&mut table, &overrides);
+
+        let config: Config = toml::Value::Table(table).try_into().unwrap();
+        assert_eq!(config.station.id, "8443970");
+        assert_eq!(config.station.name, "Portland, ME");
+        assert_eq!(config.display.hardware.dc_pin, 22);
+        assert!(!config.display.use_red);
+        assert_eq!(config.station.timezone, Some(chrono_tz::America::New_York));
+        assert_eq!(
+            config.station.providers,
+            [
+                crate::provider::ProviderKind::Noaa,
+                crate::provider::ProviderKind::Model
+            ]
+        );
+    }
 }
@@ -0,0 +1,223 @@
+//! # Configuration Checks
+//!
+//! Backs the `config` subcommands:
+//!
+//! ```text
+//! tide-tracker config validate [--online]   # check the configuration file
+//! tide-tracker config init [FILE] [--force] # write the commented default
+//! ```
+//!
+//! [`check`] reports what `Config::load` would otherwise pass over quietly:
+//! keys serde ignores (usually typos, which silently fall back to the
+//! default), pins the hardware setup would reject, and alerts enabled with
+//! nowhere to send them. Unknown keys are found by reading the file into a
+//! [`Config`] and writing it back out: whatever the file has that the round
+//! trip lost was never read.
+//!
+//! NOAA needs no API key, so the only credentials to check are the alert
+//! sinks'. With `--online`, [`station_exists`] also asks NOAA's metadata API
+//! about every station ID.
+
+use crate::config::Config;
+#[cfg(feature = "net")]
+use crate::config::StationConfig;
+use crate::provider::ProviderKind;
+#[cfg(feature = "net")]
+use crate::tide_data::TideError;
+use std::fmt;
+
+/// The annotated `tide-config.toml` written by `config init`
+pub const DEFAULT_CONFIG_TOML: &str = include_str!("../tide-config.toml");
+
+/// How bad a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The configuration can't be used as written
+    Error,
+    /// Works, but probably not as intended
+    Warning,
+}
+
+/// One problem with the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+/// Check configuration file contents without touching the network
+///
+/// Returns the parsed configuration when there is one, for further checks.
+pub fn check(contents: &str) -> (Option<Config>, Vec<Finding>) {
+    let table = match contents.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(e) => return (None, vec![Finding::error(e.to_string().trim_end())]),
+    };
+    let config = match toml::Value::Table(table.clone()).try_into::<Config>() {
+        Ok(config) => config,
+        Err(e) => return (None, vec![Finding::error(e.to_string().trim_end())]),
+    };
+
+    let mut findings = Vec::new();
+    if let Ok(toml::Value::Table(read)) = toml::Value::try_from(&config) {
+        let mut unknown = Vec::new();
+        unknown_keys(&table, &read, "", &mut unknown);
+        findings.extend(
+            unknown
+                .into_iter()
+                .map(|key| Finding::warning(format!("unknown key `{}` is ignored", key))),
+        );
+    }
+    if let Err(e) = config.display.hardware.check_pins() {
+        findings.push(Finding::error(e.to_string()));
+    }
+    if config.alerts.enabled && config.alerts.sinks.is_empty() {
+        findings.push(Finding::warning(
+            "alerts are enabled but there is no [[alerts.sinks]] to send them to",
+        ));
+    }
+    for station in config.all_stations() {
+        if station.id.len() != 7 || !station.id.bytes().all(|b| b.is_ascii_digit()) {
+            findings.push(Finding::warning(format!(
+                "station ID \"{}\" ({}) is not a 7-digit NOAA station ID",
+                station.id, station.name
+            )));
+        }
+        if station.providers.contains(&ProviderKind::File) && station.data_file.is_none() {
+            findings.push(Finding::error(format!(
+                "station {} uses the \"file\" provider but sets no data_file",
+                station.name
+            )));
+        }
+    }
+    (Some(config), findings)
+}
+
+/// Collect the keys of `given` missing from `read`, as dotted paths
+///
+/// Arrays of tables (`[[stations]]`, ...) are compared entry by entry.
+fn unknown_keys(given: &toml::Table, read: &toml::Table, prefix: &str, out: &mut Vec<String>) {
+    for (key, value) in given {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match (value, read.get(key)) {
+            (_, None) => out.push(path),
+            (toml::Value::Table(given), Some(toml::Value::Table(read))) => {
+                unknown_keys(given, read, &path, out)
+            }
+            (toml::Value::Array(given), Some(toml::Value::Array(read))) => {
+                for (i, pair) in given.iter().zip(read).enumerate() {
+                    if let (toml::Value::Table(given), toml::Value::Table(read)) = pair {
+                        unknown_keys(given, read, &format!("{}[{}]", path, i), out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Ask NOAA's station metadata API whether `station`'s ID exists
+#[cfg(feature = "net")]
+pub async fn station_exists(station: &StationConfig) -> Result<bool, TideError> {
+    let url = format!(
+        "https://api.tidesandcurrents.noaa.gov/mdapi/prod/webapi/stations/{}.json",
+        station.id
+    );
+    let client = reqwest::Client::builder()
+        .timeout(station.fetch_deadline())
+        .build()?;
+    let response = client.get(&url).send().await?;
+    if !response.status().is_success() {
+        return Ok(false);
+    }
+    let json: serde_json::Value = response.json().await?;
+    Ok(json["stations"].as_array().is_some_and(|s| !s.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_clean() {
+        let (config, findings) = check(DEFAULT_CONFIG_TOML);
+        assert!(config.is_some());
+        assert!(findings.is_empty(), "{:?}", findings);
+    }
+
+    #[test]
+    fn test_unknown_keys_are_reported() {
+        let contents = format!(
+            "colour = \"red\"\n{}\n{}",
+            DEFAULT_CONFIG_TOML.replace("[display]\n", "[display]\nlayuot = \"table\"\n"),
+            r#"
+            [[stations]]
+            id = "8443970"
+            nmae = "Boston, MA"
+            name = "Boston, MA"
+            msl_offset = 5.0
+            show_msl = false
+            "#
+        );
+        let (config, findings) = check(&contents);
+        assert!(config.is_some(), "{:?}", findings);
+        let messages: Vec<String> = findings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "warning: unknown key `colour` is ignored",
+                "warning: unknown key `display.layuot` is ignored",
+                "warning: unknown key `stations[0].nmae` is ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bad_values_are_errors() {
+        let (config, findings) = check("[station]\nid = 8418150\n");
+        assert!(config.is_none());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Error);
+
+        let contents = DEFAULT_CONFIG_TOML.replace("dc_pin = 25", "dc_pin = 24");
+        let (_, findings) = check(&contents);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(findings[0].message.contains("busy_pin"), "{}", findings[0]);
+
+        let contents =
+            DEFAULT_CONFIG_TOML.replace("# providers = [\"noaa\"]", "providers = [\"file\"]");
+        let (_, findings) = check(&contents);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.contains("data_file"), "{}", findings[0]);
+    }
+}
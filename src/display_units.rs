@@ -0,0 +1,86 @@
+//! # Display Units
+//!
+//! Heights are stored in the station's datum (or relative to a custom
+//! reference). With `show_msl` they are shown relative to mean sea level
+//! instead, signed so that "+2" and "-1.5" read as above and below it. Both
+//! the ASCII and the e-ink renderer take the offset from
+//! [`SeriesMeta::msl_offset`](crate::layout::SeriesMeta::msl_offset), which is
+//! `None` when heights are shown as stored.
+//!
+//! ```
+//! use tide_clock_lib::display_units::{format_display_height, tide_to_display};
+//!
+//! assert_eq!(tide_to_display(6.9, Some(4.9)), 2.0);
+//! assert_eq!(format_display_height(6.9, Some(4.9)), "+2");
+//! assert_eq!(format_display_height(3.4, Some(4.9)), "-1.5");
+//! assert_eq!(format_display_height(6.5, None), "6.5");
+//! ```
+
+/// Height as shown, from a stored height
+pub fn tide_to_display(tide_ft: f32, msl_offset: Option<f32>) -> f32 {
+    match msl_offset {
+        Some(offset) => tide_ft - offset,
+        None => tide_ft,
+    }
+}
+
+/// Stored height, from a height as shown
+pub fn display_to_tide(display_ft: f32, msl_offset: Option<f32>) -> f32 {
+    match msl_offset {
+        Some(offset) => display_ft + offset,
+        None => display_ft,
+    }
+}
+
+/// Axis label for a stored height: whole feet without decimals, relative to
+/// MSL with a sign (and " 0 " for sea level itself)
+pub fn format_display_height(tide_ft: f32, msl_offset: Option<f32>) -> String {
+    format_display_value(tide_to_display(tide_ft, msl_offset), msl_offset.is_some())
+}
+
+/// Axis label for a height already converted with [`tide_to_display`]
+///
+/// `signed` marks MSL-relative values, which get a "+" above sea level.
+pub fn format_display_value(display_ft: f32, signed: bool) -> String {
+    let decimals = if display_ft.fract() == 0.0 { 0 } else { 1 };
+    format_axis_value(display_ft, signed, decimals)
+}
+
+/// Like [`format_display_value`], with a fixed number of decimals so that
+/// every tick on an axis reads alike
+pub fn format_axis_value(display_ft: f32, signed: bool, decimals: usize) -> String {
+    let digits = format!("{:.*}", decimals, display_ft);
+    let unsigned = digits.trim_start_matches('-');
+    if !signed {
+        digits
+    } else if unsigned.chars().all(|c| c == '0' || c == '.') {
+        format!(" {} ", unsigned)
+    } else if display_ft > 0.0 {
+        format!("+{}", digits)
+    } else {
+        digits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_formats() {
+        for tide in [-1.5f32, 0.0, 4.9, 9.25] {
+            for offset in [None, Some(4.9)] {
+                let shown = tide_to_display(tide, offset);
+                assert!((display_to_tide(shown, offset) - tide).abs() < 1e-6);
+            }
+        }
+        assert_eq!(format_display_height(4.9, Some(4.9)), " 0 ");
+        assert_eq!(format_display_height(0.9, Some(4.9)), "-4");
+        assert_eq!(format_display_height(4.0, None), "4");
+        assert_eq!(format_display_value(3.0, true), "+3");
+        assert_eq!(format_display_value(-0.5, false), "-0.5");
+        assert_eq!(format_axis_value(4.000_001, true, 0), "+4");
+        assert_eq!(format_axis_value(3.0, true, 1), "+3.0");
+        assert_eq!(format_axis_value(-0.0, true, 1), " 0.0 ");
+    }
+}
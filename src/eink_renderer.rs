@@ -5,21 +5,95 @@
 //! the Waveshare C examples for maximum reliability.
 
 // --- Required imports ---
-use crate::epd4in2b_v2::Epd4in2bV2;
-use crate::TideSeries;
-use embedded_graphics::mono_font::{ascii::FONT_10X20, MonoTextStyle};
+use crate::config::{Config, DisplayConfig};
+use crate::display_units;
+#[cfg(feature = "eink")]
+use crate::epd::EpdDriver;
+use crate::epd4in2b_v2::{DisplayBuffer, DrawPrimitives};
+use crate::layout::{Layout, LayoutContext, SeriesMeta};
+use crate::lunar::LunarEphemeris;
+use crate::renderer::{ChartScale, Frame, TideRenderer};
+use crate::theme::FillPattern;
+use crate::{TideSeries, Trend};
+use chrono::{DateTime, Utc};
+use embedded_graphics::mono_font::{
+    ascii::{FONT_10X20, FONT_6X10, FONT_8X13},
+    MonoTextStyle, MonoTextStyleBuilder,
+};
 use embedded_graphics::pixelcolor::BinaryColor;
 use embedded_graphics::prelude::*;
-use embedded_graphics::primitives::{Circle, Line, PrimitiveStyle};
-use embedded_graphics::text::Text;
+use embedded_graphics::primitives::{Polyline, PrimitiveStyle};
+use embedded_graphics::text::{Baseline, Text};
+use serde::{Deserialize, Serialize};
 
 // ...existing code...
 
-/// Chart renderer for the Waveshare 4.2" e-ink display using embedded-graphics
+/// Spacing and tick settings of the chart, in pixels
+///
+/// Loaded from `[display.chart]`; any field left out keeps its default, which
+/// suits the 400x300 panel.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChartStyle {
+    /// Space around the whole chart
+    pub margin: u32,
+    /// Space between the chart area and the plot's axes, on every side
+    pub plot_inset: u32,
+    /// Intervals the height axis is divided into (one more labeled tick)
+    pub y_ticks: u32,
+    /// Length of the height axis ticks
+    pub tick_length: u32,
+    /// Distance left of the height axis where its numbers start
+    pub y_label_offset: u32,
+    /// Distance left of the height axis where "Hi" and "Lo" start
+    pub hi_lo_offset: u32,
+    /// Distance below the time axis to the baseline of its labels
+    pub x_label_offset: u32,
+    /// Hours between clock-time ticks on the time axis, aligned to the
+    /// station's clock (3 gives 3PM, 6PM, ...); 0 turns them off. Wider
+    /// windows space them further apart, see [`tick_hours`]
+    pub x_tick_hours: u32,
+    /// Diameter of the moon phase glyph
+    pub moon_diameter: u32,
+}
+
+impl Default for ChartStyle {
+    fn default() -> Self {
+        Self {
+            margin: 20,
+            plot_inset: 20,
+            y_ticks: 4,
+            tick_length: 5,
+            y_label_offset: 32,
+            hi_lo_offset: 40,
+            x_label_offset: 16,
+            x_tick_hours: 3,
+            moon_diameter: 24,
+        }
+    }
+}
+
+impl From<&DisplayConfig> for ChartStyle {
+    fn from(display: &DisplayConfig) -> Self {
+        display.chart.clone()
+    }
+}
+
+/// Hours between time axis ticks on a chart `span_mins` wide
+///
+/// `x_tick_hours` as configured up to a 24 hour chart (±12 h), twice that up
+/// to 48 hours and four times beyond, so a 72 hour chart gets about as many
+/// ticks as the standard one instead of three times as many.
+pub fn tick_hours(x_tick_hours: u32, span_mins: i64) -> u32 {
+    let half_hours = (span_mins / 120).max(1) as u32;
+    x_tick_hours * half_hours.div_ceil(12).next_power_of_two()
+}
+
+/// Chart renderer for the Waveshare e-ink displays using embedded-graphics
 pub struct EinkTideRenderer {
     pub width: u32,
     pub height: u32,
-    pub margin: u32,
+    pub style: ChartStyle,
 }
 
 impl Default for EinkTideRenderer {
@@ -31,176 +105,775 @@ impl Default for EinkTideRenderer {
 impl EinkTideRenderer {
     /// Create a new renderer for 400x300 e-ink panel
     pub fn new() -> Self {
+        Self::with_style(ChartStyle::default())
+    }
+
+    /// Create a renderer for a 400x300 panel with custom spacing
+    pub fn with_style(style: ChartStyle) -> Self {
+        Self::with_size(400, 300, style)
+    }
+
+    /// Create a renderer for a `width` x `height` panel with custom spacing
+    pub fn with_size(width: u32, height: u32, style: ChartStyle) -> Self {
         Self {
-            width: 400,
-            height: 300,
-            margin: 20,
+            width,
+            height,
+            style,
         }
     }
 
+    /// Create a renderer sized for the panel `epd` drives
+    #[cfg(feature = "eink")]
+    pub fn for_driver(epd: &impl EpdDriver, style: ChartStyle) -> Self {
+        Self::with_size(epd.width(), epd.height(), style)
+    }
+
     /// Render a complete tide chart to the e-ink display
-    pub fn render_chart<SPI, CS, DC, RST, BUSY, DT>(
+    ///
+    /// Convenience wrapper around the [`Layout`] implementation with the
+    /// configured station and theme at `now`, at the size of the panel `epd`
+    /// drives.
+    #[cfg(feature = "eink")]
+    pub fn render_chart<DT>(
         &self,
-        _epd: &mut Epd4in2bV2<SPI, CS, DC, RST, BUSY>,
+        epd: &impl EpdDriver,
         draw_target: &mut DT,
         tide: &TideSeries,
+        config: &Config,
+        now: DateTime<Utc>,
     ) where
         DT: DrawTarget<Color = BinaryColor>,
     {
+        let meta = SeriesMeta::from_config(config, now);
+        let ctx = LayoutContext {
+            series: tide,
+            meta: &meta,
+            theme: &config.theme,
+            size: epd.size(),
+            overlays: &[],
+        };
+        self.draw(&ctx, draw_target).ok();
+    }
+
+    /// Draw the height at "now" in extra-large digits at the top left, e.g.
+    /// "6.4 ft" (or "+1.5 ft" relative to MSL), with the trend arrow after it
+    ///
+    /// Returns the x coordinate just right of what was drawn.
+    pub fn draw_current_height<D>(
+        &self,
+        ctx: &LayoutContext<'_>,
+        draw_target: &mut D,
+    ) -> Result<i32, D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let Some(height) = ctx.series.height_now() else {
+            return Ok(self.style.margin as i32);
+        };
+        let text = ctx.meta.format_height(height);
+        let origin = Point::new(self.style.margin as i32, 2);
+        let color = ctx.theme.text_color.binary();
+        crate::large_print::draw_scaled(draw_target, &text, origin, 2, color)?;
+
+        let arrow_x = origin.x as u32 + text.len() as u32 * 20 + 8;
+        crate::large_print::draw_arrow(
+            draw_target,
+            arrow_x,
+            12,
+            20,
+            ctx.series.trend(),
+            ctx.theme.now_marker_color.binary(),
+        )?;
+        Ok(arrow_x as i32 + 20)
+    }
+}
+
+impl TideRenderer for EinkTideRenderer {
+    /// The chart at this renderer's size, red elements on the red plane
+    /// unless `use_red` is off
+    fn render(&self, series: &TideSeries, config: &Config, now: DateTime<Utc>) -> Frame {
+        let meta = SeriesMeta::from_config(config, now);
+        let ctx = LayoutContext {
+            series,
+            meta: &meta,
+            theme: &config.theme,
+            size: Size::new(self.width, self.height),
+            overlays: &[],
+        };
+        let mut buffer = DisplayBuffer::new(self.width, self.height);
+        if config.display.use_red {
+            crate::layout::draw_tricolor(self, &ctx, &mut buffer);
+        } else {
+            let Ok(()) = self.draw(&ctx, &mut buffer);
+        }
+        Frame::Bitmap(buffer)
+    }
+}
+
+impl<D> Layout<D> for EinkTideRenderer
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    fn name(&self) -> &'static str {
+        "chart"
+    }
+
+    fn draw(&self, ctx: &LayoutContext<'_>, draw_target: &mut D) -> Result<(), D::Error> {
+        let tide = ctx.series;
+        let theme = ctx.theme;
+        let style = &self.style;
+
         // Chart and plot dimensions
-        let chart_x = self.margin;
-        let chart_y = self.margin;
-        let chart_width = self.width - 2 * self.margin;
-        let chart_height = self.height - 2 * self.margin;
-        let plot_margin = 20;
+        let chart_x = style.margin;
+        let chart_y = style.margin;
+        let chart_width = ctx.size.width.saturating_sub(2 * style.margin);
+        let chart_height = ctx.size.height.saturating_sub(2 * style.margin);
+        let plot_margin = style.plot_inset;
+        // Current height in 2x FONT_10X20, or the countdown in FONT_10X20,
+        // plus spacing, taken from the top
+        let header_height = if theme.current_height {
+            44
+        } else if theme.countdown_header {
+            22
+        } else {
+            0
+        };
         let plot_x = chart_x + plot_margin;
-        let plot_y = chart_y + plot_margin;
-        let plot_width = chart_width - 2 * plot_margin;
-        let plot_height = chart_height - 2 * plot_margin;
+        let plot_y = chart_y + plot_margin + header_height;
+        let plot_width = chart_width.saturating_sub(2 * plot_margin);
+        // Two lines of FONT_6X10 plus spacing, taken from the bottom
+        let footer_height = if theme.extremes_footer { 24 } else { 0 };
+        let plot_height = chart_height
+            .saturating_sub(2 * plot_margin + header_height + footer_height)
+            .max(1);
+
+        // Header: current height and trend, then the countdown to the next
+        // high or low (smaller when sharing the line)
+        let mut header_x = style.margin as i32;
+        if theme.current_height {
+            header_x = self.draw_current_height(ctx, draw_target)? + 12;
+        }
+        if theme.countdown_header {
+            let now = ctx.meta.generated_at;
+            if let Some(next) = tide.next_extreme(now) {
+                let (font, y) = if theme.current_height {
+                    (&FONT_8X13, 15)
+                } else {
+                    (&FONT_10X20, 2)
+                };
+                Text::with_baseline(
+                    &next.countdown(ctx.meta),
+                    Point::new(header_x, y),
+                    MonoTextStyle::new(font, theme.text_color.binary()),
+                    Baseline::Top,
+                )
+                .draw(draw_target)?;
+            }
+        }
 
         // Draw axes
-        let axis_style = PrimitiveStyle::with_stroke(BinaryColor::On, 2);
-        let x_axis = Line::new(
+        let (axis_color, axis_stroke) = (theme.axis_color.binary(), theme.axis_stroke);
+        draw_target.draw_line(
             Point::new(plot_x as i32, (plot_y + plot_height) as i32),
             Point::new((plot_x + plot_width) as i32, (plot_y + plot_height) as i32),
-        );
-        let y_axis = Line::new(
+            axis_stroke,
+            axis_color,
+        )?;
+        draw_target.draw_line(
             Point::new(plot_x as i32, plot_y as i32),
             Point::new(plot_x as i32, (plot_y + plot_height) as i32),
-        );
-        x_axis.into_styled(axis_style).draw(draw_target).ok();
-        y_axis.into_styled(axis_style).draw(draw_target).ok();
+            axis_stroke,
+            axis_color,
+        )?;
 
         // Draw Y-axis ticks and labels
-        let num_ticks = 4;
+        let num_ticks = style.y_ticks.max(1);
         let samples = &tide.samples;
-        let (min_height, max_height) = if !samples.is_empty() {
-            let min = samples
-                .iter()
-                .map(|s| s.tide_ft)
-                .fold(f32::INFINITY, f32::min);
-            let max = samples
-                .iter()
-                .map(|s| s.tide_ft)
-                .fold(f32::NEG_INFINITY, f32::max);
-            (min, max)
-        } else {
-            (0.0, 10.0)
-        };
-        let height_range = max_height - min_height;
-        let label_style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+        // Scale to fit every curve, including multi-station overlays
+        let scale = ChartScale::new(tide, ctx.overlays.iter().map(|o| o.series));
+        let label_style = MonoTextStyle::new(theme.label_font.font(), theme.axis_color.binary());
         for i in 0..=num_ticks {
             let tick_y = plot_y + (i * plot_height / num_ticks);
-            let tick = Line::new(
-                Point::new((plot_x - 5) as i32, tick_y as i32),
+            draw_target.draw_line(
+                Point::new(plot_x as i32 - style.tick_length as i32, tick_y as i32),
                 Point::new(plot_x as i32, tick_y as i32),
+                axis_stroke,
+                axis_color,
+            )?;
+            let tick_height = scale.height_at(1.0 - i as f32 / num_ticks as f32);
+            let msl_offset = ctx.meta.msl_offset;
+            let label = display_units::format_display_value(
+                display_units::tide_to_display(tick_height, msl_offset).round(),
+                msl_offset.is_some(),
             );
-            tick.into_styled(axis_style).draw(draw_target).ok();
-            let tick_height = max_height - (i as f32 / num_ticks as f32) * height_range;
-            let label = format!("{:.0}", tick_height);
-            // Move Y labels closer to axis (from -40 to -32), and align vertically with tick (from -6 to +7)
+            // Aligned vertically with the tick
             Text::new(
                 &label,
-                Point::new((plot_x - 32) as i32, (tick_y + 7) as i32),
+                Point::new(
+                    plot_x as i32 - style.y_label_offset as i32,
+                    (tick_y + 7) as i32,
+                ),
                 label_style,
             )
-            .draw(draw_target)
-            .ok();
+            .draw(draw_target)?;
         }
         // Y-axis labels "Hi" and "Lo"
         Text::new(
             "Hi",
-            Point::new((plot_x - 40) as i32, (plot_y + 30) as i32),
+            Point::new(
+                plot_x as i32 - style.hi_lo_offset as i32,
+                (plot_y + 30) as i32,
+            ),
             label_style,
         )
-        .draw(draw_target)
-        .ok();
+        .draw(draw_target)?;
         // Move "Lo" down to be between the lowest two Y labels (no overlap)
-        let lo_y = plot_y + plot_height - 18;
+        let lo_y = (plot_y + plot_height).saturating_sub(18);
         Text::new(
             "Lo",
-            Point::new((plot_x - 40) as i32, lo_y as i32),
+            Point::new(plot_x as i32 - style.hi_lo_offset as i32, lo_y as i32),
             label_style,
         )
-        .draw(draw_target)
-        .ok();
+        .draw(draw_target)?;
+        // Datum the heights are referenced to, just above the top of the Y-axis
+        let axis_label = ctx.meta.axis_label();
+        Text::with_baseline(
+            &axis_label,
+            Point::new((plot_x + 4) as i32, plot_y as i32 - 10),
+            MonoTextStyle::new(&FONT_6X10, theme.axis_color.binary()),
+            Baseline::Top,
+        )
+        .draw(draw_target)?;
+
+        // Spring/neap tag on the same line, e.g. "SPRING 92%"
+        if let Some(fraction) = ctx.meta.spring_neap {
+            let x = plot_x as usize + 4 + (axis_label.len() + 2) * 6;
+            Text::with_baseline(
+                &crate::lunar::range_label(fraction),
+                Point::new(x as i32, plot_y as i32 - 10),
+                MonoTextStyle::new(&FONT_6X10, theme.text_color.binary()),
+                Baseline::Top,
+            )
+            .draw(draw_target)?;
+        }
+
+        // Clock-time ticks along the time axis, labeled below them where
+        // there is room for the label
+        let axis_y = (plot_y + plot_height) as i32;
+        let mut label_y = plot_y + plot_height + style.x_label_offset;
+        if !samples.is_empty() {
+            let (from, to) = (scale.first_mins as i64, scale.last_mins as i64);
+            let ticks = ctx
+                .meta
+                .hour_ticks(from, to, tick_hours(style.x_tick_hours, to - from));
+            let tick_font = MonoTextStyle::new(&FONT_6X10, theme.axis_color.binary());
+            let text_y = axis_y + style.tick_length as i32 + 2;
+            let mut free_x = plot_x as i32;
+            for (mins, label) in &ticks {
+                let progress = scale.time_fraction(*mins);
+                let x = plot_x as i32 + (progress * plot_width as f32) as i32;
+                draw_target.draw_line(
+                    Point::new(x, axis_y),
+                    Point::new(x, axis_y + style.tick_length as i32),
+                    axis_stroke,
+                    axis_color,
+                )?;
+                let left = x - label.len() as i32 * 3;
+                let right = left + label.len() as i32 * 6;
+                if left >= free_x && right <= (plot_x + plot_width) as i32 {
+                    Text::with_baseline(label, Point::new(left, text_y), tick_font, Baseline::Top)
+                        .draw(draw_target)?;
+                    free_x = right + 6;
+                }
+            }
+            // The relative labels move down below the clock times
+            if !ticks.is_empty() {
+                label_y = label_y.max((text_y + 10 + 15) as u32);
+            }
+        }
 
-        // Draw X-axis time labels
-        let label_y = plot_y + plot_height + 16; // move labels further below the axis
-        if label_y + 12 < self.height {
+        // "Now" on the time axis: at the sample closest to it, the middle of
+        // the plot only for a complete, symmetric series
+        let now_mins = tide.now_index().map_or(0, |i| tide.samples[i].mins_rel);
+        let now_x = plot_x + (scale.time_fraction(now_mins as f32) * plot_width as f32) as u32;
+
+        // Draw X-axis time labels, from the window the samples cover
+        if label_y + 12 < ctx.size.height {
+            let (start_label, end_label) = tide.window_labels();
+            let char_width = theme.label_font.font().character_size.width;
             Text::new(
-                "-12h",
+                &start_label,
                 Point::new(plot_x as i32, label_y as i32),
                 label_style,
             )
-            .draw(draw_target)
-            .ok();
+            .draw(draw_target)?;
             Text::new(
                 "Now",
-                Point::new((plot_x + plot_width / 2 - 15) as i32, label_y as i32),
+                Point::new(now_x as i32 - 15, label_y as i32),
                 label_style,
             )
-            .draw(draw_target)
-            .ok();
+            .draw(draw_target)?;
             Text::new(
-                "+12h",
-                Point::new((plot_x + plot_width - 40) as i32, label_y as i32),
+                &end_label,
+                Point::new(
+                    (plot_x + plot_width - end_label.len() as u32 * char_width) as i32,
+                    label_y as i32,
+                ),
                 label_style,
             )
-            .draw(draw_target)
-            .ok();
+            .draw(draw_target)?;
+        }
+
+        // Footer: today's remaining and tomorrow's first highs/lows
+        if theme.extremes_footer {
+            let footer_font = MonoTextStyle::new(&FONT_6X10, theme.axis_color.binary());
+            let mut y = ctx.size.height as i32 - 23;
+            for line in crate::extremes::footer_lines(tide, ctx.meta) {
+                Text::with_baseline(
+                    &line,
+                    Point::new(plot_x as i32, y),
+                    footer_font,
+                    Baseline::Top,
+                )
+                .draw(draw_target)?;
+                y += 11;
+            }
         }
 
-        // Draw 'now' marker (dotted vertical line)
-        let center_x = plot_x + plot_width / 2;
-        let marker_style = PrimitiveStyle::with_stroke(BinaryColor::On, 2);
-        let mut y = plot_y;
-        while y < plot_y + plot_height {
-            let end = (y + 4).min(plot_y + plot_height);
-            Line::new(
-                Point::new(center_x as i32, y as i32),
-                Point::new(center_x as i32, end as i32),
+        // Moon phase glyph in the bottom-right corner, age in days beside it
+        if let Some(moon) = &ctx.meta.moon {
+            let diameter = style.moon_diameter;
+            let top_left = Point::new(
+                ctx.size.width as i32 - (style.margin + diameter) as i32,
+                ctx.size.height as i32 - diameter as i32 - 2,
+            );
+            draw_moon_phase(
+                draw_target,
+                top_left,
+                diameter,
+                moon,
+                theme.axis_color.binary(),
+            )?;
+            let age = format!("{:.0}d", moon.age_days);
+            Text::with_baseline(
+                &age,
+                top_left + Point::new(-(age.len() as i32 * 6 + 4), diameter as i32 / 2 - 5),
+                MonoTextStyle::new(&FONT_6X10, theme.axis_color.binary()),
+                Baseline::Top,
             )
-            .into_styled(marker_style)
-            .draw(draw_target)
-            .ok();
-            y += 8;
+            .draw(draw_target)?;
+        }
+
+        // Draw 'now' marker (dashed vertical line, pattern from theme)
+        let marker_color = theme.now_marker_color.binary();
+        if let Some(runs) = theme.now_line.runs() {
+            draw_target.draw_dotted_vline(
+                now_x as i32,
+                plot_y as i32,
+                (plot_y + plot_height) as i32,
+                runs,
+                theme.axis_stroke,
+                marker_color,
+            )?;
+        }
+
+        // Custom reference level (e.g. an intake): dashed horizontal line at 0 ft
+        if ctx.meta.reference.is_some() && scale.min_ft <= 0.0 && scale.max_ft >= 0.0 {
+            let progress = scale.height_fraction(0.0);
+            let y = (plot_y + plot_height - (progress * plot_height as f32) as u32) as i32;
+            let (on, off) = crate::theme::LinePattern::Dashed.runs().unwrap_or((1, 0));
+            let mut x = plot_x;
+            while x < plot_x + plot_width {
+                let end = (x + on).min(plot_x + plot_width);
+                draw_target.draw_line(
+                    Point::new(x as i32, y),
+                    Point::new(end as i32, y),
+                    1,
+                    marker_color,
+                )?;
+                x = end + off;
+            }
         }
 
         // Draw tide data as polyline and highlight 'now' point
         if samples.len() >= 2 {
-            let min_time = samples.iter().map(|s| s.mins_rel).min().unwrap_or(-720);
-            let max_time = samples.iter().map(|s| s.mins_rel).max().unwrap_or(720);
-            let time_range = (max_time - min_time) as f32;
-            let mut prev: Option<Point> = None;
-            for sample in samples {
-                let time_progress = (sample.mins_rel - min_time) as f32 / time_range;
+            let to_screen = |sample: &crate::Sample| {
+                let time_progress = scale.time_fraction(sample.mins_rel as f32);
                 let screen_x = plot_x + (time_progress * plot_width as f32) as u32;
-                let height_progress = (sample.tide_ft - min_height) / height_range;
+                let height_progress = scale.height_fraction(sample.tide_ft);
                 let screen_y = plot_y + plot_height - (height_progress * plot_height as f32) as u32;
-                let pt = Point::new(screen_x as i32, screen_y as i32);
-                // Draw polyline
-                if let Some(prev_pt) = prev {
-                    Line::new(prev_pt, pt)
-                        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
-                        .draw(draw_target)
-                        .ok();
+                Point::new(screen_x as i32, screen_y as i32)
+            };
+            let curve_color = theme.curve_color.binary();
+            let points: Vec<Point> = samples.iter().map(to_screen).collect();
+
+            // Patterned fill under the curve, drawn first so the line stays crisp
+            if theme.curve_fill != FillPattern::None {
+                let bottom = (plot_y + plot_height) as i32;
+                for w in points.windows(2) {
+                    let (a, b) = (w[0], w[1]);
+                    for x in a.x..b.x {
+                        let t = (x - a.x) as f32 / (b.x - a.x) as f32;
+                        let top = a.y + ((b.y - a.y) as f32 * t) as i32;
+                        draw_target.draw_iter(
+                            (top..bottom)
+                                .filter(|&y| theme.curve_fill.covers(x, y))
+                                .map(|y| Pixel(Point::new(x, y), curve_color)),
+                        )?;
+                    }
+                }
+            }
+
+            // Flood warning: once the water is due above the threshold, hatch
+            // it down to a threshold line, labeled below with the first time
+            let flood = ctx.meta.flood_threshold_ft.and_then(|threshold| {
+                let first = tide.first_above(threshold, i16::MAX)?;
+                Some((threshold, first.mins_rel))
+            });
+            let warning_color = theme.warning_color.binary();
+            let mut flood_label = None;
+            if let Some((threshold, first_mins)) = flood {
+                let progress = scale.height_fraction(threshold);
+                let line_y = (plot_y + plot_height) as i32 - (progress * plot_height as f32) as i32;
+                for w in points.windows(2) {
+                    let (a, b) = (w[0], w[1]);
+                    for x in a.x..b.x {
+                        let t = (x - a.x) as f32 / (b.x - a.x) as f32;
+                        let top = a.y + ((b.y - a.y) as f32 * t) as i32;
+                        draw_target.draw_iter(
+                            (top..line_y)
+                                .filter(|&y| FillPattern::Hatch.covers(x, y))
+                                .map(|y| Pixel(Point::new(x, y), warning_color)),
+                        )?;
+                    }
                 }
-                prev = Some(pt);
-                // Draw 'now' marker as red circle
-                if sample.mins_rel.abs() <= 5 {
-                    Circle::new(pt, 8)
-                        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
-                        .draw(draw_target)
-                        .ok();
+                draw_target.draw_line(
+                    Point::new(plot_x as i32, line_y),
+                    Point::new((plot_x + plot_width) as i32, line_y),
+                    1,
+                    warning_color,
+                )?;
+
+                let when = if first_mins <= 0 {
+                    "NOW".to_string()
                 } else {
-                    Circle::new(pt, 3)
-                        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
-                        .draw(draw_target)
-                        .ok();
+                    ctx.meta.clock_at(first_mins as i64)
+                };
+                flood_label = Some((format!("FLOOD RISK {}", when), line_y + 3));
+            }
+
+            // One connected polyline per stretch of real data, so thick
+            // strokes join without gaps on the steep parts of the curve;
+            // stretches the model filled in (see `Sample::modeled`) are
+            // dotted instead, one dot per sample
+            let mut start = 0;
+            while start < points.len() {
+                let modeled = samples[start].modeled;
+                let end = samples[start..]
+                    .iter()
+                    .position(|s| s.modeled != modeled)
+                    .map_or(points.len(), |n| start + n);
+                if modeled {
+                    for &pt in &points[start..end] {
+                        draw_target.fill_circle(pt, theme.curve_stroke + 1, curve_color)?;
+                    }
+                } else {
+                    Polyline::new(&points[start..end])
+                        .into_styled(PrimitiveStyle::with_stroke(curve_color, theme.curve_stroke))
+                        .draw(draw_target)?;
+                }
+                start = end;
+            }
+            if theme.point_diameter > 0 {
+                for &pt in &points {
+                    draw_target.fill_circle(pt, theme.point_diameter, curve_color)?;
+                }
+            }
+
+            // Flood label on a white background so the curve can't hide it
+            if let Some((label, y)) = flood_label {
+                Text::with_baseline(
+                    &label,
+                    Point::new(plot_x as i32 + 4, y),
+                    MonoTextStyleBuilder::new()
+                        .font(&FONT_6X10)
+                        .text_color(warning_color)
+                        .background_color(BinaryColor::Off)
+                        .build(),
+                    Baseline::Top,
+                )
+                .draw(draw_target)?;
+            }
+
+            // 'Now' ring last so it stays on top of the curve, hollowed out
+            // so the curve doesn't run through it
+            let now_point = samples
+                .iter()
+                .filter(|s| s.mins_rel.abs() <= 5)
+                .min_by_key(|s| s.mins_rel.abs());
+            if let Some(sample) = now_point {
+                let center = to_screen(sample);
+                let diameter = theme.now_marker_diameter;
+                draw_target.fill_circle(center, diameter, BinaryColor::Off)?;
+                draw_target.draw_circle(center, diameter, theme.axis_stroke, marker_color)?;
+            }
+
+            // Trend arrow left of the now marker, on the side the curve isn't
+            // (beside the current-height readout instead when that is shown)
+            let now_sample = samples.iter().min_by_key(|s| s.mins_rel.abs());
+            if let Some(now) = now_sample.filter(|_| !theme.current_height) {
+                let pt = to_screen(now);
+                let size = 12;
+                let trend = tide.trend();
+                let radius = theme.now_marker_diameter as i32 / 2;
+                let top = match trend {
+                    Trend::Rising => pt.y - radius - size - 2,
+                    Trend::Falling => pt.y + radius + 2,
+                };
+                crate::large_print::draw_arrow(
+                    draw_target,
+                    (pt.x - size - 4).max(0) as u32,
+                    top.max(0) as u32,
+                    size as u32,
+                    trend,
+                    marker_color,
+                )?;
+            }
+
+            // Other stations: thin dashed curves (every other segment) plus legend
+            if !ctx.overlays.is_empty() {
+                for overlay in ctx.overlays {
+                    let points: Vec<Point> = overlay.series.samples.iter().map(to_screen).collect();
+                    for w in points.windows(2).step_by(2) {
+                        draw_target.draw_line(w[0], w[1], 1, curve_color)?;
+                    }
+                }
+
+                let legend_font = MonoTextStyle::new(&FONT_6X10, theme.axis_color.binary());
+                let legend_x = (plot_x + 8) as i32;
+                let mut legend_y = (plot_y + 4) as i32;
+                let entries = std::iter::once((ctx.meta.station_name.as_str(), true))
+                    .chain(ctx.overlays.iter().map(|o| (o.label, false)));
+                for (label, main) in entries {
+                    let y = legend_y + 4;
+                    if main {
+                        draw_target.draw_line(
+                            Point::new(legend_x, y),
+                            Point::new(legend_x + 16, y),
+                            theme.curve_stroke,
+                            curve_color,
+                        )?;
+                    } else {
+                        for dash in [0, 8] {
+                            draw_target.draw_line(
+                                Point::new(legend_x + dash, y),
+                                Point::new(legend_x + dash + 4, y),
+                                1,
+                                curve_color,
+                            )?;
+                        }
+                    }
+                    Text::with_baseline(
+                        label,
+                        Point::new(legend_x + 22, legend_y),
+                        legend_font,
+                        Baseline::Top,
+                    )
+                    .draw(draw_target)?;
+                    legend_y += 11;
                 }
             }
         }
+        Ok(())
     }
 }
 // End of impl block
+
+/// Draw one of the eight moon phases as a `diameter`-pixel disc
+///
+/// The unlit part is filled with `color` (paper is the lit part), as seen
+/// from the northern hemisphere: waxing moons are lit on the right.
+pub(crate) fn draw_moon_phase<D>(
+    target: &mut D,
+    top_left: Point,
+    diameter: u32,
+    moon: &LunarEphemeris,
+    color: BinaryColor,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let r = diameter as f32 / 2.0;
+    let center = top_left + Point::new(r as i32, r as i32);
+    let angle = moon.phase_index as f32 * std::f32::consts::FRAC_PI_4;
+    let waxing = moon.phase_index <= 4;
+    let dark = (0..diameter as i32).flat_map(move |y| {
+        let dy = y as f32 + 0.5 - r;
+        let half_width = (r * r - dy * dy).max(0.0).sqrt();
+        let terminator = half_width * angle.cos();
+        (0..diameter as i32).filter_map(move |x| {
+            let dx = x as f32 + 0.5 - r;
+            let lit = if waxing {
+                dx > terminator
+            } else {
+                dx < -terminator
+            };
+            (dx.abs() <= half_width && !lit).then(|| Pixel(top_left + Point::new(x, y), color))
+        })
+    });
+    target.draw_iter(dark)?;
+    target.draw_circle(center, diameter, 1, color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd4in2b_v2::{Color, DisplayBuffer};
+    use crate::theme::Theme;
+
+    fn dark_pixels(phase_index: u8) -> (u32, u32) {
+        let mut buffer = DisplayBuffer::new(400, 300);
+        buffer.clear(Color::White);
+        let moon = LunarEphemeris {
+            phase_index,
+            age_days: phase_index as f64 * 3.7,
+            illum_frac: 0.0,
+            distance_er: 60.0,
+            lon_deg: 0.0,
+            lat_deg: 0.0,
+        };
+        draw_moon_phase(&mut buffer, Point::zero(), 24, &moon, BinaryColor::On).unwrap();
+        // 50 bytes per row, a cleared bit is black
+        let is_dark = |x: i32, y: i32| {
+            buffer.black_buffer()[y as usize * 50 + x as usize / 8] & (0x80 >> (x % 8)) == 0
+        };
+        let left = (0..24).flat_map(|y| (1..11).map(move |x| (x, y)));
+        let right = (0..24).flat_map(|y| (13..23).map(move |x| (x, y)));
+        (
+            left.filter(|&(x, y)| is_dark(x, y)).count() as u32,
+            right.filter(|&(x, y)| is_dark(x, y)).count() as u32,
+        )
+    }
+
+    #[test]
+    fn test_current_height_readout() {
+        let mut config = crate::config::Config::default();
+        let series = crate::fallback::approximate(None);
+        let theme = Theme::default();
+        let renderer = EinkTideRenderer::new();
+        let mut buffer = DisplayBuffer::new(400, 300);
+
+        let meta = SeriesMeta::from_config(&config, chrono::Utc::now());
+        let text = meta.format_height(series.height_now().unwrap());
+        assert!(text.ends_with(" ft") && !text.starts_with('+'));
+        let ctx = LayoutContext {
+            series: &series,
+            meta: &meta,
+            theme: &theme,
+            size: buffer.size(),
+            overlays: &[],
+        };
+        let right = renderer.draw_current_height(&ctx, &mut buffer).unwrap();
+        // 2x FONT_10X20 digits plus the arrow
+        assert_eq!(right, 20 + text.len() as i32 * 20 + 8 + 20);
+
+        config.station.show_msl = true;
+        let meta = SeriesMeta::from_config(&config, chrono::Utc::now());
+        assert_eq!(meta.format_height(6.4), "+1.5 ft");
+        assert_eq!(meta.format_height(3.9), "-1.0 ft");
+        assert_eq!(meta.axis_label(), "ft MSL");
+    }
+
+    #[test]
+    fn test_flood_threshold_band() {
+        use chrono::TimeZone;
+        let config = crate::config::Config::default();
+        let now = chrono::Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let series = crate::fallback::approximate(Some(now));
+        let theme = Theme::default();
+        let red_pixels = |threshold: Option<f32>| {
+            let mut meta = SeriesMeta::from_config(&config, now);
+            meta.flood_threshold_ft = threshold;
+            let ctx = LayoutContext {
+                series: &series,
+                meta: &meta,
+                theme: &theme,
+                size: Size::new(400, 300),
+                overlays: &[],
+            };
+            let mut buffer = DisplayBuffer::new(400, 300);
+            crate::layout::draw_tricolor(&EinkTideRenderer::new(), &ctx, &mut buffer);
+            buffer
+                .red_buffer()
+                .iter()
+                .map(|b| b.count_ones())
+                .sum::<u32>()
+        };
+
+        let plain = red_pixels(None);
+        // The curve peaks around 9 ft: the band, line and label are red
+        assert!(red_pixels(Some(8.0)) > plain + 400);
+        // Never reached, nothing to flag
+        assert_eq!(red_pixels(Some(20.0)), plain);
+    }
+
+    #[test]
+    fn test_chart_style() {
+        let style: ChartStyle = toml::from_str("margin = 10\ny_ticks = 6").unwrap();
+        assert_eq!(style.margin, 10);
+        assert_eq!(style.y_ticks, 6);
+        assert_eq!(style.plot_inset, ChartStyle::default().plot_inset);
+
+        let config = crate::config::Config::default();
+        assert_eq!(ChartStyle::from(&config.display), ChartStyle::default());
+        let series = crate::fallback::approximate(None);
+        let meta = SeriesMeta::from_config(&config, chrono::Utc::now());
+        let theme = Theme::default();
+        let ctx = LayoutContext {
+            series: &series,
+            meta: &meta,
+            theme: &theme,
+            size: Size::new(400, 300),
+            overlays: &[],
+        };
+        let draw = |style: ChartStyle| {
+            let mut buffer = DisplayBuffer::new(400, 300);
+            EinkTideRenderer::with_style(style)
+                .draw(&ctx, &mut buffer)
+                .unwrap();
+            buffer.black_buffer().to_vec()
+        };
+        assert_ne!(draw(ChartStyle::default()), draw(style));
+        // Spacing too large for the panel squeezes the plot instead of panicking
+        draw(ChartStyle {
+            margin: 500,
+            plot_inset: 500,
+            y_ticks: 0,
+            ..ChartStyle::default()
+        });
+    }
+
+    #[test]
+    fn test_tick_hours_widen_with_window() {
+        assert_eq!(tick_hours(3, 720), 3);
+        assert_eq!(tick_hours(3, 24 * 60), 3);
+        assert_eq!(tick_hours(3, 48 * 60), 6);
+        assert_eq!(tick_hours(3, 72 * 60), 12);
+        assert_eq!(tick_hours(0, 72 * 60), 0);
+    }
+
+    #[test]
+    fn test_moon_phase_glyph_shading() {
+        let (new_left, new_right) = dark_pixels(0);
+        let (full_left, full_right) = dark_pixels(4);
+        assert!(new_left > 150 && new_right > 150);
+        // Only the outline remains at full moon
+        assert!(full_left < 40 && full_right < 40);
+
+        // First quarter is lit on the right, last quarter on the left
+        let (first_left, first_right) = dark_pixels(2);
+        assert!(first_left > 150 && first_right < 40);
+        let (last_left, last_right) = dark_pixels(6);
+        assert!(last_right > 150 && last_left < 40);
+    }
+}
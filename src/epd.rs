@@ -0,0 +1,114 @@
+//! # E-ink Panel Drivers
+//!
+//! Every supported Waveshare panel implements [`EpdDriver`], so the rest of
+//! the program (and the renderer sizing its frame) only needs to know which
+//! model `[display.hardware] panel` names:
+//!
+//! | `panel`      | Driver                                          | Size    | Colors        |
+//! |--------------|-------------------------------------------------|---------|---------------|
+//! | `"4in2b_v2"` | [`Epd4in2bV2`](crate::epd4in2b_v2::Epd4in2bV2)  | 400x300 | black/white/red |
+//! | `"7in5_v2"`  | [`Epd7in5V2`](crate::epd7in5_v2::Epd7in5V2)     | 800x480 | black/white   |
+//!
+//! Frames are always [`DisplayBuffer`]s with both planes; a panel without red
+//! ink draws the red plane black.
+
+use crate::epd4in2b_v2::{DisplayBuffer, EpdError};
+use embedded_graphics::prelude::Size;
+
+/// An e-ink panel the tide chart can be drawn on
+pub trait EpdDriver {
+    /// Panel width in pixels
+    fn width(&self) -> u32;
+
+    /// Panel height in pixels
+    fn height(&self) -> u32;
+
+    /// Panel size, the size of every frame passed to [`display`](Self::display)
+    fn size(&self) -> Size {
+        Size::new(self.width(), self.height())
+    }
+
+    /// Human-readable description of the panel, for diagnostics and setup
+    fn model_name(&self) -> String;
+
+    /// Reset the controller and get it ready for a frame
+    fn init(&mut self) -> Result<(), EpdError>;
+
+    /// Blank the whole panel to white
+    fn clear(&mut self) -> Result<(), EpdError>;
+
+    /// Full refresh showing `frame`
+    fn display(&mut self, frame: &DisplayBuffer) -> Result<(), EpdError>;
+
+    /// Update what changed in `frame` since its last flush, see
+    /// [`DisplayBuffer::take_dirty_regions`]
+    ///
+    /// Returns whether anything was sent. Panels without partial refresh
+    /// redraw the whole frame whenever anything changed.
+    fn display_dirty(&mut self, frame: &mut DisplayBuffer) -> Result<bool, EpdError> {
+        if frame.take_dirty_regions().is_empty() {
+            return Ok(false);
+        }
+        self.display(frame)?;
+        Ok(true)
+    }
+
+    /// Power the panel down into deep sleep; the picture stays
+    ///
+    /// Nothing but a hardware reset wakes the controller again, so every
+    /// other call fails with [`EpdError::NotInitialized`] until
+    /// [`wake`](Self::wake).
+    fn sleep(&mut self) -> Result<(), EpdError>;
+
+    /// Bring the panel back out of [`sleep`](Self::sleep): reset and
+    /// re-initialize it
+    fn wake(&mut self) -> Result<(), EpdError> {
+        self.init()
+    }
+
+    /// Panel temperature in °C from the controller's own sensor, `None` for
+    /// panels that can't report it
+    fn temperature(&mut self) -> Result<Option<f32>, EpdError> {
+        Ok(None)
+    }
+
+    /// Drive full refreshes with the slower cold-weather waveform; panels
+    /// without one ignore this
+    fn set_cold_waveform(&mut self, _cold: bool) {}
+}
+
+impl<T: EpdDriver + ?Sized> EpdDriver for Box<T> {
+    fn width(&self) -> u32 {
+        (**self).width()
+    }
+    fn height(&self) -> u32 {
+        (**self).height()
+    }
+    fn model_name(&self) -> String {
+        (**self).model_name()
+    }
+    fn init(&mut self) -> Result<(), EpdError> {
+        (**self).init()
+    }
+    fn clear(&mut self) -> Result<(), EpdError> {
+        (**self).clear()
+    }
+    fn display(&mut self, frame: &DisplayBuffer) -> Result<(), EpdError> {
+        (**self).display(frame)
+    }
+    fn display_dirty(&mut self, frame: &mut DisplayBuffer) -> Result<bool, EpdError> {
+        (**self).display_dirty(frame)
+    }
+    fn sleep(&mut self) -> Result<(), EpdError> {
+        (**self).sleep()
+    }
+    fn wake(&mut self) -> Result<(), EpdError> {
+        (**self).wake()
+    }
+    fn temperature(&mut self) -> Result<Option<f32>, EpdError> {
+        (**self).temperature()
+    }
+    fn set_cold_waveform(&mut self, cold: bool) {
+        (**self).set_cold_waveform(cold)
+    }
+}
@@ -0,0 +1,322 @@
+// Custom EPD 7.5" B/W V2 Driver
+//
+// Follows the Waveshare C EPD_7IN5_V2 example (UC8179 controller). The panel
+// has no red ink, so the red plane of a frame is drawn black.
+
+#[cfg(feature = "eink")]
+use crate::epd::EpdDriver;
+#[cfg(feature = "eink")]
+use crate::epd4in2b_v2::{DisplayBuffer, EpdError, GpioPin, InputPin, SoftwareSpi, BUSY_TIMEOUT};
+#[cfg(feature = "eink")]
+use embedded_graphics::prelude::*;
+#[cfg(feature = "eink")]
+use std::thread;
+#[cfg(feature = "eink")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "eink")]
+use tracing::{debug, trace, warn};
+
+/// Display dimensions
+pub const EPD_WIDTH: u32 = 800;
+pub const EPD_HEIGHT: u32 = 480;
+
+/// EPD 7.5" B/W V2 display driver
+#[cfg(feature = "eink")]
+pub struct Epd7in5V2<SPI, CS, DC, RST, BUSY> {
+    spi: SPI,
+    cs_pin: Option<CS>,
+    dc_pin: DC,
+    rst_pin: RST,
+    busy_pin: BUSY,
+    width: u32,
+    height: u32,
+    /// Set by `init()`, cleared again by `sleep()`
+    initialized: bool,
+}
+
+#[cfg(feature = "eink")]
+impl<SPI, CS, DC, RST, BUSY> Epd7in5V2<SPI, CS, DC, RST, BUSY>
+where
+    SPI: SoftwareSpi,
+    CS: GpioPin,
+    DC: GpioPin,
+    RST: GpioPin,
+    BUSY: InputPin,
+{
+    /// Create a new EPD instance
+    pub fn new(spi: SPI, cs_pin: Option<CS>, dc_pin: DC, rst_pin: RST, busy_pin: BUSY) -> Self {
+        Self {
+            spi,
+            cs_pin,
+            dc_pin,
+            rst_pin,
+            busy_pin,
+            width: EPD_WIDTH,
+            height: EPD_HEIGHT,
+            initialized: false,
+        }
+    }
+
+    /// Hardware reset - follows C EPD_7IN5_V2_Reset()
+    fn reset(&mut self) -> Result<(), EpdError> {
+        debug!("Performing hardware reset...");
+        self.rst_pin.set_high()?;
+        thread::sleep(Duration::from_millis(20));
+        self.rst_pin.set_low()?;
+        thread::sleep(Duration::from_millis(2));
+        self.rst_pin.set_high()?;
+        thread::sleep(Duration::from_millis(20));
+        Ok(())
+    }
+
+    fn send(&mut self, data_mode: bool, byte: u8) -> Result<(), EpdError> {
+        if data_mode {
+            self.dc_pin.set_high()?;
+        } else {
+            self.dc_pin.set_low()?;
+        }
+        if let Some(cs) = &mut self.cs_pin {
+            cs.set_low()?;
+        }
+        self.spi.write_byte(byte)?;
+        if let Some(cs) = &mut self.cs_pin {
+            cs.set_high()?;
+        }
+        Ok(())
+    }
+
+    fn send_command(&mut self, command: u8) -> Result<(), EpdError> {
+        self.send(false, command)
+    }
+
+    fn send_data(&mut self, data: u8) -> Result<(), EpdError> {
+        self.send(true, data)
+    }
+
+    /// Wait while BUSY is LOW (this controller's BUSY is active low), polling
+    /// with GET_STATUS (0x71) like the C code
+    fn read_busy(&mut self) -> Result<(), EpdError> {
+        trace!("Waiting for display (BUSY pin check)...");
+        // A full refresh of this panel takes about 4 seconds
+        let started = Instant::now();
+        loop {
+            self.send_command(0x71)?;
+            if self.busy_pin.is_high()? {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+            if started.elapsed() > BUSY_TIMEOUT {
+                warn!("BUSY pin timeout - display may be stuck");
+                return Err(EpdError::BusyTimeout(BUSY_TIMEOUT));
+            }
+        }
+        thread::sleep(Duration::from_millis(20));
+        Ok(())
+    }
+
+    /// DISPLAY_REFRESH (0x12) and wait for it to finish
+    fn turn_on_display(&mut self) -> Result<(), EpdError> {
+        trace!("Turning on display...");
+        self.send_command(0x12)?;
+        thread::sleep(Duration::from_millis(100));
+        self.read_busy()
+    }
+
+    /// Send both frame RAMs: the old data (0x10) with white as 1 and the new
+    /// data (0x13) with black as 1, one `row` of bytes at a time
+    fn write_frame(&mut self, row: impl Fn(usize) -> u8) -> Result<(), EpdError> {
+        if !self.initialized {
+            return Err(EpdError::NotInitialized);
+        }
+        let bytes = (self.width.div_ceil(8) * self.height) as usize;
+        self.send_command(0x10)?;
+        for i in 0..bytes {
+            self.send_data(!row(i))?;
+        }
+        self.send_command(0x13)?;
+        for i in 0..bytes {
+            self.send_data(row(i))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eink")]
+impl<SPI, CS, DC, RST, BUSY> EpdDriver for Epd7in5V2<SPI, CS, DC, RST, BUSY>
+where
+    SPI: SoftwareSpi,
+    CS: GpioPin,
+    DC: GpioPin,
+    RST: GpioPin,
+    BUSY: InputPin,
+{
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn model_name(&self) -> String {
+        format!("7.5in B/W V2 {}x{}", self.width, self.height)
+    }
+
+    /// Follows C EPD_7IN5_V2_Init()
+    fn init(&mut self) -> Result<(), EpdError> {
+        debug!("Initializing 7.5in EPD...");
+        self.reset()?;
+
+        self.send_command(0x01)?; // POWER SETTING
+        for data in [0x07, 0x07, 0x3F, 0x3F] {
+            // VGH=20V, VGL=-20V, VDH=15V, VDL=-15V
+            self.send_data(data)?;
+        }
+        self.send_command(0x06)?; // BOOSTER SOFT START
+        for data in [0x17, 0x17, 0x28, 0x17] {
+            self.send_data(data)?;
+        }
+        self.send_command(0x04)?; // POWER ON
+        thread::sleep(Duration::from_millis(100));
+        self.read_busy()?;
+
+        self.send_command(0x00)?; // PANEL SETTING
+        self.send_data(0x1F)?; // KW mode, LUT from OTP
+        self.send_command(0x61)?; // RESOLUTION: 800x480
+        self.send_data((self.width >> 8) as u8)?;
+        self.send_data(self.width as u8)?;
+        self.send_data((self.height >> 8) as u8)?;
+        self.send_data(self.height as u8)?;
+        self.send_command(0x15)?; // DUAL SPI off
+        self.send_data(0x00)?;
+        self.send_command(0x50)?; // VCOM AND DATA INTERVAL
+        self.send_data(0x10)?;
+        self.send_data(0x07)?;
+        self.send_command(0x60)?; // TCON
+        self.send_data(0x22)?;
+
+        self.initialized = true;
+        debug!("7.5in EPD initialized");
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), EpdError> {
+        debug!("Clearing display...");
+        self.write_frame(|_| 0x00)?;
+        self.turn_on_display()
+    }
+
+    /// Red pixels are inked black, there being no red on this panel
+    fn display(&mut self, frame: &DisplayBuffer) -> Result<(), EpdError> {
+        EpdError::check_size(EpdDriver::size(self), frame.size())?;
+        debug!("Sending {}x{} frame...", self.width, self.height);
+        let (black, red) = (frame.black_buffer(), frame.red_buffer());
+        self.write_frame(|i| !black[i] | red[i])?;
+        self.turn_on_display()
+    }
+
+    /// Follows C EPD_7IN5_V2_Sleep()
+    fn sleep(&mut self) -> Result<(), EpdError> {
+        debug!("Putting display to sleep...");
+        self.send_command(0x50)?; // VCOM AND DATA INTERVAL: floating border
+        self.send_data(0xF7)?;
+        self.send_command(0x02)?; // POWER OFF
+        self.read_busy()?;
+        self.send_command(0x07)?; // DEEP SLEEP
+        self.send_data(0xA5)?;
+        self.initialized = false;
+        debug!("Display sleeping");
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "eink"))]
+mod tests {
+    use super::*;
+    use crate::epd4in2b_v2::Color;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    /// Records every byte sent, with whether DC marked it as data
+    #[derive(Clone, Default)]
+    struct Recorder {
+        data_mode: Rc<Cell<bool>>,
+        sent: Rc<RefCell<Vec<(bool, u8)>>>,
+    }
+
+    impl SoftwareSpi for Recorder {
+        fn write_byte(&mut self, data: u8) -> Result<(), EpdError> {
+            self.sent.borrow_mut().push((self.data_mode.get(), data));
+            Ok(())
+        }
+        fn read_byte(&mut self) -> Result<u8, EpdError> {
+            Ok(0)
+        }
+    }
+
+    impl GpioPin for Recorder {
+        fn set_high(&mut self) -> Result<(), EpdError> {
+            self.data_mode.set(true);
+            Ok(())
+        }
+        fn set_low(&mut self) -> Result<(), EpdError> {
+            self.data_mode.set(false);
+            Ok(())
+        }
+    }
+
+    impl InputPin for Recorder {
+        fn is_high(&self) -> Result<bool, EpdError> {
+            // Never busy
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn test_display_sends_both_rams_with_red_as_black() {
+        let spi = Recorder::default();
+        let mut epd = Epd7in5V2::new(
+            spi.clone(),
+            None::<Recorder>,
+            spi.clone(),
+            Recorder::default(),
+            Recorder::default(),
+        );
+        assert_eq!(epd.size(), Size::new(800, 480));
+
+        let mut frame = DisplayBuffer::new(EPD_WIDTH, EPD_HEIGHT);
+        frame.set_pixel(0, 0, Color::Black);
+        frame.set_pixel(9, 0, Color::Red);
+        assert!(matches!(epd.display(&frame), Err(EpdError::NotInitialized)));
+        epd.init().unwrap();
+        spi.sent.borrow_mut().clear();
+        epd.display(&frame).unwrap();
+
+        let sent = spi.sent.borrow();
+        let ram = |command: u8| -> Vec<u8> {
+            let at = sent.iter().position(|&(d, b)| !d && b == command).unwrap();
+            sent[at + 1..]
+                .iter()
+                .take_while(|(d, _)| *d)
+                .map(|&(_, b)| b)
+                .collect()
+        };
+        let (old, new) = (ram(0x10), ram(0x13));
+        assert_eq!(new.len(), 100 * 480);
+        assert_eq!(&new[..3], [0x80, 0x40, 0x00]);
+        assert!(old.iter().zip(&new).all(|(o, n)| *o == !*n));
+        assert!(sent.contains(&(false, 0x12)));
+
+        // A frame for another panel is refused
+        drop(sent);
+        assert!(matches!(
+            epd.display(&DisplayBuffer::new(400, 300)),
+            Err(EpdError::FrameSizeMismatch { .. })
+        ));
+        // and after sleep() it needs init() again
+        epd.sleep().unwrap();
+        assert!(matches!(epd.clear(), Err(EpdError::NotInitialized)));
+        epd.wake().unwrap();
+        epd.display(&frame).unwrap();
+    }
+}
@@ -0,0 +1,506 @@
+//! # Mock Panel
+//!
+//! Stand-ins for the SPI bus and GPIO lines of the 4.2" panel that behave
+//! like its controller (SSD1683) instead of just recording bytes, so driver
+//! logic can be tested without hardware: what matters is the picture that
+//! ends up on the glass, not the exact bytes.
+//!
+//! [`MockPanel`] keeps the full command/data stream, runs it through the
+//! controller's RAM windows and address counters (data entry mode 0x03: x
+//! then y, wrapping inside the window) and latches the RAM into the shown
+//! frame on each refresh (0x22 with the display bit, then 0x20). A driver
+//! that forgets to reset the cursor, sends rows out of order or inverts the
+//! red plane shows a different frame than the one it was given.
+//!
+//! Set `TIDE_MOCK_PANEL_DIR` to keep the PNGs the tests write, e.g. as CI
+//! artifacts; they go to a temporary directory otherwise.
+
+use crate::epd4in2b_v2::{
+    DisplayBuffer, Epd4in2bV2, EpdError, GpioPin, InputPin, SoftwareSpi, Variant, EPD_HEIGHT,
+    EPD_WIDTH,
+};
+use crate::panel::{self, ImageError};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Revision byte the mock answers the detection command (0x2F) with
+pub(crate) const REVISION: u8 = 0x01;
+
+/// One byte on the bus, as DC marked it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Transfer {
+    Command(u8),
+    Data(u8),
+    /// Clocked in from the controller
+    Read(u8),
+    /// RST released after being pulled low
+    Reset,
+}
+
+/// The log grouped the way the reference code is written: each command
+/// with the data bytes after it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Step {
+    Reset,
+    Command(u8, Vec<u8>),
+    Read(u8),
+}
+
+/// A panel refresh triggered by 0x20
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Refresh {
+    /// Display mode 1, the flashing full refresh
+    Full,
+    /// Display mode 2 (0x22 0xFF), the partial update
+    Partial,
+}
+
+/// Controller state shared by the bus and the lines
+struct Controller {
+    variant: Variant,
+    width_bytes: usize,
+    height: usize,
+    /// DC level: high for data
+    data_mode: bool,
+    /// CS level, low while selected (stays low without a CS line)
+    cs_high: bool,
+    rst_high: bool,
+    asleep: bool,
+    log: Vec<Transfer>,
+    command: Option<u8>,
+    args: Vec<u8>,
+    /// The 0x24 (black/white) and 0x26 (red, or previous image) RAMs
+    ram: [Vec<u8>; 2],
+    /// Window in bytes (x) and rows (y), both ends included
+    window: (usize, usize, usize, usize),
+    cursor: (usize, usize),
+    /// 0x21: the red RAM reads as 0 ("bypass")
+    bypass_red: bool,
+    sequence: u8,
+    reads: VecDeque<u8>,
+    /// Black and red planes on the glass after the last refresh
+    shown: Option<(Vec<u8>, Vec<u8>)>,
+    refreshes: Vec<Refresh>,
+}
+
+impl Controller {
+    fn new(variant: Variant) -> Self {
+        let width_bytes = EPD_WIDTH.div_ceil(8) as usize;
+        let height = EPD_HEIGHT as usize;
+        let mut controller = Self {
+            variant,
+            width_bytes,
+            height,
+            data_mode: false,
+            cs_high: false,
+            rst_high: true,
+            asleep: false,
+            log: Vec::new(),
+            command: None,
+            args: Vec::new(),
+            // Power-on RAM content is undefined; start from a white panel
+            ram: [
+                vec![0xFF; width_bytes * height],
+                vec![0x00; width_bytes * height],
+            ],
+            window: (0, 0, 0, 0),
+            cursor: (0, 0),
+            bypass_red: false,
+            sequence: 0,
+            reads: VecDeque::new(),
+            shown: None,
+            refreshes: Vec::new(),
+        };
+        controller.reset_registers();
+        controller
+    }
+
+    /// Hardware reset or SWRESET: registers to their defaults, RAM kept
+    fn reset_registers(&mut self) {
+        self.window = (0, self.width_bytes - 1, 0, self.height - 1);
+        self.cursor = (0, 0);
+        self.bypass_red = false;
+        self.sequence = 0;
+        self.command = None;
+        self.args.clear();
+    }
+
+    fn write(&mut self, byte: u8) {
+        if self.cs_high {
+            return;
+        }
+        if !self.data_mode {
+            self.log.push(Transfer::Command(byte));
+            if !self.asleep {
+                self.command(byte);
+            }
+        } else {
+            self.log.push(Transfer::Data(byte));
+            if !self.asleep {
+                self.data(byte);
+            }
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let byte = self.reads.pop_front().unwrap_or(0);
+        self.log.push(Transfer::Read(byte));
+        byte
+    }
+
+    fn command(&mut self, command: u8) {
+        self.command = Some(command);
+        self.args.clear();
+        match command {
+            0x12 => self.reset_registers(),
+            0x20 => self.activate(),
+            0x2F => self.reads = VecDeque::from([REVISION]),
+            // 25 °C, as the temperature sensor would report
+            0x1B => self.reads = VecDeque::from([0x19, 0x00]),
+            _ => {}
+        }
+    }
+
+    fn data(&mut self, byte: u8) {
+        let Some(command) = self.command else {
+            return;
+        };
+        self.args.push(byte);
+        let args = &self.args;
+        match (command, args.len()) {
+            (0x24, _) => self.write_ram(0, byte),
+            (0x26, _) => self.write_ram(1, byte),
+            (0x44, 2) => {
+                self.window.0 = args[0] as usize;
+                self.window.1 = args[1] as usize;
+            }
+            (0x45, 4) => {
+                self.window.2 = u16::from_le_bytes([args[0], args[1]]) as usize;
+                self.window.3 = u16::from_le_bytes([args[2], args[3]]) as usize;
+            }
+            (0x4E, 1) => self.cursor.0 = args[0] as usize,
+            (0x4F, 2) => self.cursor.1 = u16::from_le_bytes([args[0], args[1]]) as usize,
+            (0x21, 1) => self.bypass_red = byte & 0x40 != 0,
+            (0x22, 1) => self.sequence = byte,
+            (0x10, 1) => self.asleep = byte & 0x03 != 0,
+            _ => {}
+        }
+    }
+
+    /// Store `byte` at the cursor and advance it inside the window
+    fn write_ram(&mut self, ram: usize, byte: u8) {
+        let (x, y) = self.cursor;
+        if x < self.width_bytes && y < self.height {
+            self.ram[ram][y * self.width_bytes + x] = byte;
+        }
+        let (x_start, x_end, y_start, y_end) = self.window;
+        self.cursor = if x < x_end {
+            (x + 1, y)
+        } else if y < y_end {
+            (x_start, y + 1)
+        } else {
+            (x_start, y_start)
+        };
+    }
+
+    /// Master activation: run the 0x22 sequence, showing the RAM when it
+    /// includes the display step
+    fn activate(&mut self) {
+        if self.sequence & 0x04 == 0 {
+            return;
+        }
+        let black = self.ram[0].clone();
+        let red = match self.variant {
+            Variant::TriColor if !self.bypass_red => self.ram[1].clone(),
+            _ => vec![0x00; black.len()],
+        };
+        self.shown = Some((black, red));
+        self.refreshes.push(if self.sequence & 0x08 != 0 {
+            Refresh::Partial
+        } else {
+            Refresh::Full
+        });
+    }
+}
+
+/// A simulated 4.2" panel; its bus and lines drive an [`Epd4in2bV2`]
+#[derive(Clone)]
+pub(crate) struct MockPanel {
+    controller: Rc<RefCell<Controller>>,
+}
+
+/// Which line a [`MockLine`] is wired to
+#[derive(Clone, Copy)]
+enum Line {
+    Cs,
+    Dc,
+    Rst,
+    Busy,
+}
+
+/// SPI bus of a [`MockPanel`]
+pub(crate) struct MockSpi(MockPanel);
+
+/// GPIO line of a [`MockPanel`]
+pub(crate) struct MockLine(MockPanel, Line);
+
+/// The driver wired to a [`MockPanel`]
+pub(crate) type MockEpd = Epd4in2bV2<MockSpi, MockLine, MockLine, MockLine, MockLine>;
+
+impl MockPanel {
+    pub(crate) fn new(variant: Variant) -> Self {
+        Self {
+            controller: Rc::new(RefCell::new(Controller::new(variant))),
+        }
+    }
+
+    /// A driver for the panel, with a CS line or with CS tied low
+    pub(crate) fn driver(&self, with_cs: bool) -> MockEpd {
+        let line = |line| MockLine(self.clone(), line);
+        Epd4in2bV2::new(
+            MockSpi(self.clone()),
+            with_cs.then(|| line(Line::Cs)),
+            line(Line::Dc),
+            line(Line::Rst),
+            line(Line::Busy),
+        )
+        .with_variant(self.controller.borrow().variant)
+    }
+
+    /// Every byte sent or read so far
+    pub(crate) fn log(&self) -> Vec<Transfer> {
+        self.controller.borrow().log.clone()
+    }
+
+    /// The log as [`Step`]s
+    pub(crate) fn steps(&self) -> Vec<Step> {
+        let mut steps = Vec::new();
+        for transfer in self.log() {
+            match transfer {
+                Transfer::Command(command) => steps.push(Step::Command(command, Vec::new())),
+                Transfer::Data(byte) => match steps.last_mut() {
+                    Some(Step::Command(_, data)) => data.push(byte),
+                    // Data without a command is a driver bug worth seeing
+                    _ => steps.push(Step::Command(0x00, vec![byte])),
+                },
+                Transfer::Read(byte) => steps.push(Step::Read(byte)),
+                Transfer::Reset => steps.push(Step::Reset),
+            }
+        }
+        steps
+    }
+
+    /// Forget the log so far, keeping the panel's state
+    pub(crate) fn clear_log(&self) {
+        self.controller.borrow_mut().log.clear();
+    }
+
+    /// Refreshes so far, oldest first
+    pub(crate) fn refreshes(&self) -> Vec<Refresh> {
+        self.controller.borrow().refreshes.clone()
+    }
+
+    /// The frame on the glass after the last refresh
+    pub(crate) fn shown(&self) -> Option<DisplayBuffer> {
+        let (black, red) = self.controller.borrow().shown.clone()?;
+        DisplayBuffer::from_planes(EPD_WIDTH, EPD_HEIGHT, black, red)
+    }
+
+    /// Whether the controller is in deep sleep
+    pub(crate) fn asleep(&self) -> bool {
+        self.controller.borrow().asleep
+    }
+
+    /// Save the shown frame as `name` in `TIDE_MOCK_PANEL_DIR`, or in `dir`,
+    /// returning where it went
+    pub(crate) fn save_png(&self, dir: &Path, name: &str) -> Result<PathBuf, ImageError> {
+        let dir = std::env::var_os("TIDE_MOCK_PANEL_DIR").map_or(dir.to_path_buf(), Into::into);
+        let shown = self.shown().expect("nothing was shown");
+        let path = dir.join(name);
+        panel::save_image(&shown, &path)?;
+        Ok(path)
+    }
+}
+
+impl SoftwareSpi for MockSpi {
+    fn write_byte(&mut self, data: u8) -> Result<(), EpdError> {
+        self.0.controller.borrow_mut().write(data);
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> Result<u8, EpdError> {
+        Ok(self.0.controller.borrow_mut().read())
+    }
+}
+
+impl MockLine {
+    fn set(&mut self, high: bool) {
+        let mut controller = self.0.controller.borrow_mut();
+        match self.1 {
+            Line::Cs => controller.cs_high = high,
+            Line::Dc => controller.data_mode = high,
+            Line::Rst => {
+                // The rising edge ends the reset pulse
+                if high && !controller.rst_high {
+                    controller.log.push(Transfer::Reset);
+                    controller.asleep = false;
+                    controller.reset_registers();
+                }
+                controller.rst_high = high;
+            }
+            Line::Busy => {}
+        }
+    }
+}
+
+impl GpioPin for MockLine {
+    fn set_high(&mut self) -> Result<(), EpdError> {
+        self.set(true);
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), EpdError> {
+        self.set(false);
+        Ok(())
+    }
+}
+
+impl InputPin for MockLine {
+    /// BUSY is never held: every operation completes at once
+    fn is_high(&self) -> Result<bool, EpdError> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::epd::EpdDriver;
+    use crate::epd4in2b_v2::Color;
+    use crate::fallback;
+    use crate::stations::StationSeries;
+    use chrono::Utc;
+    use embedded_graphics::prelude::Size;
+
+    /// The panel frame `tide-tracker --png` draws, red and all
+    fn chart_frame() -> DisplayBuffer {
+        let mut config = Config::default();
+        config.display.use_red = true;
+        let displayed = [StationSeries {
+            station: config.station.clone(),
+            series: fallback::approximate(None),
+        }];
+        panel::compose_frame(
+            &displayed,
+            &config,
+            Size::new(EPD_WIDTH, EPD_HEIGHT),
+            Utc::now(),
+        )
+    }
+
+    fn assert_shows(panel: &MockPanel, frame: &DisplayBuffer) {
+        let shown = panel.shown().unwrap();
+        assert!(
+            shown.black_buffer() == frame.black_buffer(),
+            "black plane differs"
+        );
+        assert!(
+            shown.red_buffer() == frame.red_buffer(),
+            "red plane differs"
+        );
+    }
+
+    #[test]
+    fn test_full_refresh_shows_the_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let frame = chart_frame();
+        assert!(
+            frame.red_buffer().iter().any(|&b| b != 0),
+            "no red to check"
+        );
+        for with_cs in [false, true] {
+            let panel = MockPanel::new(Variant::TriColor);
+            let mut epd = panel.driver(with_cs);
+            epd.init().unwrap();
+            assert_eq!(epd.hardware_revision(), Some(REVISION));
+            EpdDriver::clear(&mut epd).unwrap();
+            assert_shows(&panel, &DisplayBuffer::new(EPD_WIDTH, EPD_HEIGHT));
+
+            EpdDriver::display(&mut epd, &frame).unwrap();
+            assert_shows(&panel, &frame);
+            assert_eq!(panel.refreshes(), [Refresh::Full, Refresh::Full]);
+        }
+
+        let panel = MockPanel::new(Variant::TriColor);
+        let mut epd = panel.driver(false);
+        epd.init().unwrap();
+        EpdDriver::display(&mut epd, &frame).unwrap();
+        let path = panel.save_png(dir.path(), "full.png").unwrap();
+        let decoder = png::Decoder::new(std::fs::File::open(path).unwrap());
+        let info = decoder.read_info().unwrap().info().clone();
+        assert_eq!((info.width, info.height), (EPD_WIDTH, EPD_HEIGHT));
+    }
+
+    #[test]
+    fn test_partial_then_full_refresh_keeps_the_picture_whole() {
+        let panel = MockPanel::new(Variant::TriColor);
+        let mut epd = panel.driver(true);
+        epd.init().unwrap();
+        let mut frame = chart_frame();
+        EpdDriver::display(&mut epd, &frame).unwrap();
+
+        // A few pixels change: only their bytes are sent, the rest stays
+        frame.take_dirty_regions();
+        frame.set_pixel(21, 5, Color::Black);
+        frame.set_pixel(398, 297, Color::Red);
+        let sent_before = panel.log().len();
+        assert!(EpdDriver::display_dirty(&mut epd, &mut frame).unwrap());
+        assert!(panel.log().len() - sent_before < 200);
+        assert_shows(&panel, &frame);
+        assert_eq!(panel.refreshes().last(), Some(&Refresh::Partial));
+
+        // The partial window and cursor moved; a full refresh resets both
+        frame.set_pixel(0, 0, Color::Red);
+        frame.set_pixel(399, 299, Color::Black);
+        EpdDriver::display(&mut epd, &frame).unwrap();
+        assert_shows(&panel, &frame);
+        assert_eq!(panel.refreshes().last(), Some(&Refresh::Full));
+    }
+
+    #[test]
+    fn test_black_white_module_inks_red_black() {
+        let panel = MockPanel::new(Variant::BlackWhite);
+        let mut epd = panel.driver(false);
+        epd.init().unwrap();
+        let mut frame = DisplayBuffer::new(EPD_WIDTH, EPD_HEIGHT);
+        frame.set_pixel(3, 0, Color::Red);
+        frame.set_pixel(4, 0, Color::Black);
+        EpdDriver::display(&mut epd, &frame).unwrap();
+
+        let shown = panel.shown().unwrap();
+        assert_eq!(shown.black_buffer()[0], 0b1110_0111);
+        assert!(shown.red_buffer().iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_sleep_ignores_the_bus_until_reset() {
+        let panel = MockPanel::new(Variant::TriColor);
+        let mut epd = panel.driver(false);
+        epd.init().unwrap();
+        let frame = chart_frame();
+        EpdDriver::display(&mut epd, &frame).unwrap();
+        EpdDriver::sleep(&mut epd).unwrap();
+        assert!(panel.asleep());
+        // The picture stays after the power-off sequence
+        assert_shows(&panel, &frame);
+
+        EpdDriver::wake(&mut epd).unwrap();
+        assert!(!panel.asleep());
+        EpdDriver::clear(&mut epd).unwrap();
+        assert_shows(&panel, &DisplayBuffer::new(EPD_WIDTH, EPD_HEIGHT));
+        assert!(panel.log().contains(&Transfer::Read(REVISION)));
+    }
+}
@@ -0,0 +1,135 @@
+//! # Error Page
+//!
+//! Drawn in place of the chart when there is nothing trustworthy to show:
+//! no series covers "now", or the regular update failed partway on the
+//! panel. E-ink keeps its last picture without power, so doing nothing would
+//! leave yesterday's tides up looking current; a big NO DATA with what went
+//! wrong and when is plainly stale.
+//!
+//! ```text
+//! ┌──────────────────────────┐
+//! │         NO DATA          │
+//! │──────────────────────────│
+//! │ Display initialization   │  ← error summary, wrapped
+//! │ failed: BUSY timeout     │
+//! │                          │
+//! │ 10/17 3:45PM             │  ← when it happened
+//! └──────────────────────────┘
+//! ```
+
+use crate::large_print::draw_scaled;
+use embedded_graphics::mono_font::ascii::{FONT_10X20, FONT_8X13};
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Line, PrimitiveStyle};
+use embedded_graphics::text::{Baseline, Text};
+
+const MARGIN: i32 = 10;
+
+/// Largest scale of the 10x20 font for the headline
+const MAX_HEADLINE_SCALE: u32 = 4;
+
+/// Draw the error page into `target`
+///
+/// `summary` is wrapped to the width and cut off where the page ends; `at`
+/// is printed at the bottom as is.
+pub fn draw_error_page<D>(target: &mut D, summary: &str, at: &str) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let size = target.bounding_box().size;
+    let (width, height) = (size.width as i32, size.height as i32);
+    let inner = (width - 2 * MARGIN).max(1) as u32;
+
+    let headline = "NO DATA";
+    let char_size = FONT_10X20.character_size;
+    let scale = (inner / (char_size.width * headline.len() as u32)).clamp(1, MAX_HEADLINE_SCALE);
+    let headline_width = (char_size.width * headline.len() as u32 * scale) as i32;
+    draw_scaled(
+        target,
+        headline,
+        Point::new((width - headline_width) / 2, MARGIN),
+        scale,
+        BinaryColor::On,
+    )?;
+    let rule = MARGIN * 2 + (char_size.height * scale) as i32;
+    Line::new(Point::new(MARGIN, rule), Point::new(width - MARGIN, rule))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
+        .draw(target)?;
+
+    let body = MonoTextStyle::new(&FONT_8X13, BinaryColor::On);
+    let line_height = FONT_8X13.character_size.height as i32 + 2;
+    let footer = height - MARGIN - line_height;
+    let columns = inner / FONT_8X13.character_size.width;
+    let mut y = rule + MARGIN;
+    for line in wrap(summary, columns as usize) {
+        if y + line_height > footer {
+            break;
+        }
+        Text::with_baseline(&line, Point::new(MARGIN, y), body, Baseline::Top).draw(target)?;
+        y += line_height;
+    }
+    Text::with_baseline(at, Point::new(MARGIN, footer), body, Baseline::Top).draw(target)?;
+    Ok(())
+}
+
+/// Split `text` into lines of at most `columns` characters at spaces,
+/// breaking words that are longer than a line
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        loop {
+            let len = line.chars().count();
+            let gap = usize::from(len > 0);
+            if len + gap + word.len() <= columns {
+                if gap > 0 {
+                    line.push(' ');
+                }
+                line.extend(word);
+                break;
+            }
+            if len > 0 {
+                lines.push(std::mem::take(&mut line));
+                continue;
+            }
+            let rest = word.split_off(columns);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd4in2b_v2::DisplayBuffer;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("Display initialization failed: BUSY timeout", 16),
+            ["Display", "initialization", "failed: BUSY", "timeout"]
+        );
+        assert_eq!(wrap("/dev/spidev0.0", 6), ["/dev/s", "pidev0", ".0"]);
+        assert!(wrap("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_error_page_renders() {
+        for (width, height) in [(400, 300), (300, 400), (800, 480)] {
+            let mut buffer = DisplayBuffer::new(width, height);
+            let summary = "Display initialization failed: BUSY pin stuck high ".repeat(20);
+            draw_error_page(&mut buffer, &summary, "10/17 3:45PM").unwrap();
+            let black: u32 = buffer.black_buffer().iter().map(|b| b.count_zeros()).sum();
+            assert!(black > 1000);
+        }
+    }
+}
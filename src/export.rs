@@ -0,0 +1,92 @@
+//! # CSV Export
+//!
+//! Writes a station's series for spreadsheets:
+//!
+//! ```text
+//! tide-tracker export --csv tides.csv [--days N]
+//! ```
+//!
+//! One `time,height_ft` row per sample, every 10 minutes. Times are the
+//! station's wall-clock time with its UTC offset (`2025-07-01T08:00:00-04:00`),
+//! so rows stay unambiguous across DST changes. Heights are in the station's
+//! datum, or relative to its `[station.reference]`, as stored, without the
+//! `show_msl` shift the panel applies.
+//!
+//! The series is centered on now like the panel's, `--days` wide (up to
+//! [`MAX_DAYS`], the widest window the grid offers).
+
+use crate::config::StationConfig;
+use crate::grid::MAX_HALF_WIDTH_HOURS;
+use crate::TideSeries;
+use chrono::{DateTime, Duration, Utc};
+use std::io::{self, Write};
+
+/// Most days `--days` accepts
+pub const MAX_DAYS: i64 = MAX_HALF_WIDTH_HOURS * 2 / 24;
+
+/// Write `series` as CSV, with a header row
+///
+/// `now` anchors the samples when the series doesn't carry its own base time.
+pub fn write_csv<W: Write>(
+    mut out: W,
+    series: &TideSeries,
+    station: &StationConfig,
+    now: DateTime<Utc>,
+) -> io::Result<()> {
+    let base = series.base_time.unwrap_or(now);
+    writeln!(out, "time,height_ft")?;
+    for sample in &series.samples {
+        let t = station.local_time(base + Duration::minutes(sample.mins_rel as i64));
+        writeln!(
+            out,
+            "{},{:.2}",
+            t.format("%Y-%m-%dT%H:%M:%S%:z"),
+            sample.tide_ft
+        )?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::DataSource;
+    use crate::Sample;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_csv_rows_are_local_times() {
+        let mut station = Config::default().station;
+        station.timezone = Some(chrono_tz::America::New_York);
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let series = TideSeries {
+            samples: vec![
+                Sample {
+                    mins_rel: -10,
+                    tide_ft: 4.5,
+                    modeled: false,
+                },
+                Sample {
+                    mins_rel: 0,
+                    tide_ft: 4.567,
+                    modeled: false,
+                },
+            ],
+            source: DataSource::Noaa,
+            base_time: Some(now),
+            extremes: None,
+            fetched_at: None,
+        };
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &series, &station, now).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "time,height_ft\n\
+             2025-07-01T07:50:00-04:00,4.50\n\
+             2025-07-01T08:00:00-04:00,4.57\n"
+        );
+        assert_eq!(MAX_DAYS, 3);
+    }
+}
@@ -0,0 +1,389 @@
+//! # High and Low Water
+//!
+//! Times and heights of the turning points of the tide. Providers record the
+//! upcoming ones in [`TideSeries::extremes`] from data reaching past the ±12h
+//! sample window (NOAA's request already spans yesterday through tomorrow),
+//! which lets the chart footer answer "when's low tomorrow?" in the evening:
+//!
+//! ```text
+//! Today  H 8:41PM 9.6
+//! Tmrw   L 2:52AM 0.4  H 9:05AM 9.9
+//! ```
+//!
+//! Turning points are located on the raw points and refined with a parabola
+//! through the three points around each one, so hourly data still gives
+//! times to within a few minutes.
+
+#[cfg(feature = "std")]
+use crate::layout::SeriesMeta;
+use crate::TideSeries;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use alloc::{format, string::String};
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A high or low water
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Extreme {
+    /// When it occurs (serialized as epoch seconds)
+    #[cfg_attr(feature = "serde", serde(with = "chrono::serde::ts_seconds"))]
+    pub at: DateTime<Utc>,
+    /// Height in feet
+    pub tide_ft: f32,
+    /// `true` for high water, `false` for low water
+    pub high: bool,
+}
+
+/// Highs and lows in `points`, in time order
+///
+/// `points` must be sorted by time and evenly spaced. A flat top or bottom
+/// counts once, at its first point.
+pub fn find(points: &[(DateTime<Utc>, f32)]) -> Vec<Extreme> {
+    points
+        .windows(3)
+        .filter_map(|w| {
+            let [(_, h0), (t1, h1), (t2, h2)] = [w[0], w[1], w[2]];
+            let high = if h1 > h0 && h1 >= h2 {
+                true
+            } else if h1 < h0 && h1 <= h2 {
+                false
+            } else {
+                return None;
+            };
+            // Vertex of the parabola through the three points, in steps from t1
+            let curvature = h0 - 2.0 * h1 + h2;
+            let offset = if curvature == 0.0 {
+                0.0
+            } else {
+                (0.5 * (h0 - h2) / curvature).clamp(-0.5, 0.5)
+            };
+            let step = (t2 - t1).num_seconds() as f32;
+            Some(Extreme {
+                at: t1 + Duration::seconds((offset * step) as i64),
+                tide_ft: h1 - 0.25 * (h0 - h2) * offset,
+                high,
+            })
+        })
+        .collect()
+}
+
+impl TideSeries {
+    /// Highs and lows in the samples, past and upcoming, in time order
+    ///
+    /// Located like [`find`], so they can fall between samples. A series
+    /// without a base time is taken to be centered on the current time.
+    #[cfg(feature = "std")]
+    pub fn extremes(&self) -> Vec<Extreme> {
+        self.extremes_based_at(self.base_time.unwrap_or_else(Utc::now))
+    }
+
+    /// Highs and lows in the samples, taking `mins_rel == 0` to be `base`
+    fn extremes_based_at(&self, base: DateTime<Utc>) -> Vec<Extreme> {
+        let points: Vec<_> = self
+            .samples
+            .iter()
+            .map(|s| (base + Duration::minutes(s.mins_rel as i64), s.tide_ft))
+            .collect();
+        find(&points)
+    }
+
+    /// Highs and lows from "now" on
+    ///
+    /// Uses the recorded [`extremes`](TideSeries::extremes) when the provider
+    /// supplied them, otherwise those visible in the samples.
+    pub fn upcoming_extremes(&self) -> Vec<Extreme> {
+        let Some(now) = self.base_time else {
+            return Vec::new();
+        };
+        let extremes = match &self.extremes {
+            Some(extremes) => extremes.to_vec(),
+            None => self.extremes_based_at(now),
+        };
+        extremes.into_iter().filter(|e| e.at > now).collect()
+    }
+
+    /// Next high or low after `now` in the samples
+    ///
+    /// A series without a base time is taken to be centered on `now`.
+    pub fn next_extreme(&self, now: DateTime<Utc>) -> Option<Extreme> {
+        self.extremes_based_at(self.base_time.unwrap_or(now))
+            .into_iter()
+            .find(|e| e.at > now)
+    }
+
+    /// Highs and lows up to "now", from the samples
+    ///
+    /// Providers only record upcoming extremes, so the earlier half of the
+    /// samples is the only record of today's past ones.
+    pub fn past_extremes(&self) -> Vec<Extreme> {
+        let Some(now) = self.base_time else {
+            return Vec::new();
+        };
+        self.extremes_based_at(now)
+            .into_iter()
+            .filter(|e| e.at <= now)
+            .collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Extreme {
+    /// Time left from `meta.generated_at` until this extreme, with its
+    /// height as shown, e.g. "High in 2h 15m (10.8 ft)"
+    pub fn countdown(&self, meta: &SeriesMeta) -> String {
+        let kind = if self.high { "High" } else { "Low" };
+        let mins = (self.at - meta.generated_at).num_minutes().max(0);
+        let wait = match (mins / 60, mins % 60) {
+            (0, m) => format!("{}m", m),
+            (h, m) => format!("{}h {}m", h, m),
+        };
+        format!(
+            "{} in {} ({})",
+            kind,
+            wait,
+            meta.format_height(self.tide_ft)
+        )
+    }
+}
+
+/// All of today's highs and lows, past and upcoming, then all of
+/// tomorrow's, by station-local date
+#[cfg(feature = "std")]
+pub fn day_extremes(series: &TideSeries, meta: &SeriesMeta) -> [Vec<Extreme>; 2] {
+    let today = meta.local_time(0).date_naive();
+    let tomorrow = today.succ_opt().unwrap_or(today);
+    let all: Vec<Extreme> = series
+        .past_extremes()
+        .into_iter()
+        .chain(series.upcoming_extremes())
+        .collect();
+    let on = |day| {
+        all.iter()
+            .filter(|e| crate::config::local_time(meta.timezone, e.at).date_naive() == day)
+            .copied()
+            .collect()
+    };
+    [on(today), on(tomorrow)]
+}
+
+/// The two footer lines: today's remaining extremes, then tomorrow's first
+/// low and first high, in station-local time
+#[cfg(feature = "std")]
+pub fn footer_lines(series: &TideSeries, meta: &SeriesMeta) -> [String; 2] {
+    let today = meta.local_time(0).date_naive();
+    let tomorrow = today.succ_opt().unwrap_or(today);
+    let upcoming = series.upcoming_extremes();
+    let on = |day| {
+        upcoming
+            .iter()
+            .filter(move |e| crate::config::local_time(meta.timezone, e.at).date_naive() == day)
+    };
+
+    let todays: Vec<&Extreme> = on(today).collect();
+    let mut tomorrows: Vec<&Extreme> = [
+        on(tomorrow).find(|e| !e.high),
+        on(tomorrow).find(|e| e.high),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    tomorrows.sort_by_key(|e| e.at);
+
+    let line = |label: &str, extremes: &[&Extreme]| {
+        let entries: Vec<String> = extremes
+            .iter()
+            .map(|e| {
+                let mins_rel = (e.at - meta.generated_at).num_minutes();
+                let kind = if e.high { "H" } else { "L" };
+                format!(
+                    "{} {} {}",
+                    kind,
+                    meta.clock_at(mins_rel),
+                    meta.format_height_value(e.tide_ft)
+                )
+            })
+            .collect();
+        if entries.is_empty() {
+            format!("{:<6} --", label)
+        } else {
+            format!("{:<6} {}", label, entries.join("  "))
+        }
+    };
+    [line("Today", &todays), line("Tmrw", &tomorrows)]
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::config::TimeFormat;
+    use crate::DataSource;
+    use crate::{grid, Sample};
+    use chrono::TimeZone;
+
+    fn at(hour: i64, min: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap()
+            + Duration::hours(hour)
+            + Duration::minutes(min)
+    }
+
+    /// Semidiurnal curve with a high at 02:00 UTC
+    fn curve(t: DateTime<Utc>) -> f32 {
+        let hours = (t - at(2, 0)).num_seconds() as f32 / 3600.0;
+        5.0 + 4.0 * (hours * std::f32::consts::TAU / 12.42).cos()
+    }
+
+    fn meta(now: DateTime<Utc>) -> SeriesMeta {
+        SeriesMeta {
+            station_id: "8418150".to_string(),
+            station_name: "Portland, ME".to_string(),
+            generated_at: now,
+            timezone: Some(chrono_tz::UTC),
+            time_format: TimeFormat::H24,
+            datum: Default::default(),
+            reference: None,
+            msl_offset: None,
+            moon: None,
+            spring_neap: None,
+            flood_threshold_ft: None,
+        }
+    }
+
+    #[test]
+    fn test_find_refines_between_hourly_points() {
+        let points: Vec<_> = (0..30).map(|h| (at(h, 0), curve(at(h, 0)))).collect();
+        let found = find(&points);
+        let expected = [at(2, 0), at(8, 13), at(14, 25), at(20, 38)];
+        assert!(found.len() >= 4);
+        for (e, t) in found.iter().zip(expected) {
+            assert!((e.at - t).num_minutes().abs() <= 3, "{:?} vs {}", e, t);
+        }
+        assert!(found[0].high && !found[1].high);
+        assert!((found[0].tide_ft - 9.0).abs() < 0.05);
+        assert!((found[1].tide_ft - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_footer_lists_today_and_tomorrow() {
+        let now = at(18, 0);
+        let window = grid::Window::standard(now);
+        let samples = grid::generate(window, Duration::minutes(10), |m| {
+            curve(now + Duration::minutes(m as i64))
+        });
+        let hourly: Vec<_> = (0..60).map(|h| (at(h, 0), curve(at(h, 0)))).collect();
+        let series = TideSeries {
+            samples,
+            source: DataSource::Noaa,
+            base_time: Some(now),
+            extremes: Some(find(&hourly)),
+            fetched_at: None,
+        };
+
+        let [today, tomorrow] = footer_lines(&series, &meta(now));
+        assert_eq!(today, "Today  L 20:38 1.0");
+        assert_eq!(tomorrow, "Tmrw   H 02:50 9.0  L 09:02 1.0");
+
+        // Relative to MSL like the axis
+        let msl = SeriesMeta {
+            msl_offset: Some(5.0),
+            ..meta(now)
+        };
+        let [today, _] = footer_lines(&series, &msl);
+        assert_eq!(today, "Today  L 20:38 -4.0");
+
+        // The table also lists the day's earlier ones, from the samples
+        let [today, tomorrow] = day_extremes(&series, &meta(now));
+        let times: Vec<_> = today
+            .iter()
+            .map(|e| e.at.format("%H:%M").to_string())
+            .collect();
+        assert_eq!(times, ["08:12", "14:25", "20:38"]);
+        assert!(!today[0].high && today[1].high && !today[2].high);
+        assert_eq!(tomorrow.len(), 4);
+        assert!(tomorrow.iter().all(|e| e.at > at(24, 0)));
+    }
+
+    #[test]
+    fn test_next_extreme_and_countdown() {
+        let now = at(18, 0);
+        let series = TideSeries {
+            samples: [(-10, 1.0), (0, 2.0), (10, 3.0), (20, 2.5), (30, 2.0)]
+                .iter()
+                .map(|&(mins_rel, tide_ft)| Sample {
+                    mins_rel,
+                    tide_ft,
+                    modeled: false,
+                })
+                .collect(),
+            source: DataSource::Noaa,
+            base_time: None,
+            extremes: None,
+            fetched_at: None,
+        };
+        // Parabola through the 10-minute samples peaks just after the middle one
+        let next = series.next_extreme(now).unwrap();
+        assert!(next.high);
+        assert_eq!(next.at, now + Duration::seconds(700));
+        assert_eq!(next.countdown(&meta(now)), "High in 11m (3.0 ft)");
+        let anchored = TideSeries {
+            base_time: Some(now),
+            ..series
+        };
+        assert_eq!(anchored.next_extreme(now + Duration::minutes(12)), None);
+
+        let low = Extreme {
+            at: now + Duration::minutes(135),
+            tide_ft: -0.4,
+            high: false,
+        };
+        assert_eq!(low.countdown(&meta(now)), "Low in 2h 15m (-0.4 ft)");
+        let msl = SeriesMeta {
+            msl_offset: Some(4.9),
+            ..meta(now)
+        };
+        assert_eq!(low.countdown(&msl), "Low in 2h 15m (-5.3 ft)");
+    }
+
+    #[test]
+    fn test_upcoming_extremes_fall_back_to_samples() {
+        let now = at(18, 0);
+        let series = TideSeries {
+            samples: vec![
+                Sample {
+                    mins_rel: -10,
+                    tide_ft: 1.0,
+                    modeled: false,
+                },
+                Sample {
+                    mins_rel: 0,
+                    tide_ft: 2.0,
+                    modeled: false,
+                },
+                Sample {
+                    mins_rel: 10,
+                    tide_ft: 3.0,
+                    modeled: false,
+                },
+                Sample {
+                    mins_rel: 20,
+                    tide_ft: 2.0,
+                    modeled: false,
+                },
+            ],
+            source: DataSource::Fallback,
+            base_time: Some(now),
+            extremes: None,
+            fetched_at: None,
+        };
+        let upcoming = series.upcoming_extremes();
+        assert_eq!(upcoming.len(), 1);
+        assert!(upcoming[0].high);
+        assert_eq!(upcoming[0].at, now + Duration::minutes(10));
+
+        let [today, tomorrow] = footer_lines(&series, &meta(now));
+        assert_eq!(today, "Today  H 18:10 3.0");
+        assert_eq!(tomorrow, "Tmrw   --");
+    }
+}
@@ -17,6 +17,8 @@
 //! - **Mean level**: 5.0 feet (typical above chart datum)
 //! - **Amplitude**: 4.5 feet (Portland M2) modulated by solar S2
 //!
+//! Other stations set their own values, see [`ModelConstants`].
+//!
 //! ### Lunar Phase & Amplitude
 //! - The curve's phase is tied to the real-time clock (advances as time passes)
 //! - The amplitude is modulated by a cosine envelope, peaking at new/full moon (cos(2φ))
@@ -25,7 +27,7 @@
 //! ### Memory Efficiency
 //! - **Pre-computed constants**: All calculations use stack scalars
 //! - **Simple arithmetic**: Only sine/cosine and basic multiplication
-//! - **Fixed allocation**: `Vec::with_capacity(145)` prevents reallocation
+//! - **Shared grid**: samples come from [`grid::generate`], same as every provider
 //! - **No external dependencies**: Pure mathematical model, except for chrono and lunar
 //!
 //! ### Accuracy Trade-offs
@@ -35,68 +37,257 @@
 //! - ✅ **Phase alignment**: Window is centered on *now* and advances with real time
 //! - ❌ **No asymmetry**: Real tides have unequal high/low water heights
 //! - ❌ **No meteorological effects**: Ignores weather-driven tide variations
-//! - ❌ **±1 day accuracy**: Not synchronized to local station, but tracks moon
+//! - ❌ **±1 day accuracy**: Only as synchronized to the station as its lunitidal offset, but tracks moon
 //!
 //! The offline indicator ensures users understand they're seeing an approximation.
+//! Stations with `[station.harmonics]` skip this model entirely, see [`for_station`].
+//!
+//! ### Calibration
+//! When the station has real data cached from an earlier fetch, the generic
+//! Portland constants are replaced by a [`Calibration`]: mean level, M2
+//! amplitude and phase least-squares fitted to the last M2 period of that
+//! data. The offline curve then continues where the real one left off
+//! instead of jumping to a different tide.
 
-use crate::{Sample, TideSeries};
-use chrono::{DateTime, Datelike, Timelike, Utc};
+use crate::config::{Config, StationConfig};
+use crate::{grid, tide_data, DataSource, TideSeries};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
 
-/// Generate an approximate tide series for the next 24 h.
-/// If `now` is `None`, fall back to `Utc::now()`.
+/// M2 period in hours, the span a [`Calibration`] is fitted over
+const M2_PERIOD_HRS: f64 = 12.420_601;
+
+/// Oldest real data worth calibrating against; after a few days the
+/// spring-neap cycle has moved the amplitude and phase too far
+const MAX_CALIBRATION_AGE_HRS: i64 = 72;
+
+/// Fewest samples a fit is attempted with (covering most of an M2 period)
+const MIN_CALIBRATION_SAMPLES: usize = 12;
+
+/// Best offline series for `station`
 ///
-/// The returned series is always centered on the current instant, and modulates
-/// phase and amplitude using the Schaefer Moon algorithm.
-pub fn approximate(now: Option<DateTime<Utc>>) -> TideSeries {
-    // 1. Current instant
+/// Uses the station's harmonic constants when configured (accurate to within
+/// inches), including those found in `[fallback] harmonics_file`. Otherwise
+/// the model is [calibrated](Calibration) to the station's last cached fetch
+/// when that is recent enough, and the station's [`ModelConstants`] are the
+/// last resort. Constants that fail to load are logged and skipped.
+pub fn for_station(
+    config: &Config,
+    station: &StationConfig,
+    now: Option<DateTime<Utc>>,
+) -> TideSeries {
     let now = now.unwrap_or_else(Utc::now);
+    let spec = config.display.series_spec(now);
+    match config.harmonics_for(station).map(|h| h.load()) {
+        Some(Ok(harmonics)) => return harmonics.series_in(spec),
+        Some(Err(e)) => warn!("Harmonic constants unusable for {}: {}", station.name, e),
+        None => {}
+    }
+    let calibration = tide_data::last_fetched(config, station)
+        .and_then(|series| Calibration::fit(&series))
+        .filter(|c| now - c.epoch <= Duration::hours(MAX_CALIBRATION_AGE_HRS));
+    match calibration {
+        Some(calibration) => calibration.series_in(spec),
+        None => config.model_for(station).series_in(spec),
+    }
+}
 
-    let y = now.year();
-    let m = now.month();
-    let d = now.day() as f64
-        + (now.hour() as f64 + now.minute() as f64 / 60.0 + now.second() as f64 / 3600.0) / 24.0;
-
-    // 2. Moon ephemeris
-    let eph = crate::lunar::schaefer_moon(y, m, d);
-    let tau: f32 = std::f32::consts::TAU;
-
-    // ---- 3. Two-constituent equilibrium tide -----------------------------
-
-    // Lunar M2 amplitude for Portland, ME (NOAA harmonics)
-    const A_M2: f32 = 4.51; // ft
-    const P_M2_HRS: f32 = 12.42;
-
-    // Solar S2 amplitude for Portland, ME
-    const A_S2: f32 = 0.68; // ft
-    const P_S2_HRS: f32 = 12.00;
-
-    // High-water interval (Moon transit → local HW) ≈ 3 h 35 m
-    const LUNITIDAL_OFFSET_HRS: f32 = 3.59; // hrs
-
-    // Real-time phase of each constituent
-    let daily_phase_m2 = ((now.timestamp() + (LUNITIDAL_OFFSET_HRS * 3600.0) as i64)
-        .rem_euclid((P_M2_HRS * 3600.0) as i64) as f32)
-        / (P_M2_HRS * 3600.0)
-        * tau;
-
-    let moon_phase_angle = (eph.age_days / 29.530_588_2) as f32 * tau;
-    let daily_phase_s2 = daily_phase_m2 + 2.0 * moon_phase_angle;
-
-    const MEAN_LEVEL_FT: f32 = 5.0; // chart datum offset
-    let mut samples = Vec::with_capacity(145);
-    for m in (-720..=720).step_by(10) {
-        let theta_m2 = daily_phase_m2 + (m as f32 / 60.0) * tau / P_M2_HRS;
-        let theta_s2 = daily_phase_s2 + (m as f32 / 60.0) * tau / P_S2_HRS;
-        let tide_ft = MEAN_LEVEL_FT + A_M2 * theta_m2.sin() + A_S2 * theta_s2.sin();
-        samples.push(Sample {
-            mins_rel: m,
-            tide_ft,
-        });
+/// Offline curve fitted to the tail of a real series
+///
+/// `height(t) = mean + amplitude · cos(ω·(t − epoch) + phase)` with ω the M2
+/// speed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// Mean level in feet above the station datum
+    pub mean_ft: f32,
+    /// Half the tidal range in feet
+    pub amplitude_ft: f32,
+    /// M2 phase in radians at `epoch` (zero at high water)
+    pub phase_rad: f32,
+    /// Time of the last real sample the fit used
+    pub epoch: DateTime<Utc>,
+}
+
+impl Calibration {
+    /// Least-squares fit over the last M2 period of `series`
+    ///
+    /// Returns `None` when the series has no base time, too few samples in
+    /// that period, or is already offline (fitting the model to itself).
+    pub fn fit(series: &TideSeries) -> Option<Self> {
+        if series.is_offline() {
+            return None;
+        }
+        let epoch = series.time_of(series.samples.last()?)?;
+        let points: Vec<(f64, f64)> = series
+            .samples
+            .iter()
+            .filter_map(|s| {
+                let hours = (series.time_of(s)? - epoch).num_seconds() as f64 / 3600.0;
+                (hours > -M2_PERIOD_HRS).then_some((hours, s.tide_ft as f64))
+            })
+            .collect();
+        if points.len() < MIN_CALIBRATION_SAMPLES {
+            return None;
+        }
+
+        // Normal equations for h = c0 + c1·cos(ωt) + c2·sin(ωt)
+        let omega = std::f64::consts::TAU / M2_PERIOD_HRS;
+        let mut ata = [[0.0f64; 3]; 3];
+        let mut atb = [0.0f64; 3];
+        for &(hours, height) in &points {
+            let row = [1.0, (omega * hours).cos(), (omega * hours).sin()];
+            for i in 0..3 {
+                for j in 0..3 {
+                    ata[i][j] += row[i] * row[j];
+                }
+                atb[i] += row[i] * height;
+            }
+        }
+        let [c0, c1, c2] = solve3(ata, atb)?;
+
+        Some(Self {
+            mean_ft: c0 as f32,
+            amplitude_ft: c1.hypot(c2) as f32,
+            phase_rad: (-c2).atan2(c1) as f32,
+            epoch,
+        })
+    }
+
+    /// Predicted height at `t`
+    pub fn height(&self, t: DateTime<Utc>) -> f32 {
+        let hours = (t - self.epoch).num_seconds() as f64 / 3600.0;
+        let theta = std::f64::consts::TAU * hours / M2_PERIOD_HRS + self.phase_rad as f64;
+        self.mean_ft + self.amplitude_ft * theta.cos() as f32
+    }
+
+    /// Offline series on the standard grid around `now`
+    pub fn series(&self, now: DateTime<Utc>) -> TideSeries {
+        self.series_in(grid::Window::standard(now))
+    }
+
+    /// Offline series of the shape `spec` asks for, or over a bare window
+    /// at the standard interval
+    ///
+    /// # Panics
+    /// If the window isn't a whole number of intervals either side of its
+    /// center, so the grid misses "now".
+    pub fn series_in(&self, spec: impl Into<grid::SeriesSpec>) -> TideSeries {
+        let spec = spec.into();
+        let now = spec.window.center;
+        let samples = spec.generate(|m| self.height(now + Duration::minutes(m as i64)));
+        TideSeries::builder(samples)
+            .source(DataSource::Fallback)
+            .base_time(now)
+            .build()
+            .expect("grid windows include now")
+    }
+}
+
+/// Solve the 3×3 system `a·x = b` by Cramer's rule; `None` when singular
+fn solve3(a: [[f64; 3]; 3], b: [f64; 3]) -> Option<[f64; 3]> {
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(a);
+    if d.abs() < 1e-9 {
+        return None;
+    }
+    let mut x = [0.0; 3];
+    for (k, xk) in x.iter_mut().enumerate() {
+        let mut m = a;
+        for (row, bi) in m.iter_mut().zip(b) {
+            row[k] = bi;
+        }
+        *xk = det(m) / d;
+    }
+    Some(x)
+}
+
+/// Generate an approximate tide series for the next 24 h with the default
+/// (Portland, ME) [`ModelConstants`].
+/// If `now` is `None`, fall back to `Utc::now()`.
+pub fn approximate(now: Option<DateTime<Utc>>) -> TideSeries {
+    ModelConstants::default().series(now.unwrap_or_else(Utc::now))
+}
+
+/// Constants of the two-constituent approximation
+///
+/// Set under `[fallback]` for every station, or `[station.model]` for one;
+/// the defaults are Portland, ME's (NOAA harmonics).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ModelConstants {
+    /// Mean level in feet above the station datum
+    pub mean_level_ft: f32,
+    /// Lunar M2 amplitude in feet
+    pub m2_amplitude_ft: f32,
+    /// Solar S2 amplitude in feet
+    pub s2_amplitude_ft: f32,
+    /// High-water interval in hours (moon transit to local high water)
+    pub lunitidal_offset_hours: f32,
+}
+
+impl Default for ModelConstants {
+    fn default() -> Self {
+        Self {
+            mean_level_ft: 5.0,
+            m2_amplitude_ft: 4.51,
+            s2_amplitude_ft: 0.68,
+            lunitidal_offset_hours: 3.59, // ≈ 3 h 35 m
+        }
     }
+}
 
-    TideSeries {
-        samples,
-        offline: true,
+impl ModelConstants {
+    /// Approximate series on the standard grid centered on `now`
+    pub fn series(&self, now: DateTime<Utc>) -> TideSeries {
+        self.series_in(grid::Window::standard(now))
+    }
+
+    /// Approximate series of the shape `spec` asks for, or over a bare
+    /// window at the standard interval
+    ///
+    /// Phase and amplitude are modulated using the Schaefer Moon algorithm.
+    ///
+    /// # Panics
+    /// If the window isn't a whole number of intervals either side of its
+    /// center, so the grid misses "now".
+    pub fn series_in(&self, spec: impl Into<grid::SeriesSpec>) -> TideSeries {
+        let spec = spec.into();
+        let now = spec.window.center;
+        // Moon ephemeris
+        let eph = crate::lunar::moon_at(now);
+        let tau: f32 = std::f32::consts::TAU;
+
+        // ---- Two-constituent equilibrium tide ----------------------------
+        const P_M2_HRS: f32 = 12.42;
+        const P_S2_HRS: f32 = 12.00;
+
+        // Real-time phase of each constituent
+        let daily_phase_m2 = ((now.timestamp() + (self.lunitidal_offset_hours * 3600.0) as i64)
+            .rem_euclid((P_M2_HRS * 3600.0) as i64) as f32)
+            / (P_M2_HRS * 3600.0)
+            * tau;
+
+        let moon_phase_angle = (eph.age_days / crate::lunar::SYNODIC_MONTH_DAYS) as f32 * tau;
+        let daily_phase_s2 = daily_phase_m2 + 2.0 * moon_phase_angle;
+
+        let samples = spec.generate(|m| {
+            let theta_m2 = daily_phase_m2 + (m as f32 / 60.0) * tau / P_M2_HRS;
+            let theta_s2 = daily_phase_s2 + (m as f32 / 60.0) * tau / P_S2_HRS;
+            self.mean_level_ft
+                + self.m2_amplitude_ft * theta_m2.sin()
+                + self.s2_amplitude_ft * theta_s2.sin()
+        });
+
+        TideSeries::builder(samples)
+            .source(DataSource::Fallback)
+            .base_time(now)
+            .build()
+            .expect("grid windows include now")
     }
 }
 
@@ -144,6 +335,67 @@ mod tests {
         );
     }
 
+    /// A real-looking series: M2 plus a little S2 around `now`
+    fn fetched(now: DateTime<Utc>) -> TideSeries {
+        let samples = grid::generate(
+            grid::Window::standard(now),
+            Duration::minutes(grid::INTERVAL_MINS),
+            |m| {
+                let hours = m as f32 / 60.0;
+                4.9 + 4.4 * (hours * std::f32::consts::TAU / 12.42 + 1.0).cos()
+                    + 0.3 * (hours * std::f32::consts::TAU / 12.0).cos()
+            },
+        );
+        TideSeries {
+            samples,
+            source: DataSource::Noaa,
+            base_time: Some(now),
+            extremes: None,
+            fetched_at: None,
+        }
+    }
+
+    #[test]
+    fn test_calibration_joins_last_real_sample() {
+        let fetched_at = Utc.with_ymd_and_hms(2025, 7, 24, 6, 0, 0).unwrap();
+        let real = fetched(fetched_at);
+        let calibration = Calibration::fit(&real).unwrap();
+        assert!((calibration.mean_ft - 4.9).abs() < 0.2);
+        assert!((calibration.amplitude_ft - 4.4).abs() < 0.4);
+
+        // Continuous with the end of the real data...
+        let last = real.samples.last().unwrap();
+        let end = real.time_of(last).unwrap();
+        assert!((calibration.height(end) - last.tide_ft).abs() < 0.3);
+
+        // ...and tracking it across the fitted period
+        for s in real.samples.iter().filter(|s| s.mins_rel > 0) {
+            let t = real.time_of(s).unwrap();
+            assert!((calibration.height(t) - s.tide_ft).abs() < 0.4);
+        }
+
+        let later = end + Duration::hours(3);
+        let series = calibration.series(later);
+        assert!(series.is_offline());
+        assert_eq!(series.samples.len(), 145);
+        let now_sample = series.samples.iter().find(|s| s.mins_rel == 0).unwrap();
+        assert_eq!(now_sample.tide_ft, calibration.height(later));
+    }
+
+    #[test]
+    fn test_calibration_needs_real_data() {
+        let now = Utc.with_ymd_and_hms(2025, 7, 24, 6, 0, 0).unwrap();
+        assert_eq!(Calibration::fit(&approximate(Some(now))), None);
+        let mut short = fetched(now);
+        short.samples.truncate(145 - 138);
+        assert_eq!(Calibration::fit(&short), None);
+        let unanchored = TideSeries {
+            base_time: None,
+            ..fetched(now)
+        };
+        assert_eq!(Calibration::fit(&unanchored), None);
+    }
+
     #[test]
     fn test_approximate_known_high_and_low() {
         // Pick a time and check if we can get a high or low at mins_rel=0
@@ -0,0 +1,281 @@
+//! # C API
+//!
+//! A small `extern "C"` interface for programs on the same Pi that want the
+//! tide data without shelling out to `tide-tracker`, e.g. a C++ kiosk. Build
+//! the library with the `ffi` feature as a shared (or static) library and
+//! include `include/tide_tracker.h`:
+//!
+//! ```text
+//! cargo rustc --release --lib --features ffi --crate-type cdylib \
+//!     --config 'profile.release.panic="unwind"'
+//! cbindgen --config cbindgen.toml --output include/tide_tracker.h
+//! ```
+//!
+//! ```c
+//! TideData *tide = tide_fetch(NULL);
+//! for (size_t i = 0; i < tide_sample_count(tide); i++) {
+//!     int16_t mins_rel;
+//!     float tide_ft;
+//!     tide_sample_at(tide, i, &mins_rel, &tide_ft);
+//! }
+//! tide_render_png(tide, "/tmp/tides.png");
+//! tide_free(tide);
+//! ```
+//!
+//! A fetch goes the way a panel refresh does for the primary station (cache,
+//! then the providers, then the offline model, within `refresh_budget_secs`),
+//! so it always has data; [`tide_is_offline`] tells when it's the model's.
+//! A `TideData` doesn't change once fetched and may be read from any thread.
+//!
+//! A panic never crosses into C: each function returns its error value
+//! instead (NULL, 0, false or -3). That takes the unwinding build above; the
+//! release profile's `panic = "abort"` would end the caller's process.
+
+use crate::budget::RefreshBudget;
+use crate::config::Config;
+use crate::panel;
+use crate::stations::{self, StationSeries};
+use chrono::Utc;
+use embedded_graphics::prelude::Size;
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+use tracing::warn;
+
+/// Fetched tide data for the primary station, freed with [`tide_free`]
+pub struct TideData {
+    config: Config,
+    displayed: StationSeries,
+}
+
+/// Run the body of export `name`, or return `on_panic` if it panics
+fn guard<T>(name: &str, on_panic: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|_| {
+        warn!("{} panicked", name);
+        on_panic
+    })
+}
+
+/// Fetch the primary station's series
+///
+/// `config_path` names the configuration file, or is NULL to find it the way
+/// `tide-tracker` does. Returns NULL when `config_path` isn't UTF-8 or the
+/// async runtime can't start, or on a panic.
+///
+/// # Safety
+///
+/// `config_path` must be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tide_fetch(config_path: *const c_char) -> *mut TideData {
+    guard("tide_fetch", ptr::null_mut(), || fetch(config_path))
+}
+
+unsafe fn fetch(config_path: *const c_char) -> *mut TideData {
+    let mut config = if config_path.is_null() {
+        Config::load()
+    } else {
+        match CStr::from_ptr(config_path).to_str() {
+            Ok(path) => Config::load_from_path(path),
+            Err(_) => return ptr::null_mut(),
+        }
+    };
+    // Only the primary station, so a rotating panel's position is left alone
+    config.stations.clear();
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            warn!("Cannot start the runtime for tide_fetch: {}", e);
+            return ptr::null_mut();
+        }
+    };
+    let budget = RefreshBudget::start(&config);
+    let displayed = runtime
+        .block_on(stations::fetch_for_display(&config, &budget, Utc::now()))
+        .swap_remove(0);
+    Box::into_raw(Box::new(TideData { config, displayed }))
+}
+
+/// Number of samples, 0 for NULL or on a panic
+///
+/// # Safety
+///
+/// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn tide_sample_count(data: *const TideData) -> usize {
+    guard("tide_sample_count", 0, || {
+        data.as_ref()
+            .map_or(0, |data| data.displayed.series.samples.len())
+    })
+}
+
+/// Sample `index`: minutes relative to [`tide_base_time`] and height in feet
+///
+/// Returns false, leaving the outputs alone, when `index` is out of range or
+/// on a panic.
+///
+/// # Safety
+///
+/// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed;
+/// `mins_rel` and `tide_ft` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tide_sample_at(
+    data: *const TideData,
+    index: usize,
+    mins_rel: *mut i16,
+    tide_ft: *mut f32,
+) -> bool {
+    guard("tide_sample_at", false, || {
+        let Some(sample) = data
+            .as_ref()
+            .and_then(|data| data.displayed.series.samples.get(index))
+        else {
+            return false;
+        };
+        *mins_rel = sample.mins_rel;
+        *tide_ft = sample.tide_ft;
+        true
+    })
+}
+
+/// The instant `mins_rel` 0 refers to, in Unix seconds (0 when unknown or
+/// on a panic)
+///
+/// # Safety
+///
+/// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn tide_base_time(data: *const TideData) -> i64 {
+    guard("tide_base_time", 0, || {
+        data.as_ref()
+            .and_then(|data| data.displayed.series.base_time)
+            .map_or(0, |t| t.timestamp())
+    })
+}
+
+/// Whether the series comes from the offline model rather than NOAA (false
+/// on a panic)
+///
+/// # Safety
+///
+/// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn tide_is_offline(data: *const TideData) -> bool {
+    guard("tide_is_offline", false, || {
+        data.as_ref()
+            .is_some_and(|data| data.displayed.series.is_offline())
+    })
+}
+
+/// Save the panel frame `tide-tracker --png` would draw for this data at
+/// `path` (BMP when it ends in `.bmp`)
+///
+/// Returns 0 on success, -1 for bad arguments, -2 when the image can't be
+/// written and -3 on a panic.
+///
+/// # Safety
+///
+/// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed;
+/// `path` must be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tide_render_png(data: *const TideData, path: *const c_char) -> c_int {
+    guard("tide_render_png", -3, || render_png(data, path))
+}
+
+unsafe fn render_png(data: *const TideData, path: *const c_char) -> c_int {
+    let Some(data) = data.as_ref() else {
+        return -1;
+    };
+    if path.is_null() {
+        return -1;
+    }
+    let Ok(path) = CStr::from_ptr(path).to_str() else {
+        return -1;
+    };
+    let (width, height) = data.config.display.hardware.panel.size();
+    let frame = panel::compose_frame(
+        std::slice::from_ref(&data.displayed),
+        &data.config,
+        Size::new(width, height),
+        Utc::now(),
+    );
+    match panel::save_image(&frame, Path::new(path)) {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("Cannot save the tide image to {}: {}", path, e);
+            -2
+        }
+    }
+}
+
+/// Free data from [`tide_fetch`]; NULL is ignored
+///
+/// # Safety
+///
+/// `data` must be NULL or a pointer from [`tide_fetch`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn tide_free(data: *mut TideData) {
+    guard("tide_free", (), || {
+        if !data.is_null() {
+            drop(Box::from_raw(data));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fallback;
+
+    #[test]
+    fn test_c_api_reads_and_renders() {
+        let config = Config::default();
+        let displayed = StationSeries {
+            station: config.station.clone(),
+            series: fallback::approximate(None),
+        };
+        let expected = displayed.series.samples.clone();
+        let data = Box::into_raw(Box::new(TideData { config, displayed }));
+
+        unsafe {
+            assert_eq!(tide_sample_count(data), expected.len());
+            assert!(tide_is_offline(data));
+            assert!(tide_base_time(data) > 0);
+            let (mut mins_rel, mut tide_ft) = (0i16, 0f32);
+            assert!(tide_sample_at(data, 1, &mut mins_rel, &mut tide_ft));
+            assert_eq!(
+                (mins_rel, tide_ft),
+                (expected[1].mins_rel, expected[1].tide_ft)
+            );
+            assert!(!tide_sample_at(
+                data,
+                expected.len(),
+                &mut mins_rel,
+                &mut tide_ft
+            ));
+
+            let dir = tempfile::tempdir().unwrap();
+            let path =
+                std::ffi::CString::new(dir.path().join("tides.png").to_str().unwrap().to_string())
+                    .unwrap();
+            assert_eq!(tide_render_png(data, path.as_ptr()), 0);
+            assert!(dir.path().join("tides.png").exists());
+            assert_eq!(tide_render_png(data, ptr::null()), -1);
+
+            tide_free(data);
+            assert_eq!(tide_sample_count(ptr::null()), 0);
+            tide_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_panics_become_the_error_value() {
+        assert_eq!(guard("test", -3, || panic!("bad data")), -3);
+        assert!(guard("test", ptr::null_mut::<TideData>(), || panic!()).is_null());
+        assert_eq!(guard("test", 0, || 7), 7);
+    }
+}
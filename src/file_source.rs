@@ -0,0 +1,232 @@
+//! # File Source
+//!
+//! Predictions read from a local file instead of NOAA, so demos, screenshots
+//! and integration tests run offline and draw the same chart every time. The
+//! `file` provider reads the station's `data_file`:
+//!
+//! ```toml
+//! [station]
+//! providers = ["file"]
+//! data_file = "demo/portland.csv"
+//! ```
+//!
+//! The format goes by the extension:
+//!
+//! - `.json`: a NOAA predictions response saved as is, e.g. with `curl` from
+//!   the URL in the [`tide_data`](crate::tide_data) docs with
+//!   `time_zone=lst_ldt` for station-local times
+//! - anything else: CSV of `time,height_ft` rows as `tide-tracker export
+//!   --csv` writes them, so an export replays as it was drawn. Times carry
+//!   their UTC offset (`2025-07-01T08:00:00-04:00`) or are taken as station
+//!   time (`2025-07-01 08:00`).
+//!
+//! The points should be evenly spaced, at least hourly. The provider cuts the
+//! window around the current time, so its file has to cover today; a test or
+//! screenshot script passes a window around its own "now" to [`series_in`]:
+//!
+//! ```no_run
+//! use chrono::{TimeZone, Utc};
+//! use tide_clock_lib::{config::Config, file_source};
+//!
+//! let config = Config::default();
+//! let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+//! let series = file_source::series_in(
+//!     "demo/portland.csv".as_ref(),
+//!     &config.station,
+//!     config.display.series_spec(now),
+//! )
+//! .unwrap();
+//! ```
+
+use crate::config::StationConfig;
+use crate::grid::SeriesSpec;
+use crate::series_builder::TideSeriesBuilder;
+use crate::tide_data::{self, TideError};
+use crate::{DataSource, TideSeries};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::fs;
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+use tracing::info;
+
+/// Why a predictions file could not be used
+#[derive(Error, Debug)]
+pub enum FileSourceError {
+    #[error("no data_file set for the station")]
+    NotConfigured,
+    #[error("reading data file: {0}")]
+    Io(#[from] io::Error),
+    #[error("line {0}: expected `time,height_ft`")]
+    BadRow(usize),
+    #[error("not a NOAA predictions response")]
+    BadJson,
+    #[error("the data file doesn't cover the chart window")]
+    NotCovered,
+}
+
+/// Times without an offset that CSV rows may use, in station time
+const NAIVE_FORMATS: [&str; 3] = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+
+/// Every `(time, height)` point in the file at `path`
+pub fn read_points(
+    path: &Path,
+    station: &StationConfig,
+) -> Result<Vec<(DateTime<Utc>, f32)>, FileSourceError> {
+    let text = fs::read_to_string(path)?;
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        tide_data::parse_predictions(&text, station).map_err(|_| FileSourceError::BadJson)
+    } else {
+        parse_csv(&text, station)
+    }
+}
+
+/// The file's predictions on the display grid `spec` asks for
+///
+/// The series is real data rather than the model's, so it isn't marked
+/// offline; it has no fetch time, so it never shows as stale either.
+pub fn series_in(
+    path: &Path,
+    station: &StationConfig,
+    spec: impl Into<SeriesSpec>,
+) -> Result<TideSeries, TideError> {
+    let points = read_points(path, station)?;
+    let mut series = TideSeriesBuilder::from_points(points, spec)
+        .ok_or(FileSourceError::NotCovered)?
+        .source(DataSource::File)
+        .build()?;
+    let repaired = series.validate()?;
+    if repaired > 0 {
+        info!(
+            "Repaired {} implausible samples in {}",
+            repaired,
+            path.display()
+        );
+    }
+    Ok(series)
+}
+
+/// Points of `time,height_ft` rows; a header and blank lines are skipped
+fn parse_csv(
+    text: &str,
+    station: &StationConfig,
+) -> Result<Vec<(DateTime<Utc>, f32)>, FileSourceError> {
+    let mut points = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.starts_with("time")) {
+            continue;
+        }
+        let bad_row = || FileSourceError::BadRow(i + 1);
+        let (time, height) = line.split_once(',').ok_or_else(bad_row)?;
+        let previous = points.last().map(|&(t, _)| t);
+        let time = parse_time(time.trim(), station, previous).ok_or_else(bad_row)?;
+        let height: f32 = height.trim().parse().map_err(|_| bad_row())?;
+        points.push((time, height));
+    }
+    Ok(points)
+}
+
+/// An RFC 3339 time, or one of [`NAIVE_FORMATS`] at the station, the row
+/// before having been at `previous`
+fn parse_time(
+    text: &str,
+    station: &StationConfig,
+    previous: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(text) {
+        return Some(t.with_timezone(&Utc));
+    }
+    NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .and_then(|naive| station.from_local_after(naive, previous))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::export;
+    use crate::grid::Window;
+    use chrono::{Duration, TimeZone};
+    use std::io::Write;
+
+    #[test]
+    fn test_replays_an_export_and_a_noaa_response() {
+        let mut station = Config::default().station;
+        station.timezone = Some(chrono_tz::America::New_York);
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let window = Window::standard(now);
+        let dir = tempfile::tempdir().unwrap();
+
+        // An export of the offline model comes back sample for sample
+        let model = crate::fallback::ModelConstants::default().series_in(window);
+        let csv = dir.path().join("tides.csv");
+        export::write_csv(fs::File::create(&csv).unwrap(), &model, &station, now).unwrap();
+        let replayed = series_in(&csv, &station, window).unwrap();
+        assert_eq!(replayed.base_time, Some(now));
+        assert_eq!(replayed.source, DataSource::File);
+        assert_eq!(replayed.samples.len(), model.samples.len());
+        for (a, b) in replayed.samples.iter().zip(&model.samples) {
+            assert_eq!(a.mins_rel, b.mins_rel);
+            assert!((a.tide_ft - b.tide_ft).abs() < 0.01);
+        }
+
+        // Hourly NOAA JSON in station time (EDT, UTC-4)
+        let predictions: Vec<String> = (-16..=16)
+            .map(|h| {
+                let t = (now + Duration::hours(h)).with_timezone(&chrono_tz::America::New_York);
+                format!(
+                    r#"{{"t":"{}","v":"{:.3}"}}"#,
+                    t.format("%Y-%m-%d %H:%M"),
+                    5.0 + h as f32 * 0.1
+                )
+            })
+            .collect();
+        let json = dir.path().join("noaa.json");
+        fs::write(
+            &json,
+            format!(r#"{{"predictions":[{}]}}"#, predictions.join(",")),
+        )
+        .unwrap();
+        let series = series_in(&json, &station, window).unwrap();
+        assert_eq!(series.height_now(), Some(5.0));
+
+        // Onto a finer grid just as well
+        let fine = series_in(&json, &station, SeriesSpec::hours(now, 12, 5)).unwrap();
+        assert_eq!(fine.samples.len(), 289);
+        assert_eq!(fine.height_now(), Some(5.0));
+
+        // A window the file doesn't reach
+        let later = Window::standard(now + Duration::days(2));
+        assert!(matches!(
+            series_in(&json, &station, later),
+            Err(TideError::File(FileSourceError::NotCovered))
+        ));
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let mut station = Config::default().station;
+        station.timezone = Some(chrono_tz::UTC);
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "time,height_ft\n2025-07-01 08:00,1.5\n\n2025-07-01T09:00:00+01:00,2"
+        )
+        .unwrap();
+        let points = read_points(file.path(), &station).unwrap();
+        let t = Utc.with_ymd_and_hms(2025, 7, 1, 8, 0, 0).unwrap();
+        assert_eq!(points, vec![(t, 1.5), (t, 2.0)]);
+
+        writeln!(file, "2025-07-01 10:00").unwrap();
+        assert!(matches!(
+            read_points(file.path(), &station),
+            Err(FileSourceError::BadRow(5))
+        ));
+    }
+}
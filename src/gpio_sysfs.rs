@@ -1,6 +1,41 @@
 // src/gpio_cdev.rs   (or gpio_sysfs.rs if you named it that)
 use gpio_cdev::{Chip, LineRequestFlags};
+use std::path::Path;
 use tide_clock_lib::epd4in2b_v2::{EpdError, GpioPin, InputPin};
+use tracing::debug;
+
+/// gpiochip used when none is configured and none is recognised
+pub const DEFAULT_GPIOCHIP: &str = "/dev/gpiochip0";
+
+/// Open `path`, or without one the chip whose line `offset` is named
+/// `GPIO<offset>` as the 40-pin header's lines are (gpiochip4 on a Pi 5, and
+/// on some CM4 carriers), falling back to [`DEFAULT_GPIOCHIP`]
+#[allow(dead_code)]
+pub fn open_chip(path: Option<&Path>, offset: u32) -> Result<Chip, EpdError> {
+    if let Some(path) = path {
+        return Chip::new(path).map_err(|e| EpdError::Gpio(format!("{}: {e}", path.display())));
+    }
+    let label = format!("GPIO{offset}");
+    let found = gpio_cdev::chips()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .find_map(|mut chip| {
+            let named = chip
+                .get_line(offset)
+                .and_then(|line| line.info())
+                .is_ok_and(|info| info.name() == Some(label.as_str()));
+            named.then_some(chip)
+        });
+    match found {
+        Some(chip) => {
+            debug!("Found {} on {}", label, chip.path().display());
+            Ok(chip)
+        }
+        None => Chip::new(DEFAULT_GPIOCHIP)
+            .map_err(|e| EpdError::Gpio(format!("{DEFAULT_GPIOCHIP}: {e}"))),
+    }
+}
 
 pub struct CdevOutputPin {
     line: gpio_cdev::LineHandle,
@@ -14,9 +49,9 @@ impl CdevOutputPin {
     pub fn new(chip: &mut Chip, offset: u32) -> Result<Self, EpdError> {
         let line = chip
             .get_line(offset)
-            .map_err(|e| EpdError(e.to_string()))?
+            .map_err(|e| EpdError::Gpio(e.to_string()))?
             .request(LineRequestFlags::OUTPUT, 0, "tide-tracker")
-            .map_err(|e| EpdError(e.to_string()))?;
+            .map_err(|e| EpdError::Gpio(e.to_string()))?;
         Ok(Self { line })
     }
 }
@@ -25,23 +60,31 @@ impl CdevInputPin {
     pub fn new(chip: &mut Chip, offset: u32) -> Result<Self, EpdError> {
         let line = chip
             .get_line(offset)
-            .map_err(|e| EpdError(e.to_string()))?
+            .map_err(|e| EpdError::Gpio(e.to_string()))?
             .request(LineRequestFlags::INPUT, 0, "tide-tracker")
-            .map_err(|e| EpdError(e.to_string()))?;
+            .map_err(|e| EpdError::Gpio(e.to_string()))?;
         Ok(Self { line })
     }
 }
 
 impl GpioPin for CdevOutputPin {
     fn set_high(&mut self) -> Result<(), EpdError> {
-        self.line.set_value(1).map_err(|e| EpdError(e.to_string()))
+        self.line
+            .set_value(1)
+            .map_err(|e| EpdError::Gpio(e.to_string()))
     }
     fn set_low(&mut self) -> Result<(), EpdError> {
-        self.line.set_value(0).map_err(|e| EpdError(e.to_string()))
+        self.line
+            .set_value(0)
+            .map_err(|e| EpdError::Gpio(e.to_string()))
     }
 }
 impl InputPin for CdevInputPin {
     fn is_high(&self) -> Result<bool, EpdError> {
-        Ok(self.line.get_value().map_err(|e| EpdError(e.to_string()))? == 1)
+        Ok(self
+            .line
+            .get_value()
+            .map_err(|e| EpdError::Gpio(e.to_string()))?
+            == 1)
     }
 }
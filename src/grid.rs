@@ -0,0 +1,598 @@
+//! # Sample Grid
+//!
+//! Every [`TideSeries`](crate::TideSeries) uses the same grid: samples every
+//! 10 minutes from 12 hours before "now" to 12 hours after, 145 in total, or
+//! as many hours either side as `time_window_hours` asks for (up to
+//! [`MAX_HALF_WIDTH_HOURS`]) every `sample_interval_minutes`. A
+//! [`SeriesSpec`] carries both, and is what providers are asked for.
+//! Providers produce data in different shapes (hourly NOAA predictions, a
+//! closed-form model, ...), so this module owns the grid itself:
+//!
+//! - [`generate`] evaluates a function of time at every grid point
+//! - [`resample`] interpolates timestamped points onto the grid
+//!
+//! Once fetched, a series can be reshaped without going back to the
+//! provider: [`TideSeries::resample`] changes the step (hourly points for a
+//! sparkline, say) and [`TideSeries::window`] cuts out part of it (one day
+//! of a multi-day fetch), both through the same [monotone
+//! cubic](crate::interp::MonotoneCubic) as [`resample`].
+//!
+//! ```
+//! use chrono::{Duration, TimeZone, Utc};
+//! use tide_clock_lib::grid::{self, Window};
+//!
+//! let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+//! let points = vec![(now - Duration::hours(13), 1.0), (now + Duration::hours(13), 3.0)];
+//! let samples = grid::resample(&points, Window::standard(now), Duration::minutes(10)).unwrap();
+//! assert_eq!(samples.len(), 145);
+//! ```
+
+use crate::interp::MonotoneCubic;
+use crate::{Sample, TideSeries};
+use alloc::vec::Vec;
+use chrono::{DateTime, Duration, Utc};
+
+/// Minutes on either side of "now" covered by the standard grid
+pub const HALF_WIDTH_MINS: i64 = 720;
+
+/// Widest window offered, in hours either side of "now" (a 72 hour chart)
+pub const MAX_HALF_WIDTH_HOURS: i64 = 36;
+
+/// Minutes between samples on the standard grid
+pub const INTERVAL_MINS: i64 = 10;
+
+/// Time span a grid covers: `center - half_width ..= center + half_width`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    /// Instant that becomes `mins_rel == 0`
+    pub center: DateTime<Utc>,
+    /// Distance from the center to either edge
+    pub half_width: Duration,
+}
+
+impl Window {
+    pub fn around(center: DateTime<Utc>, half_width: Duration) -> Self {
+        Self { center, half_width }
+    }
+
+    /// The ±12 hour window used for the display
+    pub fn standard(center: DateTime<Utc>) -> Self {
+        Self::around(center, Duration::minutes(HALF_WIDTH_MINS))
+    }
+
+    /// `hours` either side of `center`, clamped to 1..=[`MAX_HALF_WIDTH_HOURS`]
+    pub fn hours(center: DateTime<Utc>, hours: i64) -> Self {
+        Self::around(
+            center,
+            Duration::hours(hours.clamp(1, MAX_HALF_WIDTH_HOURS)),
+        )
+    }
+
+    /// Whether `t` lies inside the window (edges included)
+    pub fn contains(&self, t: DateTime<Utc>) -> bool {
+        (self.center - self.half_width..=self.center + self.half_width).contains(&t)
+    }
+}
+
+/// Steps a grid may take, in minutes: the divisors of an hour, so a window
+/// of whole hours has a sample at "now" and at both edges
+pub const INTERVALS_MINS: [i64; 12] = [1, 2, 3, 4, 5, 6, 10, 12, 15, 20, 30, 60];
+
+/// Shape of a series: the window it covers and the step between samples
+///
+/// What providers are asked for. A bare [`Window`] converts into one at the
+/// standard [`INTERVAL_MINS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeriesSpec {
+    pub window: Window,
+    /// Time between samples
+    pub interval: Duration,
+}
+
+impl SeriesSpec {
+    pub fn new(window: Window, interval: Duration) -> Self {
+        Self { window, interval }
+    }
+
+    /// The ±12 hour window every 10 minutes
+    pub fn standard(center: DateTime<Utc>) -> Self {
+        Window::standard(center).into()
+    }
+
+    /// `hours` either side of `center` (see [`Window::hours`]) every
+    /// `interval_mins`, rounded down to one of [`INTERVALS_MINS`]
+    pub fn hours(center: DateTime<Utc>, hours: i64, interval_mins: i64) -> Self {
+        let step = INTERVALS_MINS
+            .into_iter()
+            .rev()
+            .find(|&step| step <= interval_mins)
+            .unwrap_or(INTERVALS_MINS[0]);
+        Self::new(Window::hours(center, hours), Duration::minutes(step))
+    }
+
+    /// Samples in a series of this shape, see [`sample_count`]
+    pub fn sample_count(&self) -> usize {
+        sample_count(self.window.half_width, self.interval)
+    }
+
+    /// Minute offsets of the samples, see [`offsets`]
+    pub fn offsets(&self) -> impl Iterator<Item = i16> {
+        offsets(self.window, self.interval)
+    }
+
+    /// Evaluate `height(mins_rel)` at every sample, see [`generate`]
+    pub fn generate(&self, height: impl FnMut(i16) -> f32) -> Vec<Sample> {
+        generate(self.window, self.interval, height)
+    }
+
+    /// Interpolate `(time, height)` points onto the samples, see [`resample`]
+    pub fn resample(&self, points: &[(DateTime<Utc>, f32)]) -> Option<Vec<Sample>> {
+        resample(points, self.window, self.interval)
+    }
+}
+
+impl From<Window> for SeriesSpec {
+    fn from(window: Window) -> Self {
+        Self::new(window, Duration::minutes(INTERVAL_MINS))
+    }
+}
+
+/// Minute offsets of the grid points, from `-half_width` to `+half_width`
+///
+/// # Panics
+/// If `interval` is not positive.
+pub fn offsets(window: Window, interval: Duration) -> impl Iterator<Item = i16> {
+    let step = interval.num_minutes();
+    assert!(step > 0, "grid interval must be at least one minute");
+    let half = window.half_width.num_minutes();
+    (-half..=half)
+        .step_by(step as usize)
+        .map(|m| m.clamp(i16::MIN as i64, i16::MAX as i64) as i16)
+}
+
+/// Grid points `half_width` either side of "now" at `interval`: 145 for ±12
+/// hours at 10 minutes, 433 for ±36
+///
+/// # Panics
+/// If `interval` is not positive.
+pub fn sample_count(half_width: Duration, interval: Duration) -> usize {
+    let step = interval.num_minutes();
+    assert!(step > 0, "grid interval must be at least one minute");
+    (2 * (half_width.num_minutes() / step) + 1) as usize
+}
+
+/// Evaluate `height(mins_rel)` at every grid point
+pub fn generate(
+    window: Window,
+    interval: Duration,
+    mut height: impl FnMut(i16) -> f32,
+) -> Vec<Sample> {
+    let mut samples = Vec::with_capacity(sample_count(window.half_width, interval));
+    samples.extend(offsets(window, interval).map(|mins_rel| Sample {
+        mins_rel,
+        tide_ft: height(mins_rel),
+        modeled: false,
+    }));
+    samples
+}
+
+/// Interpolate `(time, height)` points onto the grid
+///
+/// Uses a [monotone cubic](crate::interp::MonotoneCubic), so highs and lows
+/// come out rounded without overshooting the predicted heights. Points may
+/// be in any order; of several at the same time the first is used. Grid
+/// points before the first or after the last point take the nearest point's
+/// height rather than extrapolating. Returns `None` with fewer than two
+/// distinct times.
+pub fn resample(
+    points: &[(DateTime<Utc>, f32)],
+    window: Window,
+    interval: Duration,
+) -> Option<Vec<Sample>> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|&(t, _)| t);
+    sorted.dedup_by_key(|&mut (t, _)| t);
+
+    // Seconds from the window center, so the spline works on small numbers
+    let seconds: Vec<(f64, f64)> = sorted
+        .iter()
+        .map(|&(t, h)| ((t - window.center).num_seconds() as f64, h as f64))
+        .collect();
+    let curve = MonotoneCubic::new(&seconds)?;
+
+    Some(generate(window, interval, |mins_rel| {
+        curve.eval(mins_rel as f64 * 60.0) as f32
+    }))
+}
+
+impl TideSeries {
+    /// Copy of this series with a sample every `step_mins` minutes
+    ///
+    /// The new samples stay aligned to "now" (every `mins_rel` a multiple of
+    /// `step_mins`) and cover no more than the old ones. Returns `None` with
+    /// fewer than two samples or a step under one minute.
+    pub fn resample(&self, step_mins: i16) -> Option<TideSeries> {
+        if step_mins < 1 {
+            return None;
+        }
+        let curve = self.curve()?;
+        let (first, last) = (
+            self.samples.first()?.mins_rel,
+            self.samples.last()?.mins_rel,
+        );
+        let step = step_mins as i32;
+        let start = (first as i32 + step - 1).div_euclid(step) * step;
+        let samples = (start..=last as i32)
+            .step_by(step as usize)
+            .map(|m| Sample {
+                mins_rel: m as i16,
+                tide_ft: curve.eval(m as f64) as f32,
+                modeled: self.modeled_near(m as i16),
+            })
+            .collect();
+        Some(TideSeries {
+            samples,
+            base_time: self.base_time,
+            extremes: self.extremes.clone(),
+            fetched_at: self.fetched_at,
+            source: self.source,
+        })
+    }
+
+    /// Copy of this series cut to `from_mins..=to_mins`
+    ///
+    /// Edges falling between two samples get a sample of their own,
+    /// interpolated, so the cut starts and ends where asked; parts of the
+    /// range the series doesn't cover are left out. Recorded extremes
+    /// outside the range are dropped.
+    pub fn window(&self, from_mins: i16, to_mins: i16) -> TideSeries {
+        let range = from_mins..=to_mins;
+        let mut samples: Vec<Sample> = self
+            .samples
+            .iter()
+            .filter(|s| range.contains(&s.mins_rel))
+            .copied()
+            .collect();
+        if let (Some(curve), Some(first), Some(last)) =
+            (self.curve(), self.samples.first(), self.samples.last())
+        {
+            let covered = first.mins_rel..=last.mins_rel;
+            let edge = |mins_rel: i16| Sample {
+                mins_rel,
+                tide_ft: curve.eval(mins_rel as f64) as f32,
+                modeled: self.modeled_near(mins_rel),
+            };
+            if from_mins <= to_mins {
+                if covered.contains(&from_mins)
+                    && samples.first().map(|s| s.mins_rel) != Some(from_mins)
+                {
+                    samples.insert(0, edge(from_mins));
+                }
+                if covered.contains(&to_mins) && samples.last().map(|s| s.mins_rel) != Some(to_mins)
+                {
+                    samples.push(edge(to_mins));
+                }
+            }
+        }
+
+        let span = self.base_time.map(|base| {
+            base + Duration::minutes(from_mins as i64)..=base + Duration::minutes(to_mins as i64)
+        });
+        let extremes = match (&self.extremes, span) {
+            (Some(extremes), Some(span)) => Some(
+                extremes
+                    .iter()
+                    .filter(|e| span.contains(&e.at))
+                    .copied()
+                    .collect(),
+            ),
+            (extremes, _) => extremes.clone(),
+        };
+        TideSeries {
+            samples,
+            base_time: self.base_time,
+            extremes,
+            fetched_at: self.fetched_at,
+            source: self.source,
+        }
+    }
+
+    /// Interpolant through the samples, by `mins_rel`
+    fn curve(&self) -> Option<MonotoneCubic> {
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .map(|s| (s.mins_rel as f64, s.tide_ft as f64))
+            .collect();
+        MonotoneCubic::new(&points)
+    }
+
+    /// Whether the sample nearest `mins_rel` was filled in by the model
+    fn modeled_near(&self, mins_rel: i16) -> bool {
+        self.samples
+            .iter()
+            .min_by_key(|s| (s.mins_rel as i32 - mins_rel as i32).abs())
+            .is_some_and(|s| s.modeled)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::DataSource;
+    use chrono::TimeZone;
+    use proptest::prelude::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap()
+    }
+
+    fn hourly(from: i64, to: i64, height: impl Fn(i64) -> f32) -> Vec<(DateTime<Utc>, f32)> {
+        (from..=to)
+            .map(|h| (now() + Duration::hours(h), height(h)))
+            .collect()
+    }
+
+    #[test]
+    fn test_standard_grid_shape() {
+        let samples = generate(Window::standard(now()), Duration::minutes(10), |_| 0.0);
+        assert_eq!(samples.len(), 145);
+        assert_eq!(samples[0].mins_rel, -720);
+        assert_eq!(samples[72].mins_rel, 0);
+        assert_eq!(samples[144].mins_rel, 720);
+
+        // Two full tide days, and no further than 72 hours in all
+        let samples = generate(Window::hours(now(), 24), Duration::minutes(10), |_| 0.0);
+        assert_eq!(samples.len(), 289);
+        assert_eq!(samples[288].mins_rel, 1440);
+        assert_eq!(Window::hours(now(), 100).half_width, Duration::hours(36));
+        assert_eq!(Window::hours(now(), 0).half_width, Duration::hours(1));
+        assert!(samples
+            .windows(2)
+            .all(|w| w[1].mins_rel - w[0].mins_rel == 10));
+
+        // Allocated once, at exactly the size every window needs
+        for hours in [1, 6, 12, 24, 36] {
+            let window = Window::hours(now(), hours);
+            let expected = sample_count(window.half_width, Duration::minutes(10));
+            let samples = generate(window, Duration::minutes(10), |_| 0.0);
+            assert_eq!((samples.len(), samples.capacity()), (expected, expected));
+        }
+        assert_eq!(
+            sample_count(Duration::hours(36), Duration::minutes(10)),
+            433
+        );
+        assert_eq!(sample_count(Duration::hours(12), Duration::hours(1)), 25);
+    }
+
+    #[test]
+    fn test_series_spec_intervals() {
+        assert_eq!(
+            SeriesSpec::from(Window::standard(now())),
+            SeriesSpec::standard(now())
+        );
+        for (hours, interval, count) in [(12, 5, 289), (12, 15, 97), (36, 15, 289), (1, 60, 3)] {
+            let spec = SeriesSpec::hours(now(), hours, interval);
+            let samples = spec.generate(|_| 0.0);
+            assert_eq!((samples.len(), spec.sample_count()), (count, count));
+            assert_eq!(samples[count / 2].mins_rel, 0);
+            assert_eq!(samples[count - 1].mins_rel as i64, hours * 60);
+            assert!(samples
+                .windows(2)
+                .all(|w| (w[1].mins_rel - w[0].mins_rel) as i64 == interval));
+        }
+
+        // Steps that don't divide an hour round down to one that does
+        let step = |mins| SeriesSpec::hours(now(), 12, mins).interval.num_minutes();
+        assert_eq!((step(7), step(25), step(0), step(90)), (6, 20, 1, 60));
+    }
+
+    #[test]
+    fn test_resample_rounds_peaks_without_overshoot() {
+        // Symmetric high between hourly points: the peak is rounded off
+        // rather than pointed, and never higher than the data
+        let points = hourly(-13, 13, |h| 10.0 - (h as f32).abs());
+        let samples = resample(&points, Window::standard(now()), Duration::minutes(10)).unwrap();
+        let at = |mins: i16| samples.iter().find(|s| s.mins_rel == mins).unwrap().tide_ft;
+        assert_eq!(at(0), 10.0);
+        assert!(at(10) > 10.0 - 1.0 / 6.0, "{}", at(10));
+        assert!(samples.iter().all(|s| s.tide_ft <= 10.0));
+    }
+
+    #[test]
+    fn test_resample_interpolates_linearly() {
+        let points = hourly(-13, 13, |h| h as f32);
+        let samples = resample(&points, Window::standard(now()), Duration::minutes(10)).unwrap();
+        for s in &samples {
+            let expected = s.mins_rel as f32 / 60.0;
+            assert!((s.tide_ft - expected).abs() < 1e-4, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_resample_unsorted_and_exact_hits() {
+        let mut points = hourly(-13, 13, |h| (h * h) as f32);
+        points.reverse();
+        let samples = resample(&points, Window::standard(now()), Duration::minutes(60)).unwrap();
+        // Every grid point falls exactly on an input point
+        for s in &samples {
+            let h = s.mins_rel as i64 / 60;
+            assert_eq!(s.tide_ft, (h * h) as f32);
+        }
+    }
+
+    #[test]
+    fn test_resample_holds_edges() {
+        // Data only covers -1h..+1h
+        let points = hourly(-1, 1, |h| 2.0 + h as f32);
+        let samples = resample(&points, Window::standard(now()), Duration::minutes(10)).unwrap();
+        assert_eq!(samples.first().unwrap().tide_ft, 1.0);
+        assert_eq!(samples.last().unwrap().tide_ft, 3.0);
+    }
+
+    #[test]
+    fn test_resample_needs_two_points() {
+        let one = hourly(0, 0, |_| 1.0);
+        assert!(resample(&one, Window::standard(now()), Duration::minutes(10)).is_none());
+        assert!(resample(&[], Window::standard(now()), Duration::minutes(10)).is_none());
+    }
+
+    #[test]
+    fn test_custom_window_and_contains() {
+        let window = Window::around(now(), Duration::hours(1));
+        let samples = resample(&hourly(-2, 2, |_| 5.0), window, Duration::minutes(15)).unwrap();
+        assert_eq!(samples.len(), 9);
+        assert!(window.contains(now() + Duration::hours(1)));
+        assert!(!window.contains(now() + Duration::minutes(61)));
+    }
+
+    fn hourly_series() -> TideSeries {
+        let points = hourly(-13, 13, |h| 5.0 + 4.0 * (h as f32 * 0.5).sin());
+        TideSeries {
+            samples: resample(&points, Window::standard(now()), Duration::minutes(10)).unwrap(),
+            source: DataSource::Noaa,
+            base_time: Some(now()),
+            extremes: None,
+            fetched_at: None,
+        }
+    }
+
+    #[test]
+    fn test_series_resample() {
+        let series = hourly_series();
+        let coarse = series.resample(60).unwrap();
+        assert_eq!(coarse.samples.len(), 25);
+        assert_eq!(coarse.half_width(), Some(Duration::hours(12)));
+        for s in &coarse.samples {
+            let original = series.samples.iter().find(|o| o.mins_rel == s.mins_rel);
+            assert!((s.tide_ft - original.unwrap().tide_ft).abs() < 1e-4);
+        }
+
+        // Finer than the data, still aligned to now
+        let fine = series.window(-25, 25).resample(6).unwrap();
+        let rel: Vec<i16> = fine.samples.iter().map(|s| s.mins_rel).collect();
+        assert_eq!(rel, [-24, -18, -12, -6, 0, 6, 12, 18, 24]);
+        assert!(series.resample(0).is_none());
+    }
+
+    #[test]
+    fn test_series_window() {
+        let mut series = hourly_series();
+        series.extremes = Some(vec![
+            crate::extremes::Extreme {
+                at: now() - Duration::hours(3),
+                tide_ft: 1.0,
+                high: false,
+            },
+            crate::extremes::Extreme {
+                at: now() + Duration::hours(3),
+                tide_ft: 9.0,
+                high: true,
+            },
+        ]);
+
+        let ahead = series.window(0, 360);
+        assert_eq!(ahead.samples.len(), 37);
+        assert_eq!(ahead.samples[0].mins_rel, 0);
+        assert_eq!(ahead.extremes.as_ref().unwrap().len(), 1);
+
+        // Edges between samples are interpolated; uncovered parts dropped
+        let cut = series.window(-5, 1000);
+        assert_eq!(cut.samples.first().unwrap().mins_rel, -5);
+        assert_eq!(cut.height_now(), series.height_now());
+        assert_eq!(cut.samples.last().unwrap().mins_rel, 720);
+
+        assert!(series.window(800, 900).samples.is_empty());
+        assert!(series.window(60, 0).samples.is_empty());
+    }
+
+    /// A window and points covering it: 10 to 90 minutes apart, from up to
+    /// 3 hours before its start to past its end, with heights that only rise
+    /// or only fall
+    fn monotone_points() -> impl Strategy<Value = (Window, Vec<(DateTime<Utc>, f32)>)> {
+        (
+            // 2000 to 2100, to the second
+            946_684_800i64..4_102_444_800,
+            1i64..=MAX_HALF_WIDTH_HOURS,
+            0i64..180,
+            prop::collection::vec((10i64..=90, 0f32..2.0), 1..50),
+            -5f32..5.0,
+            any::<bool>(),
+        )
+            .prop_map(|(center, hours, lead, steps, start, falling)| {
+                let window = Window::hours(DateTime::from_timestamp(center, 0).unwrap(), hours);
+                let end = window.center + window.half_width;
+                let mut t = window.center - window.half_width - Duration::minutes(lead);
+                let mut height = start;
+                let mut points = vec![(t, height)];
+                for (minutes, rise) in steps.iter().cycle() {
+                    if t > end {
+                        break;
+                    }
+                    t += Duration::minutes(*minutes);
+                    height += if falling { -rise } else { *rise };
+                    points.push((t, height));
+                }
+                (window, points)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_resample_stays_on_a_bounded_monotone_grid(
+            (window, points) in monotone_points(),
+        ) {
+            let samples = resample(&points, window, Duration::minutes(10)).unwrap();
+            let half = window.half_width.num_minutes() as i16;
+
+            // Every 10 minutes from edge to edge, "now" exactly once
+            prop_assert_eq!(samples.len(), half as usize / 5 + 1);
+            prop_assert_eq!(samples[0].mins_rel, -half);
+            prop_assert!(samples.windows(2).all(|w| w[1].mins_rel - w[0].mins_rel == 10));
+            prop_assert_eq!(samples.iter().filter(|s| s.mins_rel == 0).count(), 1);
+
+            // Within the data, in the data's direction, without jumps: ten
+            // minutes never span more than two of the data's steps
+            let heights = || points.iter().map(|&(_, h)| h);
+            let low = heights().fold(f32::INFINITY, f32::min) - 1e-3;
+            let high = heights().fold(f32::NEG_INFINITY, f32::max) + 1e-3;
+            let max_jump = points
+                .windows(3)
+                .map(|w| (w[2].1 - w[0].1).abs())
+                .chain(points.windows(2).map(|w| (w[1].1 - w[0].1).abs()))
+                .fold(0.0, f32::max)
+                + 1e-3;
+            let falling = points.last().unwrap().1 < points[0].1;
+            for pair in samples.windows(2) {
+                let (a, b) = (pair[0].tide_ft, pair[1].tide_ft);
+                prop_assert!((low..=high).contains(&a), "{} outside {}..{}", a, low, high);
+                prop_assert!(if falling { b <= a + 1e-3 } else { b >= a - 1e-3 }, "{:?}", pair);
+                prop_assert!((b - a).abs() <= max_jump, "{:?} jumps past {}", pair, max_jump);
+            }
+        }
+
+        #[test]
+        fn test_window_cuts_exactly_to_the_covered_range(
+            (window, points) in monotone_points(),
+            from in -2400i16..2400,
+            length in 0i16..2400,
+        ) {
+            let samples = resample(&points, window, Duration::minutes(10)).unwrap();
+            let series = TideSeries::builder(samples).base_time(window.center).build().unwrap();
+            let half = window.half_width.num_minutes() as i16;
+            let to = from.saturating_add(length);
+
+            let cut = series.window(from, to);
+            let (first, last) = (from.max(-half), to.min(half));
+            if first > last {
+                prop_assert!(cut.samples.is_empty());
+            } else {
+                prop_assert_eq!(cut.samples.first().unwrap().mins_rel, first);
+                prop_assert_eq!(cut.samples.last().unwrap().mins_rel, last);
+                prop_assert!(cut.samples.windows(2).all(|w| w[0].mins_rel < w[1].mins_rel));
+                let nows = cut.samples.iter().filter(|s| s.mins_rel == 0).count();
+                prop_assert_eq!(nows, usize::from((first..=last).contains(&0)));
+            }
+        }
+    }
+}
@@ -0,0 +1,658 @@
+//! # Harmonic Tide Prediction
+//!
+//! The same method NOAA uses for its published predictions: the tide is a sum
+//! of cosine *constituents*, each tied to an astronomical cycle,
+//!
+//! ```text
+//! h(t) = Z0 + Σ f·H·cos(V(t) + u − κ)
+//! ```
+//!
+//! where `H` (amplitude) and `κ` (Greenwich phase lag) are the station's
+//! harmonic constants, `V` the constituent's astronomical argument at `t`,
+//! and `f`/`u` the nodal corrections for the 18.6-year lunar node cycle.
+//! With a station's constants this predicts offline to within inches of
+//! NOAA, unlike the two-constituent sketch in [`fallback`](crate::fallback).
+//!
+//! Constants come from the station's "Harmonic Constituents" page on
+//! tidesandcurrents.noaa.gov (use feet and the **Phase GMT** column), either
+//! inline in the config or from a file:
+//!
+//! ```toml
+//! [station.harmonics]
+//! mean_level_ft = 5.0    # Z0: mean sea level above the station datum
+//! file = "/etc/tide-tracker/8418150.txt"
+//! constituents = [
+//!     { name = "M2", amplitude_ft = 4.5, phase_deg = 103.9 },
+//! ]
+//! ```
+//!
+//! The file is plain text, one constituent per line as
+//! `NAME amplitude phase [speed]`, with an optional `Z0 level` line and `#`
+//! comments. Inline constituents override file entries of the same name.
+//!
+//! A station can also come from an XTide harmonics file (`xtide_file`, or
+//! `[fallback] harmonics_file` for every station), see [`crate::xtide`].
+//!
+//! Standard constituents (M2, S2, N2, K1, O1, ... see [`standard_speeds`]) need no
+//! speed. Any other name must give `speed_deg_per_hour`; its argument is then
+//! counted from J2000 (2000-01-01 12:00 UTC) without nodal correction.
+
+use crate::{extremes, grid, DataSource, TideSeries};
+use alloc::string::String;
+use alloc::vec::Vec;
+use chrono::{DateTime, Duration, Timelike, Utc};
+#[cfg(feature = "std")]
+use chrono::{Datelike, TimeZone};
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Harmonic constants for a station (`[station.harmonics]`)
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct HarmonicsConfig {
+    /// Mean water level (Z0) above the station datum, in feet; overrides
+    /// the file's `Z0` line (default: the file's, else 0)
+    #[serde(default)]
+    pub mean_level_ft: Option<f32>,
+    /// Constituents given inline
+    #[serde(default)]
+    pub constituents: Vec<Constituent>,
+    /// Plain-text constants file, read before the inline constituents
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    /// XTide harmonics file (legacy text format) to take the station from,
+    /// see [`crate::xtide`]; `[fallback] harmonics_file` fills this in
+    #[serde(default)]
+    pub xtide_file: Option<PathBuf>,
+    /// Station name to look up in `xtide_file` (default: the station's `name`)
+    #[serde(default)]
+    pub xtide_station: Option<String>,
+}
+
+/// One constituent's harmonic constants
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Constituent {
+    /// Standard name, e.g. "M2" (case-insensitive)
+    pub name: String,
+    /// Amplitude `H` in feet
+    pub amplitude_ft: f32,
+    /// Greenwich phase lag `κ` in degrees
+    pub phase_deg: f32,
+    /// Speed in degrees per hour; only needed for non-standard constituents
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub speed_deg_per_hour: Option<f64>,
+}
+
+/// Why harmonic constants could not be loaded
+#[derive(Error, Debug)]
+pub enum HarmonicsError {
+    #[error("no [station.harmonics] configured")]
+    NotConfigured,
+    #[error("no constituents given")]
+    Empty,
+    #[error("unknown constituent {0} (give speed_deg_per_hour)")]
+    UnknownConstituent(String),
+    #[cfg(feature = "std")]
+    #[error("{path}:{line}: {message}")]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    #[error("no station matching \"{0}\" in the harmonics file")]
+    StationNotFound(String),
+    #[error("unsupported harmonics file: {0}")]
+    Unsupported(String),
+    #[cfg(feature = "std")]
+    #[error("reading constants: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Nodal correction family of a constituent
+#[derive(Debug, Clone, Copy)]
+enum Nodal {
+    None,
+    M2,
+    K1,
+    O1,
+    K2,
+    M2Squared,
+    M2Cubed,
+    M2K1,
+}
+
+/// Astronomical definition of a standard constituent
+#[derive(Debug)]
+struct Standard {
+    name: &'static str,
+    /// Multipliers of (T, s, h, p, N, p1), Schureman's convention
+    doodson: [i8; 6],
+    /// Constant added to the argument, in degrees
+    offset_deg: f64,
+    nodal: Nodal,
+}
+
+/// Standard constituents NOAA publishes for most stations
+const STANDARD: &[Standard] = &[
+    std_c("M2", [2, -2, 2, 0, 0, 0], 0.0, Nodal::M2),
+    std_c("S2", [2, 0, 0, 0, 0, 0], 0.0, Nodal::None),
+    std_c("N2", [2, -3, 2, 1, 0, 0], 0.0, Nodal::M2),
+    std_c("K2", [2, 0, 2, 0, 0, 0], 0.0, Nodal::K2),
+    std_c("K1", [1, 0, 1, 0, 0, 0], -90.0, Nodal::K1),
+    std_c("O1", [1, -2, 1, 0, 0, 0], 90.0, Nodal::O1),
+    std_c("P1", [1, 0, -1, 0, 0, 0], 90.0, Nodal::None),
+    std_c("Q1", [1, -3, 1, 1, 0, 0], 90.0, Nodal::O1),
+    std_c("2N2", [2, -4, 2, 2, 0, 0], 0.0, Nodal::M2),
+    std_c("NU2", [2, -3, 4, -1, 0, 0], 0.0, Nodal::M2),
+    std_c("MU2", [2, -4, 4, 0, 0, 0], 0.0, Nodal::M2),
+    std_c("L2", [2, -1, 2, -1, 0, 0], 180.0, Nodal::M2),
+    std_c("T2", [2, 0, -1, 0, 0, 1], 0.0, Nodal::None),
+    std_c("M4", [4, -4, 4, 0, 0, 0], 0.0, Nodal::M2Squared),
+    std_c("MN4", [4, -5, 4, 1, 0, 0], 0.0, Nodal::M2Squared),
+    std_c("MS4", [4, -2, 2, 0, 0, 0], 0.0, Nodal::M2),
+    std_c("M6", [6, -6, 6, 0, 0, 0], 0.0, Nodal::M2Cubed),
+    std_c("MK3", [3, -2, 3, 0, 0, 0], -90.0, Nodal::M2K1),
+    std_c("SA", [0, 0, 1, 0, 0, 0], 0.0, Nodal::None),
+    std_c("SSA", [0, 0, 2, 0, 0, 0], 0.0, Nodal::None),
+];
+
+const fn std_c(name: &'static str, doodson: [i8; 6], offset_deg: f64, nodal: Nodal) -> Standard {
+    Standard {
+        name,
+        doodson,
+        offset_deg,
+        nodal,
+    }
+}
+
+/// Rates of (T, s, h, p, N, p1) in degrees per hour
+const RATES: [f64; 6] = [
+    15.0,
+    0.549_016_5,
+    0.041_068_6,
+    0.004_641_8,
+    -0.002_206_4,
+    0.000_001_96,
+];
+
+/// Names and speeds (degrees per hour) of the standard constituents
+pub fn standard_speeds() -> impl Iterator<Item = (&'static str, f64)> {
+    STANDARD.iter().map(|c| (c.name, doodson_speed(&c.doodson)))
+}
+
+fn doodson_speed(doodson: &[i8; 6]) -> f64 {
+    doodson
+        .iter()
+        .zip(RATES)
+        .map(|(&d, rate)| d as f64 * rate)
+        .sum()
+}
+
+/// Equilibrium arguments and node factors per year, as tabulated in XTide files
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct YearTable {
+    pub first_year: i32,
+    /// `V0 + u` at 00:00 UTC on January 1st of each year, in degrees
+    pub equilibrium_deg: Vec<f32>,
+    /// Node factor `f` for each year
+    pub node_factor: Vec<f32>,
+}
+
+#[cfg(feature = "std")]
+impl YearTable {
+    /// Hours since the start of `t`'s year with that year's `V0 + u` and `f`
+    fn at(&self, t: DateTime<Utc>) -> Option<(f64, f64, f64)> {
+        let index = usize::try_from(t.year() - self.first_year).ok()?;
+        let equilibrium = *self.equilibrium_deg.get(index)?;
+        let node = *self.node_factor.get(index)?;
+        let start = Utc.with_ymd_and_hms(t.year(), 1, 1, 0, 0, 0).single()?;
+        let hours = (t - start).num_seconds() as f64 / 3600.0;
+        Some((hours, equilibrium as f64, node as f64))
+    }
+}
+
+/// How one term's argument is computed
+#[derive(Debug, Clone)]
+enum Argument {
+    Standard(&'static Standard),
+    /// Non-standard constituent, counted from J2000 at a fixed speed
+    Custom(f64),
+    /// From a [`YearTable`]; outside its years standard constituents are
+    /// computed astronomically and others use the nearest year
+    #[cfg(feature = "std")]
+    Tabulated {
+        speed: f64,
+        table: Arc<YearTable>,
+        standard: Option<&'static Standard>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    /// Matched against overrides when loading, which needs std
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    name: String,
+    amplitude_ft: f64,
+    phase_deg: f64,
+    argument: Argument,
+}
+
+/// A constituent loaded with its own year table, see [`crate::xtide`]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub(crate) struct TabulatedConstituent {
+    pub name: String,
+    pub speed_deg_per_hour: f64,
+    pub amplitude_ft: f32,
+    /// Greenwich phase lag in degrees
+    pub phase_deg: f32,
+    pub table: Arc<YearTable>,
+}
+
+/// A station's loaded constants, ready to predict
+#[derive(Debug, Clone)]
+pub struct Harmonics {
+    mean_level_ft: f64,
+    terms: Vec<Term>,
+}
+
+#[cfg(feature = "std")]
+impl HarmonicsConfig {
+    /// Read the files (if any), merge the inline constituents and resolve names
+    ///
+    /// Layers, later ones overriding constituents of the same name: the XTide
+    /// station, the plain-text file, the inline constituents.
+    pub fn load(&self) -> Result<Harmonics, HarmonicsError> {
+        let mut harmonics = match &self.xtide_file {
+            Some(path) => {
+                let name = self.xtide_station.as_deref().unwrap_or_default();
+                crate::xtide::load(path, name)?
+            }
+            None => Harmonics {
+                mean_level_ft: 0.0,
+                terms: Vec::new(),
+            },
+        };
+
+        let (mut z0, mut constituents) = (None, Vec::new());
+        if let Some(path) = &self.file {
+            let text = std::fs::read_to_string(path)?;
+            (z0, constituents) = parse_constants(path, &text)?;
+        }
+        for c in &self.constituents {
+            constituents
+                .retain(|existing: &Constituent| !existing.name.eq_ignore_ascii_case(&c.name));
+            constituents.push(c.clone());
+        }
+        let overrides = resolve(&constituents)?;
+        harmonics.terms.retain(|t| {
+            !overrides
+                .iter()
+                .any(|o| o.name.eq_ignore_ascii_case(&t.name))
+        });
+        harmonics.terms.extend(overrides);
+
+        if let Some(level) = self.mean_level_ft.or(z0) {
+            harmonics.mean_level_ft = level as f64;
+        }
+        if harmonics.terms.is_empty() {
+            return Err(HarmonicsError::Empty);
+        }
+        Ok(harmonics)
+    }
+}
+
+/// The standard constituent called `name`, if any
+fn standard(name: &str) -> Option<&'static Standard> {
+    STANDARD.iter().find(|s| s.name.eq_ignore_ascii_case(name))
+}
+
+/// Terms for `constituents`, failing on unknown names without a speed
+fn resolve(constituents: &[Constituent]) -> Result<Vec<Term>, HarmonicsError> {
+    constituents
+        .iter()
+        .map(|c| {
+            let argument = match (standard(&c.name), c.speed_deg_per_hour) {
+                (Some(s), _) => Argument::Standard(s),
+                (None, Some(speed)) => Argument::Custom(speed),
+                (None, None) => return Err(HarmonicsError::UnknownConstituent(c.name.clone())),
+            };
+            Ok(Term {
+                name: c.name.clone(),
+                amplitude_ft: c.amplitude_ft as f64,
+                phase_deg: c.phase_deg as f64,
+                argument,
+            })
+        })
+        .collect()
+}
+
+/// Parse a plain-text constants file: `NAME amplitude phase [speed]` lines
+/// and an optional `Z0 level` line
+#[cfg(feature = "std")]
+fn parse_constants(
+    path: &Path,
+    text: &str,
+) -> Result<(Option<f32>, Vec<Constituent>), HarmonicsError> {
+    let mut z0 = None;
+    let mut constituents = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: &str| HarmonicsError::Parse {
+            path: path.to_path_buf(),
+            line: i + 1,
+            message: message.to_string(),
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let number = |field: &str| field.parse::<f64>().map_err(|_| error("expected a number"));
+        match fields.as_slice() {
+            [name, level] if name.eq_ignore_ascii_case("Z0") => z0 = Some(number(level)? as f32),
+            [name, amplitude, phase, rest @ ..] if rest.len() <= 1 => {
+                constituents.push(Constituent {
+                    name: name.to_string(),
+                    amplitude_ft: number(amplitude)? as f32,
+                    phase_deg: number(phase)? as f32,
+                    speed_deg_per_hour: rest.first().map(|s| number(s)).transpose()?,
+                });
+            }
+            _ => return Err(error("expected NAME amplitude phase [speed]")),
+        }
+    }
+    Ok((z0, constituents))
+}
+
+impl Harmonics {
+    pub fn new(mean_level_ft: f32, constituents: &[Constituent]) -> Result<Self, HarmonicsError> {
+        if constituents.is_empty() {
+            return Err(HarmonicsError::Empty);
+        }
+        Ok(Self {
+            mean_level_ft: mean_level_ft as f64,
+            terms: resolve(constituents)?,
+        })
+    }
+
+    /// Constants whose arguments come from per-year tables
+    #[cfg(feature = "std")]
+    pub(crate) fn tabulated(mean_level_ft: f32, constituents: Vec<TabulatedConstituent>) -> Self {
+        let terms = constituents
+            .into_iter()
+            .map(|c| Term {
+                argument: Argument::Tabulated {
+                    speed: c.speed_deg_per_hour,
+                    standard: standard(&c.name),
+                    table: c.table,
+                },
+                name: c.name,
+                amplitude_ft: c.amplitude_ft as f64,
+                phase_deg: c.phase_deg as f64,
+            })
+            .collect();
+        Self {
+            mean_level_ft: mean_level_ft as f64,
+            terms,
+        }
+    }
+
+    /// Predicted height at `t`, in feet above the station datum
+    pub fn predict(&self, t: DateTime<Utc>) -> f32 {
+        let astro = Astro::at(t);
+        let height: f64 = self
+            .terms
+            .iter()
+            .map(|term| {
+                let (argument, f, u) = match &term.argument {
+                    Argument::Standard(s) => astro.equilibrium(s),
+                    Argument::Custom(speed) => (speed * astro.hours_since_j2000, 1.0, 0.0),
+                    #[cfg(feature = "std")]
+                    Argument::Tabulated {
+                        speed,
+                        table,
+                        standard,
+                    } => match (table.at(t), standard) {
+                        (Some((hours, v0, f)), _) => (speed * hours + v0, f, 0.0),
+                        (None, Some(s)) => astro.equilibrium(s),
+                        (None, None) => {
+                            let year = t.year().clamp(
+                                table.first_year,
+                                table.first_year + table.equilibrium_deg.len() as i32 - 1,
+                            );
+                            let nearest = t.with_year(year).unwrap_or(t);
+                            let (hours, v0, f) = table.at(nearest).unwrap_or((0.0, 0.0, 1.0));
+                            (
+                                speed * (hours + (t - nearest).num_seconds() as f64 / 3600.0) + v0,
+                                f,
+                                0.0,
+                            )
+                        }
+                    },
+                };
+                f * term.amplitude_ft * (argument + u - term.phase_deg).to_radians().cos()
+            })
+            .sum();
+        (self.mean_level_ft + height) as f32
+    }
+
+    /// The standard ±12 hour grid around `now`
+    pub fn series(&self, now: DateTime<Utc>) -> TideSeries {
+        self.series_in(grid::Window::standard(now))
+    }
+
+    /// Predictions of the shape `spec` asks for, or over a bare window at
+    /// the standard interval
+    ///
+    /// Marked offline like any prediction that didn't come from the network.
+    /// Highs and lows are recorded for the next 48 hours, or to the end of
+    /// the window when that is further.
+    ///
+    /// # Panics
+    /// If the window isn't a whole number of intervals either side of its
+    /// center, so the grid misses "now".
+    pub fn series_in(&self, spec: impl Into<grid::SeriesSpec>) -> TideSeries {
+        let spec = spec.into();
+        let now = spec.window.center;
+        let samples = spec.generate(|m| self.predict(now + Duration::minutes(m as i64)));
+        let hourly: Vec<_> = (0..=spec.window.half_width.num_hours().max(48))
+            .map(|h| now + Duration::hours(h))
+            .map(|t| (t, self.predict(t)))
+            .collect();
+        TideSeries::builder(samples)
+            .source(DataSource::Fallback)
+            .base_time(now)
+            .extremes(extremes::find(&hourly))
+            .build()
+            .expect("grid windows include now")
+    }
+}
+
+/// `V + u` in degrees and `f` of the standard constituent `name` at `t`
+#[cfg(all(test, feature = "std"))]
+pub(crate) fn equilibrium(name: &str, t: DateTime<Utc>) -> Option<(f64, f64)> {
+    let (v, f, u) = Astro::at(t).equilibrium(standard(name)?);
+    Some((v + u, f))
+}
+
+/// Mean astronomical longitudes at an instant, in degrees
+struct Astro {
+    /// Mean solar hour angle `T`, then the longitudes `s, h, p, N, p1`
+    values: [f64; 6],
+    hours_since_j2000: f64,
+}
+
+impl Astro {
+    fn at(t: DateTime<Utc>) -> Self {
+        let days = (t.timestamp() as f64 - 946_728_000.0) / 86_400.0;
+        let c = days / 36_525.0;
+        let hours = t.hour() as f64 + t.minute() as f64 / 60.0 + t.second() as f64 / 3600.0;
+        Self {
+            values: [
+                15.0 * hours + 180.0,
+                218.316_447_7 + 481_267.881_234_21 * c,
+                280.466_46 + 36_000.769_83 * c,
+                83.353_246_5 + 4_069.013_728_7 * c,
+                125.044_52 - 1_934.136_261 * c,
+                282.937_35 + 1.719_46 * c,
+            ],
+            hours_since_j2000: days * 24.0,
+        }
+    }
+
+    /// Argument `V`, node factor `f` and nodal angle `u` of a standard constituent
+    fn equilibrium(&self, c: &Standard) -> (f64, f64, f64) {
+        let (f, u) = self.nodal(c.nodal);
+        (self.argument(c), f, u)
+    }
+
+    /// Equilibrium argument `V` of a standard constituent
+    fn argument(&self, c: &Standard) -> f64 {
+        c.doodson
+            .iter()
+            .zip(self.values)
+            .map(|(&d, value)| d as f64 * value)
+            .sum::<f64>()
+            + c.offset_deg
+    }
+
+    /// Node factor `f` and nodal angle `u` (degrees), after Schureman
+    fn nodal(&self, nodal: Nodal) -> (f64, f64) {
+        let n = self.values[4].to_radians();
+        let m2 = (
+            1.0004 - 0.0373 * n.cos() + 0.0002 * (2.0 * n).cos(),
+            -2.14 * n.sin(),
+        );
+        let k1 = (
+            1.0060 + 0.1150 * n.cos() - 0.0088 * (2.0 * n).cos() + 0.0006 * (3.0 * n).cos(),
+            -8.86 * n.sin() + 0.68 * (2.0 * n).sin() - 0.07 * (3.0 * n).sin(),
+        );
+        match nodal {
+            Nodal::None => (1.0, 0.0),
+            Nodal::M2 => m2,
+            Nodal::K1 => k1,
+            Nodal::O1 => (
+                1.0089 + 0.1871 * n.cos() - 0.0147 * (2.0 * n).cos() + 0.0014 * (3.0 * n).cos(),
+                10.80 * n.sin() - 1.34 * (2.0 * n).sin() + 0.19 * (3.0 * n).sin(),
+            ),
+            Nodal::K2 => (
+                1.0241 + 0.2863 * n.cos() + 0.0083 * (2.0 * n).cos() - 0.0015 * (3.0 * n).cos(),
+                -17.74 * n.sin() + 0.68 * (2.0 * n).sin() - 0.04 * (3.0 * n).sin(),
+            ),
+            Nodal::M2Squared => (m2.0 * m2.0, 2.0 * m2.1),
+            Nodal::M2Cubed => (m2.0.powi(3), 3.0 * m2.1),
+            Nodal::M2K1 => (m2.0 * k1.0, m2.1 + k1.1),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn constituent(name: &str, amplitude_ft: f32, phase_deg: f32) -> Constituent {
+        Constituent {
+            name: name.to_string(),
+            amplitude_ft,
+            phase_deg,
+            speed_deg_per_hour: None,
+        }
+    }
+
+    #[test]
+    fn test_standard_speeds_match_published() {
+        let speeds: Vec<_> = standard_speeds().collect();
+        let speed = |name| speeds.iter().find(|(n, _)| *n == name).unwrap().1;
+        assert!((speed("M2") - 28.984_104_2).abs() < 1e-5);
+        assert!((speed("S2") - 30.0).abs() < 1e-9);
+        assert!((speed("N2") - 28.439_729_5).abs() < 1e-5);
+        assert!((speed("K1") - 15.041_068_6).abs() < 1e-5);
+        assert!((speed("O1") - 13.943_035_6).abs() < 1e-5);
+        assert!((speed("M4") - 57.968_208_4).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_s2_peaks_at_greenwich_noon_and_midnight() {
+        // S2's argument is twice the mean solar hour angle: 0° at 00:00 and 12:00 UTC
+        let model = Harmonics::new(5.0, &[constituent("S2", 1.0, 0.0)]).unwrap();
+        let midnight = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        assert!((model.predict(midnight) - 6.0).abs() < 1e-4);
+        assert!((model.predict(midnight + Duration::hours(3)) - 5.0).abs() < 1e-4);
+        assert!((model.predict(midnight + Duration::hours(12)) - 6.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_m2_repeats_each_period_and_series_is_on_grid() {
+        let model = Harmonics::new(
+            5.0,
+            &[constituent("M2", 4.5, 103.9), constituent("K1", 0.4, 200.0)],
+        )
+        .unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+
+        let m2_only = Harmonics::new(0.0, &[constituent("M2", 4.5, 103.9)]).unwrap();
+        let period = Duration::seconds((360.0 / 28.984_104_2 * 3600.0) as i64);
+        assert!((m2_only.predict(now) - m2_only.predict(now + period)).abs() < 0.01);
+
+        let series = model.series(now);
+        assert_eq!(series.samples.len(), 145);
+        assert!(series.is_offline());
+        assert_eq!(series.samples[72].tide_ft, model.predict(now));
+        // Nodal factors keep the range near (not exactly at) the raw amplitudes
+        let max = series.max_height().unwrap();
+        assert!((8.5..=10.5).contains(&max), "{max}");
+    }
+
+    #[test]
+    fn test_load_merges_file_and_inline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("8418150.txt");
+        std::fs::write(
+            &path,
+            "# Portland, ME\nZ0 5.0\nM2 4.5 103.9\nS2 0.7 140.1  # solar\n\nXX 0.1 10 12.5\n",
+        )
+        .unwrap();
+
+        let config = HarmonicsConfig {
+            mean_level_ft: None,
+            constituents: vec![constituent("m2", 1.0, 0.0)],
+            file: Some(path.clone()),
+            ..HarmonicsConfig::default()
+        };
+        let model = config.load().unwrap();
+        assert_eq!(model.mean_level_ft, 5.0);
+        assert_eq!(model.terms.len(), 3);
+        assert_eq!(model.terms[2].amplitude_ft, 1.0);
+        assert!(matches!(model.terms[1].argument, Argument::Custom(speed) if speed == 12.5));
+
+        std::fs::write(&path, "M2 4.5\n").unwrap();
+        assert!(matches!(
+            config.load(),
+            Err(HarmonicsError::Parse { line: 1, .. })
+        ));
+
+        let unknown = HarmonicsConfig {
+            constituents: vec![constituent("ZZ9", 1.0, 0.0)],
+            ..HarmonicsConfig::default()
+        };
+        assert!(matches!(
+            unknown.load(),
+            Err(HarmonicsError::UnknownConstituent(name)) if name == "ZZ9"
+        ));
+        assert!(matches!(
+            HarmonicsConfig::default().load(),
+            Err(HarmonicsError::Empty)
+        ));
+    }
+}
@@ -0,0 +1,402 @@
+//! # Home Assistant Sensor
+//!
+//! The current tide as a [REST sensor](https://www.home-assistant.io/integrations/sensor.rest/)
+//! reading, so automations can use it without scraping the panel:
+//!
+//! ```text
+//! tide-tracker --ha                         # print the JSON once
+//! tide-tracker --ha-listen 0.0.0.0:8080     # answer GET requests with it
+//! ```
+//!
+//! ```json
+//! {"state": 6.42, "attributes": {"station": "Portland, ME", "station_id": "8418150",
+//!  "unit_of_measurement": "ft", "datum": "MLLW", "trend": "rising",
+//!  "rate_ft_per_hour": 1.31, "next_high": "2025-07-01T10:38:00-04:00",
+//!  "next_high_ft": 8.9, "next_low": "2025-07-01T16:53:00-04:00",
+//!  "next_low_ft": 1.1, "offline": false, "source": "NOAA", "surge_ft": 0.4,
+//!  "surge": false, "updated": "2025-07-01T08:00:00-04:00"}}
+//! ```
+//!
+//! Attributes are flat so each one can be picked out with `json_attributes`.
+//! Heights are in the station's datum, or relative to `[station.reference]`,
+//! like the CSV export. Each request reads the data the way a panel refresh
+//! does (cache first, then the providers, then the offline model), so polling
+//! more often than `cache_ttl_minutes` costs no extra NOAA requests.
+//!
+//! With `surge_threshold_ft` set for the station, each reading also fetches
+//! its observed water levels (see [`crate::surge`]): `surge_ft` is observed
+//! minus predicted, null without observations, and `surge` whether it
+//! reaches the threshold. `--ha-listen` keeps them for `cache_ttl_minutes`
+//! too, like the predictions.
+
+use crate::config::{Config, StationConfig};
+use crate::{fallback, surge, tide_data, TideSeries, Trend};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+#[cfg(feature = "net")]
+use std::io;
+#[cfg(feature = "net")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "net")]
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "net")]
+use tokio::net::{TcpListener, TcpStream};
+use tracing::warn;
+#[cfg(feature = "net")]
+use tracing::{debug, info};
+
+/// How long a client gets to send its request head before the connection
+/// is dropped
+#[cfg(feature = "net")]
+const HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The JSON body: a state and its attributes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SensorState {
+    /// Height now in feet, `None` (null, "unknown" in Home Assistant) when
+    /// the series doesn't cover now
+    pub state: Option<f64>,
+    pub attributes: Attributes,
+}
+
+/// Sensor attributes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Attributes {
+    pub station: String,
+    pub station_id: String,
+    pub unit_of_measurement: &'static str,
+    /// Datum label, or "vs <reference>" for heights relative to a reference
+    pub datum: String,
+    /// "rising" or "falling"
+    pub trend: &'static str,
+    pub rate_ft_per_hour: f64,
+    /// Station-local time of the next high water, RFC 3339
+    pub next_high: Option<String>,
+    pub next_high_ft: Option<f64>,
+    /// Station-local time of the next low water, RFC 3339
+    pub next_low: Option<String>,
+    pub next_low_ft: Option<f64>,
+    /// The heights come from the offline model, not NOAA
+    pub offline: bool,
+    /// Where the heights come from, e.g. "NOAA", "CACHED 25m" or
+    /// "OFFLINE MODEL"
+    pub source: String,
+    /// Observed minus predicted height in feet, `None` without recent
+    /// observations
+    pub surge_ft: Option<f64>,
+    /// `surge_ft` reaches the station's `surge_threshold_ft`
+    pub surge: bool,
+    pub updated: String,
+}
+
+/// Sensor reading for `series` of `station` at `now`, with the surge from
+/// `observed` water levels when there are some
+pub fn sensor_state(
+    series: &TideSeries,
+    station: &StationConfig,
+    observed: Option<&TideSeries>,
+    now: DateTime<Utc>,
+) -> SensorState {
+    let time = |t: DateTime<Utc>| {
+        station
+            .local_time(t)
+            .to_rfc3339_opts(SecondsFormat::Secs, false)
+    };
+    let upcoming = series.upcoming_extremes();
+    let next = |high: bool| upcoming.iter().find(|e| e.high == high);
+    let (next_high, next_low) = (next(true), next(false));
+    let surge = observed.and_then(|observed| surge::residual(series, observed));
+    let threshold = station.surge_threshold_ft;
+
+    SensorState {
+        state: series.height_now().map(round2),
+        attributes: Attributes {
+            station: station.name.clone(),
+            station_id: station.id.clone(),
+            unit_of_measurement: "ft",
+            datum: match &station.reference {
+                Some(reference) => format!("vs {}", reference.name),
+                None => station.datum.label().to_string(),
+            },
+            trend: match series.trend() {
+                Trend::Rising => "rising",
+                Trend::Falling => "falling",
+            },
+            rate_ft_per_hour: round2(series.rate_ft_per_hour()),
+            next_high: next_high.map(|e| time(e.at)),
+            next_high_ft: next_high.map(|e| round2(e.tide_ft)),
+            next_low: next_low.map(|e| time(e.at)),
+            next_low_ft: next_low.map(|e| round2(e.tide_ft)),
+            offline: series.is_offline(),
+            source: series.source.to_string(),
+            surge_ft: surge.map(|s| round2(s.residual_ft)),
+            surge: surge.zip(threshold).is_some_and(|(s, t)| s.exceeds(t)),
+            updated: time(now),
+        },
+    }
+}
+
+/// To hundredths, widened first so the JSON doesn't show f32 noise
+/// (1.2000000476837158)
+fn round2(ft: f32) -> f64 {
+    (ft as f64 * 100.0).round() / 100.0
+}
+
+/// Reading at `now` for the configured station
+///
+/// Never fails: a fetch error falls back to the offline model, as on the panel.
+pub async fn current(config: &Config, now: DateTime<Utc>) -> SensorState {
+    let series = predicted(config, now).await;
+    #[cfg(feature = "net")]
+    let observed = observed(config, &series).await;
+    #[cfg(not(feature = "net"))]
+    let observed: Option<TideSeries> = None;
+    sensor_state(&series, &config.station, observed.as_ref(), now)
+}
+
+/// The station's series at `now` the way a panel refresh reads it, in its
+/// reference
+async fn predicted(config: &Config, now: DateTime<Utc>) -> TideSeries {
+    let station = &config.station;
+    let series = match tide_data::fetch_station_at(config, station, now).await {
+        Ok(series) => series,
+        Err(e) => {
+            warn!("Tide data fetch failed, reporting the offline model: {}", e);
+            fallback::for_station(config, station, Some(now))
+        }
+    };
+    match &station.reference {
+        Some(reference) => series.relative_to(reference.elevation_ft),
+        None => series,
+    }
+}
+
+/// Observed water levels around `series`, fetched only with a surge
+/// threshold set
+#[cfg(feature = "net")]
+async fn observed(config: &Config, series: &TideSeries) -> Option<TideSeries> {
+    let main = crate::stations::StationSeries {
+        station: config.station.clone(),
+        series: series.clone(),
+    };
+    match surge::fetch_observed(&main).await {
+        Ok(observed) => observed.map(|o| o.series),
+        Err(e) => {
+            warn!("Observed water levels unavailable: {}", e);
+            None
+        }
+    }
+}
+
+/// The observed water levels `--ha-listen` last fetched, kept for the cache
+/// TTL so polling doesn't ask NOAA for them on every request
+#[cfg(feature = "net")]
+#[derive(Default)]
+struct ObservedCache {
+    last: tokio::sync::Mutex<Option<(Instant, Option<TideSeries>)>>,
+}
+
+#[cfg(feature = "net")]
+impl ObservedCache {
+    /// The levels fetched within `cache_ttl_minutes`, or fresh ones; a
+    /// failed fetch is kept too, so a NOAA outage isn't retried every poll
+    async fn get(&self, config: &Config, series: &TideSeries) -> Option<TideSeries> {
+        // Held across the fetch, so requests arriving together share one
+        let mut last = self.last.lock().await;
+        if let Some((at, observed)) = last.as_ref() {
+            if at.elapsed() < config.display.cache_ttl() {
+                return observed.clone();
+            }
+        }
+        let observed = observed(config, series).await;
+        *last = Some((Instant::now(), observed.clone()));
+        observed
+    }
+}
+
+/// Answer every GET on `addr` with the current reading for `config`, or
+/// the reading at `at` when it's pinned
+///
+/// Edits to the configuration apply when the server is restarted.
+#[cfg(feature = "net")]
+pub async fn serve(addr: &str, config: Config, at: Option<DateTime<Utc>>) -> io::Result<()> {
+    let config = std::sync::Arc::new(config);
+    let cache = std::sync::Arc::new(ObservedCache::default());
+    let listener = TcpListener::bind(addr).await?;
+    info!(
+        "Serving the Home Assistant sensor on http://{}/",
+        listener.local_addr()?
+    );
+    loop {
+        let (mut stream, peer) = listener.accept().await?;
+        let config = config.clone();
+        let cache = cache.clone();
+        tokio::spawn(async move {
+            if let Err(e) = answer(&mut stream, &config, &cache, at).await {
+                debug!("Request from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Read one request head and write the response, the reading at `at` or
+/// else the system clock's now; any path will do
+#[cfg(feature = "net")]
+async fn answer(
+    stream: &mut TcpStream,
+    config: &Config,
+    cache: &ObservedCache,
+    at: Option<DateTime<Utc>>,
+) -> io::Result<()> {
+    let head = tokio::time::timeout(HEAD_TIMEOUT, read_head(stream))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no request head"))??;
+
+    let response = if head.starts_with(b"GET ") {
+        let now = at.unwrap_or_else(Utc::now);
+        let series = predicted(config, now).await;
+        let observed = cache.get(config, &series).await;
+        let state = sensor_state(&series, &config.station, observed.as_ref(), now);
+        http_response("200 OK", &serde_json::to_string(&state)?)
+    } else {
+        http_response("405 Method Not Allowed", "")
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Bytes up to the blank line ending the request head (or 8 KiB, or the
+/// client closing)
+#[cfg(feature = "net")]
+async fn read_head(stream: &mut (impl AsyncRead + Unpin)) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 512];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < 8192 {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    Ok(head)
+}
+
+/// A complete HTTP/1.1 response with a JSON body, closing the connection
+pub fn http_response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extremes::Extreme;
+    use crate::DataSource;
+    use crate::Sample;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_sensor_state() {
+        let mut station = Config::default().station;
+        station.timezone = Some(chrono_tz::America::New_York);
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let series = TideSeries {
+            samples: (-6..=6)
+                .map(|i| Sample {
+                    mins_rel: i * 10,
+                    tide_ft: 5.0 + i as f32 * 0.2,
+                    modeled: false,
+                })
+                .collect(),
+            source: DataSource::Noaa,
+            base_time: Some(now),
+            extremes: Some(vec![
+                Extreme {
+                    at: now + Duration::minutes(158),
+                    tide_ft: 8.914,
+                    high: true,
+                },
+                Extreme {
+                    at: now + Duration::minutes(533),
+                    tide_ft: 1.1,
+                    high: false,
+                },
+            ]),
+            fetched_at: None,
+        };
+
+        let state = sensor_state(&series, &station, None, now);
+        assert_eq!(state.state, Some(5.0));
+        let json = serde_json::to_value(&state).unwrap();
+        let attributes = &json["attributes"];
+        assert_eq!(attributes["trend"], "rising");
+        assert_eq!(attributes["datum"], "MLLW");
+        assert_eq!(attributes["rate_ft_per_hour"], 1.2);
+        assert_eq!(attributes["next_high"], "2025-07-01T10:38:00-04:00");
+        assert_eq!(attributes["next_low_ft"], 1.1);
+        assert_eq!(attributes["updated"], "2025-07-01T08:00:00-04:00");
+        assert_eq!(attributes["offline"], false);
+        assert_eq!(attributes["source"], "NOAA");
+        assert_eq!(attributes["surge_ft"], serde_json::Value::Null);
+        assert_eq!(attributes["surge"], false);
+
+        // Water observed 1.3 ft over the predictions for the last half hour
+        let observed = TideSeries {
+            source: DataSource::Sensor,
+            ..series.relative_to(-1.3)
+        };
+        let mut surge = |threshold| {
+            station.surge_threshold_ft = threshold;
+            let state = sensor_state(&series, &station, Some(&observed), now);
+            (state.attributes.surge_ft, state.attributes.surge)
+        };
+        assert_eq!(surge(None), (Some(1.3), false));
+        assert_eq!(surge(Some(1.0)), (Some(1.3), true));
+        assert_eq!(surge(Some(1.5)), (Some(1.3), false));
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[cfg(feature = "net")]
+    async fn test_silent_client_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+        // As `serve`'s task does: answer, then drop the connection
+        let served = tokio::spawn(async move {
+            answer(
+                &mut stream,
+                &Config::default(),
+                &ObservedCache::default(),
+                None,
+            )
+            .await
+        });
+
+        // The client connects and sends nothing; the server hangs up
+        let mut buf = [0u8; 16];
+        assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+        let err = served.await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let head = tokio::time::timeout(HEAD_TIMEOUT, read_head(&mut server));
+        assert!(head.await.unwrap().unwrap().starts_with(b"GET "));
+    }
+
+    #[test]
+    fn test_http_response() {
+        assert_eq!(
+            http_response("200 OK", "{}"),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+             Content-Length: 2\r\nConnection: close\r\n\r\n{}"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// src/hw_rppal.rs
+//! GPIO and SPI through `rppal` (`--features rppal`, `backend = "rppal"`)
+//!
+//! rppal finds the header's GPIO lines itself instead of asking a gpiochip by
+//! number, so it keeps working on kernels that renumber the chips, and it
+//! offers PWM should a backlight or buzzer ever be added.
+use rppal::gpio::{Gpio, InputPin as RppalInput, OutputPin as RppalOutput};
+use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+use tide_clock_lib::config::HardwareConfig;
+use tide_clock_lib::epd4in2b_v2::{EpdError, GpioPin, InputPin, SoftwareSpi};
+
+fn spi_error(context: &str, e: rppal::spi::Error) -> EpdError {
+    let source = match e {
+        rppal::spi::Error::Io(io) => io,
+        other => std::io::Error::other(other.to_string()),
+    };
+    EpdError::spi(context, source)
+}
+
+fn gpio_error(pin: u32, e: rppal::gpio::Error) -> EpdError {
+    EpdError::Gpio(format!("GPIO {pin}: {e}"))
+}
+
+pub struct RppalOutputPin(RppalOutput);
+pub struct RppalInputPin(RppalInput);
+
+impl RppalOutputPin {
+    pub fn new(gpio: &Gpio, pin: u32) -> Result<Self, EpdError> {
+        let line = gpio.get(pin as u8).map_err(|e| gpio_error(pin, e))?;
+        Ok(Self(line.into_output_low()))
+    }
+}
+
+impl RppalInputPin {
+    pub fn new(gpio: &Gpio, pin: u32) -> Result<Self, EpdError> {
+        let line = gpio.get(pin as u8).map_err(|e| gpio_error(pin, e))?;
+        Ok(Self(line.into_input()))
+    }
+
+    /// Input with the internal pull-up on, for a button to ground
+    pub fn pulled_up(gpio: &Gpio, pin: u32) -> Result<Self, EpdError> {
+        let line = gpio.get(pin as u8).map_err(|e| gpio_error(pin, e))?;
+        Ok(Self(line.into_input_pullup()))
+    }
+}
+
+impl GpioPin for RppalOutputPin {
+    fn set_high(&mut self) -> Result<(), EpdError> {
+        self.0.set_high();
+        Ok(())
+    }
+    fn set_low(&mut self) -> Result<(), EpdError> {
+        self.0.set_low();
+        Ok(())
+    }
+}
+
+impl InputPin for RppalInputPin {
+    fn is_high(&self) -> Result<bool, EpdError> {
+        Ok(self.0.is_high())
+    }
+}
+
+pub struct RppalSpi {
+    spi: Spi,
+    /// Toggled around every transfer when `cs_pin` isn't CE0 or CE1
+    cs: Option<RppalOutputPin>,
+}
+
+impl RppalSpi {
+    /// Open the display's SPI device with `hw`'s bus, mode and speed
+    ///
+    /// CS on GPIO 8 or 7 is driven by the kernel; any other pin is toggled
+    /// by hand, with the bus opened on CE0.
+    pub fn open(gpio: &Gpio, hw: &HardwareConfig) -> Result<Self, EpdError> {
+        let bus = match hw.spi_bus {
+            0 => Bus::Spi0,
+            1 => Bus::Spi1,
+            2 => Bus::Spi2,
+            3 => Bus::Spi3,
+            4 => Bus::Spi4,
+            5 => Bus::Spi5,
+            6 => Bus::Spi6,
+            n => {
+                return Err(EpdError::spi(
+                    "opening the bus",
+                    std::io::Error::other(format!("no SPI bus {n}")),
+                ))
+            }
+        };
+        let mode = match hw.spi_mode {
+            1 => Mode::Mode1,
+            2 => Mode::Mode2,
+            3 => Mode::Mode3,
+            _ => Mode::Mode0,
+        };
+        let (ss, cs) = match hw.cs_pin {
+            8 => (SlaveSelect::Ss0, None),
+            7 => (SlaveSelect::Ss1, None),
+            pin => {
+                let mut cs = RppalOutputPin::new(gpio, pin)?;
+                cs.set_high()?;
+                (SlaveSelect::Ss0, Some(cs))
+            }
+        };
+        let context = format!("opening spidev{}.{}", hw.spi_bus, ss as u8);
+        let spi = Spi::new(bus, ss, hw.spi_speed_hz, mode).map_err(|e| spi_error(&context, e))?;
+        Ok(Self { spi, cs })
+    }
+
+    fn selected<T>(
+        &mut self,
+        op: impl FnOnce(&mut Spi) -> Result<T, EpdError>,
+    ) -> Result<T, EpdError> {
+        if let Some(cs) = &mut self.cs {
+            cs.set_low()?;
+        }
+        let r = op(&mut self.spi);
+        if let Some(cs) = &mut self.cs {
+            cs.set_high()?;
+        }
+        r
+    }
+}
+
+impl SoftwareSpi for RppalSpi {
+    fn write_byte(&mut self, data: u8) -> Result<(), EpdError> {
+        self.selected(|spi| {
+            spi.write(&[data])
+                .map(|_| ())
+                .map_err(|e| spi_error("write", e))
+        })
+    }
+    fn read_byte(&mut self) -> Result<u8, EpdError> {
+        let mut rx = [0u8];
+        self.read_bytes(&mut rx)?;
+        Ok(rx[0])
+    }
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), EpdError> {
+        self.selected(|spi| {
+            let tx = vec![0x00u8; buf.len()];
+            spi.transfer(buf, &tx)
+                .map(|_| ())
+                .map_err(|e| spi_error("read", e))
+        })
+    }
+}
@@ -1,76 +1,45 @@
-use crate::gpio_sysfs::CdevOutputPin;
-
-/// Manual CS wrapper: toggles CS GPIO around every SPI transfer
-pub struct SpidevManualCs {
-    spi: SpidevHwSpi,
-    cs: CdevOutputPin,
-}
-
-#[allow(dead_code)]
-impl SpidevManualCs {
-    pub fn new(spi: SpidevHwSpi, cs: CdevOutputPin) -> Self {
-        Self { spi, cs }
-    }
-}
-
-impl SoftwareSpi for SpidevManualCs {
-    fn write_byte(&mut self, data: u8) -> Result<(), EpdError> {
-        self.cs.set_low()?;
-        let r = self.spi.write_byte(data);
-        self.cs.set_high()?;
-        r
-    }
-    fn read_byte(&mut self) -> Result<u8, EpdError> {
-        self.cs.set_low()?;
-        let r = self.spi.read_byte();
-        self.cs.set_high()?;
-        r
-    }
-}
 // src/hw_spi_spidev.rs
+use crate::gpio_sysfs::CdevOutputPin;
 use spidev::{SpiModeFlags, Spidev, SpidevOptions, SpidevTransfer};
+use std::collections::HashMap;
 use std::io::Write; // <-- add this
 use tide_clock_lib::epd4in2b_v2::{EpdError, GpioPin, SoftwareSpi};
+use tide_clock_lib::spi_bus::{
+    SharedSpiBus, SharedSpiDevice, SpiController, SpiRoute, SpiSettings,
+};
 
-/// SPI bus selection for hardware CS
-#[derive(Debug, Clone, Copy)]
-pub enum SlaveSelect {
-    Ce0,
-    Ce1,
+fn options(settings: SpiSettings) -> SpidevOptions {
+    let mut mode = match settings.mode {
+        1 => SpiModeFlags::SPI_MODE_1,
+        2 => SpiModeFlags::SPI_MODE_2,
+        3 => SpiModeFlags::SPI_MODE_3,
+        _ => SpiModeFlags::SPI_MODE_0,
+    };
+    if settings.no_cs {
+        // The node's CE line stays idle; a GPIO selects the device
+        mode |= SpiModeFlags::SPI_NO_CS;
+    }
+    SpidevOptions::new()
+        .bits_per_word(8)
+        .max_speed_hz(settings.speed_hz)
+        .mode(mode)
+        .build()
 }
 
 pub struct SpidevHwSpi {
     dev: Spidev,
 }
 
-#[allow(dead_code)]
 impl SpidevHwSpi {
-    /// Create a new SPI device for the given slave select (CE0 or CE1)
-    pub fn new(ss: SlaveSelect) -> Result<Self, EpdError> {
-        let dev_path = match ss {
-            SlaveSelect::Ce0 => "/dev/spidev0.0",
-            SlaveSelect::Ce1 => "/dev/spidev0.1",
-        };
-        let mut dev = Spidev::open(dev_path).map_err(|e| EpdError(e.to_string()))?;
-
-        let opts = SpidevOptions::new()
-            .bits_per_word(8)
-            .max_speed_hz(8_000_000) // SSD1683 spec tops at 8 MHz
-            .mode(SpiModeFlags::SPI_MODE_0)
-            .build();
-        dev.configure(&opts).map_err(|e| EpdError(e.to_string()))?;
+    /// Open `/dev/spidev{bus}.{node}` with explicit mode/speed
+    pub fn open(bus: u8, node: u8, settings: SpiSettings) -> Result<Self, EpdError> {
+        let dev_path = format!("/dev/spidev{bus}.{node}");
+        let mut dev =
+            Spidev::open(&dev_path).map_err(|e| EpdError::spi(format!("opening {dev_path}"), e))?;
+        dev.configure(&options(settings))
+            .map_err(|e| EpdError::spi(format!("configuring {dev_path}"), e))?;
         Ok(Self { dev })
     }
-
-    /// Convenience: open CE0 (GPIO 8)
-    pub fn new_ce0() -> Result<Self, EpdError> {
-        Self::new(SlaveSelect::Ce0)
-    }
-
-    /// Convenience: open CE1 (GPIO 7)
-    pub fn new_ce1() -> Result<Self, EpdError> {
-        Self::new(SlaveSelect::Ce1)
-    }
 }
 
 impl SoftwareSpi for SpidevHwSpi {
@@ -78,15 +47,75 @@ impl SoftwareSpi for SpidevHwSpi {
         self.dev
             .write(&[data]) // returns Result<usize> :contentReference[oaicite:1]{index=1}
             .map(|_| ()) // map Ok(len)  → Ok(())
-            .map_err(|e| EpdError(e.to_string()))
+            .map_err(|e| EpdError::spi("write", e))
     }
     fn read_byte(&mut self) -> Result<u8, EpdError> {
-        let tx = [0x00u8]; // dummy
         let mut rx = [0u8];
-        let mut tr = SpidevTransfer::read_write(&tx, &mut rx);
+        self.read_bytes(&mut rx)?;
+        Ok(rx[0])
+    }
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), EpdError> {
+        let tx = vec![0x00u8; buf.len()]; // dummy
+        let mut tr = SpidevTransfer::read_write(&tx, buf);
         self.dev
             .transfer(&mut tr)
-            .map_err(|e| EpdError(e.to_string()))?;
-        Ok(rx[0])
+            .map_err(|e| EpdError::spi("read", e))
+    }
+}
+
+impl SpiController for SpidevHwSpi {
+    fn configure(&mut self, settings: SpiSettings) -> Result<(), EpdError> {
+        self.dev
+            .configure(&options(settings))
+            .map_err(|e| EpdError::spi("reconfiguring the shared bus", e))
+    }
+}
+
+/// The display's handle on its bus, holding the other devices' handles so
+/// their chip selects stay deselected while the display is in use
+pub struct DisplaySpi {
+    display: SharedSpiDevice<SpidevHwSpi>,
+    _peripherals: Vec<SharedSpiDevice<SpidevHwSpi>>,
+}
+
+impl DisplaySpi {
+    /// Open every node `routes` go through, once each, with the display
+    /// first; manual chip selects are claimed on `chip`
+    pub fn open(routes: &[SpiRoute], chip: &mut gpio_cdev::Chip) -> Result<Self, EpdError> {
+        let mut buses: HashMap<(u8, u8), SharedSpiBus<SpidevHwSpi>> = HashMap::new();
+        let mut devices = Vec::with_capacity(routes.len());
+        for route in routes {
+            let bus = match buses.get(&(route.bus, route.node)) {
+                Some(bus) => bus.clone(),
+                None => {
+                    let spi = SpidevHwSpi::open(route.bus, route.node, route.settings)?;
+                    let bus = SharedSpiBus::new(spi, route.settings);
+                    buses.insert((route.bus, route.node), bus.clone());
+                    bus
+                }
+            };
+            let cs = match route.manual_cs {
+                Some(pin) => Some(Box::new(CdevOutputPin::new(chip, pin)?) as Box<dyn GpioPin>),
+                None => None,
+            };
+            devices.push(bus.device(route.settings, cs)?);
+        }
+        let mut devices = devices.into_iter();
+        Ok(Self {
+            display: devices.next().expect("routes start with the display"),
+            _peripherals: devices.collect(),
+        })
+    }
+}
+
+impl SoftwareSpi for DisplaySpi {
+    fn write_byte(&mut self, data: u8) -> Result<(), EpdError> {
+        self.display.write_byte(data)
+    }
+    fn read_byte(&mut self) -> Result<u8, EpdError> {
+        self.display.read_byte()
+    }
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), EpdError> {
+        self.display.read_bytes(buf)
     }
 }
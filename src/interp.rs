@@ -0,0 +1,219 @@
+//! # Interpolation
+//!
+//! Monotone cubic Hermite interpolation (Fritsch–Carlson) for resampling
+//! NOAA's 6-minute or hourly points onto the display grid. Unlike linear
+//! interpolation it has no kinks at the input points, so highs and lows come
+//! out rounded; unlike an ordinary cubic spline it never overshoots, so a
+//! curve through a high tide can't invent water above the predicted peak.
+//!
+//! Between two points the curve stays within their heights, and wherever the
+//! data rises (or falls) the curve does too.
+//!
+//! ```
+//! use tide_clock_lib::interp::MonotoneCubic;
+//!
+//! let curve = MonotoneCubic::new(&[(0.0, 1.0), (1.0, 3.0), (2.0, 3.0), (3.0, 0.0)]).unwrap();
+//! assert_eq!(curve.eval(1.0), 3.0);
+//! assert!(curve.eval(1.5) <= 3.0); // flat between the equal points, no bump
+//! assert_eq!(curve.eval(-5.0), 1.0); // holds the edge value outside
+//! ```
+
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::Float;
+
+/// Monotone cubic Hermite interpolant through a set of points
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonotoneCubic {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    /// Tangent at each point, limited so every interval stays monotone
+    slopes: Vec<f64>,
+}
+
+impl MonotoneCubic {
+    /// Interpolant through `points`, which must be sorted by strictly
+    /// increasing `x`
+    ///
+    /// Returns `None` with fewer than two points or when `x` doesn't
+    /// strictly increase.
+    pub fn new(points: &[(f64, f64)]) -> Option<Self> {
+        if points.len() < 2 || points.windows(2).any(|w| w[1].0 <= w[0].0) {
+            return None;
+        }
+        let (xs, ys): (Vec<f64>, Vec<f64>) = points.iter().copied().unzip();
+        let n = xs.len();
+
+        // Secant slope of each interval
+        let secants: Vec<f64> = (0..n - 1)
+            .map(|k| (ys[k + 1] - ys[k]) / (xs[k + 1] - xs[k]))
+            .collect();
+
+        // Initial tangents: one-sided at the ends, the average of the two
+        // secants inside, and flat at a turning point
+        let mut slopes = Vec::with_capacity(n);
+        slopes.push(secants[0]);
+        for w in secants.windows(2) {
+            slopes.push(if w[0] * w[1] <= 0.0 {
+                0.0
+            } else {
+                (w[0] + w[1]) / 2.0
+            });
+        }
+        slopes.push(secants[n - 2]);
+
+        // Fritsch–Carlson: flatten tangents beside flat intervals, and scale
+        // any pair that would make its interval overshoot back into the
+        // monotone region (α² + β² ≤ 9)
+        for (k, &secant) in secants.iter().enumerate() {
+            if secant == 0.0 {
+                slopes[k] = 0.0;
+                slopes[k + 1] = 0.0;
+                continue;
+            }
+            let alpha = slopes[k] / secant;
+            let beta = slopes[k + 1] / secant;
+            let norm = alpha.hypot(beta);
+            if norm > 3.0 {
+                let tau = 3.0 / norm;
+                slopes[k] = tau * alpha * secant;
+                slopes[k + 1] = tau * beta * secant;
+            }
+        }
+
+        Some(Self { xs, ys, slopes })
+    }
+
+    /// Value at `x`; outside the points the nearest end value is held
+    pub fn eval(&self, x: f64) -> f64 {
+        let last = self.xs.len() - 1;
+        if x <= self.xs[0] {
+            return self.ys[0];
+        }
+        if x >= self.xs[last] {
+            return self.ys[last];
+        }
+        // Interval [k, k + 1] containing x
+        let k = self.xs.partition_point(|&xi| xi <= x) - 1;
+        let h = self.xs[k + 1] - self.xs[k];
+        let t = (x - self.xs[k]) / h;
+        let (t2, t3) = (t * t, t * t * t);
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = t3 - 2.0 * t2 + t;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = t3 - t2;
+        h00 * self.ys[k]
+            + h10 * h * self.slopes[k]
+            + h01 * self.ys[k + 1]
+            + h11 * h * self.slopes[k + 1]
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    /// Evaluate `curve` at 50 steps inside every interval of `points`
+    fn within_intervals(points: &[(f64, f64)], curve: &MonotoneCubic) -> Vec<(usize, f64)> {
+        points
+            .windows(2)
+            .enumerate()
+            .flat_map(|(k, w)| {
+                (0..=50).map(move |i| (k, w[0].0 + (w[1].0 - w[0].0) * i as f64 / 50.0))
+            })
+            .map(|(k, x)| (k, curve.eval(x)))
+            .collect()
+    }
+
+    #[test]
+    fn test_passes_through_points_and_reproduces_lines() {
+        let points: Vec<_> = (0..10).map(|i| (i as f64, 2.0 * i as f64 - 3.0)).collect();
+        let curve = MonotoneCubic::new(&points).unwrap();
+        for &(x, y) in &points {
+            assert_eq!(curve.eval(x), y);
+        }
+        for i in 0..90 {
+            let x = i as f64 / 10.0;
+            assert!((curve.eval(x) - (2.0 * x - 3.0)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_no_overshoot() {
+        // A step and a spike: an ordinary cubic spline rings around both
+        let points = [
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (3.0, 10.0),
+            (4.0, 10.0),
+            (5.0, 10.0),
+            (6.0, 0.0),
+            (6.5, 9.0),
+            (7.0, 0.0),
+        ];
+        let curve = MonotoneCubic::new(&points).unwrap();
+        for (k, y) in within_intervals(&points, &curve) {
+            let (a, b) = (points[k].1, points[k + 1].1);
+            assert!(
+                y >= a.min(b) - 1e-9 && y <= a.max(b) + 1e-9,
+                "interval {k}: {y} outside {a}..{b}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_monotone_data_stays_monotone() {
+        // Uneven spacing and steepness, always rising
+        let points = [
+            (0.0, 0.0),
+            (0.5, 0.1),
+            (2.0, 4.0),
+            (2.2, 4.1),
+            (5.0, 4.2),
+            (6.0, 9.0),
+        ];
+        let curve = MonotoneCubic::new(&points).unwrap();
+        let values: Vec<f64> = within_intervals(&points, &curve)
+            .into_iter()
+            .map(|(_, y)| y)
+            .collect();
+        assert!(values.windows(2).all(|w| w[1] >= w[0] - 1e-12));
+    }
+
+    #[test]
+    fn test_rounds_highs_closer_than_linear() {
+        // Hourly samples of a semidiurnal tide, checked every 6 minutes
+        let tide = |h: f64| 5.0 + 4.0 * (h * std::f64::consts::TAU / 12.42).cos();
+        let points: Vec<_> = (0..=24).map(|h| (h as f64, tide(h as f64))).collect();
+        let curve = MonotoneCubic::new(&points).unwrap();
+        let linear = |x: f64| {
+            let k = (x.floor() as usize).min(23);
+            let t = x - k as f64;
+            points[k].1 + t * (points[k + 1].1 - points[k].1)
+        };
+        // Less than half the linear error overall, and no worse at the peaks
+        // (which the monotone limit flattens between samples)
+        let (mut cubic_sum, mut linear_sum) = (0.0f64, 0.0f64);
+        let (mut cubic_max, mut linear_max) = (0.0f64, 0.0f64);
+        for i in 0..=240 {
+            let x = i as f64 / 10.0;
+            let cubic = (curve.eval(x) - tide(x)).abs();
+            let straight = (linear(x) - tide(x)).abs();
+            cubic_sum += cubic;
+            linear_sum += straight;
+            cubic_max = cubic_max.max(cubic);
+            linear_max = linear_max.max(straight);
+        }
+        assert!(cubic_sum < linear_sum / 2.0, "{cubic_sum} vs {linear_sum}");
+        assert!(cubic_max < linear_max, "{cubic_max} vs {linear_max}");
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert!(MonotoneCubic::new(&[(0.0, 1.0)]).is_none());
+        assert!(MonotoneCubic::new(&[(0.0, 1.0), (0.0, 2.0)]).is_none());
+        assert!(MonotoneCubic::new(&[(1.0, 1.0), (0.0, 2.0)]).is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! # Large-Print Layout
+//!
+//! A high-contrast, minimal layout for readers who can't make out the chart:
+//! only the current height, a trend arrow and the next high/low, each drawn
+//! in the largest integer scale of the 10x20 font that fits its band.
+//!
+//! ```text
+//! ┌──────────────────────────┐
+//! │          6.4 ft          │  ← current height (largest)
+//! │        ▲  RISING         │  ← trend arrow + word
+//! │      HIGH 3:45PM         │  ← next extreme
+//! └──────────────────────────┘
+//! ```
+
+use crate::layout::{Layout, LayoutContext};
+use crate::{Sample, TideSeries, Trend};
+use embedded_graphics::mono_font::{ascii::FONT_10X20, MonoTextStyle};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle, Triangle};
+use embedded_graphics::text::{Baseline, Text};
+
+/// Outer margin around the whole layout in pixels
+const MARGIN: u32 = 8;
+
+/// Built-in large-print layout (`layout = "large"`)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LargePrintLayout;
+
+impl LargePrintLayout {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<D> Layout<D> for LargePrintLayout
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    fn name(&self) -> &'static str {
+        "large"
+    }
+
+    fn draw(&self, ctx: &LayoutContext<'_>, target: &mut D) -> Result<(), D::Error> {
+        let series = ctx.series;
+        let width = ctx.size.width.saturating_sub(2 * MARGIN);
+        let height = ctx.size.height.saturating_sub(2 * MARGIN);
+
+        // Bands: 45% current height, 25% trend, 30% next event
+        let band_a = height * 45 / 100;
+        let band_b = height * 25 / 100;
+        let band_c = height - band_a - band_b;
+        let top_a = MARGIN;
+        let top_b = top_a + band_a;
+        let top_c = top_b + band_b;
+
+        let Some(now) = now_sample(series) else {
+            return draw_scaled_centered(target, "NO DATA", top_a, width, height);
+        };
+
+        let current = ctx.meta.format_height(now.tide_ft);
+        draw_scaled_centered(target, &current, top_a, width, band_a)?;
+
+        // Trend: arrow plus word, arrow sized to the text scale
+        let trend = series.trend();
+        let word = trend.label();
+        let arrow_w = band_b.saturating_sub(8);
+        let word_width = width.saturating_sub(arrow_w + MARGIN);
+        let scale = fit_scale(word, word_width, band_b);
+        let text_w = text_width(word) * scale;
+        let total = arrow_w + MARGIN + text_w;
+        let left = MARGIN + width.saturating_sub(total) / 2;
+        draw_arrow(target, left, top_b + 4, arrow_w, trend, BinaryColor::On)?;
+        draw_scaled(
+            target,
+            word,
+            Point::new(
+                (left + arrow_w + MARGIN) as i32,
+                (top_b + band_b.saturating_sub(20 * scale) / 2) as i32,
+            ),
+            scale,
+            BinaryColor::On,
+        )?;
+
+        // Next high or low with its clock time
+        let now = ctx.meta.generated_at;
+        let next = match series.next_extreme(now) {
+            Some(extreme) => {
+                let kind = if extreme.high { "HIGH" } else { "LOW" };
+                let mins_rel = (extreme.at - now).num_minutes();
+                format!("{} {}", kind, ctx.meta.clock_at(mins_rel))
+            }
+            None => "--".to_string(),
+        };
+        draw_scaled_centered(target, &next, top_c, width, band_c)
+    }
+}
+
+/// Sample closest to "now"
+fn now_sample(series: &TideSeries) -> Option<&Sample> {
+    series.samples.iter().min_by_key(|s| s.mins_rel.abs())
+}
+
+fn text_width(text: &str) -> u32 {
+    text.chars().count() as u32 * FONT_10X20.character_size.width
+}
+
+/// Largest integer scale of FONT_10X20 at which `text` fits in `w` x `h`
+fn fit_scale(text: &str, w: u32, h: u32) -> u32 {
+    let by_width = w / text_width(text).max(1);
+    let by_height = h / FONT_10X20.character_size.height;
+    by_width.min(by_height).max(1)
+}
+
+fn draw_scaled_centered<D>(
+    target: &mut D,
+    text: &str,
+    top: u32,
+    w: u32,
+    h: u32,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let scale = fit_scale(text, w, h);
+    let x = MARGIN + w.saturating_sub(text_width(text) * scale) / 2;
+    let y = top + h.saturating_sub(FONT_10X20.character_size.height * scale) / 2;
+    draw_scaled(
+        target,
+        text,
+        Point::new(x as i32, y as i32),
+        scale,
+        BinaryColor::On,
+    )
+}
+
+/// Draw `text` with its top-left at `origin`, each font pixel as a `scale` square
+pub(crate) fn draw_scaled<D>(
+    target: &mut D,
+    text: &str,
+    origin: Point,
+    scale: u32,
+    color: BinaryColor,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let mut scaled = Scaled {
+        inner: target,
+        origin,
+        scale,
+    };
+    Text::with_baseline(
+        text,
+        Point::zero(),
+        MonoTextStyle::new(&FONT_10X20, color),
+        Baseline::Top,
+    )
+    .draw(&mut scaled)?;
+    Ok(())
+}
+
+/// Filled `size`-pixel triangle pointing up for rising water, down for falling
+pub(crate) fn draw_arrow<D>(
+    target: &mut D,
+    left: u32,
+    top: u32,
+    size: u32,
+    trend: Trend,
+    color: BinaryColor,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let (l, t, s) = (left as i32, top as i32, size as i32);
+    let triangle = if trend == Trend::Rising {
+        Triangle::new(
+            Point::new(l + s / 2, t),
+            Point::new(l, t + s),
+            Point::new(l + s, t + s),
+        )
+    } else {
+        Triangle::new(
+            Point::new(l, t),
+            Point::new(l + s, t),
+            Point::new(l + s / 2, t + s),
+        )
+    };
+    triangle
+        .into_styled(PrimitiveStyle::with_fill(color))
+        .draw(target)
+}
+
+/// Draw target adapter that magnifies every pixel into a `scale` x `scale` block
+struct Scaled<'a, D> {
+    inner: &'a mut D,
+    origin: Point,
+    scale: u32,
+}
+
+impl<D> OriginDimensions for Scaled<'_, D>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    fn size(&self) -> Size {
+        let inner = self.inner.bounding_box().size;
+        Size::new(inner.width / self.scale, inner.height / self.scale)
+    }
+}
+
+impl<D> DrawTarget for Scaled<'_, D>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    type Color = BinaryColor;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(p, color) in pixels {
+            let top_left = self.origin + p * self.scale as i32;
+            self.inner.fill_solid(
+                &Rectangle::new(top_left, Size::new_equal(self.scale)),
+                color,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataSource;
+
+    fn series(points: &[(i16, f32)]) -> TideSeries {
+        TideSeries {
+            samples: points
+                .iter()
+                .map(|&(mins_rel, tide_ft)| Sample {
+                    mins_rel,
+                    tide_ft,
+                    modeled: false,
+                })
+                .collect(),
+            source: DataSource::Noaa,
+            base_time: None,
+            extremes: None,
+            fetched_at: None,
+        }
+    }
+
+    #[test]
+    fn test_fit_scale_prefers_largest() {
+        // "6.4 ft" is 60x20 at scale 1; 384x130 allows scale 6
+        assert_eq!(fit_scale("6.4 ft", 384, 130), 6);
+        // Never below 1 even if it doesn't fit
+        assert_eq!(fit_scale("a very long string", 10, 10), 1);
+    }
+
+    #[test]
+    fn test_trend() {
+        let s = series(&[(-10, 1.0), (0, 2.0), (10, 3.0), (20, 2.5), (30, 2.0)]);
+        assert!(s.rate_ft_per_hour() > 0.0);
+    }
+}
@@ -0,0 +1,395 @@
+//! # Layout API
+//!
+//! A [`Layout`] turns a [`TideSeries`] plus some context into pixels on any
+//! embedded-graphics [`DrawTarget`]. The built-in chart is implemented through
+//! this trait, and so can layouts living in other crates:
+//!
+//! ```
+//! use embedded_graphics::pixelcolor::BinaryColor;
+//! use embedded_graphics::prelude::*;
+//! use embedded_graphics::primitives::{Line, PrimitiveStyle};
+//! use tide_clock_lib::layout::{Layout, LayoutContext};
+//!
+//! /// A single horizontal line at the current tide level.
+//! struct WaterLine;
+//!
+//! impl<D: DrawTarget<Color = BinaryColor>> Layout<D> for WaterLine {
+//!     fn name(&self) -> &'static str {
+//!         "waterline"
+//!     }
+//!
+//!     fn draw(&self, ctx: &LayoutContext<'_>, target: &mut D) -> Result<(), D::Error> {
+//!         let y = ctx.size.height as i32 / 2;
+//!         Line::new(Point::new(0, y), Point::new(ctx.size.width as i32 - 1, y))
+//!             .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, ctx.theme.curve_stroke))
+//!             .draw(target)
+//!     }
+//! }
+//! ```
+//!
+//! Layouts only draw; fetching data, choosing a theme and pushing the frame to
+//! the panel stay with the caller.
+
+use crate::config::{Config, Datum, StationConfig, TimeFormat};
+use crate::eink_renderer::{ChartStyle, EinkTideRenderer};
+use crate::epd4in2b_v2::DisplayBuffer;
+use crate::large_print::LargePrintLayout;
+use crate::lunar::LunarEphemeris;
+use crate::theme::{InkColor, Theme};
+use crate::tide_table::{SplitLayout, TideTableLayout};
+use crate::TideSeries;
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+
+/// Descriptive information about a series that is not part of the samples
+#[derive(Debug, Clone)]
+pub struct SeriesMeta {
+    /// NOAA station ID the series was requested for
+    pub station_id: String,
+    /// Human-readable station name
+    pub station_name: String,
+    /// Instant the frame is being rendered for
+    pub generated_at: DateTime<Utc>,
+    /// Station timezone for printed times (`None` = the system's)
+    pub timezone: Option<Tz>,
+    /// 12-hour or 24-hour clock for any times the layout prints
+    pub time_format: TimeFormat,
+    /// Vertical datum the heights are referenced to
+    pub datum: Datum,
+    /// Name of the custom zero reference, when heights are relative to one
+    pub reference: Option<String>,
+    /// MLLW to MSL offset to subtract from heights that are shown relative
+    /// to mean sea level (`show_msl`), `None` to show them as they are
+    pub msl_offset: Option<f32>,
+    /// The moon at `generated_at`, when it should be shown
+    pub moon: Option<LunarEphemeris>,
+    /// Position in the spring–neap cycle (0 neap, 1 spring), when it should
+    /// be shown, see [`LunarEphemeris::spring_fraction`]
+    pub spring_neap: Option<f64>,
+    /// Flood threshold in the same units as the series (relative to the
+    /// reference when there is one), when one is configured
+    pub flood_threshold_ft: Option<f32>,
+}
+
+impl SeriesMeta {
+    /// Build metadata for the configured station at `generated_at`
+    pub fn from_config(config: &Config, generated_at: DateTime<Utc>) -> Self {
+        Self::for_station(config, &config.station, generated_at)
+    }
+
+    /// Build metadata for any of the configured stations
+    pub fn for_station(
+        config: &Config,
+        station: &StationConfig,
+        generated_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            station_id: station.id.clone(),
+            station_name: station.name.clone(),
+            generated_at,
+            timezone: station.timezone,
+            time_format: config.display.time_format,
+            datum: station.datum,
+            reference: station.reference.as_ref().map(|r| r.name.clone()),
+            msl_offset: (station.show_msl && station.reference.is_none())
+                .then_some(station.msl_offset),
+            moon: config
+                .display
+                .show_moon_phase
+                .then(|| crate::lunar::moon_at(generated_at)),
+            spring_neap: config
+                .display
+                .show_spring_neap
+                .then(|| crate::lunar::moon_at(generated_at).spring_fraction()),
+            flood_threshold_ft: station.flood_threshold_ft.map(|threshold| {
+                threshold - station.reference.as_ref().map_or(0.0, |r| r.elevation_ft)
+            }),
+        }
+    }
+
+    /// What the height axis is measured from, e.g. "ft MLLW", "ft MSL" or
+    /// "ft vs intake"
+    pub fn axis_label(&self) -> String {
+        match &self.reference {
+            Some(name) => format!("ft vs {}", name),
+            None if self.msl_offset.is_some() => "ft MSL".to_string(),
+            None => format!("ft {}", self.datum.label()),
+        }
+    }
+
+    /// Station wall-clock time `mins_rel` minutes from `generated_at`
+    pub fn local_time(&self, mins_rel: i64) -> DateTime<FixedOffset> {
+        crate::config::local_time(
+            self.timezone,
+            self.generated_at + chrono::Duration::minutes(mins_rel),
+        )
+    }
+
+    /// Height as shown to the user, e.g. "6.4 ft", or "+1.5 ft" relative to MSL
+    pub fn format_height(&self, tide_ft: f32) -> String {
+        format!("{} ft", self.format_height_value(tide_ft))
+    }
+
+    /// [`format_height`](Self::format_height) without the unit, for
+    /// compact lists such as the footer
+    pub fn format_height_value(&self, tide_ft: f32) -> String {
+        let shown = crate::display_units::tide_to_display(tide_ft, self.msl_offset);
+        match self.msl_offset {
+            Some(_) => format!("{:+.1}", shown),
+            None => format!("{:.1}", shown),
+        }
+    }
+
+    /// Format the wall-clock time `mins_rel` minutes from `generated_at`
+    pub fn clock_at(&self, mins_rel: i64) -> String {
+        self.local_time(mins_rel)
+            .format(self.time_format.clock())
+            .to_string()
+    }
+
+    /// Station-local whole hours that are a multiple of `every_hours`, from
+    /// `from_mins` to `to_mins` minutes after `generated_at`
+    ///
+    /// Each comes as its offset in minutes from `generated_at` (fractional
+    /// when `generated_at` isn't on the minute) and a short label such as
+    /// "3PM" or "15:00".
+    pub fn hour_ticks(&self, from_mins: i64, to_mins: i64, every_hours: u32) -> Vec<(f32, String)> {
+        use chrono::{Duration, Timelike};
+
+        if every_hours == 0 {
+            return Vec::new();
+        }
+        let start = self.generated_at + Duration::minutes(from_mins);
+        let end = self.generated_at + Duration::minutes(to_mins);
+        let local = crate::config::local_time(self.timezone, start);
+        let into_hour = (local.minute() * 60 + local.second()) as i64;
+        let mut hour = start + Duration::seconds((3600 - into_hour) % 3600)
+            - Duration::nanoseconds(local.nanosecond() as i64);
+        if hour < start {
+            hour += Duration::hours(1);
+        }
+
+        let mut ticks = Vec::new();
+        while hour <= end {
+            let local = crate::config::local_time(self.timezone, hour);
+            if local.hour() % every_hours == 0 {
+                let mins = (hour - self.generated_at).num_seconds() as f32 / 60.0;
+                ticks.push((mins, local.format(self.time_format.hour()).to_string()));
+            }
+            hour += Duration::hours(1);
+        }
+        ticks
+    }
+}
+
+/// A secondary series drawn on top of the main one (multi-station overlay)
+#[derive(Debug, Clone, Copy)]
+pub struct OverlaySeries<'a> {
+    /// Legend label, usually the station name
+    pub label: &'a str,
+    pub series: &'a TideSeries,
+}
+
+/// Everything a layout receives besides the draw target
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutContext<'a> {
+    /// Tide samples to draw
+    pub series: &'a TideSeries,
+    /// Station and timing information
+    pub meta: &'a SeriesMeta,
+    /// Appearance settings
+    pub theme: &'a Theme,
+    /// Size of the area the layout may draw into, starting at (0, 0)
+    pub size: Size,
+    /// Additional series to draw alongside `series`; layouts that can't
+    /// show more than one curve ignore these
+    pub overlays: &'a [OverlaySeries<'a>],
+}
+
+/// A way of drawing tide data onto a monochrome draw target
+///
+/// The trait is generic over the target so it can be used as a trait object
+/// (`Box<dyn Layout<DisplayBuffer>>`) while implementations stay generic.
+pub trait Layout<D>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    /// Short identifier used to select the layout from configuration
+    fn name(&self) -> &'static str;
+
+    /// Draw the layout into `target`
+    fn draw(&self, ctx: &LayoutContext<'_>, target: &mut D) -> Result<(), D::Error>;
+}
+
+/// Name of the layout used when none is configured
+pub const DEFAULT_LAYOUT: &str = "chart";
+
+/// Names of all layouts shipped with this crate
+pub const BUILTIN_LAYOUTS: &[&str] = &["chart", "large", "table", "split"];
+
+/// Look up a built-in layout by name
+pub fn builtin<D>(name: &str) -> Option<Box<dyn Layout<D>>>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    builtin_with_style(name, &ChartStyle::default())
+}
+
+/// Look up a built-in layout by name, drawing any chart with `style`
+pub fn builtin_with_style<D>(name: &str, style: &ChartStyle) -> Option<Box<dyn Layout<D>>>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    match name {
+        "chart" => Some(Box::new(EinkTideRenderer::with_style(style.clone()))),
+        "large" => Some(Box::new(LargePrintLayout::new())),
+        "table" => Some(Box::new(TideTableLayout::new())),
+        "split" => Some(Box::new(SplitLayout::with_chart_style(style.clone()))),
+        _ => None,
+    }
+}
+
+/// Draw `layout` onto both planes of a tri-color panel's `buffer`
+///
+/// Elements the theme colors [`InkColor::Red`] go to the red plane and
+/// everything else to the black one. The layout is drawn twice, once with
+/// its red elements in black and once without them, so any layout gets red
+/// accents without knowing about the second plane.
+pub fn draw_tricolor(
+    layout: &dyn Layout<DisplayBuffer>,
+    ctx: &LayoutContext<'_>,
+    buffer: &mut DisplayBuffer,
+) {
+    let mut with_red = DisplayBuffer::new(buffer.size().width, buffer.size().height);
+    let Ok(()) = layout.draw(ctx, &mut with_red);
+
+    let without_red = ctx.theme.with_red_as(InkColor::White);
+    let black_ctx = LayoutContext {
+        theme: &without_red,
+        ..*ctx
+    };
+    let Ok(()) = layout.draw(&black_ctx, buffer);
+    buffer.mark_red(&with_red);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_layouts_resolve() {
+        for name in BUILTIN_LAYOUTS {
+            let layout = builtin::<DisplayBuffer>(name).expect("built-in layout");
+            assert_eq!(layout.name(), *name);
+        }
+        assert!(builtin::<DisplayBuffer>("nope").is_none());
+    }
+
+    #[test]
+    fn test_hour_ticks_follow_the_station_clock() {
+        use chrono::TimeZone;
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 20, 30).unwrap();
+        let mut meta = SeriesMeta::from_config(&Config::default(), now);
+        meta.timezone = Some(chrono_tz::UTC);
+
+        let ticks = meta.hour_ticks(-720, 720, 3);
+        let labels: Vec<&str> = ticks.iter().map(|(_, l)| l.as_str()).collect();
+        assert_eq!(
+            labels,
+            ["3AM", "6AM", "9AM", "12PM", "3PM", "6PM", "9PM", "12AM"]
+        );
+        // 03:00 is 9h 20m 30s before now
+        assert_eq!(ticks[0].0, -560.5);
+
+        meta.time_format = TimeFormat::H24;
+        assert_eq!(meta.hour_ticks(-720, 720, 6)[0].1, "06:00");
+        assert!(meta.hour_ticks(-720, 720, 0).is_empty());
+
+        // Half-hour zones tick on their own whole hours: 12:20:30 UTC is
+        // 17:50:30 in India, so 18:00 there is 9.5 minutes away
+        meta.timezone = Some(chrono_tz::Asia::Kolkata);
+        let ticks = meta.hour_ticks(0, 60, 3);
+        assert_eq!(ticks, vec![(9.5, "18:00".to_string())]);
+    }
+
+    #[test]
+    fn test_builtin_layouts_draw_pixels() {
+        let config = Config::default();
+        let series = crate::fallback::approximate(None);
+        let meta = SeriesMeta::from_config(&config, Utc::now());
+        let theme = Theme::default();
+        let mut buffer = DisplayBuffer::new(400, 300);
+        let ctx = LayoutContext {
+            series: &series,
+            meta: &meta,
+            theme: &theme,
+            size: buffer.size(),
+            overlays: &[],
+        };
+
+        for name in BUILTIN_LAYOUTS {
+            buffer.clear(crate::epd4in2b_v2::Color::White);
+            builtin(name).unwrap().draw(&ctx, &mut buffer).unwrap();
+
+            let black_pixels: u32 = buffer.black_buffer().iter().map(|b| b.count_zeros()).sum();
+            assert!(black_pixels > 0, "layout {name} drew nothing");
+        }
+    }
+
+    #[test]
+    fn test_tricolor_splits_red_elements() {
+        let config = Config::default();
+        let series = crate::fallback::approximate(None);
+        let meta = SeriesMeta::from_config(&config, Utc::now());
+        let theme = Theme::default();
+        let size = Size::new(400, 300);
+        let ctx = LayoutContext {
+            series: &series,
+            meta: &meta,
+            theme: &theme,
+            size,
+            overlays: &[],
+        };
+        let chart = EinkTideRenderer::new();
+        let count = |plane: &[u8], red: bool| -> u32 {
+            plane
+                .iter()
+                .map(|b| if red { b.count_ones() } else { b.count_zeros() })
+                .sum()
+        };
+
+        let mut mono = DisplayBuffer::new(size.width, size.height);
+        Layout::draw(&chart, &ctx, &mut mono).unwrap();
+        let mut tricolor = DisplayBuffer::new(size.width, size.height);
+        draw_tricolor(&chart, &ctx, &mut tricolor);
+
+        // The now marker moved to red, and no pixel is both
+        let red = count(tricolor.red_buffer(), true);
+        assert!(red > 0);
+        assert!(count(tricolor.black_buffer(), false) < count(mono.black_buffer(), false));
+        assert!(tricolor
+            .black_buffer()
+            .iter()
+            .zip(tricolor.red_buffer())
+            .all(|(black, red)| !black & red == 0));
+
+        // An all-black theme leaves the red plane empty
+        let plain = Theme {
+            now_marker_color: InkColor::Black,
+            warning_color: InkColor::Black,
+            ..Theme::default()
+        };
+        let mut tricolor = DisplayBuffer::new(size.width, size.height);
+        draw_tricolor(
+            &chart,
+            &LayoutContext {
+                theme: &plain,
+                ..ctx
+            },
+            &mut tricolor,
+        );
+        assert_eq!(count(tricolor.red_buffer(), true), 0);
+    }
+}
@@ -7,15 +7,17 @@
 // ## Design Philosophy
 //
 // ### Memory Efficiency
-// - **Fixed-size data structures**: All containers use `Vec::with_capacity(145)` to
-//   pre-allocate exactly the needed memory for 24 hours of 10-minute samples
+// - **Fixed-size data structures**: Sample buffers are pre-allocated to exactly
+//   `grid::sample_count` for the configured window (145 for 24 hours)
 // - **Minimal allocations**: Uses primitive types (`i16`, `f32`) to minimize memory overhead
 // - **Serialization-friendly**: Structures implement `Serialize`/`Deserialize` for efficient
 //   binary caching without additional heap allocations
 //
 // ### Temporal Resolution
-// The application samples tide data every 10 minutes for 24 hours:
+// The application samples tide data every 10 minutes for 24 hours by default:
 // - **145 samples total**: -720 to +720 minutes (24 hours) in 10-minute increments
+// - **Wider windows**: `time_window_hours` up to 36 (433 samples for 72 hours)
+// - **Other steps**: `sample_interval_minutes` from 1 to 60 (289 samples at 5 minutes)
 // - **Smooth visualization**: 10-minute granularity provides much smoother curves than
 //   traditional hourly sampling, critical for accurate tide prediction display
 // - **Current time marker**: Sample with `mins_rel == 0` represents "now"
@@ -29,18 +31,113 @@
 //
 // The library exports two primary types optimized for the embedded target:
 // - [`Sample`]: A single tide measurement at a specific time
-// - [`TideSeries`]: Complete 24-hour dataset with offline status indicator
+// - [`TideSeries`]: Complete 24-hour dataset with its data source
+//
+// ## `no_std`
+//
+// With `default-features = false` (no `std` feature) the crate builds under
+// `no_std` + `alloc` for microcontrollers: these types, plus `grid`,
+// `interp`, `extremes`, `lunar`, `harmonics` (constants given in code) and
+// `series_builder`. Fetching, caching, configuration and rendering need std.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use chrono::{DateTime, Duration, Utc};
+use extremes::Extreme;
+// Unused when std is linked anyway (tests, where proptest turns on
+// num-traits/std), as the inherent float methods win
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::Float;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-// Module declarations
+// Module declarations: the data model and prediction math first, then the
+// std-only parts of the application
+pub mod extremes;
+pub mod grid;
+pub mod harmonics;
+pub mod interp;
+pub mod lunar;
+pub mod series_builder;
+
+#[cfg(feature = "net")]
+pub mod accuracy;
+#[cfg(feature = "std")]
+pub mod alerts;
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "std")]
+pub mod budget;
+#[cfg(feature = "std")]
+pub mod button;
+#[cfg(feature = "std")]
 pub mod config;
+#[cfg(feature = "std")]
+pub mod config_check;
+#[cfg(feature = "std")]
+pub mod display_units;
+#[cfg(feature = "std")]
 pub mod eink_renderer;
+#[cfg(feature = "eink")]
+pub mod epd;
+#[cfg(feature = "std")]
 pub mod epd4in2b_v2;
+#[cfg(feature = "std")]
+pub mod epd7in5_v2;
+#[cfg(all(test, feature = "eink"))]
+mod epd_mock;
+#[cfg(feature = "eink")]
+pub mod error_page;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
 pub mod fallback;
-pub mod lunar;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod file_source;
+#[cfg(feature = "std")]
+pub mod home_assistant;
+#[cfg(feature = "std")]
+pub mod large_print;
+#[cfg(feature = "std")]
+pub mod layout;
+#[cfg(feature = "std")]
+pub mod nmea;
+#[cfg(feature = "eink")]
+pub mod panel;
+#[cfg(feature = "std")]
+pub mod provider;
+#[cfg(feature = "std")]
 pub mod renderer;
+#[cfg(feature = "eink")]
+pub mod setup_page;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+#[cfg(feature = "std")]
+pub mod spi_bus;
+#[cfg(feature = "std")]
+pub mod stations;
+#[cfg(feature = "std")]
+pub mod surge;
+#[cfg(feature = "std")]
+pub mod theme;
+#[cfg(feature = "std")]
 pub mod tide_data;
+#[cfg(feature = "std")]
+pub mod tide_table;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod xtide;
 
 /// A single tide measurement at a specific time relative to "now".
 ///
@@ -59,57 +156,542 @@ pub mod tide_data;
 /// use tide_clock_lib::Sample;
 ///
 /// // Current tide height
-/// let now_sample = Sample { mins_rel: 0, tide_ft: 3.2 };
+/// let now_sample = Sample { mins_rel: 0, tide_ft: 3.2, modeled: false };
 ///
 /// // Tide 2 hours ago
-/// let past_sample = Sample { mins_rel: -120, tide_ft: 1.8 };
+/// let past_sample = Sample { mins_rel: -120, tide_ft: 1.8, modeled: false };
 /// ```
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Sample {
-    /// Minutes relative to current time (-720 to +720)
+    /// Minutes relative to current time (-720 to +720 for the standard window)
     pub mins_rel: i16,
     /// Tide height in feet
     pub tide_ft: f32,
+    /// Filled in from the offline model where the real data had a gap (see
+    /// [`TideSeriesBuilder::backfilled`](series_builder::TideSeriesBuilder::backfilled)),
+    /// for renderers to draw differently. Not serialized: a backfilled
+    /// series is never cached.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub modeled: bool,
 }
 
 /// Complete 24-hour tide dataset with metadata.
 ///
-/// Contains exactly 145 samples covering 24 hours at 10-minute intervals,
-/// plus an offline flag to indicate data source reliability.
+/// Contains 145 samples covering 24 hours at 10-minute intervals (more for a
+/// wider `time_window_hours`), plus where the data came from to indicate its
+/// reliability.
 ///
 /// Memory layout:
 /// - `Vec<Sample>`: 145 samples × 6 bytes = 870 bytes
-/// - `bool`: 1 byte
+/// - `DataSource`: 8 bytes (one byte when cached)
+/// - `Option<DateTime<Utc>>` (twice): 12 bytes each in memory, one varint
+///   each when cached
 /// - Vec overhead: ~24 bytes
-/// - **Total**: ~900 bytes per series
+/// - **Total**: ~950 bytes per series
 ///
 /// # Offline Behavior
-/// When `offline = true`, the data comes from a mathematical fallback model
-/// rather than real NOAA predictions. The display shows "⚠ OFFLINE" to
-/// inform users of reduced accuracy.
+/// When `source` is [`DataSource::Fallback`], the data comes from a
+/// mathematical fallback model rather than real NOAA predictions. The display
+/// shows "⚠ OFFLINE MODEL" to inform users of reduced accuracy.
+///
+/// # Absolute Time
+/// `mins_rel` is relative to `base_time`, the instant the series was built
+/// for. With a base time the series can be re-anchored to a later "now"
+/// ([`TideSeries::rebased`]), so cached data doesn't go stale the moment
+/// it's written, and samples from different sources can be lined up by
+/// their absolute timestamps ([`TideSeries::time_of`]).
+///
+/// # Construction
+/// Providers build series with [`TideSeries::builder`], which checks the
+/// samples are in order, evenly spaced and include "now". The fields stay
+/// public for tests and hand-built series.
 ///
 /// # Example
 /// ```
-/// use tide_clock_lib::{Sample, TideSeries};
+/// use tide_clock_lib::{DataSource, Sample, TideSeries};
 ///
 /// let series = TideSeries {
 ///     samples: vec![
-///         Sample { mins_rel: -10, tide_ft: 2.1 },
-///         Sample { mins_rel: 0, tide_ft: 2.3 },
-///         Sample { mins_rel: 10, tide_ft: 2.5 },
+///         Sample { mins_rel: -10, tide_ft: 2.1, modeled: false },
+///         Sample { mins_rel: 0, tide_ft: 2.3, modeled: false },
+///         Sample { mins_rel: 10, tide_ft: 2.5, modeled: false },
 ///     ],
-///     offline: false
+///     source: DataSource::Noaa,
+///     base_time: None,
+///     extremes: None,
+///     fetched_at: None,
 /// };
 ///
 /// assert_eq!(series.samples.len(), 3);
-/// assert!(!series.offline);
+/// assert!(!series.is_offline());
 /// ```
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TideSeries {
-    /// Exactly 145 tide samples spanning 24 hours
+    /// Tide samples every 10 minutes (or `sample_interval_minutes`), 145 for
+    /// the standard 24 hours
     pub samples: Vec<Sample>,
-    /// True if using fallback model instead of real NOAA data
-    pub offline: bool,
+    /// Where the samples came from; [`DataSource::Fallback`] instead of real
+    /// NOAA data when offline
+    pub source: DataSource,
+    /// Instant that `mins_rel == 0` refers to (serialized as epoch seconds)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "chrono::serde::ts_seconds_option")
+    )]
+    pub base_time: Option<DateTime<Utc>>,
+    /// Highs and lows the provider knows of, possibly beyond the sample
+    /// window (`None` when it only produced samples)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extremes: Option<Vec<Extreme>>,
+    /// When the data was fetched from NOAA, kept through the cache so the
+    /// panel can tell how old it is; `None` for model output
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "chrono::serde::ts_seconds_option")
+    )]
+    pub fetched_at: Option<DateTime<Utc>>,
+}
+
+/// Direction the water is moving, see [`TideSeries::trend`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Rising,
+    Falling,
+}
+
+impl Trend {
+    /// Upper-case word for displays, "RISING" or "FALLING"
+    pub fn label(self) -> &'static str {
+        match self {
+            Trend::Rising => "RISING",
+            Trend::Falling => "FALLING",
+        }
+    }
+}
+
+/// Where a [`TideSeries`] came from
+///
+/// Shown next to the "last updated" time ([`DataSource::label`]) and, for
+/// data that is cached or not from NOAA at all, as a notice
+/// ([`Display`](core::fmt::Display): "CACHED 25m", "OFFLINE MODEL").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DataSource {
+    /// NOAA predictions fetched on this refresh
+    Noaa,
+    /// Read back from the on-disk cache, `age_mins` after it was fetched
+    Cache { age_mins: u32 },
+    /// Computed by the offline model or harmonics
+    Fallback,
+    /// Predictions replayed from `data_file`
+    File,
+    /// Depths logged from the boat's sounder, see `nmea`
+    Sensor,
+}
+
+impl DataSource {
+    /// Upper-case tag for displays, e.g. "NOAA" or "CACHE"
+    pub fn label(self) -> &'static str {
+        match self {
+            DataSource::Noaa => "NOAA",
+            DataSource::Cache { .. } => "CACHE",
+            DataSource::Fallback => "FALLBACK",
+            DataSource::File => "FILE",
+            DataSource::Sensor => "SENSOR",
+        }
+    }
+}
+
+impl core::fmt::Display for DataSource {
+    /// "CACHED 25m" (hours past an hour, days past two), "OFFLINE MODEL", or
+    /// the label
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            DataSource::Cache { age_mins } => match age_mins {
+                mins if mins < 60 => write!(f, "CACHED {}m", mins),
+                mins if mins < 48 * 60 => write!(f, "CACHED {}h", mins / 60),
+                mins => write!(f, "CACHED {}d", mins / (24 * 60)),
+            },
+            DataSource::Fallback => f.write_str("OFFLINE MODEL"),
+            source => f.write_str(source.label()),
+        }
+    }
+}
+
+impl TideSeries {
+    /// True if the heights come from the offline model instead of real data
+    pub fn is_offline(&self) -> bool {
+        self.source == DataSource::Fallback
+    }
+
+    /// True if some samples were filled in by the model where the real data
+    /// had gaps (see [`Sample::modeled`])
+    pub fn is_backfilled(&self) -> bool {
+        self.samples.iter().any(|s| s.modeled)
+    }
+
+    /// Absolute timestamp of `sample`, if the series has a base time
+    pub fn time_of(&self, sample: &Sample) -> Option<DateTime<Utc>> {
+        self.base_time
+            .map(|base| base + Duration::minutes(sample.mins_rel as i64))
+    }
+
+    /// Distance from "now" to either edge, if the samples are centered on it
+    pub fn half_width(&self) -> Option<Duration> {
+        let (first, last) = (self.samples.first()?, self.samples.last()?);
+        (first.mins_rel == -last.mins_rel).then(|| Duration::minutes(last.mins_rel as i64))
+    }
+
+    /// Time between the first two samples, `None` with fewer than two
+    pub fn interval(&self) -> Option<Duration> {
+        let (first, second) = (self.samples.first()?, self.samples.get(1)?);
+        Some(Duration::minutes((second.mins_rel - first.mins_rel) as i64))
+    }
+
+    /// Time axis labels for the first and last sample, e.g. ("-12h", "+12h")
+    pub fn window_labels(&self) -> (String, String) {
+        let hours = |mins: Option<&Sample>, default: i64| {
+            mins.map_or(default, |s| (s.mins_rel as f32 / 60.0).round() as i64)
+        };
+        (
+            format!("{}h", hours(self.samples.first(), -12)),
+            format!("{:+}h", hours(self.samples.last(), 12)),
+        )
+    }
+
+    /// Minutes from `now` to `sample`, computed from its absolute timestamp
+    pub fn mins_rel_at(&self, sample: &Sample, now: DateTime<Utc>) -> Option<i64> {
+        self.time_of(sample).map(|t| (t - now).num_minutes())
+    }
+
+    /// Copy of this series with every `mins_rel` re-anchored to `now`
+    ///
+    /// Returns `None` without a base time, or if `now` is so far away that
+    /// the offsets no longer fit in `i16`.
+    pub fn rebased(&self, now: DateTime<Utc>) -> Option<TideSeries> {
+        let shift = (now - self.base_time?).num_minutes();
+        let samples = self
+            .samples
+            .iter()
+            .map(|s| {
+                let mins_rel = i16::try_from(s.mins_rel as i64 - shift).ok()?;
+                Some(Sample { mins_rel, ..*s })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(TideSeries {
+            samples,
+            base_time: Some(now),
+            extremes: self.extremes.clone(),
+            fetched_at: self.fetched_at,
+            source: self.source,
+        })
+    }
+
+    /// Copy of this series with heights measured from `level` instead of the datum
+    pub fn relative_to(&self, level: f32) -> TideSeries {
+        TideSeries {
+            samples: self
+                .samples
+                .iter()
+                .map(|s| Sample {
+                    tide_ft: s.tide_ft - level,
+                    ..*s
+                })
+                .collect(),
+            base_time: self.base_time,
+            extremes: self.extremes.as_ref().map(|extremes| {
+                extremes
+                    .iter()
+                    .map(|e| Extreme {
+                        tide_ft: e.tide_ft - level,
+                        ..*e
+                    })
+                    .collect()
+            }),
+            fetched_at: self.fetched_at,
+            source: self.source,
+        }
+    }
+
+    /// Lowest sample height, `None` without samples
+    pub fn min_height(&self) -> Option<f32> {
+        self.samples.iter().map(|s| s.tide_ft).reduce(f32::min)
+    }
+
+    /// Highest sample height, `None` without samples
+    pub fn max_height(&self) -> Option<f32> {
+        self.samples.iter().map(|s| s.tide_ft).reduce(f32::max)
+    }
+
+    /// Height `mins_rel` minutes from "now", linearly interpolated between
+    /// the samples either side; `None` outside the samples
+    pub fn height_at(&self, mins_rel: f32) -> Option<f32> {
+        let after = self
+            .samples
+            .iter()
+            .position(|s| s.mins_rel as f32 >= mins_rel)?;
+        let b = &self.samples[after];
+        if b.mins_rel as f32 == mins_rel {
+            return Some(b.tide_ft);
+        }
+        let a = self.samples.get(after.checked_sub(1)?)?;
+        let t = (mins_rel - a.mins_rel as f32) / (b.mins_rel - a.mins_rel) as f32;
+        Some(a.tide_ft + (b.tide_ft - a.tide_ft) * t)
+    }
+
+    /// Height at "now" (`mins_rel == 0`), see [`height_at`](TideSeries::height_at)
+    pub fn height_now(&self) -> Option<f32> {
+        self.height_at(0.0)
+    }
+
+    /// Rate of change `mins_rel` minutes from "now" in feet per hour
+    ///
+    /// Central difference across the samples either side of the one closest
+    /// to `mins_rel`; zero with fewer than two samples.
+    pub fn rate_at(&self, mins_rel: i16) -> f32 {
+        let Some((idx, _)) = self
+            .samples
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, s)| (s.mins_rel as i32 - mins_rel as i32).abs())
+        else {
+            return 0.0;
+        };
+        let a = &self.samples[idx.saturating_sub(1)];
+        let b = &self.samples[(idx + 1).min(self.samples.len() - 1)];
+        let dt = (b.mins_rel - a.mins_rel) as f32;
+        if dt == 0.0 {
+            0.0
+        } else {
+            (b.tide_ft - a.tide_ft) / dt * 60.0
+        }
+    }
+
+    /// Rate of change at "now" in feet per hour, see [`rate_at`](TideSeries::rate_at)
+    pub fn rate_ft_per_hour(&self) -> f32 {
+        self.rate_at(0)
+    }
+
+    /// Whether the water is rising or falling `mins_rel` minutes from "now"
+    pub fn trend_at(&self, mins_rel: i16) -> Trend {
+        if self.rate_at(mins_rel) >= 0.0 {
+            Trend::Rising
+        } else {
+            Trend::Falling
+        }
+    }
+
+    /// Whether the water is rising or falling at "now"
+    pub fn trend(&self) -> Trend {
+        self.trend_at(0)
+    }
+
+    /// First sample from now up to `within_mins` ahead that is below `level`
+    ///
+    /// Starts at the sample closest to now, so water that is already below
+    /// `level` is reported immediately.
+    pub fn first_below(&self, level: f32, within_mins: i16) -> Option<&Sample> {
+        self.first_ahead(within_mins, |s| s.tide_ft < level)
+    }
+
+    /// First sample from now up to `within_mins` ahead that is above `level`
+    ///
+    /// Like [`first_below`](TideSeries::first_below), water that is already
+    /// above `level` is reported immediately.
+    pub fn first_above(&self, level: f32, within_mins: i16) -> Option<&Sample> {
+        self.first_ahead(within_mins, |s| s.tide_ft > level)
+    }
+
+    /// Index of the sample closest to "now", which need not be the middle
+    /// one (a truncated fetch, a series rebased since); `None` without samples
+    pub fn now_index(&self) -> Option<usize> {
+        self.samples
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, s)| s.mins_rel.unsigned_abs())
+            .map(|(i, _)| i)
+    }
+
+    fn first_ahead(&self, within_mins: i16, matches: impl Fn(&Sample) -> bool) -> Option<&Sample> {
+        let now_idx = self.now_index()?;
+        self.samples[now_idx..]
+            .iter()
+            .take_while(|s| s.mins_rel <= within_mins)
+            .find(|s| matches(s))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_window_labels_follow_samples() {
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let standard = fallback::approximate(Some(now));
+        assert_eq!(standard.half_width(), Some(Duration::hours(12)));
+        assert_eq!(standard.window_labels(), ("-12h".into(), "+12h".into()));
+
+        let wide = fallback::ModelConstants::default().series_in(grid::Window::hours(now, 24));
+        assert_eq!(wide.half_width(), Some(Duration::hours(24)));
+        assert_eq!(wide.window_labels(), ("-24h".into(), "+24h".into()));
+
+        // Re-anchored two hours later it is no longer centered
+        let rebased = standard.rebased(now + Duration::hours(2)).unwrap();
+        assert_eq!(rebased.half_width(), None);
+        assert_eq!(rebased.window_labels(), ("-14h".into(), "+10h".into()));
+    }
+
+    fn series_at(base: DateTime<Utc>) -> TideSeries {
+        TideSeries {
+            samples: vec![
+                Sample {
+                    mins_rel: -10,
+                    tide_ft: 1.0,
+                    modeled: false,
+                },
+                Sample {
+                    mins_rel: 0,
+                    tide_ft: 2.0,
+                    modeled: false,
+                },
+                Sample {
+                    mins_rel: 10,
+                    tide_ft: 3.0,
+                    modeled: false,
+                },
+            ],
+            source: DataSource::Noaa,
+            base_time: Some(base),
+            extremes: None,
+            fetched_at: None,
+        }
+    }
+
+    #[test]
+    fn test_relative_to_and_first_below() {
+        let series = series_at(Utc::now()).relative_to(2.5);
+        assert_eq!(series.samples[1].tide_ft, -0.5);
+        // Past samples are ignored; the now sample is already below
+        assert_eq!(series.first_below(0.0, 60).unwrap().mins_rel, 0);
+        assert!(series.first_below(-1.0, 60).is_none());
+
+        // Rising to 0.5 in ten minutes
+        assert_eq!(series.first_above(0.0, 60).unwrap().mins_rel, 10);
+        assert!(series.first_above(0.0, 5).is_none());
+        assert!(series.first_above(1.0, 60).is_none());
+    }
+
+    #[test]
+    fn test_now_index_follows_mins_rel() {
+        let mut series = fallback::approximate(None);
+        assert_eq!(series.now_index(), Some(72));
+        // A fetch cut short after five hours
+        series.samples.retain(|s| s.mins_rel <= 300);
+        assert_eq!(series.now_index(), Some(72));
+        // Four minutes on, the sample at -720 + 10 * 72 = 0 is still closest
+        let later = series.rebased(series.base_time.unwrap() + Duration::minutes(4));
+        assert_eq!(later.unwrap().now_index(), Some(72));
+        series.samples.clear();
+        assert_eq!(series.now_index(), None);
+    }
+
+    #[test]
+    fn test_absolute_time_helpers() {
+        let base = Utc.with_ymd_and_hms(2025, 7, 24, 12, 0, 0).unwrap();
+        let series = series_at(base);
+        let later = base + Duration::minutes(25);
+
+        assert_eq!(
+            series.time_of(&series.samples[2]),
+            Some(base + Duration::minutes(10))
+        );
+        assert_eq!(series.mins_rel_at(&series.samples[2], later), Some(-15));
+
+        let rebased = series.rebased(later).unwrap();
+        let rel: Vec<i16> = rebased.samples.iter().map(|s| s.mins_rel).collect();
+        assert_eq!(rel, vec![-35, -25, -15]);
+        assert_eq!(rebased.base_time, Some(later));
+    }
+
+    #[test]
+    fn test_trend_at_now() {
+        let rising = series_at(Utc::now());
+        assert_eq!(rising.rate_ft_per_hour(), 6.0);
+        assert_eq!(rising.trend(), Trend::Rising);
+
+        let mut falling = rising.clone();
+        falling.samples.reverse();
+        for (sample, mins_rel) in falling.samples.iter_mut().zip([-10, 0, 10]) {
+            sample.mins_rel = mins_rel;
+        }
+        assert_eq!(falling.trend(), Trend::Falling);
+        assert_eq!(falling.trend().label(), "FALLING");
+    }
+
+    #[test]
+    fn test_source_notices() {
+        let cached = |age_mins| DataSource::Cache { age_mins };
+        assert_eq!(cached(25).to_string(), "CACHED 25m");
+        assert_eq!(cached(150).to_string(), "CACHED 2h");
+        assert_eq!(cached(3 * 24 * 60).to_string(), "CACHED 3d");
+        assert_eq!(cached(25).label(), "CACHE");
+        assert_eq!(DataSource::Fallback.to_string(), "OFFLINE MODEL");
+        assert_eq!(DataSource::Noaa.to_string(), "NOAA");
+    }
+
+    #[test]
+    fn test_height_now_interpolates() {
+        let series = series_at(Utc::now());
+        assert_eq!(series.height_now(), Some(2.0));
+        let shifted = series.rebased(Utc::now() + Duration::minutes(4)).unwrap();
+        assert!((shifted.height_now().unwrap() - 2.4).abs() < 0.05);
+        let future = series.rebased(Utc::now() - Duration::minutes(30)).unwrap();
+        assert_eq!(future.height_now(), None);
+    }
+
+    #[test]
+    fn test_analysis_helpers() {
+        let base = Utc.with_ymd_and_hms(2025, 7, 24, 12, 0, 0).unwrap();
+        let mut series = series_at(base);
+        series.samples.push(Sample {
+            mins_rel: 20,
+            tide_ft: 2.0,
+            modeled: false,
+        });
+        assert_eq!(series.min_height(), Some(1.0));
+        assert_eq!(series.max_height(), Some(3.0));
+        assert_eq!(series.height_at(-5.0), Some(1.5));
+        assert_eq!(series.height_at(15.0), Some(2.5));
+        assert_eq!(series.height_at(-11.0), None);
+        assert_eq!(series.height_at(21.0), None);
+        assert_eq!(series.trend_at(0), Trend::Rising);
+        assert_eq!(series.trend_at(20), Trend::Falling);
+
+        let extremes = series.extremes();
+        assert_eq!(extremes.len(), 1);
+        assert!(extremes[0].high);
+        assert!(extremes[0].at > base && extremes[0].at < base + Duration::minutes(20));
+
+        let empty = TideSeries {
+            samples: Vec::new(),
+            ..series
+        };
+        assert_eq!(empty.min_height(), None);
+        assert_eq!(empty.height_at(0.0), None);
+        assert!(empty.extremes().is_empty());
+    }
+
+    #[test]
+    fn test_rebase_requires_base_time() {
+        let mut series = series_at(Utc::now());
+        series.base_time = None;
+        assert!(series.rebased(Utc::now()).is_none());
+        assert!(series.time_of(&series.samples[0]).is_none());
+    }
 }
 
 // Custom EPD module for hardware rendering (already declared above)
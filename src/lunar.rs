@@ -4,8 +4,41 @@
 //! Accuracy: ±1 day for phase index; a few degrees for λ, β; ~6 % for Δ.
 //! References: Sky & Telescope BASIC “MOONFX.BAS” (Apr 1994) and
 //! original phase routine (Mar 1985).  See docs for citation list.
+//!
+//! ```
+//! use chrono::{TimeZone, Utc};
+//! use tide_clock_lib::lunar;
+//!
+//! let moon = lunar::moon_at(Utc.with_ymd_and_hms(2024, 1, 25, 18, 0, 0).unwrap());
+//! assert_eq!(moon.phase_name(), "Full Moon");
+//! assert!(moon.illum_frac > 0.95);
+//! ```
 
+use alloc::format;
+use alloc::string::String;
+use chrono::{DateTime, Datelike, Timelike, Utc};
 use core::f64::consts::PI;
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::Float;
+
+/// Mean synodic month in days
+pub const SYNODIC_MONTH_DAYS: f64 = 29.530_588_2;
+
+/// Typical delay from new/full moon to the largest tides ("age of the tide")
+pub const TIDE_AGE_DAYS: f64 = 1.5;
+
+/// Names of the eight phases, indexed by [`LunarEphemeris::phase_index`]
+pub const PHASE_NAMES: [&str; 8] = [
+    "New Moon",
+    "Waxing Crescent",
+    "First Quarter",
+    "Waxing Gibbous",
+    "Full Moon",
+    "Waning Gibbous",
+    "Last Quarter",
+    "Waning Crescent",
+];
 
 /// Return type holding everything Schaefer’s 1994 routine can compute.
 #[derive(Debug, Clone, Copy)]
@@ -14,7 +47,7 @@ pub struct LunarEphemeris {
     pub phase_index: u8,
     /// Age of the Moon in civil days since New.
     pub age_days: f64,
-    /// Illuminated fraction (0–1).
+    /// Illuminated fraction (0–1), from the phase angle.
     pub illum_frac: f64,
     /// Geocentric distance in Earth radii.
     pub distance_er: f64,
@@ -24,6 +57,52 @@ pub struct LunarEphemeris {
     pub lat_deg: f64,
 }
 
+impl LunarEphemeris {
+    /// Human-readable phase, e.g. "Waxing Gibbous".
+    pub fn phase_name(&self) -> &'static str {
+        PHASE_NAMES[self.phase_index as usize & 7]
+    }
+
+    /// Unicode moon symbol for the phase, e.g. '🌔'.
+    pub fn symbol(&self) -> char {
+        ['🌑', '🌒', '🌓', '🌔', '🌕', '🌖', '🌗', '🌘'][self.phase_index as usize & 7]
+    }
+
+    /// Where the tides are in the spring–neap cycle: 0 at neaps, 1 at springs.
+    ///
+    /// Follows the S2/M2 beat (twice per synodic month), delayed by
+    /// [`TIDE_AGE_DAYS`].
+    pub fn spring_fraction(&self) -> f64 {
+        let phase = 2.0 * PI * (self.age_days - TIDE_AGE_DAYS) / SYNODIC_MONTH_DAYS;
+        (1.0 + (2.0 * phase).cos()) / 2.0
+    }
+
+    /// Whether the Moon is between new and full.
+    pub fn is_waxing(&self) -> bool {
+        self.age_days < SYNODIC_MONTH_DAYS / 2.0
+    }
+}
+
+/// Short tag for a [`spring_fraction`](LunarEphemeris::spring_fraction),
+/// e.g. "SPRING 92%", "NEAP 5%" or "RANGE 60%" in between.
+pub fn range_label(spring_fraction: f64) -> String {
+    let kind = if spring_fraction >= 0.75 {
+        "SPRING"
+    } else if spring_fraction <= 0.25 {
+        "NEAP"
+    } else {
+        "RANGE"
+    };
+    format!("{} {:.0}%", kind, spring_fraction * 100.0)
+}
+
+/// Schaefer’s phase & ephemeris for an instant.
+pub fn moon_at(t: DateTime<Utc>) -> LunarEphemeris {
+    let day = t.day() as f64
+        + (t.hour() as f64 + t.minute() as f64 / 60.0 + t.second() as f64 / 3600.0) / 24.0;
+    schaefer_moon(t.year(), t.month(), day)
+}
+
 /// Compute Schaefer’s phase & ephemeris for a proleptic-Gregorian Y-M-D.
 ///
 /// `year` is astronomer’s year (e.g. 2000).  
@@ -44,13 +123,13 @@ pub fn schaefer_moon(year: i32, month: u32, day: f64) -> LunarEphemeris {
 
     // ---------- 3. Phase index (0–7)  ----------------------------------------
     // Divide by synodic month length; drop integer cycles; scale ×8 & round.
-    let mut jd_norm = days / 29.530_588_2; // mean synodic month length
+    let mut jd_norm = days / SYNODIC_MONTH_DAYS;
     jd_norm -= jd_norm.floor(); // keep fractional part only
     let phase_index = ((jd_norm * 8.0) + 0.5).floor() as u8 & 7;
 
     // Extra goodies: illuminated fraction & age
-    let age_days = jd_norm * 29.530_588_2;
-    let illum_frac = (1.0 - (age_days - 14.765_294_1).abs() / 14.765_294_1).clamp(0.0, 1.0); // simple cosine proxy
+    let age_days = jd_norm * SYNODIC_MONTH_DAYS;
+    let illum_frac = (1.0 - (2.0 * PI * jd_norm).cos()) / 2.0;
 
     //  ---------- 4. 1994 add-ons (four separate lunar “cycles”) --------------
     // All periods and epochs are straight from MOONFX.BAS.
@@ -91,3 +170,79 @@ pub fn schaefer_moon(year: i32, month: u32, day: f64) -> LunarEphemeris {
         lat_deg,
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> LunarEphemeris {
+        moon_at(Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap())
+    }
+
+    /// Days between the computed age and `expected`, across the month wrap
+    fn age_error(moon: &LunarEphemeris, expected: f64) -> f64 {
+        let diff = (moon.age_days - expected).rem_euclid(SYNODIC_MONTH_DAYS);
+        diff.min(SYNODIC_MONTH_DAYS - diff)
+    }
+
+    #[test]
+    fn test_known_new_moons() {
+        for moon in [
+            at(2000, 1, 6, 18, 14),
+            at(2024, 1, 11, 11, 57),
+            at(2025, 7, 24, 19, 11),
+        ] {
+            assert!(age_error(&moon, 0.0) < 1.0, "{:?}", moon);
+            assert_eq!(moon.phase_name(), "New Moon");
+            assert!(moon.illum_frac < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_known_full_moons() {
+        for moon in [
+            at(2024, 1, 25, 17, 54),
+            at(2025, 7, 10, 20, 37),
+            at(2026, 10, 26, 4, 12),
+        ] {
+            assert!(
+                age_error(&moon, SYNODIC_MONTH_DAYS / 2.0) < 1.0,
+                "{:?}",
+                moon
+            );
+            assert_eq!(moon.phase_name(), "Full Moon");
+            assert!(moon.illum_frac > 0.95);
+        }
+    }
+
+    #[test]
+    fn test_spring_neap_cycle() {
+        // Springs a day or two after new moon (2024-01-11), neaps after first quarter
+        let spring = at(2024, 1, 13, 0, 0);
+        assert!(
+            spring.spring_fraction() > 0.95,
+            "{}",
+            spring.spring_fraction()
+        );
+        assert!(range_label(spring.spring_fraction()).starts_with("SPRING"));
+        let neap = at(2024, 1, 19, 12, 0);
+        assert!(neap.spring_fraction() < 0.05, "{}", neap.spring_fraction());
+        assert!(range_label(neap.spring_fraction()).starts_with("NEAP"));
+        assert_eq!(range_label(0.5), "RANGE 50%");
+    }
+
+    #[test]
+    fn test_quarters_and_waxing() {
+        // First quarter 2024-01-18 03:53, last quarter 2024-02-02 23:18 UTC
+        let first = at(2024, 1, 18, 3, 53);
+        assert_eq!(first.phase_name(), "First Quarter");
+        assert!(first.is_waxing());
+        assert!((first.illum_frac - 0.5).abs() < 0.1);
+
+        let last = at(2024, 2, 2, 23, 18);
+        assert_eq!(last.phase_name(), "Last Quarter");
+        assert!(!last.is_waxing());
+        assert!((last.illum_frac - 0.5).abs() < 0.1);
+    }
+}
@@ -8,23 +8,60 @@
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "hardware")]
 mod gpio_sysfs;
+#[cfg(feature = "rppal")]
+mod hw_rppal;
+#[cfg(feature = "hardware")]
 mod hw_spi_spidev;
+#[cfg(feature = "hardware")]
+mod serial_port;
 
 // Re-export library types for internal use
 pub use tide_clock_lib::{config::Config, Sample, TideSeries};
 
 // Import new GPIO and SPI types for hardware mode
+#[cfg(feature = "hardware")]
 #[allow(unused_imports)]
 use crate::gpio_sysfs::{CdevInputPin, CdevOutputPin};
 #[allow(unused_imports)]
-use crate::hw_spi_spidev::SpidevHwSpi;
-#[allow(unused_imports)]
 use anyhow::Context;
 
 // Application dependencies
+use embedded_graphics::prelude::Size;
 use std::env;
-use tide_clock_lib::{fallback, renderer::draw_ascii, tide_data};
+use tide_clock_lib::budget::RefreshBudget;
+use tide_clock_lib::renderer::{draw_ascii, TerminalStyle};
+use tide_clock_lib::stations::{self, StationSeries};
+use tide_clock_lib::{fallback, home_assistant, panel, surge, tide_data};
+#[allow(unused_imports)]
+use tracing::{debug, error, info, trace, warn};
+
+/// Log to stderr through `tracing`
+///
+/// `RUST_LOG` picks the levels when set (e.g. `RUST_LOG=tide_clock_lib=trace`
+/// for every byte-level driver step); otherwise info and up, or debug for
+/// this program's own messages with `--verbose`. Lines carry no timestamp,
+/// which the systemd journal adds, and colors only on a terminal.
+fn init_logging(verbose: bool) {
+    use std::io::IsTerminal;
+    use tracing_subscriber::EnvFilter;
+
+    let default = if verbose {
+        "info,tide_tracker=debug,tide_clock_lib=debug"
+    } else {
+        "info"
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_target(false)
+        .without_time()
+        .compact()
+        .init();
+}
 
 /// Convert GPIO number to physical pin number for display
 /// This is a simplified mapping for common pins
@@ -41,6 +78,257 @@ fn gpio_to_pin(gpio: u32) -> u32 {
     }
 }
 
+/// What to check when the panel fails with an error that has a usual cause
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn display_error_hint(
+    e: &tide_clock_lib::epd4in2b_v2::EpdError,
+    hw: &tide_clock_lib::config::HardwareConfig,
+) -> Option<String> {
+    use tide_clock_lib::epd4in2b_v2::EpdError;
+
+    match e {
+        EpdError::BusyTimeout(_) => Some(format!(
+            "The panel never went idle: check the BUSY pin (GPIO {}), power and \
+             ribbon cable, then power-cycle it",
+            hw.busy_pin
+        )),
+        e if e.is_permission_denied() => {
+            Some("Add this user to the spi group: sudo usermod -aG spi $USER".to_string())
+        }
+        _ => None,
+    }
+}
+
+/// SPI bus and control lines for the panel, from either GPIO backend
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+struct DisplayLines {
+    spi: Box<dyn tide_clock_lib::epd4in2b_v2::SoftwareSpi>,
+    dc: Box<dyn tide_clock_lib::epd4in2b_v2::GpioPin>,
+    rst: Box<dyn tide_clock_lib::epd4in2b_v2::GpioPin>,
+    busy: Box<dyn tide_clock_lib::epd4in2b_v2::InputPin>,
+}
+
+/// Claim the display's SPI bus and DC, RST and BUSY lines through
+/// `hw.backend`
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn open_display_lines(hw: &tide_clock_lib::config::HardwareConfig) -> anyhow::Result<DisplayLines> {
+    use tide_clock_lib::config::GpioBackend;
+
+    hw.check_pins()
+        .context("invalid [display.hardware] pin configuration")?;
+    match hw.backend {
+        GpioBackend::Cdev => {
+            let mut chip = crate::gpio_sysfs::open_chip(hw.gpiochip.as_deref(), hw.dc_pin)?;
+
+            // Only request DC, RST, BUSY via gpiod for hardware SPI
+            let dc = CdevOutputPin::new(&mut chip, hw.dc_pin)?;
+            let rst = CdevOutputPin::new(&mut chip, hw.rst_pin)?;
+            let busy = CdevInputPin::new(&mut chip, hw.busy_pin)?;
+
+            // SPI setup: hardware CS for GPIO 8 (CE0) or 7 (CE1), manual CS for
+            // others, on buses shared with any `spi_devices`
+            let spi = open_display_spi(&mut chip, hw)?;
+            Ok(DisplayLines {
+                spi,
+                dc: Box::new(dc),
+                rst: Box::new(rst),
+                busy: Box::new(busy),
+            })
+        }
+        #[cfg(feature = "rppal")]
+        GpioBackend::Rppal => {
+            use crate::hw_rppal::{RppalInputPin, RppalOutputPin, RppalSpi};
+
+            if !hw.spi_devices.is_empty() {
+                anyhow::bail!("spi_devices need backend = \"cdev\"");
+            }
+            let gpio = rppal::gpio::Gpio::new().context("open GPIO through rppal")?;
+            Ok(DisplayLines {
+                spi: Box::new(RppalSpi::open(&gpio, hw)?),
+                dc: Box::new(RppalOutputPin::new(&gpio, hw.dc_pin)?),
+                rst: Box::new(RppalOutputPin::new(&gpio, hw.rst_pin)?),
+                busy: Box::new(RppalInputPin::new(&gpio, hw.busy_pin)?),
+            })
+        }
+        #[cfg(not(feature = "rppal"))]
+        GpioBackend::Rppal => {
+            anyhow::bail!("backend = \"rppal\" needs a build with --features rppal")
+        }
+    }
+}
+
+/// Open `[display.hardware] button_pin`, if one is configured
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn open_button(
+    hw: &tide_clock_lib::config::HardwareConfig,
+) -> anyhow::Result<Option<Box<dyn tide_clock_lib::epd4in2b_v2::InputPin>>> {
+    use tide_clock_lib::config::GpioBackend;
+
+    let Some(pin) = hw.button_pin else {
+        return Ok(None);
+    };
+    hw.check_pins()
+        .context("invalid [display.hardware] pin configuration")?;
+    let line: Box<dyn tide_clock_lib::epd4in2b_v2::InputPin> = match hw.backend {
+        GpioBackend::Cdev => {
+            let mut chip = crate::gpio_sysfs::open_chip(hw.gpiochip.as_deref(), pin)?;
+            Box::new(CdevInputPin::new(&mut chip, pin)?)
+        }
+        #[cfg(feature = "rppal")]
+        GpioBackend::Rppal => {
+            let gpio = rppal::gpio::Gpio::new().context("open GPIO through rppal")?;
+            Box::new(crate::hw_rppal::RppalInputPin::pulled_up(&gpio, pin)?)
+        }
+        #[cfg(not(feature = "rppal"))]
+        GpioBackend::Rppal => {
+            anyhow::bail!("backend = \"rppal\" needs a build with --features rppal")
+        }
+    };
+    info!(
+        "Button on GPIO {} (Pin {}): short press for the next layout, long press to refetch",
+        pin,
+        gpio_to_pin(pin)
+    );
+    Ok(Some(line))
+}
+
+/// Open the SPI device for the e-ink display according to `HardwareConfig`
+///
+/// Every `spi_devices` peripheral gets a handle too, so a manual chip
+/// select is held deselected and each transfer applies its own device's
+/// mode and speed; see `tide_clock_lib::spi_bus`.
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn open_display_spi(
+    chip: &mut gpio_cdev::Chip,
+    hw: &tide_clock_lib::config::HardwareConfig,
+) -> anyhow::Result<Box<dyn tide_clock_lib::epd4in2b_v2::SoftwareSpi>> {
+    use crate::hw_spi_spidev::DisplaySpi;
+
+    let routes = tide_clock_lib::spi_bus::routes(hw);
+    for route in &routes[1..] {
+        info!(
+            "Sharing /dev/spidev{}.{} with '{}' ({}, mode {}, {} Hz)",
+            route.bus,
+            route.node,
+            route.name,
+            match route.manual_cs {
+                Some(pin) => format!("CS GPIO {pin}"),
+                None => "kernel CS".to_string(),
+            },
+            route.settings.mode,
+            route.settings.speed_hz
+        );
+    }
+    Ok(Box::new(DisplaySpi::open(&routes, chip)?))
+}
+
+/// Open the configured panel's driver on its SPI bus and control lines
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn open_panel(
+    hw: &tide_clock_lib::config::HardwareConfig,
+) -> anyhow::Result<Box<dyn tide_clock_lib::epd::EpdDriver>> {
+    use tide_clock_lib::config::PanelModel;
+    use tide_clock_lib::epd::EpdDriver;
+    use tide_clock_lib::epd4in2b_v2::{Epd4in2bV2, GpioPin, Variant};
+    use tide_clock_lib::epd7in5_v2::Epd7in5V2;
+
+    debug!("Opening the e-ink display...");
+
+    // SPI and DC, RST, BUSY through the configured backend
+    let DisplayLines { spi, dc, rst, busy } = open_display_lines(hw).inspect_err(|e| {
+        if let Some(hint) = e.downcast_ref().and_then(|e| display_error_hint(e, hw)) {
+            info!("Hint: {}", hint);
+        }
+    })?;
+    let no_cs = None::<Box<dyn GpioPin>>;
+    let epd: Box<dyn EpdDriver> = match hw.panel {
+        PanelModel::Epd4in2bV2 => Box::new(Epd4in2bV2::new(spi, no_cs, dc, rst, busy)),
+        PanelModel::Epd4in2V2 => {
+            Box::new(Epd4in2bV2::new(spi, no_cs, dc, rst, busy).with_variant(Variant::BlackWhite))
+        }
+        PanelModel::Epd7in5V2 => Box::new(Epd7in5V2::new(spi, no_cs, dc, rst, busy)),
+    };
+    info!("Panel: {}", epd.model_name());
+    Ok(epd)
+}
+
+/// Times `wake_panel` resets the panel before giving up
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+const WAKE_ATTEMPTS: u32 = 3;
+
+/// Reset and initialize the panel, retrying a few times: a marginal supply
+/// or a loose BUSY wire often gets through on a second try
+///
+/// The previous run left the panel in deep sleep (or it just powered up);
+/// either way a reset and init wakes it.
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn wake_panel(
+    epd: &mut Box<dyn tide_clock_lib::epd::EpdDriver>,
+    hw: &tide_clock_lib::config::HardwareConfig,
+) -> anyhow::Result<()> {
+    let mut attempt = 1;
+    loop {
+        match epd.wake() {
+            Ok(()) => {
+                debug!("Display initialized");
+                return Ok(());
+            }
+            Err(e) if attempt < WAKE_ATTEMPTS => {
+                warn!(
+                    "Display initialization failed (attempt {} of {}): {}",
+                    attempt, WAKE_ATTEMPTS, e
+                );
+                std::thread::sleep(std::time::Duration::from_secs(1));
+                attempt += 1;
+            }
+            Err(e) => {
+                if let Some(hint) = display_error_hint(&e, hw) {
+                    info!("Hint: {}", hint);
+                }
+                return Err(anyhow::Error::new(e).context(format!(
+                    "Display initialization failed {} times",
+                    WAKE_ATTEMPTS
+                )));
+            }
+        }
+    }
+}
+
+/// Replace whatever the panel shows with the error page, after an update
+/// failed, rather than leave the last chart up as if it were current
+///
+/// A minimal text-only render with a fresh reset, so it can get through
+/// where the failed update didn't (e.g. a BUSY timeout partway).
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn show_error_page(config: &Config, summary: &str) -> anyhow::Result<()> {
+    use tide_clock_lib::epd4in2b_v2::DisplayBuffer;
+    use tide_clock_lib::error_page::draw_error_page;
+    use tide_clock_lib::panel::PanelState;
+
+    // The panel no longer shows the frame the state describes, so the next
+    // update must be a full refresh
+    std::fs::remove_file(PanelState::path(config)).ok();
+
+    let hw = &config.display.hardware;
+    let mut epd = open_panel(hw)?;
+    wake_panel(&mut epd, hw)?;
+    let at = config
+        .station
+        .local_time(chrono::Utc::now())
+        .format(config.display.time_format.date_time())
+        .to_string();
+    // Drawn at 300x400 when the panel is mounted sideways, then turned
+    let rotation = config.display.rotation;
+    let (width, height) = rotation.drawing_size(epd.width(), epd.height());
+    let mut buffer = DisplayBuffer::new(width, height);
+    draw_error_page(&mut buffer, summary, &at).ok();
+    epd.display(&buffer.transformed(rotation.quarter_turns(), config.display.mirror))?;
+    if config.display.deep_sleep_after_update {
+        epd.sleep()?;
+    }
+    Ok(())
+}
+
 /// Initialize e-ink display with configurable GPIO pins and render tide data
 /// Following the Waveshare example pattern - using rppal GPIO (like Python's gpiozero)
 ///
@@ -49,100 +337,126 @@ fn gpio_to_pin(gpio: u32) -> u32 {
 /// - Older modules use BUSY active LOW (flag=0)  
 /// - The code automatically forces flag=1 for newer modules to prevent hanging
 #[cfg(all(target_os = "linux", feature = "hardware"))]
-fn initialize_eink_display(tide_series: &TideSeries, config: &Config) -> anyhow::Result<()> {
-    use tide_clock_lib::epd4in2b_v2::{DisplayBuffer, Epd4in2bV2};
+fn initialize_eink_display(
+    displayed: &[StationSeries],
+    config: &Config,
+    now: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<()> {
+    use tide_clock_lib::epd::EpdDriver;
+    use tide_clock_lib::epd4in2b_v2::DisplayBuffer;
+    use tide_clock_lib::panel::{PanelState, PlannedFrame, Refresh};
 
-    eprintln!("🚀 Initializing GPIO-only e-ink display (SPI disabled mode)...");
-
-    let mut chip = gpio_cdev::Chip::new("/dev/gpiochip0").context("open gpiochip0")?;
+    // Fetch and offline model both came up empty: nothing to chart
+    let main = &displayed[0];
+    if Config::file_exists() && main.series.height_now().is_none() {
+        anyhow::bail!(
+            "No tide data covering now for {} ({} samples)",
+            main.station.name,
+            main.series.samples.len()
+        );
+    }
 
-    // Get hardware pin config from Config
     let hw = &config.display.hardware;
+    let mut epd = open_panel(hw)?;
 
-    // Only request DC, RST, BUSY via gpiod for hardware SPI
-    let dc = CdevOutputPin::new(&mut chip, hw.dc_pin)?;
-    let rst = CdevOutputPin::new(&mut chip, hw.rst_pin)?;
-    let busy = CdevInputPin::new(&mut chip, hw.busy_pin)?;
-
-    // SPI setup: use hardware CS for GPIO 8 (CE0) or 7 (CE1), manual CS for others
-    let use_hw_cs = hw.cs_pin == 8 || hw.cs_pin == 7;
-    let spi: Box<dyn tide_clock_lib::epd4in2b_v2::SoftwareSpi> = if use_hw_cs {
-        if hw.cs_pin == 8 {
-            Box::new(SpidevHwSpi::new_ce0()?)
-        } else {
-            Box::new(SpidevHwSpi::new_ce1()?)
-        }
-    } else {
-        // Warn if user tries to use manual CS on a kernel-controlled pin
-        if hw.cs_pin == 8 || hw.cs_pin == 7 {
-            eprintln!("⚠️  Config error: cs_pin {} is kernel-controlled (CE0/CE1), manual CS will not work!", hw.cs_pin);
+    // Layout, OFFLINE banner and update time at the panel's size, turned to
+    // match the mounting; between full refreshes only what changed needs sending
+    let state_path = PanelState::path(config);
+    let mut plan = Config::file_exists().then(|| {
+        panel::plan_frame(
+            displayed,
+            config,
+            epd.size(),
+            now,
+            PanelState::load(&state_path),
+        )
+    });
+    if plan
+        .as_ref()
+        .is_some_and(|p| p.refresh == Refresh::Unchanged)
+    {
+        info!("Frame unchanged since the last update, leaving the panel as it is");
+        return Ok(());
+    }
+    let save_state = |state: &PanelState| {
+        if let Err(e) = state.save(&state_path) {
+            warn!(
+                "Could not save the panel state to {}: {}",
+                state_path.display(),
+                e
+            );
         }
-        let spi = SpidevHwSpi::new_ce0()?; // Default to CE0 for manual CS
-        let cs = CdevOutputPin::new(&mut chip, hw.cs_pin)?;
-        Box::new(crate::hw_spi_spidev::SpidevManualCs::new(spi, cs))
     };
-    let mut epd = Epd4in2bV2::new(spi, None::<CdevOutputPin>, dc, rst, busy);
-
-    match epd.init() {
-        Ok(_) => {
-            eprintln!("🎉 SUCCESS! Custom E-ink display driver initialized!");
-            eprintln!("   The EPD initialization completed without hanging!");
+    // Once the picture is on screen the panel needs no power to keep it
+    let power_down = |epd: &mut Box<dyn EpdDriver>| {
+        if !config.display.deep_sleep_after_update {
+            return;
         }
-        Err(e) => {
-            eprintln!(
-                "❌ Custom E-ink display driver initialization failed: {:?}",
-                e
-            );
-            return Err(anyhow::anyhow!("Display initialization failed: {:?}", e));
+        match epd.sleep() {
+            Ok(()) => debug!("Panel in deep sleep until the next update"),
+            Err(e) => warn!("Could not put the panel to sleep: {}", e),
         }
-    }
+    };
 
-    eprintln!("🎨 Creating display buffer and rendering content...");
+    wake_panel(&mut epd, hw)?;
 
-    // Create display buffer - 4.2" display is 400x300 pixels
-    let mut display_buffer = DisplayBuffer::new(400, 300);
-    // Buffer is already initialized to white by default - no need to clear again
+    match epd.temperature() {
+        Ok(Some(celsius)) => {
+            info!("Panel temperature: {:.1}°C", celsius);
+            if config
+                .display
+                .cold_refresh_below_c
+                .is_some_and(|limit| celsius < limit)
+            {
+                info!("Below cold_refresh_below_c, using the slow cold waveform");
+                epd.set_cold_waveform(true);
+            }
+        }
+        Ok(None) => debug!("Panel temperature unavailable, keeping the default waveform"),
+        Err(e) => warn!("Could not read the panel temperature: {}", e),
+    }
 
-    eprintln!("📊 CHART MODE: Rendering tide chart...");
+    if let Some(PlannedFrame {
+        frame,
+        refresh: Refresh::Partial,
+        state,
+    }) = &mut plan
+    {
+        // Only the bytes that differ from what the panel shows are sent
+        info!(
+            "Partial update {} of what changed since the last frame",
+            state.partials_since_full
+        );
+        epd.display_dirty(frame)?;
+        save_state(state);
+        power_down(&mut epd);
+        return Ok(());
+    }
 
     // First, clear the display to remove any previous content (like alternating stripes)
-    eprintln!("🧹 Clearing display to remove previous content...");
+    debug!("Clearing display to remove previous content...");
     epd.clear()?;
-    eprintln!("✅ Display cleared successfully");
 
-    let renderer = tide_clock_lib::eink_renderer::EinkTideRenderer::new();
-    // New API: pass epd, display_buffer, tide_series
-    renderer.render_chart(&mut epd, &mut display_buffer, tide_series);
+    // First boot without any config: show how to reach and set up the device
+    let Some(plan) = plan else {
+        use tide_clock_lib::setup_page::{draw_setup_page, SetupInfo};
 
-    // --- Draw OFFLINE notice if needed ---
-    if tide_series.offline {
-        use embedded_graphics::mono_font::iso_8859_1::FONT_10X20;
-        use embedded_graphics::{
-            mono_font::MonoTextStyle, pixelcolor::BinaryColor, prelude::*, text::Text,
-        };
-        let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
-        Text::new("OFFLINE!", Point::new(10, 24), style)
-            .draw(&mut display_buffer)
-            .ok();
-    }
-
-    // Overlay the last update time/date using embedded-graphics Text primitive
-    use chrono::Local;
-    use embedded_graphics::mono_font::iso_8859_1::FONT_10X20;
-    use embedded_graphics::{
-        mono_font::MonoTextStyle, pixelcolor::BinaryColor, prelude::*, text::Text,
+        let info = SetupInfo::detect(epd.model_name());
+        info!(
+            "No config file found - showing setup page ({} / {:?})",
+            info.hostname, info.ip
+        );
+        // Drawn at 300x400 when the panel is mounted sideways, then turned
+        let rotation = config.display.rotation;
+        let (width, height) = rotation.drawing_size(epd.width(), epd.height());
+        let mut setup_buffer = DisplayBuffer::new(width, height);
+        draw_setup_page(&mut setup_buffer, &info).ok();
+        let frame = setup_buffer.transformed(rotation.quarter_turns(), config.display.mirror);
+        epd.display(&frame)?;
+        power_down(&mut epd);
+        return Ok(());
     };
-
-    let now = Local::now();
-    let time_str = now.format("%-m/%-d %-I:%M%p").to_string(); // e.g. "7/23 8:14PM"
-                                                               // Overlay at top right, 10px from right, 10px from top
-    let char_width = 10; // FONT_10X20 width
-    let overlay_x = 400 - 10 - (time_str.len() as i32 * char_width);
-    let overlay_y = 10;
-    let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
-    Text::new(&time_str, Point::new(overlay_x, overlay_y + 16), style)
-        .draw(&mut display_buffer)
-        .ok();
+    let display_buffer = &plan.frame;
 
     // Debug: Check what we actually rendered
     let black_pixels = display_buffer
@@ -155,15 +469,15 @@ fn initialize_eink_display(tide_series: &TideSeries, config: &Config) -> anyhow:
         .iter()
         .map(|&b| b.count_ones())
         .sum::<u32>();
-    eprintln!(
-        "📊 Rendered buffer: {} black pixels, {} red pixels",
+    debug!(
+        "Rendered buffer: {} black pixels, {} red pixels",
         black_pixels, red_pixels
     );
 
     // Sample a few bytes from the middle of the buffer to verify content
     let mid_offset = display_buffer.black_buffer().len() / 2;
-    eprintln!(
-        "📊 Buffer sample (middle): black={:02X} {:02X} {:02X}, red={:02X} {:02X} {:02X}",
+    trace!(
+        "Buffer sample (middle): black={:02X} {:02X} {:02X}, red={:02X} {:02X} {:02X}",
         display_buffer.black_buffer().get(mid_offset).unwrap_or(&0),
         display_buffer
             .black_buffer()
@@ -189,74 +503,774 @@ fn initialize_eink_display(tide_series: &TideSeries, config: &Config) -> anyhow:
         &display_buffer.black_buffer()[..16.min(display_buffer.black_buffer().len())];
     let all_ff = first_few_black.iter().all(|&b| b == 0xFF);
     if all_ff {
-        eprintln!("⚠️  WARNING: Buffer appears to be all 0xFF (white) - may need bit inversion");
+        warn!("Buffer appears to be all 0xFF (white) - may need bit inversion");
     }
 
-    eprintln!("📤 Updating e-ink display...");
-    eprintln!("     ⚠️  This should be called EXACTLY ONCE to avoid flickering");
+    // Called exactly once, to avoid flickering
+    info!("Full refresh of the e-ink display");
+    epd.display(display_buffer)?;
+    save_state(&plan.state);
+    power_down(&mut epd);
+
+    Ok(())
+}
 
-    // Try the normal display method first since we cleared the display
-    eprintln!("     🎨 Trying normal display method after clear...");
-    match epd.display(display_buffer.black_buffer(), display_buffer.red_buffer()) {
-        Ok(_) => {
-            eprintln!("     ✅ Normal display method completed successfully");
+/// Report offline stations and water below a custom reference level
+/// through the alert scheduler
+fn send_alerts(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    displayed: &[StationSeries],
+    observed: Option<&StationSeries>,
+    budget: &RefreshBudget,
+    now: chrono::DateTime<chrono::Utc>,
+) {
+    use tide_clock_lib::alerts::{Alert, AlertScheduler};
+    use tide_clock_lib::layout::SeriesMeta;
+
+    let mut alerts: Vec<Alert> = displayed
+        .iter()
+        .filter(|s| s.series.is_offline())
+        .map(|s| {
+            Alert::new(
+                format!("offline:{}", s.station.id),
+                "Tide data offline",
+                format!(
+                    "Could not fetch NOAA data for {}; showing the offline approximation",
+                    s.station.name
+                ),
+            )
+        })
+        .collect();
+
+    // Series of stations with a reference level are already relative to it
+    for s in displayed {
+        let Some(reference) = s.station.reference.as_ref().filter(|r| r.alert_below) else {
+            continue;
+        };
+        if let Some(sample) = s.series.first_below(0.0, reference.alert_lead_minutes) {
+            let when = if sample.mins_rel <= 0 {
+                "now".to_string()
+            } else {
+                let meta = SeriesMeta::for_station(config, &s.station, now);
+                format!("at {}", meta.clock_at(sample.mins_rel as i64))
+            };
+            alerts.push(Alert::new(
+                format!("below-reference:{}", s.station.id),
+                format!("Water below {}", reference.name),
+                format!(
+                    "{}: water {:.1} ft below {} {}",
+                    s.station.name, -sample.tide_ft, reference.name, when
+                ),
+            ));
         }
+    }
+
+    // Observed water running well away from the main station's predictions
+    let surge = observed.and_then(|o| surge::warning(&displayed[0], std::slice::from_ref(o)));
+    if let Some(surge) = surge {
+        let main = &displayed[0].station;
+        let direction = if surge.residual_ft > 0.0 {
+            "above"
+        } else {
+            "below"
+        };
+        alerts.push(Alert::new(
+            format!("surge:{}", main.id),
+            format!("Storm surge at {}", main.name),
+            format!(
+                "{}: water {:.1} ft {} the predicted tide",
+                main.name,
+                surge.residual_ft.abs(),
+                direction
+            ),
+        ));
+    }
+
+    let scheduler = AlertScheduler::from_config(config);
+    // Alerts whose delivery overruns the budget are dropped; the condition
+    // still holds on the next refresh if it matters
+    // Quiet hours follow the main station's clock
+    let timezone = displayed[0].station.timezone;
+    rt.block_on(budget.run("alerts", scheduler.dispatch(&alerts, now, timezone)));
+}
+
+/// Handle `tide-tracker report <kind> [--days N]`
+fn run_report(
+    rt: &tokio::runtime::Runtime,
+    config: &Config,
+    args: &[String],
+) -> anyhow::Result<()> {
+    use tide_clock_lib::accuracy;
+
+    if args.first().map(String::as_str) != Some("accuracy") {
+        anyhow::bail!("usage: tide-tracker report accuracy [--days N]");
+    }
+    let days = match args.iter().position(|a| a == "--days") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|d| d.parse::<u32>().ok())
+            .filter(|d| *d > 0)
+            .context("--days needs a positive number")?,
+        None => 30,
+    };
+
+    println!("Prediction accuracy over the last {} days", days);
+    for station in config.all_stations() {
+        match rt.block_on(accuracy::station_accuracy(station, days)) {
+            Ok(Some(stats)) => {
+                println!("  {} ({}): {}", station.name, station.id, stats);
+                if stats.needs_correction() {
+                    println!(
+                        "    -> consider a subordinate-station correction or a closer station"
+                    );
+                }
+            }
+            Ok(None) => println!(
+                "  {} ({}): no observations (prediction-only station)",
+                station.name, station.id
+            ),
+            Err(e) => println!("  {} ({}): failed: {}", station.name, station.id, e),
+        }
+    }
+    Ok(())
+}
+
+/// Handle `tide-tracker export --csv FILE [--days N]`
+fn run_export(
+    rt: &tokio::runtime::Runtime,
+    mut config: Config,
+    args: &[String],
+    now: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<()> {
+    use tide_clock_lib::export::{self, MAX_DAYS};
+
+    let path = match args.iter().position(|a| a == "--csv") {
+        Some(i) => args.get(i + 1).context("--csv needs an output file")?,
+        None => anyhow::bail!("usage: tide-tracker export --csv FILE [--days N]"),
+    };
+    let days = match args.iter().position(|a| a == "--days") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|d| d.parse::<i64>().ok())
+            .filter(|d| (1..=MAX_DAYS).contains(d))
+            .with_context(|| format!("--days needs a number from 1 to {}", MAX_DAYS))?,
+        None => 1,
+    };
+
+    let hours = days * 12;
+    if hours != config.display.time_window_hours {
+        // Its own cache file, so the export doesn't evict the panel's
+        config.display.cache_path = tide_clock_lib::config::with_stem_suffix(
+            &config.display.cache_path,
+            &format!("{}h", hours * 2),
+        );
+        config.display.time_window_hours = hours;
+    }
+    let station = config.station.clone();
+    let series = match rt.block_on(tide_data::fetch_station_at(&config, &station, now)) {
+        Ok(series) => series,
         Err(e) => {
-            eprintln!("     ⚠️  Normal display failed: {:?}", e);
-            eprintln!("     🔄 Falling back to C test sequence...");
-            epd.display_c_test_sequence(
-                display_buffer.black_buffer(),
-                display_buffer.red_buffer(),
-            )?;
-            eprintln!("     ✅ C test sequence fallback completed");
-        }
-    }
-
-    eprintln!("✅ E-ink display updated successfully with PERSISTENCE SEQUENCE!");
-    eprintln!("   📋 Persistence checklist completed:");
-    eprintln!("   ✅ 1. Drew image once (no clear after)");
-    eprintln!("   ✅ 2. Sent POWER_OFF (0x02) + wait BUSY");
-    eprintln!("   ✅ 3. Sent DEEP_SLEEP (0x10) + 0x01 + wait BUSY");
-    eprintln!("   ✅ 4. Display controller parked safely");
-    eprintln!();
-    eprintln!("🎯 Image should now persist indefinitely (even with Pi powered off)");
-    eprintln!("   This follows the persistence cheat sheet exactly");
-    eprintln!("   You can now safely power off the Pi - image will remain");
+            warn!("Tide data fetch failed, exporting the offline model: {}", e);
+            fallback::for_station(&config, &station, Some(now))
+        }
+    };
+    let series = match &station.reference {
+        Some(reference) => series.relative_to(reference.elevation_ft),
+        None => series,
+    };
 
+    let file = std::fs::File::create(path).with_context(|| format!("Could not create {}", path))?;
+    export::write_csv(std::io::BufWriter::new(file), &series, &station, now)
+        .with_context(|| format!("Could not write {}", path))?;
+    info!(
+        "Exported {} samples for {} to {}",
+        series.samples.len(),
+        station.name,
+        path
+    );
     Ok(())
 }
 
+/// Handle `tide-tracker config validate [--online]` and
+/// `tide-tracker config init [FILE] [--force]`
+fn run_config(rt: &tokio::runtime::Runtime, args: &[String]) -> anyhow::Result<()> {
+    use tide_clock_lib::config_check::{self, Severity};
+
+    let usage = "usage: tide-tracker config validate [--online] | config init [FILE] [--force]";
+    match args.first().map(String::as_str) {
+        Some("validate") => {
+            let path = Config::path();
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Could not read {}", path.display()))?;
+            let (config, mut findings) = config_check::check(&contents);
+            if let (Some(config), true) = (&config, args.iter().any(|a| a == "--online")) {
+                for station in config.all_stations() {
+                    match rt.block_on(config_check::station_exists(station)) {
+                        Ok(true) => {}
+                        Ok(false) => findings.push(config_check::Finding {
+                            severity: Severity::Error,
+                            message: format!(
+                                "NOAA has no station \"{}\" ({})",
+                                station.id, station.name
+                            ),
+                        }),
+                        Err(e) => findings.push(config_check::Finding {
+                            severity: Severity::Warning,
+                            message: format!("could not look up station {}: {}", station.id, e),
+                        }),
+                    }
+                }
+            }
+
+            println!("{}", path.display());
+            for finding in &findings {
+                println!("  {}", finding);
+            }
+            let errors = findings
+                .iter()
+                .filter(|f| f.severity == Severity::Error)
+                .count();
+            if errors > 0 {
+                anyhow::bail!("{} error(s) in {}", errors, path.display());
+            }
+            if findings.is_empty() {
+                println!("  OK");
+            }
+            Ok(())
+        }
+        Some("init") => {
+            let path = match args.get(1).filter(|a| !a.starts_with("--")) {
+                Some(path) => std::path::PathBuf::from(path),
+                None => Config::path(),
+            };
+            if path.exists() && !args.iter().any(|a| a == "--force") {
+                anyhow::bail!("{} already exists (--force overwrites it)", path.display());
+            }
+            if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("Could not create {}", dir.display()))?;
+            }
+            std::fs::write(&path, config_check::DEFAULT_CONFIG_TOML)
+                .with_context(|| format!("Could not write {}", path.display()))?;
+            println!("Wrote the default configuration to {}", path.display());
+            Ok(())
+        }
+        _ => anyhow::bail!(usage),
+    }
+}
+
+/// Show the panel frame in a desktop window, drawn again every minute (or on
+/// R) with the config re-read, until the window is closed.
+#[cfg(feature = "simulator")]
+fn run_simulator(
+    rt: &tokio::runtime::Runtime,
+    config: Config,
+    test_offline_mode: bool,
+) -> anyhow::Result<()> {
+    use tide_clock_lib::simulator::{PreviewAction, PreviewWindow};
+
+    let mut window = PreviewWindow::new("Tide Tracker", 2);
+    let mut loaded = Some(config);
+    loop {
+        // Re-read so edits to tide-config.toml show up on the next refresh
+        let config = loaded.take().unwrap_or_else(Config::load);
+        let now = chrono::Utc::now();
+        let displayed = if test_offline_mode {
+            vec![StationSeries {
+                station: config.station.clone(),
+                series: fallback::for_station(&config, &config.station, Some(now)),
+            }]
+        } else {
+            let budget = RefreshBudget::start(&config);
+            rt.block_on(stations::fetch_for_display(&config, &budget, now))
+        };
+        let (width, height) = config.display.hardware.panel.size();
+        window.show(&panel::compose_frame(
+            &displayed,
+            &config,
+            Size::new(width, height),
+            now,
+        ));
+        if window.wait(std::time::Duration::from_secs(60)) == PreviewAction::Quit {
+            return Ok(());
+        }
+    }
+}
+
+/// Terminal dashboard until `q`; the screen is restored however it ends
+#[cfg(feature = "tui")]
+fn run_tui(rt: &tokio::runtime::Runtime, config: Config) -> anyhow::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = dashboard_loop(rt, config, &mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[cfg(feature = "tui")]
+fn dashboard_loop(
+    rt: &tokio::runtime::Runtime,
+    config: Config,
+    terminal: &mut ratatui::DefaultTerminal,
+) -> anyhow::Result<()> {
+    use ratatui::crossterm::event::{self, Event, KeyEventKind};
+    use std::time::{Duration, Instant};
+    use tide_clock_lib::tui::{Action, Dashboard, FetchRequest, Fetched};
+
+    let interval = Duration::from_secs(config.display.update_interval_minutes.max(1) * 60);
+    let mut dashboard = Dashboard::new(config);
+    let (sender, results) = std::sync::mpsc::channel();
+    // On a thread, so the chart keeps redrawing while NOAA is slow
+    let fetch = |request: FetchRequest| {
+        let sender = sender.clone();
+        let handle = rt.handle().clone();
+        std::thread::spawn(move || {
+            // Re-read so edits to tide-config.toml apply on the next fetch
+            let mut config = Config::load();
+            config.display.time_window_hours = request.window_hours;
+            let stations = config.all_stations();
+            let station = stations[request.station.min(stations.len() - 1)].clone();
+            let (series, error) = match handle.block_on(tide_data::fetch_station(&config, &station))
+            {
+                Ok(series) => (series, None),
+                Err(e) => (
+                    fallback::for_station(&config, &station, None),
+                    Some(e.to_string()),
+                ),
+            };
+            let generation = request.generation;
+            sender
+                .send(Fetched {
+                    generation,
+                    config,
+                    series,
+                    error,
+                })
+                .ok();
+        });
+    };
+
+    let mut next_fetch = Instant::now();
+    loop {
+        if Instant::now() >= next_fetch {
+            fetch(dashboard.fetch_request());
+            next_fetch = Instant::now() + interval;
+        }
+        while let Ok(fetched) = results.try_recv() {
+            dashboard.receive(fetched);
+        }
+        terminal.draw(|frame| dashboard.draw(frame, chrono::Utc::now()))?;
+
+        // Back at least every quarter second to pick up fetch results
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match dashboard.handle_key(key.code) {
+                Action::Quit => return Ok(()),
+                Action::Fetch => next_fetch = Instant::now(),
+                Action::Nothing => {}
+            }
+        }
+    }
+}
+
+/// NOAA's observed water levels at the main station, drawn over its curve
+/// and checked for a surge; only fetched with `surge_threshold_ft` set
+fn observe(
+    rt: &tokio::runtime::Runtime,
+    displayed: &[StationSeries],
+    budget: &RefreshBudget,
+) -> Option<StationSeries> {
+    let main = displayed.first()?;
+    match rt.block_on(budget.run("observed", surge::fetch_observed(main)))? {
+        Ok(observed) => observed,
+        Err(e) => {
+            warn!(
+                "Observed water levels unavailable for {}: {}",
+                main.station.name, e
+            );
+            None
+        }
+    }
+}
+
+/// Append the main station's heights, and the `observed` ones, to its
+/// `[archive]` file, when there is one, and with `overlay_yesterday` return
+/// the curve from 24 hours earlier to draw over the chart (see
+/// [`tide_clock_lib::archive`])
+#[cfg(feature = "archive")]
+fn archive(
+    config: &Config,
+    displayed: &[StationSeries],
+    observed: Option<&StationSeries>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<StationSeries> {
+    use tide_clock_lib::archive;
+
+    let main = displayed.first()?;
+    let path = config.archive_path_for(&main.station.id)?;
+    for series in std::iter::once(main).chain(observed) {
+        match archive::record(&path, series, now) {
+            Ok(rows) => debug!(
+                "Archived {} {} samples to {}",
+                rows,
+                series.series.source.label(),
+                path.display()
+            ),
+            Err(e) => warn!("Could not append to {}: {}", path.display(), e),
+        }
+    }
+    if !config.archive.overlay_yesterday {
+        return None;
+    }
+    archive::yesterday(&path, main, &config.archive.label).unwrap_or_else(|e| {
+        warn!("Could not read {}: {}", path.display(), e);
+        None
+    })
+}
+
+/// Without the `archive` feature there is nothing to record to
+#[cfg(not(feature = "archive"))]
+fn archive(
+    config: &Config,
+    _displayed: &[StationSeries],
+    _observed: Option<&StationSeries>,
+    _now: chrono::DateTime<chrono::Utc>,
+) -> Option<StationSeries> {
+    if config.archive.path.is_some() {
+        warn!(
+            "[archive] path is set, but this build has no archive; rebuild with --features archive"
+        );
+    }
+    None
+}
+
+/// Start reading the NMEA depth sensor in the background, if one is
+/// configured; its readings accumulate for the daemon's lifetime
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn start_sensor(
+    sensor: &tide_clock_lib::nmea::SensorConfig,
+) -> Option<std::sync::Arc<std::sync::Mutex<tide_clock_lib::nmea::Readings>>> {
+    use std::io::BufReader;
+    use std::sync::{Arc, Mutex};
+    use tide_clock_lib::nmea::{self, Readings};
+
+    let device = sensor.device.as_ref()?;
+    let port = match serial_port::open(device, sensor.baud) {
+        Ok(port) => port,
+        Err(e) => {
+            warn!(
+                "Depth sensor unavailable, drawing predictions only: {:#}",
+                e
+            );
+            return None;
+        }
+    };
+    info!(
+        "Reading depth from {} at {} baud",
+        device.display(),
+        sensor.baud
+    );
+    let readings = Arc::new(Mutex::new(Readings::default()));
+    let shared = readings.clone();
+    let offset_ft = sensor.offset_ft;
+    let device = device.clone();
+    std::thread::spawn(
+        move || match nmea::read_into(BufReader::new(port), &shared, offset_ft) {
+            Ok(()) => warn!("Depth sensor {} closed", device.display()),
+            Err(e) => warn!("Depth sensor {} failed: {}", device.display(), e),
+        },
+    );
+    Some(readings)
+}
+
+/// What woke the daemon up
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Wake {
+    /// `update_interval_minutes` passed
+    Update,
+    /// Short press: draw the next layout from the same data
+    Redraw,
+    /// Long press: fetch past the cache and refresh fully
+    Refetch,
+}
+
+/// Keep running instead of updating once: update every
+/// `update_interval_minutes`, watching the button in between (see
+/// [`tide_clock_lib::button`])
+#[cfg(all(target_os = "linux", feature = "hardware"))]
+fn run_daemon(rt: &tokio::runtime::Runtime, mut config: Config) -> anyhow::Result<()> {
+    use std::time::{Duration, Instant};
+    use tide_clock_lib::button::{self, Button, Press, POLL_INTERVAL};
+    use tide_clock_lib::panel::PanelState;
+
+    let mut button =
+        open_button(&config.display.hardware)?.map(|line| (line, Button::new(Instant::now())));
+    // Opened once; changes to `[sensor]` apply when the daemon restarts
+    let sensor = start_sensor(&config.sensor);
+    // Picked with the button; kept across config reloads
+    let mut layout: Option<String> = None;
+    let mut displayed = Vec::new();
+    // What was on the panel before a restart, redrawn while the first fetch
+    // waits on the network
+    let mut restore = PanelState::load(&PanelState::path(&config)).and_then(|s| s.restore(&config));
+    // NOAA's observed water levels and the archived curve from a day
+    // earlier, read once per fetch
+    let mut observed = None;
+    let mut yesterday = None;
+    let mut wake = Wake::Update;
+    loop {
+        if wake != Wake::Redraw {
+            // Re-read so edits to tide-config.toml apply on the next update;
+            // the first one goes with the config main loaded
+            if !displayed.is_empty() {
+                config = Config::load();
+            }
+            if wake == Wake::Refetch {
+                config.display.cache_ttl_minutes = 0;
+                // Without the last frame to diff against, the refresh is full
+                std::fs::remove_file(PanelState::path(&config)).ok();
+            }
+            let budget = RefreshBudget::start(&config);
+            let now = chrono::Utc::now();
+            let fetch = || rt.block_on(stations::fetch_for_display(&config, &budget, now));
+            displayed = match restore.take() {
+                Some(saved) => std::thread::scope(|scope| {
+                    scope.spawn(|| {
+                        info!("Restoring the last frame while fetching");
+                        if let Err(e) = initialize_eink_display(&saved, &config, now) {
+                            warn!("Could not restore the last frame: {:#}", e);
+                        }
+                    });
+                    fetch()
+                }),
+                None => fetch(),
+            };
+            observed = observe(rt, &displayed, &budget);
+            send_alerts(rt, &config, &displayed, observed.as_ref(), &budget, now);
+            yesterday = archive(&config, &displayed, observed.as_ref(), now);
+        }
+        if let Some(layout) = &layout {
+            config.display.layout = layout.clone();
+        }
+        // Observed water, yesterday's heights and the measured depth drawn
+        // over the main station's curve
+        let mut shown = displayed.clone();
+        shown.extend(observed.clone());
+        shown.extend(yesterday.clone());
+        if let Some(readings) = sensor.as_ref().and_then(|r| r.lock().ok()) {
+            shown.extend(readings.overlay(&displayed[0], &config.sensor.label));
+        }
+        if let Err(e) = initialize_eink_display(&shown, &config, chrono::Utc::now()) {
+            error!("E-ink display update failed: {:#}", e);
+            if let Err(e) = show_error_page(&config, &format!("{:#}", e)) {
+                warn!("Could not show the error page either: {:#}", e);
+            }
+        }
+
+        let interval = Duration::from_secs(config.display.update_interval_minutes.max(1) * 60);
+        let deadline = Instant::now() + interval;
+        wake = Wake::Update;
+        while let Some((line, state)) = &mut button {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            // Wired to ground: low while pressed
+            let press = match line.is_high() {
+                Ok(high) => state.update(!high, now),
+                Err(e) => {
+                    warn!("Could not read the button, ignoring it: {}", e);
+                    button = None;
+                    break;
+                }
+            };
+            match press {
+                Some(Press::Short) => {
+                    let current = &config.display.layout;
+                    match button::next_layout(current, &config.display.button_layouts) {
+                        Some(next) => {
+                            info!("Short press: showing the '{}' layout", next);
+                            layout = Some(next);
+                            wake = Wake::Redraw;
+                            break;
+                        }
+                        None => info!("Short press, but button_layouts is empty"),
+                    }
+                }
+                Some(Press::Long) => {
+                    info!("Long press: refetching and redrawing");
+                    wake = Wake::Refetch;
+                    break;
+                }
+                None => std::thread::sleep(POLL_INTERVAL),
+            }
+        }
+        if button.is_none() {
+            std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+        }
+    }
+}
+
 /// Main application entry point.
 fn main() -> anyhow::Result<()> {
     // Parse command line arguments
     // Development mode: render to stdout for testing without hardware
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    // The dashboard owns the screen, and shows fetch errors itself
+    let tui = cfg!(feature = "tui") && args.get(1).map(String::as_str) == Some("tui");
+    if !tui {
+        init_logging(args.iter().any(|arg| arg == "--verbose" || arg == "-v"));
+    }
+    // Configuration file, ahead of $TIDE_TRACKER_CONFIG and the search path
+    if let Some(i) = args.iter().position(|arg| arg == "--config") {
+        let path = args.get(i + 1).context("--config needs a file")?;
+        Config::set_path(path.into());
+        // Out of the way of the subcommands below, which come first
+        args.drain(i..i + 2);
+    }
     let development_mode = args.iter().any(|arg| arg == "--stdout");
     let test_offline_mode = args.iter().any(|arg| arg == "--test-offline");
+    // Braille and colors when the terminal takes them, unless asked not to
+    let style = match args.iter().any(|arg| arg == "--plain") {
+        true => TerminalStyle::Plain,
+        false => TerminalStyle::detect(),
+    };
+    // Preview mode: save the panel frame as a PNG (or .bmp) instead of drawing it
+    let image_path = match args.iter().position(|arg| arg == "--png") {
+        Some(i) => Some(args.get(i + 1).context("--png needs an output file")?),
+        None => None,
+    };
+    // ...or write it to the terminal, for checking the pixels over SSH
+    let sixel = args.iter().any(|arg| arg == "--sixel");
+    let kitty = args.iter().any(|arg| arg == "--kitty");
+    // The moment to draw, for replaying a data file or a reproducible
+    // screenshot; the system clock otherwise
+    let at = match args.iter().position(|arg| arg == "--at") {
+        Some(i) => Some(
+            args.get(i + 1)
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .context("--at needs an RFC 3339 time, e.g. 2025-07-01T12:00:00-04:00")?,
+        ),
+        None => None,
+    };
+    let now = at.unwrap_or_else(chrono::Utc::now);
 
     // Create Tokio runtime for async operations
     let rt = tokio::runtime::Runtime::new()?;
 
-    // Fetch tide data with automatic fallback on failure, or force offline if requested
-    let tide_series = if test_offline_mode {
+    // Subcommands: `config validate [--online]`, `config init [FILE] [--force]`
+    // (ahead of loading, which would warn about what validate reports)
+    if args.get(1).map(String::as_str) == Some("config") {
+        return run_config(&rt, &args[2..]);
+    }
+
+    // The one copy everything below fetches, draws and alerts with
+    let config = Config::load();
+
+    // Subcommand: `report accuracy [--days N]`
+    if args.get(1).map(String::as_str) == Some("report") {
+        return run_report(&rt, &config, &args[2..]);
+    }
+
+    // Home Assistant REST sensor: print the JSON once, or serve it
+    if let Some(i) = args.iter().position(|arg| arg == "--ha-listen") {
+        let addr = args
+            .get(i + 1)
+            .context("--ha-listen needs an address, e.g. 0.0.0.0:8080")?;
+        // Pinned with --at, else each request reads the clock
+        rt.block_on(home_assistant::serve(addr, config, at))
+            .with_context(|| format!("Could not serve on {}", addr))?;
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--ha") {
+        let state = rt.block_on(home_assistant::current(&config, now));
+        println!("{}", serde_json::to_string(&state)?);
+        return Ok(());
+    }
+
+    // Subcommand: `export --csv FILE [--days N]`
+    if args.get(1).map(String::as_str) == Some("export") {
+        return run_export(&rt, config, &args[2..], now);
+    }
+
+    // Subcommand: `tui`, a live dashboard in the terminal
+    #[cfg(feature = "tui")]
+    if tui {
+        return run_tui(&rt, config);
+    }
+
+    // Desktop preview window, refreshed in place of the panel (no alerts)
+    #[cfg(feature = "simulator")]
+    if args.iter().any(|arg| arg == "--simulator") {
+        return run_simulator(&rt, config, test_offline_mode);
+    }
+
+    // Long-running alternative to the systemd timer, with button support
+    #[cfg(all(target_os = "linux", feature = "hardware"))]
+    if args.iter().any(|arg| arg == "--daemon") {
+        return run_daemon(&rt, config);
+    }
+
+    let budget = RefreshBudget::start(&config);
+
+    // Fetch tide data with automatic per-station fallback on failure, or force offline if requested
+    let mut displayed = if test_offline_mode {
         // Force offline fallback mode for testing: this sets offline=true in the returned TideSeries
-        eprintln!("[TEST] Forcing offline fallback mode (--test-offline flag set)");
-        fallback::approximate(None)
+        warn!("[TEST] Forcing offline fallback mode (--test-offline flag set)");
+        vec![StationSeries {
+            station: config.station.clone(),
+            series: fallback::for_station(&config, &config.station, Some(now)),
+        }]
     } else {
-        rt.block_on(async {
-            tide_data::fetch().await.unwrap_or_else(|error| {
-                // Log fetch failure for debugging (visible in systemd journal)
-                eprintln!("Tide data fetch failed: {}", error);
-                eprintln!("Falling back to offline mathematical model");
-                // Continue with synthetic data rather than crashing
-                fallback::approximate(None)
-            })
-        })
+        // Failures are logged (visible in systemd journal) and replaced with synthetic data
+        rt.block_on(stations::fetch_for_display(&config, &budget, now))
+    };
+
+    let observed = match test_offline_mode {
+        true => None,
+        false => observe(&rt, &displayed, &budget),
     };
+    if !test_offline_mode {
+        send_alerts(&rt, &config, &displayed, observed.as_ref(), &budget, now);
+    }
+    // Drawn as more overlays, after the alerts so they never raise one
+    let yesterday = archive(&config, &displayed, observed.as_ref(), now);
+    displayed.extend(observed);
+    displayed.extend(yesterday);
+    let tide_series = &displayed[0].series;
+
+    // Sized for the configured panel, as the hardware would draw it
+    let panel_frame = || {
+        let (width, height) = config.display.hardware.panel.size();
+        panel::compose_frame(&displayed, &config, Size::new(width, height), now)
+    };
+    if let Some(path) = image_path {
+        panel::save_image(&panel_frame(), path.as_ref())?;
+        info!("Saved the panel frame to {}", path);
+        return Ok(());
+    }
+    if sixel || kitty {
+        use std::io::Write;
+
+        let mut out = std::io::stdout().lock();
+        match kitty {
+            true => panel::write_kitty(&panel_frame(), &mut out)?,
+            false => panel::write_sixel(&panel_frame(), &mut out)?,
+        }
+        writeln!(out)?;
+        return Ok(());
+    }
 
     // Development mode: ASCII output for testing
     if development_mode {
-        draw_ascii(&tide_series);
+        draw_ascii(tide_series, &config, now, style);
         return Ok(());
     }
 
@@ -264,56 +1278,46 @@ fn main() -> anyhow::Result<()> {
     // This section requires SPI access and proper GPIO permissions
     #[cfg(all(target_os = "linux", feature = "hardware"))]
     {
-        // Load configuration for GPIO pins
-        let config = Config::load();
         let hw = &config.display.hardware;
 
-        eprintln!("🔧 E-ink hardware integration with configurable GPIO pins");
-        eprintln!("📋 GPIO pin configuration:");
-        eprintln!(
-            "   CS (Chip Select): GPIO {} (Pin {})",
+        debug!(
+            "GPIO pins: CS {} (pin {}), DC {} (pin {}), RST {} (pin {}), BUSY {} (pin {})",
             hw.cs_pin,
-            gpio_to_pin(hw.cs_pin)
-        );
-        eprintln!(
-            "   DC (Data/Command): GPIO {} (Pin {})",
+            gpio_to_pin(hw.cs_pin),
             hw.dc_pin,
-            gpio_to_pin(hw.dc_pin)
-        );
-        eprintln!(
-            "   RST (Reset): GPIO {} (Pin {})",
+            gpio_to_pin(hw.dc_pin),
             hw.rst_pin,
-            gpio_to_pin(hw.rst_pin)
-        );
-        eprintln!(
-            "   BUSY: GPIO {} (Pin {})",
+            gpio_to_pin(hw.rst_pin),
             hw.busy_pin,
             gpio_to_pin(hw.busy_pin)
         );
 
         // Initialize e-ink display with configured GPIO pins
-        match initialize_eink_display(&tide_series, &config) {
+        match initialize_eink_display(&displayed, &config, now) {
             Ok(_) => {
-                eprintln!("✅ E-ink display updated successfully");
+                info!("E-ink display updated successfully");
             }
             Err(e) => {
-                eprintln!("❌ E-ink display initialization failed: {}", e);
-                eprintln!("Falling back to ASCII output for debugging:");
-                draw_ascii(&tide_series);
+                error!("E-ink display update failed: {:#}", e);
+                if let Err(e) = show_error_page(&config, &format!("{:#}", e)) {
+                    warn!("Could not show the error page either: {:#}", e);
+                }
+                info!("Falling back to ASCII output for debugging:");
+                draw_ascii(tide_series, &config, now, style);
             }
         }
     }
 
     #[cfg(all(target_os = "linux", not(feature = "hardware")))]
     {
-        eprintln!("E-ink display support not enabled. Rebuild with --features hardware for display functionality.");
-        eprintln!("Showing ASCII output instead:");
-        draw_ascii(&tide_series);
+        warn!("E-ink display support not enabled. Rebuild with --features hardware for display functionality.");
+        warn!("Showing ASCII output instead:");
+        draw_ascii(tide_series, &config, now, style);
     }
 
     #[cfg(not(target_os = "linux"))]
     {
-        eprintln!("Hardware mode is only available on Linux. Use --stdout for development mode.");
+        error!("Hardware mode is only available on Linux. Use --stdout for development mode.");
         #[allow(unreachable_code)]
         return Err(anyhow::anyhow!(
             "Hardware mode not supported on this platform"
@@ -0,0 +1,284 @@
+//! # NMEA Depth Sensor
+//!
+//! Measured water depth from a boat's depth sounder, drawn over the
+//! predicted curve so the two can be compared. The sounder talks NMEA 0183
+//! over a serial adapter; `tide-tracker --daemon` (built with `hardware`)
+//! reads it in the background and adds the measurements to every frame as
+//! a dashed overlay:
+//!
+//! ```toml
+//! [sensor]
+//! device = "/dev/ttyUSB0"
+//! baud = 4800
+//! offset_ft = -12.5
+//! ```
+//!
+//! Two sentences are understood, from any talker:
+//!
+//! - `DPT` (depth of water): meters below the transducer, plus the
+//!   sounder's own offset when it gives a positive (waterline) one
+//! - `XDR` (transducer measurement): the first distance (`D`) in meters
+//!
+//! `offset_ft` is added to every reading to put it on the chart's datum,
+//! e.g. minus the charted depth below the boat's berth at MLLW. Readings are
+//! averaged into 10 minute bins as they arrive, so a sounder talking once a
+//! second never holds more than the widest window's worth of bins.
+//!
+//! With `full_refresh_minutes` set, partial updates redraw the last full
+//! chart, so new readings show up at the next full refresh.
+
+use crate::config::StationConfig;
+use crate::grid::{Window, INTERVAL_MINS, MAX_HALF_WIDTH_HOURS};
+use crate::stations::StationSeries;
+use crate::{DataSource, Sample, TideSeries};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::{debug, trace};
+
+/// Feet in a meter
+const FT_PER_M: f32 = 3.28084;
+
+/// `[sensor]` configuration section
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SensorConfig {
+    /// Serial port the sounder is connected to; no sensor when unset
+    pub device: Option<PathBuf>,
+    /// Line speed; NMEA 0183 is 4800 baud, some adapters use 38400
+    pub baud: u32,
+    /// Feet added to every reading to put it on the chart's datum
+    pub offset_ft: f32,
+    /// Legend label of the measured curve
+    pub label: String,
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        Self {
+            device: None,
+            baud: 4800,
+            offset_ft: 0.0,
+            label: "Measured".to_string(),
+        }
+    }
+}
+
+/// Water depth in meters from one NMEA sentence
+///
+/// Returns `None` for other sentences, empty fields and sentences whose
+/// checksum (when they carry one) doesn't match.
+pub fn parse_depth_m(sentence: &str) -> Option<f32> {
+    let body = sentence.trim().strip_prefix('$')?;
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum.get(..2)?, 16).ok()?;
+            (body.bytes().fold(0, |sum, b| sum ^ b) == expected).then_some(body)?
+        }
+        None => body,
+    };
+    let mut fields = body.split(',');
+    // Two-letter talker ("SD", "II", ...) then the sentence type
+    let kind = fields.next()?.get(2..)?;
+    match kind {
+        "DPT" => {
+            let depth: f32 = fields.next()?.parse().ok()?;
+            let offset = fields
+                .next()
+                .and_then(|f| f.parse::<f32>().ok())
+                .filter(|offset| *offset > 0.0)
+                .unwrap_or(0.0);
+            Some(depth + offset)
+        }
+        "XDR" => {
+            let fields: Vec<&str> = fields.collect();
+            fields.chunks(4).find_map(|quad| match quad {
+                ["D", value, "M", ..] => value.parse().ok(),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Running average of the readings in one 10 minute bin
+#[derive(Debug, Clone, Copy)]
+struct Bin {
+    start: DateTime<Utc>,
+    sum: f32,
+    count: u32,
+}
+
+/// Measured heights, averaged into 10 minute bins
+#[derive(Debug, Default)]
+pub struct Readings {
+    bins: VecDeque<Bin>,
+}
+
+impl Readings {
+    /// Add a height (already offset) measured at `at`
+    ///
+    /// Bins older than the widest chart window are dropped.
+    pub fn push(&mut self, at: DateTime<Utc>, height_ft: f32) {
+        let Ok(start) = at.duration_trunc(Duration::minutes(INTERVAL_MINS)) else {
+            return;
+        };
+        match self.bins.back_mut() {
+            Some(bin) if bin.start == start => {
+                bin.sum += height_ft;
+                bin.count += 1;
+            }
+            _ => self.bins.push_back(Bin {
+                start,
+                sum: height_ft,
+                count: 1,
+            }),
+        }
+        let oldest = start - Duration::hours(MAX_HALF_WIDTH_HOURS * 2);
+        while self.bins.front().is_some_and(|bin| bin.start < oldest) {
+            self.bins.pop_front();
+        }
+    }
+
+    /// The bins inside `window` as a series centered on it, each sample at
+    /// the middle of its bin; `None` when there are none
+    pub fn series_in(&self, window: Window) -> Option<TideSeries> {
+        let samples: Vec<Sample> = self
+            .bins
+            .iter()
+            .filter_map(|bin| {
+                let middle = bin.start + Duration::minutes(INTERVAL_MINS) / 2;
+                window.contains(middle).then(|| Sample {
+                    mins_rel: (middle - window.center).num_minutes() as i16,
+                    tide_ft: bin.sum / bin.count as f32,
+                    modeled: false,
+                })
+            })
+            .collect();
+        if samples.is_empty() {
+            return None;
+        }
+        Some(TideSeries {
+            samples,
+            source: DataSource::Sensor,
+            base_time: Some(window.center),
+            extremes: None,
+            fetched_at: None,
+        })
+    }
+
+    /// The measured curve to draw over `main`'s chart, with the same
+    /// reference level applied and `label` as its legend entry
+    pub fn overlay(&self, main: &StationSeries, label: &str) -> Option<StationSeries> {
+        let center = main.series.base_time?;
+        let half_width = main.series.half_width()?;
+        let series = self.series_in(Window::around(center, half_width))?;
+        let series = match &main.station.reference {
+            Some(reference) => series.relative_to(reference.elevation_ft),
+            None => series,
+        };
+        Some(StationSeries {
+            station: StationConfig {
+                name: label.to_string(),
+                ..main.station.clone()
+            },
+            series,
+        })
+    }
+}
+
+/// Read sentences from `port` into `readings` until it closes or fails,
+/// adding `offset_ft` to each depth
+pub fn read_into(port: impl BufRead, readings: &Mutex<Readings>, offset_ft: f32) -> io::Result<()> {
+    for line in port.lines() {
+        let line = line?;
+        match parse_depth_m(&line) {
+            Some(depth_m) => {
+                let height_ft = depth_m * FT_PER_M + offset_ft;
+                debug!("Measured {:.2} ft", height_ft);
+                if let Ok(mut readings) = readings.lock() {
+                    readings.push(Utc::now(), height_ft);
+                }
+            }
+            None => trace!("Ignoring NMEA line {:?}", line),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parses_dpt_and_xdr() {
+        // Checksums as a sounder would send them
+        assert_eq!(parse_depth_m("$SDDPT,3.4,0.5*55"), Some(3.9));
+        assert_eq!(parse_depth_m("$SDDPT,3.4,-1.2*7E\r\n"), Some(3.4));
+        assert_eq!(parse_depth_m("$SDDPT,3.4,0.5"), Some(3.9));
+        assert_eq!(parse_depth_m("$SDDPT,3.4,0.5*00"), None);
+        assert_eq!(parse_depth_m("$SDDPT,,*57"), None);
+        assert_eq!(
+            parse_depth_m("$IIXDR,C,12.1,C,WATER,D,2.5,M,DEPTH"),
+            Some(2.5)
+        );
+        assert_eq!(parse_depth_m("$IIXDR,C,12.1,C,WATER"), None);
+        assert_eq!(parse_depth_m("$GPGGA,123519,4807.038,N"), None);
+        assert_eq!(parse_depth_m("garbage"), None);
+    }
+
+    #[test]
+    fn test_readings_average_into_the_window() {
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let mut readings = Readings::default();
+        readings.push(now - Duration::days(4), 9.0);
+        readings.push(now - Duration::minutes(20), 1.0);
+        readings.push(now - Duration::minutes(15), 2.0);
+        readings.push(now - Duration::minutes(5), 3.0);
+        // The four-day-old bin is dropped once newer ones arrive
+        assert_eq!(readings.bins.len(), 2);
+
+        let series = readings.series_in(Window::standard(now)).unwrap();
+        assert_eq!(series.base_time, Some(now));
+        let samples: Vec<(i16, f32)> = series
+            .samples
+            .iter()
+            .map(|s| (s.mins_rel, s.tide_ft))
+            .collect();
+        assert_eq!(samples, vec![(-15, 1.5), (-5, 3.0)]);
+        assert!(readings
+            .series_in(Window::standard(now + Duration::days(1)))
+            .is_none());
+    }
+
+    #[test]
+    fn test_reads_a_port_into_an_overlay() {
+        let port = "$SDDPT,2.0,0.0*55\r\n$GPGGA,1\r\n$IIXDR,D,2.0,M,DEPTH\r\n";
+        let readings = Mutex::new(Readings::default());
+        read_into(port.as_bytes(), &readings, -1.0).unwrap();
+        let readings = readings.into_inner().unwrap();
+        assert_eq!(readings.bins.iter().map(|b| b.count).sum::<u32>(), 2);
+
+        let mut station = Config::default().station;
+        station.reference = Some(crate::config::ReferenceLevel {
+            name: "Dock".to_string(),
+            elevation_ft: 1.0,
+            alert_below: false,
+            alert_lead_minutes: 0,
+        });
+        let main = StationSeries {
+            station,
+            series: crate::fallback::approximate(None),
+        };
+        let overlay = readings.overlay(&main, "Measured").unwrap();
+        assert_eq!(overlay.station.name, "Measured");
+        // 2 m is 6.56 ft; less the offset and the reference level
+        let height = overlay.series.samples[0].tide_ft;
+        assert!((height - 4.56).abs() < 0.01, "{height}");
+    }
+}
@@ -0,0 +1,1136 @@
+//! # Panel Frame
+//!
+//! Composes the complete frame the e-ink panel shows — the configured layout,
+//! the OFFLINE (or "SURGE +1.3ft", or "DATA >2h OLD") banner and the update
+//! time, turned to match how the panel is mounted — and saves it as an image.
+//! `tide-tracker --png out.png` uses the same path as the hardware, so a
+//! preview on a laptop is pixel-for-pixel what the panel would get:
+//!
+//! ```no_run
+//! use embedded_graphics::prelude::Size;
+//! use tide_clock_lib::{config::Config, fallback, panel, stations::StationSeries};
+//!
+//! let config = Config::load();
+//! let displayed = vec![StationSeries {
+//!     station: config.station.clone(),
+//!     series: fallback::for_station(&config, &config.station, None),
+//! }];
+//! let (width, height) = config.display.hardware.panel.size();
+//! let frame = panel::compose_frame(&displayed, &config, Size::new(width, height), chrono::Utc::now());
+//! panel::save_image(&frame, "preview.png".as_ref()).unwrap();
+//! ```
+//!
+//! With `full_refresh_minutes` set, [`plan_frame`] decides between a full
+//! refresh and a partial one: in between full refreshes the last full chart
+//! is drawn again for the current time, so only the clock, the now marker and
+//! what hangs off them change, and just that region is sent to the panel.
+//! Partial updates leave faint ghosts behind, so after
+//! `max_partial_refreshes` of them in a row the next run refreshes fully
+//! again; the count is kept in the saved [`PanelState`].
+//!
+//! `--sixel` and `--kitty` write the same frame to the terminal instead, as
+//! DEC sixel graphics ([`write_sixel`]) or through the kitty graphics
+//! protocol ([`write_kitty`]), so the real pixels can be checked over SSH.
+//!
+//! The saved state also holds the series behind the frame, so after a
+//! restart `--daemon` puts the last frame back on the panel right away (see
+//! [`PanelState::restore`]) instead of waiting for the first fetch, which
+//! without a network can take the whole refresh budget.
+
+use crate::config::Config;
+use crate::eink_renderer::ChartStyle;
+use crate::epd4in2b_v2::DisplayBuffer;
+use crate::layout::{self, LayoutContext, OverlaySeries, SeriesMeta};
+use crate::stations::StationSeries;
+use crate::theme::InkColor;
+use crate::TideSeries;
+use chrono::{DateTime, Utc};
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::Text;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::warn;
+
+/// Why a frame could not be saved
+#[derive(Error, Debug)]
+pub enum ImageError {
+    #[error("writing image: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("encoding PNG: {0}")]
+    Png(#[from] png::EncodingError),
+}
+
+/// Draw everything a `panel`-sized display shows for `displayed` (main
+/// station first)
+///
+/// The result is in panel orientation, with `rotation` and `mirror` applied.
+/// `panel` comes from the driver ([`EpdDriver::size`](crate::epd::EpdDriver::size)),
+/// or from `[display.hardware] panel` when there is no hardware.
+pub fn compose_frame(
+    displayed: &[StationSeries],
+    config: &Config,
+    panel: Size,
+    now: DateTime<Utc>,
+) -> DisplayBuffer {
+    let main_station = &displayed[0];
+    let tide_series = &main_station.series;
+
+    // Drawn at 300x400 when the panel is mounted sideways and turned after
+    let rotation = config.display.rotation;
+    let (draw_width, draw_height) = rotation.drawing_size(panel.width, panel.height);
+    let mut buffer = DisplayBuffer::new(draw_width, draw_height);
+
+    // Draw through the Layout API so built-in and custom layouts share one path
+    let meta = SeriesMeta::for_station(config, &main_station.station, now);
+    let overlays: Vec<OverlaySeries<'_>> = displayed[1..]
+        .iter()
+        .map(|s| OverlaySeries {
+            label: &s.station.name,
+            series: &s.series,
+        })
+        .collect();
+    let style = ChartStyle::from(&config.display);
+    let chart = layout::builtin_with_style(&config.display.layout, &style).unwrap_or_else(|| {
+        warn!(
+            "Unknown layout '{}', using '{}'",
+            config.display.layout,
+            layout::DEFAULT_LAYOUT
+        );
+        layout::builtin_with_style(layout::DEFAULT_LAYOUT, &style).expect("default layout exists")
+    });
+    let ctx = LayoutContext {
+        series: tide_series,
+        meta: &meta,
+        theme: &config.theme,
+        size: buffer.size(),
+        overlays: &overlays,
+    };
+    if config.display.use_red {
+        layout::draw_tricolor(chart.as_ref(), &ctx, &mut buffer);
+    } else {
+        chart.draw(&ctx, &mut buffer).ok();
+    }
+
+    // OFFLINE, surge or stale data notice, in red when the theme and panel
+    // allow it
+    let overlay_font = config.theme.overlay_font.font();
+    let notice = if tide_series.is_offline() {
+        Some(tide_series.source.to_string()) // "OFFLINE MODEL"
+    } else if let Some(surge) = crate::surge::warning(main_station, &displayed[1..]) {
+        Some(surge.to_string()) // "SURGE +1.3ft"
+    } else {
+        stale_notice(tide_series, config.display.stale_after_hours, now)
+    };
+    let char_width = overlay_font.character_size.width as i32;
+    let banner_end = notice
+        .as_ref()
+        .map_or(0, |n| 10 + n.len() as i32 * char_width);
+    if let Some(notice) = notice {
+        let warning = config.theme.warning_color;
+        let banner = Text::new(
+            &notice,
+            Point::new(10, 24),
+            MonoTextStyle::new(overlay_font, warning.binary()),
+        );
+        if config.display.use_red && warning == InkColor::Red {
+            banner.draw(&mut buffer.red_plane()).ok();
+        } else {
+            banner.draw(&mut buffer).ok();
+        }
+    }
+
+    // Where the data came from and when it was obtained (in the station's
+    // timezone), 10px from the top right, or a line lower when a narrow
+    // panel would run it into the banner
+    let time_str = updated_label(main_station, config, now); // e.g. "NOAA 7/23 8:14PM"
+    let overlay_x = draw_width as i32 - 10 - (time_str.len() as i32 * char_width);
+    let mut overlay_y = 10 + 16;
+    if overlay_x < banner_end + char_width {
+        overlay_y += overlay_font.character_size.height as i32;
+    }
+    Text::new(
+        &time_str,
+        Point::new(overlay_x, overlay_y),
+        MonoTextStyle::new(overlay_font, config.theme.text_color.binary()),
+    )
+    .draw(&mut buffer)
+    .ok();
+
+    // Turn (and flip) the picture to match how the panel is mounted
+    buffer.transformed(rotation.quarter_turns(), config.display.mirror)
+}
+
+/// Source and time of the data behind `station`'s chart, e.g.
+/// "CACHE 7/23 8:14PM"
+///
+/// The time is when the series was fetched, so data read back from the
+/// cache doesn't look fresher than it is; series without a fetch time (the
+/// offline model, `data_file`) were made for `now`.
+pub fn updated_label(station: &StationSeries, config: &Config, now: DateTime<Utc>) -> String {
+    let series = &station.series;
+    let time = station
+        .station
+        .local_time(series.fetched_at.unwrap_or(now))
+        .format(config.display.time_format.date_time()); // "7/23 8:14PM" or "7/23 20:14"
+    format!("{} {}", series.source.label(), time)
+}
+
+/// "DATA >2h OLD" once `series` was fetched `stale_after_hours` or more
+/// before `now` (0 never), counting whole hours, or days past two
+pub fn stale_notice(
+    series: &TideSeries,
+    stale_after_hours: u32,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    let age = now - series.fetched_at?;
+    if stale_after_hours == 0 || age < chrono::Duration::hours(stale_after_hours as i64) {
+        return None;
+    }
+    Some(match age.num_hours() {
+        hours if hours < 48 => format!("DATA >{}h OLD", hours),
+        hours => format!("DATA >{}d OLD", hours / 24),
+    })
+}
+
+/// First byte of `tide_panel.bin`. Files from before the planes were
+/// compressed start with a timestamp varint, whose first byte has the
+/// continuation bit (0x80) set, so they are never mistaken for this format.
+/// Format 3 stores each series' `source` in place of its `offline` flag; an
+/// older state is ignored, costing one full refresh.
+const STATE_FORMAT: u8 = 3;
+
+/// What the panel showed after the last run, kept next to the tide cache
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PanelState {
+    /// When the panel last had a full refresh
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub drawn_at: DateTime<Utc>,
+    /// Stations the full refresh showed, main station first
+    pub station_ids: Vec<String>,
+    /// Layout the full refresh drew
+    pub layout: String,
+    /// Series drawn at the full refresh, main station first
+    pub series: Vec<TideSeries>,
+    /// The frame on the panel now, which may be from a later partial update;
+    /// the planes are deflated, mostly white paper shrinking to a few kB
+    width: u32,
+    height: u32,
+    black: Vec<u8>,
+    red: Vec<u8>,
+    /// Partial updates since the full refresh
+    pub partials_since_full: u32,
+}
+
+impl PanelState {
+    /// Where the state is kept: `tide_panel.bin` beside the cache file
+    pub fn path(config: &Config) -> PathBuf {
+        config.display.cache_path.with_file_name("tide_panel.bin")
+    }
+
+    /// Last saved state, `None` if there is none or it can't be read
+    pub fn load(path: &Path) -> Option<Self> {
+        let bytes = std::fs::read(path).ok()?;
+        match bytes.split_first() {
+            Some((&STATE_FORMAT, state)) => postcard::from_bytes(state).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let bytes = postcard::to_extend(self, vec![STATE_FORMAT]).map_err(std::io::Error::other)?;
+        std::fs::write(path, bytes)
+    }
+
+    /// Frame on the panel now
+    pub fn frame(&self) -> Option<DisplayBuffer> {
+        DisplayBuffer::from_planes(
+            self.width,
+            self.height,
+            inflate(&self.black)?,
+            inflate(&self.red)?,
+        )
+    }
+
+    fn set_frame(&mut self, frame: &DisplayBuffer) {
+        let size = frame.size();
+        (self.width, self.height) = (size.width, size.height);
+        self.black = deflate(frame.black_buffer());
+        self.red = deflate(frame.red_buffer());
+    }
+
+    /// The saved series with today's settings for their stations, to draw
+    /// the last frame again; `None` if a station is no longer configured
+    pub fn restore(&self, config: &Config) -> Option<Vec<StationSeries>> {
+        let stations = config.all_stations();
+        let restored: Option<Vec<StationSeries>> = self
+            .station_ids
+            .iter()
+            .zip(&self.series)
+            .map(|(id, series)| {
+                let station = stations.iter().find(|s| &s.id == id)?;
+                Some(StationSeries {
+                    station: (*station).clone(),
+                    series: series.clone(),
+                })
+            })
+            .collect();
+        restored.filter(|r| !r.is_empty() && r.len() == self.series.len())
+    }
+}
+
+fn deflate(plane: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    // Writing to a Vec can't fail
+    encoder.write_all(plane).expect("deflating into memory");
+    encoder.finish().expect("deflating into memory")
+}
+
+fn inflate(deflated: &[u8]) -> Option<Vec<u8>> {
+    let mut plane = Vec::new();
+    DeflateDecoder::new(deflated).read_to_end(&mut plane).ok()?;
+    Some(plane)
+}
+
+/// How much of the panel to update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refresh {
+    /// Everything, with the flashing full refresh
+    Full,
+    /// Only the frame's dirty regions, which hold what changed since the
+    /// last frame
+    Partial,
+    /// Nothing changed; leave the panel alone
+    Unchanged,
+}
+
+/// Frame to show, how to show it, and the state to save once it is shown
+pub struct PlannedFrame {
+    pub frame: DisplayBuffer,
+    pub refresh: Refresh,
+    pub state: PanelState,
+}
+
+/// Compose the frame for `displayed` and pick a full or partial refresh
+///
+/// A partial refresh is planned while the last full one (`last`) is less than
+/// `full_refresh_minutes` old, has been followed by fewer than
+/// `max_partial_refreshes` partial ones, and showed the same layout and
+/// stations with the same offline status. Its frame is the last full chart re-anchored to `now`
+/// rather than the freshly fetched series, so the curve stays put and only
+/// the time-dependent parts move; the fetch time and source shown are the
+/// fresh series'.
+///
+/// When a full refresh is due but the frame comes out identical to the one
+/// on the panel, and that one was shown by a full refresh too (so there is no
+/// partial-update ghosting to clear), nothing is sent: overnight the chart
+/// can go unchanged for several updates.
+pub fn plan_frame(
+    displayed: &[StationSeries],
+    config: &Config,
+    panel: Size,
+    now: DateTime<Utc>,
+    last: Option<PanelState>,
+) -> PlannedFrame {
+    let interval = chrono::Duration::minutes(config.display.full_refresh_minutes as i64);
+    let max_partials = config.display.max_partial_refreshes;
+    let reusable = last.as_ref().is_some_and(|last| {
+        let age = now - last.drawn_at;
+        age >= chrono::Duration::zero()
+            && age < interval
+            && (max_partials == 0 || last.partials_since_full < max_partials)
+            && Size::new(last.width, last.height) == panel
+            && last.layout == config.display.layout
+            && last
+                .station_ids
+                .iter()
+                .eq(displayed.iter().map(|s| &s.station.id))
+            && last
+                .series
+                .iter()
+                .zip(displayed)
+                .all(|(old, new)| old.is_offline() == new.series.is_offline())
+    });
+    if let Some(previous) = last.as_ref().filter(|_| reusable) {
+        let rebased: Option<Vec<StationSeries>> = previous
+            .series
+            .iter()
+            .zip(displayed)
+            .map(|(series, shown)| {
+                // The overlay still tells when and where this refresh got its data
+                Some(StationSeries {
+                    station: shown.station.clone(),
+                    series: TideSeries {
+                        fetched_at: shown.series.fetched_at,
+                        source: shown.series.source,
+                        ..series.rebased(now)?
+                    },
+                })
+            })
+            .collect();
+        if let (Some(mut frame), Some(rebased)) = (previous.frame(), rebased) {
+            // Drawn over what the panel shows, so only real changes are dirty
+            frame.copy_from(&compose_frame(&rebased, config, panel, now));
+            let mut last = last.expect("checked above");
+            let refresh = if frame.is_dirty() {
+                last.partials_since_full += 1;
+                Refresh::Partial
+            } else {
+                Refresh::Unchanged
+            };
+            last.set_frame(&frame);
+            return PlannedFrame {
+                frame,
+                refresh,
+                state: last,
+            };
+        }
+    }
+
+    let frame = compose_frame(displayed, config, panel, now);
+    let mut state = PanelState {
+        drawn_at: now,
+        station_ids: displayed.iter().map(|s| s.station.id.clone()).collect(),
+        layout: config.display.layout.clone(),
+        series: displayed.iter().map(|s| s.series.clone()).collect(),
+        width: 0,
+        height: 0,
+        black: Vec::new(),
+        red: Vec::new(),
+        partials_since_full: 0,
+    };
+    state.set_frame(&frame);
+    // Deflate is deterministic: the same planes compress to the same bytes
+    let unchanged = last.is_some_and(|last| {
+        last.partials_since_full == 0
+            && (last.width, last.height, &last.black, &last.red)
+                == (state.width, state.height, &state.black, &state.red)
+    });
+    PlannedFrame {
+        frame,
+        refresh: if unchanged {
+            Refresh::Unchanged
+        } else {
+            Refresh::Full
+        },
+        state,
+    }
+}
+
+/// Save `buffer` as an image: BMP for a `.bmp` path, PNG otherwise
+///
+/// White, black and red pixels come out as they would on the panel.
+pub fn save_image(buffer: &DisplayBuffer, path: &Path) -> Result<(), ImageError> {
+    let is_bmp = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("bmp"));
+    let mut out = BufWriter::new(File::create(path)?);
+    if is_bmp {
+        write_bmp(buffer, &mut out)?;
+    } else {
+        write_png(buffer, &mut out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Palette index of each pixel, row by row: 0 white, 1 black, 2 red
+pub(crate) fn palette_indices(buffer: &DisplayBuffer) -> impl Iterator<Item = u8> + '_ {
+    let size = buffer.size();
+    let bytes_per_row = size.width.div_ceil(8) as usize;
+    (0..size.height as usize).flat_map(move |y| {
+        (0..size.width as usize).map(move |x| {
+            let index = y * bytes_per_row + x / 8;
+            let mask = 0x80 >> (x % 8);
+            if buffer.red_buffer()[index] & mask != 0 {
+                2
+            } else if buffer.black_buffer()[index] & mask == 0 {
+                1
+            } else {
+                0
+            }
+        })
+    })
+}
+
+/// White, black and red, as RGB
+pub(crate) const PALETTE: [[u8; 3]; 3] = [[255, 255, 255], [0, 0, 0], [204, 0, 0]];
+
+/// RGBA bytes row by row, the layout of a browser canvas's `ImageData`
+pub fn to_rgba(buffer: &DisplayBuffer) -> Vec<u8> {
+    palette_indices(buffer)
+        .flat_map(|index| {
+            let [r, g, b] = PALETTE[index as usize];
+            [r, g, b, 255]
+        })
+        .collect()
+}
+
+/// 8-bit indexed PNG
+pub fn write_png(buffer: &DisplayBuffer, out: impl Write) -> Result<(), ImageError> {
+    let size = buffer.size();
+    let mut encoder = png::Encoder::new(out, size.width, size.height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(PALETTE.concat());
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&palette_indices(buffer).collect::<Vec<u8>>())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// DEC sixel graphics, for the terminals that show them (xterm with
+/// `-ti vt340`, foot, WezTerm, mlterm)
+///
+/// White is painted like the other colors, so the frame doesn't take on a
+/// dark terminal's background.
+pub fn write_sixel(buffer: &DisplayBuffer, mut out: impl Write) -> Result<(), ImageError> {
+    use std::fmt::Write as _;
+
+    let size = buffer.size();
+    let (width, height) = (size.width as usize, size.height as usize);
+    let indices: Vec<u8> = palette_indices(buffer).collect();
+
+    // Square pixels, then the palette with components in percent
+    let mut sixel = format!("\x1bPq\"1;1;{};{}", width, height);
+    let percent = |component: u8| component as u32 * 100 / 255;
+    for (index, [r, g, b]) in PALETTE.iter().enumerate() {
+        write!(
+            sixel,
+            "#{};2;{};{};{}",
+            index,
+            percent(*r),
+            percent(*g),
+            percent(*b)
+        )
+        .ok();
+    }
+    // Each band is six rows, drawn once per color: a character per column
+    // whose low bit is the band's top row
+    let mut columns = vec![0u8; width];
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        for color in 0..PALETTE.len() as u8 {
+            for (x, bits) in columns.iter_mut().enumerate() {
+                *bits = rows
+                    .clone()
+                    .filter(|y| indices[y * width + x] == color)
+                    .fold(0, |bits, y| bits | 1 << (y - band));
+            }
+            // Nothing past the last set column needs sending
+            let Some(end) = columns.iter().rposition(|&bits| bits != 0) else {
+                continue;
+            };
+            write!(sixel, "#{}", color).ok();
+            for run in columns[..=end].chunk_by(|a, b| a == b) {
+                let ch = (b'?' + run[0]) as char;
+                match run.len() {
+                    n if n > 3 => write!(sixel, "!{}{}", n, ch).ok(),
+                    n => (0..n).try_for_each(|_| sixel.write_char(ch)).ok(),
+                };
+            }
+            // Back to the start of the band for the next color
+            sixel.push('$');
+        }
+        sixel.push('-');
+    }
+    sixel.push_str("\x1b\\");
+    out.write_all(sixel.as_bytes())?;
+    Ok(())
+}
+
+/// The PNG through the kitty graphics protocol (kitty, WezTerm, Ghostty,
+/// Konsole), base64 in the protocol's chunks of at most 4096 bytes
+pub fn write_kitty(buffer: &DisplayBuffer, mut out: impl Write) -> Result<(), ImageError> {
+    let mut png = Vec::new();
+    write_png(buffer, &mut png)?;
+    let encoded = base64(&png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        match i {
+            // Format PNG, transmit and display
+            0 => write!(out, "\x1b_Gf=100,a=T,m={};", more)?,
+            _ => write!(out, "\x1b_Gm={};", more)?,
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let bits = group.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+            bits | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            match i <= group.len() {
+                true => encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+/// Uncompressed 24-bit BMP
+pub fn write_bmp(buffer: &DisplayBuffer, mut out: impl Write) -> Result<(), ImageError> {
+    let size = buffer.size();
+    let (width, height) = (size.width as usize, size.height as usize);
+    // Rows are padded to a multiple of four bytes
+    let row_len = (width * 3).div_ceil(4) * 4;
+    let image_len = row_len * height;
+    let file_len = 14 + 40 + image_len;
+
+    let mut header = Vec::with_capacity(54);
+    header.extend_from_slice(b"BM");
+    header.extend_from_slice(&(file_len as u32).to_le_bytes());
+    header.extend_from_slice(&[0; 4]);
+    header.extend_from_slice(&54u32.to_le_bytes()); // pixel data offset
+    header.extend_from_slice(&40u32.to_le_bytes()); // BITMAPINFOHEADER
+    header.extend_from_slice(&(width as i32).to_le_bytes());
+    header.extend_from_slice(&(height as i32).to_le_bytes()); // bottom-up rows
+    header.extend_from_slice(&1u16.to_le_bytes()); // planes
+    header.extend_from_slice(&24u16.to_le_bytes()); // bits per pixel
+    header.extend_from_slice(&[0; 4]); // no compression
+    header.extend_from_slice(&(image_len as u32).to_le_bytes());
+    header.extend_from_slice(&2835u32.to_le_bytes()); // 72 dpi
+    header.extend_from_slice(&2835u32.to_le_bytes());
+    header.extend_from_slice(&[0; 8]); // palette sizes
+    out.write_all(&header)?;
+
+    let indices: Vec<u8> = palette_indices(buffer).collect();
+    let mut row = vec![0u8; row_len];
+    for y in (0..height).rev() {
+        for (x, &index) in indices[y * width..(y + 1) * width].iter().enumerate() {
+            let [r, g, b] = PALETTE[index as usize];
+            row[x * 3..x * 3 + 3].copy_from_slice(&[b, g, r]);
+        }
+        out.write_all(&row)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd4in2b_v2::Color;
+    use crate::DataSource;
+    use chrono::TimeZone;
+
+    fn frame() -> DisplayBuffer {
+        let config = Config::default();
+        let displayed = vec![StationSeries {
+            station: config.station.clone(),
+            series: crate::fallback::approximate(None),
+        }];
+        compose_frame(&displayed, &config, PANEL, Utc::now())
+    }
+
+    /// The default 4.2" panel
+    const PANEL: Size = Size::new(400, 300);
+
+    #[test]
+    fn test_compose_frame_is_panel_sized_with_both_planes() {
+        let buffer = frame();
+        assert_eq!(buffer.size(), PANEL);
+        let indices: Vec<u8> = palette_indices(&buffer).collect();
+        assert_eq!(indices.len(), 400 * 300);
+        // Offline fallback: curve in black, OFFLINE banner and marker in red
+        assert!(indices.iter().filter(|&&i| i == 1).count() > 1000);
+        assert!(indices.iter().filter(|&&i| i == 2).count() > 50);
+    }
+
+    #[test]
+    fn test_compose_frame_fills_a_larger_panel() {
+        let mut config = Config::default();
+        let displayed = vec![StationSeries {
+            station: config.station.clone(),
+            series: crate::fallback::approximate(None),
+        }];
+        let large = Size::new(800, 480);
+        let buffer = compose_frame(&displayed, &config, large, Utc::now());
+        assert_eq!(buffer.size(), large);
+        // The chart stretches across: ink in the right half, not just the left
+        let inked_right = palette_indices(&buffer)
+            .enumerate()
+            .filter(|&(i, index)| i % 800 >= 600 && index != 0)
+            .count();
+        assert!(inked_right > 500, "{inked_right}");
+
+        config.display.rotation = crate::config::Rotation::Deg90;
+        let turned = compose_frame(&displayed, &config, large, Utc::now());
+        assert_eq!(turned.size(), large);
+    }
+
+    #[test]
+    fn test_stale_data_banner() {
+        let now = Utc::now();
+        let fetched = |hours_ago: i64| TideSeries {
+            source: DataSource::Noaa,
+            fetched_at: Some(now - chrono::Duration::minutes(hours_ago * 60 + 5)),
+            ..crate::fallback::approximate(Some(now))
+        };
+        assert_eq!(stale_notice(&fetched(1), 2, now), None);
+        assert_eq!(
+            stale_notice(&fetched(2), 2, now).as_deref(),
+            Some("DATA >2h OLD")
+        );
+        assert_eq!(
+            stale_notice(&fetched(75), 2, now).as_deref(),
+            Some("DATA >3d OLD")
+        );
+        assert_eq!(stale_notice(&fetched(75), 0, now), None);
+        // Model output has no fetch time
+        assert_eq!(
+            stale_notice(&crate::fallback::approximate(Some(now)), 2, now),
+            None
+        );
+
+        // The banner is red, like OFFLINE
+        let config = Config::default();
+        let red = |series: TideSeries| {
+            let displayed = vec![StationSeries {
+                station: config.station.clone(),
+                series,
+            }];
+            palette_indices(&compose_frame(&displayed, &config, PANEL, now))
+                .filter(|&i| i == 2)
+                .count()
+        };
+        assert!(red(fetched(3)) > red(fetched(1)) + 50);
+    }
+
+    #[test]
+    fn test_surge_banner() {
+        let now = Utc::now();
+        let mut config = Config::default();
+        config.station.surge_threshold_ft = Some(1.0);
+        let predicted = TideSeries {
+            source: DataSource::Noaa,
+            ..crate::fallback::approximate(Some(now))
+        };
+        // Observed water running `residual` above the predictions
+        let red = |residual: f32| {
+            let observed = predicted.relative_to(-residual);
+            let displayed = vec![
+                StationSeries {
+                    station: config.station.clone(),
+                    series: predicted.clone(),
+                },
+                StationSeries {
+                    station: crate::config::StationConfig {
+                        name: crate::surge::OBSERVED_LABEL.to_string(),
+                        ..config.station.clone()
+                    },
+                    series: TideSeries {
+                        source: DataSource::Sensor,
+                        ..observed
+                    },
+                },
+            ];
+            palette_indices(&compose_frame(&displayed, &config, PANEL, now))
+                .filter(|&i| i == 2)
+                .count()
+        };
+        assert!(red(1.3) > red(0.5) + 50);
+    }
+
+    #[test]
+    fn test_updated_label_shows_fetch_time_and_source() {
+        let mut config = Config::default();
+        config.station.timezone = Some(chrono_tz::America::New_York);
+        let now = Utc.with_ymd_and_hms(2025, 7, 24, 0, 14, 0).unwrap();
+        let label = |series: TideSeries| {
+            let station = StationSeries {
+                station: config.station.clone(),
+                series,
+            };
+            updated_label(&station, &config, now)
+        };
+
+        // Cached data keeps the time it was fetched, not the render time
+        let cached = TideSeries {
+            fetched_at: Some(now - chrono::Duration::hours(3)),
+            source: DataSource::Cache { age_mins: 180 },
+            ..crate::fallback::approximate(Some(now))
+        };
+        assert_eq!(label(cached), "CACHE 7/23 5:14PM");
+        let fresh = TideSeries {
+            fetched_at: Some(now),
+            source: DataSource::Noaa,
+            ..crate::fallback::approximate(Some(now))
+        };
+        assert_eq!(label(fresh), "NOAA 7/23 8:14PM");
+        // The model has no fetch time; it was computed for now
+        assert_eq!(
+            label(crate::fallback::approximate(Some(now))),
+            "FALLBACK 7/23 8:14PM"
+        );
+    }
+
+    #[test]
+    fn test_partial_refresh_between_full_ones() {
+        let mut config = Config::default();
+        config.display.full_refresh_minutes = 30;
+        let start = Utc::now();
+        let displayed = |offline: bool| {
+            let mut series = crate::fallback::approximate(None);
+            if !offline {
+                series.source = DataSource::Noaa;
+            }
+            series.base_time = Some(start);
+            vec![StationSeries {
+                station: config.station.clone(),
+                series,
+            }]
+        };
+        let first = plan_frame(&displayed(false), &config, PANEL, start, None);
+        assert_eq!(first.refresh, Refresh::Full);
+
+        // The state survives a round trip through its file
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tide_panel.bin");
+        first.state.save(&path).unwrap();
+        let load = || PanelState::load(&path);
+        let mut saved = load().unwrap().frame().unwrap();
+        saved.copy_from(&first.frame);
+        assert!(!saved.is_dirty());
+
+        // Ten minutes on, the marker and clock move but the curve stays
+        let later = start + chrono::Duration::minutes(10);
+        let mut second = plan_frame(&displayed(false), &config, PANEL, later, load());
+        assert_eq!(second.refresh, Refresh::Partial);
+        let regions = second.frame.take_dirty_regions();
+        // Far less than the whole panel is sent, in a few separate regions
+        assert!(regions.len() > 1);
+        let area: u32 = regions.iter().map(|r| r.size.width * r.size.height).sum();
+        assert!(area < PANEL.width * PANEL.height / 4, "{:?}", regions);
+        assert_eq!(second.state.drawn_at.timestamp(), start.timestamp());
+
+        // Drawing the same moment again changes nothing
+        let again = plan_frame(&displayed(false), &config, PANEL, start, load());
+        assert_eq!(again.refresh, Refresh::Unchanged);
+
+        // Too old, a change of offline status, or partial updates turned off
+        let late = start + chrono::Duration::minutes(30);
+        assert_eq!(
+            plan_frame(&displayed(false), &config, PANEL, late, load()).refresh,
+            Refresh::Full
+        );
+        assert_eq!(
+            plan_frame(&displayed(true), &config, PANEL, later, load()).refresh,
+            Refresh::Full
+        );
+        // So does another layout, or a different panel
+        let mut table = Config::default();
+        table.display.full_refresh_minutes = 30;
+        table.display.layout = "table".into();
+        assert_eq!(
+            plan_frame(&displayed(false), &table, PANEL, later, load()).refresh,
+            Refresh::Full
+        );
+        let large = Size::new(800, 480);
+        assert_eq!(
+            plan_frame(&displayed(false), &config, large, later, load()).refresh,
+            Refresh::Full
+        );
+        config.display.full_refresh_minutes = 0;
+        assert_eq!(
+            plan_frame(&displayed(false), &config, PANEL, later, load()).refresh,
+            Refresh::Full
+        );
+    }
+
+    #[test]
+    fn test_saved_state_is_compressed_and_restores_its_stations() {
+        let mut config = Config::default();
+        let mut other = config.station.clone();
+        other.id = "9410230".into();
+        other.name = "La Jolla".into();
+        config.stations.push(other);
+        let now = Utc::now();
+        let displayed: Vec<StationSeries> = config
+            .all_stations()
+            .into_iter()
+            .map(|station| StationSeries {
+                station: station.clone(),
+                series: crate::fallback::approximate(Some(now)),
+            })
+            .collect();
+        let plan = plan_frame(&displayed, &config, PANEL, now, None);
+        let raw = plan.frame.black_buffer().len() * 2;
+        assert!(plan.state.black.len() + plan.state.red.len() < raw / 10);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tide_panel.bin");
+        plan.state.save(&path).unwrap();
+        let state = PanelState::load(&path).unwrap();
+        let frame = state.frame().unwrap();
+        assert!(frame.black_buffer() == plan.frame.black_buffer());
+        assert!(frame.red_buffer() == plan.frame.red_buffer());
+
+        // The series come back with today's settings for their stations
+        config.stations[0].name = "Scripps Pier".into();
+        let restored = state.restore(&config).unwrap();
+        let names: Vec<&str> = restored.iter().map(|s| s.station.name.as_str()).collect();
+        assert_eq!(names, ["Portland, ME", "Scripps Pier"]);
+        assert_eq!(
+            restored[1].series.samples.len(),
+            displayed[1].series.samples.len()
+        );
+        // ...unless one of them is gone
+        config.stations.clear();
+        assert!(state.restore(&config).is_none());
+
+        // Files from before compression start with a timestamp varint
+        let mut old = postcard::to_allocvec(&now.timestamp()).unwrap();
+        old.extend([0; 64]);
+        std::fs::write(&path, old).unwrap();
+        assert!(PanelState::load(&path).is_none());
+    }
+
+    #[test]
+    fn test_identical_full_frame_is_skipped_unless_ghosted() {
+        // Every update is a full one, and the chart hasn't moved since
+        let config = Config::default();
+        assert_eq!(config.display.full_refresh_minutes, 0);
+        let start = Utc::now();
+        let mut series = crate::fallback::approximate(None);
+        series.base_time = Some(start);
+        let displayed = vec![StationSeries {
+            station: config.station.clone(),
+            series,
+        }];
+        let first = plan_frame(&displayed, &config, PANEL, start, None);
+        assert_eq!(first.refresh, Refresh::Full);
+        let again = plan_frame(&displayed, &config, PANEL, start, Some(first.state));
+        assert_eq!(again.refresh, Refresh::Unchanged);
+
+        // Something moved: refresh
+        let later = start + chrono::Duration::minutes(10);
+        let moved = plan_frame(&displayed, &config, PANEL, later, Some(again.state));
+        assert_eq!(moved.refresh, Refresh::Full);
+
+        // The same frame after partial updates still gets its full refresh,
+        // which clears their ghosting
+        let mut ghosted = moved.state;
+        ghosted.partials_since_full = 2;
+        let plan = plan_frame(&displayed, &config, PANEL, later, Some(ghosted));
+        assert_eq!(plan.refresh, Refresh::Full);
+    }
+
+    #[test]
+    fn test_full_refresh_after_max_partial_refreshes() {
+        let mut config = Config::default();
+        config.display.full_refresh_minutes = 600;
+        config.display.max_partial_refreshes = 3;
+        let start = Utc::now();
+        let mut series = crate::fallback::approximate(None);
+        series.base_time = Some(start);
+        let displayed = vec![StationSeries {
+            station: config.station.clone(),
+            series,
+        }];
+
+        // The count is carried from run to run in the saved state
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tide_panel.bin");
+        let mut refreshes = Vec::new();
+        let mut last = None;
+        for run in 0..6 {
+            let now = start + chrono::Duration::minutes(15 * run);
+            let plan = plan_frame(&displayed, &config, PANEL, now, last);
+            refreshes.push((plan.refresh, plan.state.partials_since_full));
+            plan.state.save(&path).unwrap();
+            last = PanelState::load(&path);
+        }
+        assert_eq!(
+            refreshes,
+            [
+                (Refresh::Full, 0),
+                (Refresh::Partial, 1),
+                (Refresh::Partial, 2),
+                (Refresh::Partial, 3),
+                (Refresh::Full, 0),
+                (Refresh::Partial, 1),
+            ]
+        );
+
+        // Frames that change nothing don't count
+        let unchanged = plan_frame(&displayed, &config, PANEL, start, None);
+        let again = plan_frame(&displayed, &config, PANEL, start, Some(unchanged.state));
+        assert_eq!(again.refresh, Refresh::Unchanged);
+        assert_eq!(again.state.partials_since_full, 0);
+    }
+
+    #[test]
+    fn test_png_and_bmp_round_trip() {
+        let mut buffer = DisplayBuffer::new(10, 3);
+        buffer.set_pixel(0, 0, Color::Black);
+        buffer.set_pixel(9, 2, Color::Red);
+
+        let mut png_bytes = Vec::new();
+        write_png(&buffer, &mut png_bytes).unwrap();
+        let decoder = png::Decoder::new(png_bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (10, 3));
+        assert_eq!(pixels[0], 1);
+        assert_eq!(pixels[29], 2);
+        assert!(pixels[1..29].iter().all(|&i| i == 0));
+
+        let mut bmp = Vec::new();
+        write_bmp(&buffer, &mut bmp).unwrap();
+        // 10 pixels * 3 bytes padded to 32 per row
+        assert_eq!(bmp.len(), 54 + 32 * 3);
+        assert_eq!(&bmp[..2], b"BM");
+        // Bottom row first: its last pixel is red (stored BGR)
+        assert_eq!(&bmp[54 + 27..54 + 30], &[0, 0, 204]);
+        // Top row last: its first pixel is black
+        assert_eq!(&bmp[54 + 64..54 + 67], &[0, 0, 0]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frame.BMP");
+        save_image(&buffer, &path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), bmp);
+
+        let rgba = to_rgba(&buffer);
+        assert_eq!(rgba.len(), 10 * 3 * 4);
+        assert_eq!(&rgba[..8], &[0, 0, 0, 255, 255, 255, 255, 255]);
+        assert_eq!(&rgba[29 * 4..], &[204, 0, 0, 255]);
+    }
+
+    /// Palette index of each pixel in a sixel stream as `write_sixel` writes it
+    fn decode_sixel(sixel: &str, width: usize, height: usize) -> Vec<u8> {
+        fn number(chars: &mut std::iter::Peekable<std::str::Chars>) -> usize {
+            let mut n = 0;
+            while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                n = n * 10 + digit as usize;
+                chars.next();
+            }
+            n
+        }
+        // Skip `count` numbers separated by semicolons
+        fn skip(chars: &mut std::iter::Peekable<std::str::Chars>, count: usize) {
+            for _ in 0..count {
+                chars.next_if_eq(&';');
+                number(chars);
+            }
+        }
+
+        let body = sixel
+            .strip_prefix("\x1bPq")
+            .unwrap()
+            .strip_suffix("\x1b\\")
+            .unwrap();
+        let mut pixels = vec![u8::MAX; width * height];
+        let (mut x, mut band, mut color) = (0, 0, 0);
+        let mut chars = body.chars().peekable();
+        while let Some(c) = chars.next() {
+            let (count, bits) = match c {
+                '"' => {
+                    assert_eq!(number(&mut chars), 1);
+                    skip(&mut chars, 3);
+                    continue;
+                }
+                '#' => {
+                    color = number(&mut chars) as u8;
+                    if chars.peek() == Some(&';') {
+                        skip(&mut chars, 4);
+                    }
+                    continue;
+                }
+                '$' => {
+                    x = 0;
+                    continue;
+                }
+                '-' => {
+                    (x, band) = (0, band + 6);
+                    continue;
+                }
+                '!' => (number(&mut chars), chars.next().unwrap() as u8 - b'?'),
+                c => (1, c as u8 - b'?'),
+            };
+            for _ in 0..count {
+                for row in (0..6).filter(|row| bits & 1 << row != 0) {
+                    assert_eq!(pixels[(band + row) * width + x], u8::MAX, "painted twice");
+                    pixels[(band + row) * width + x] = color;
+                }
+                x += 1;
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_sixel_paints_every_pixel_once() {
+        let buffer = frame();
+        let sixel = {
+            let mut out = Vec::new();
+            write_sixel(&buffer, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert!(sixel.starts_with("\x1bPq\"1;1;400;300#0;2;100;100;100#1;2;0;0;0#2;2;80;0;0"));
+        assert_eq!(sixel.matches('-').count(), 50);
+        let indices: Vec<u8> = palette_indices(&buffer).collect();
+        assert!(decode_sixel(&sixel, 400, 300) == indices);
+        // Long runs of white are sent as repeats
+        assert!(sixel.len() < 400 * 50 * 3 / 4, "{} bytes", sixel.len());
+
+        // A last band of fewer than six rows, and runs up to the right edge
+        let mut small = DisplayBuffer::new(13, 8);
+        for x in 9..13 {
+            small.set_pixel(x, 7, Color::Red);
+        }
+        small.set_pixel(0, 0, Color::Black);
+        let mut out = Vec::new();
+        write_sixel(&small, &mut out).unwrap();
+        let sixel = String::from_utf8(out).unwrap();
+        let indices: Vec<u8> = palette_indices(&small).collect();
+        assert!(decode_sixel(&sixel, 13, 8) == indices);
+    }
+
+    #[test]
+    fn test_kitty_sends_the_png_in_chunks() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(&[0xFB, 0xFF]), "+/8=");
+        assert_eq!(base64(b""), "");
+
+        let buffer = frame();
+        let mut png = Vec::new();
+        write_png(&buffer, &mut png).unwrap();
+        let mut out = Vec::new();
+        write_kitty(&buffer, &mut out).unwrap();
+        let kitty = String::from_utf8(out).unwrap();
+
+        let chunks: Vec<&str> = kitty
+            .strip_suffix("\x1b\\")
+            .unwrap()
+            .split("\x1b\\")
+            .collect();
+        assert!(chunks[0].starts_with("\x1b_Gf=100,a=T,m=1;"));
+        assert!(chunks.last().unwrap().starts_with("\x1b_Gm=0;"));
+        let payload: String = chunks
+            .iter()
+            .map(|chunk| {
+                let (_, data) = chunk.split_once(';').unwrap();
+                assert!(data.len() <= 4096);
+                data
+            })
+            .collect();
+        assert_eq!(payload, base64(&png));
+    }
+}
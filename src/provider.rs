@@ -0,0 +1,272 @@
+//! # Data Providers and Racing
+//!
+//! A [`Provider`] is any source that can produce a [`TideSeries`] for a
+//! station. When a station lists more than one in `providers`, they are raced:
+//! the first (preferred) provider starts immediately, the others only after
+//! `race_head_start_ms`, and whichever succeeds first wins. A fast NOAA
+//! response therefore always wins, while a slow one is hidden behind a
+//! consistent render time instead of eating the whole fetch deadline.
+//!
+//! ```toml
+//! [station]
+//! providers = ["noaa", "harmonic"]
+//! race_head_start_ms = 3000
+//! ```
+
+use crate::config::StationConfig;
+use crate::file_source::{self, FileSourceError};
+use crate::grid::SeriesSpec;
+use crate::harmonics::HarmonicsError;
+#[cfg(feature = "net")]
+use crate::tide_data;
+use crate::tide_data::TideError;
+use crate::TideSeries;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::{error, warn};
+
+/// Boxed future returned by [`Provider::fetch`]
+pub type FetchFuture = Pin<Box<dyn Future<Output = Result<TideSeries, TideError>> + Send>>;
+
+/// A source of tide predictions
+pub trait Provider: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Produce a series for `station` of the shape `spec` asks for
+    fn fetch(&self, station: StationConfig, spec: SeriesSpec) -> FetchFuture;
+}
+
+/// Providers selectable from configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// NOAA CO-OPS predictions API
+    Noaa,
+    /// Built-in offline model (always succeeds, marked offline)
+    Model,
+    /// Offline prediction from `[station.harmonics]` (marked offline)
+    Harmonic,
+    /// Predictions replayed from `data_file`, see [`crate::file_source`]
+    File,
+}
+
+impl ProviderKind {
+    /// Name as written in `providers`, e.g. "noaa"
+    pub fn name(self) -> &'static str {
+        match self {
+            ProviderKind::Noaa => "noaa",
+            ProviderKind::Model => "model",
+            ProviderKind::Harmonic => "harmonic",
+            ProviderKind::File => "file",
+        }
+    }
+
+    pub fn build(self) -> Arc<dyn Provider> {
+        match self {
+            ProviderKind::Noaa => Arc::new(NoaaProvider),
+            ProviderKind::Model => Arc::new(ModelProvider),
+            ProviderKind::Harmonic => Arc::new(HarmonicProvider),
+            ProviderKind::File => Arc::new(FileProvider),
+        }
+    }
+}
+
+/// NOAA CO-OPS predictions
+pub struct NoaaProvider;
+
+impl Provider for NoaaProvider {
+    fn name(&self) -> &'static str {
+        "noaa"
+    }
+
+    #[cfg(feature = "net")]
+    fn fetch(&self, station: StationConfig, spec: SeriesSpec) -> FetchFuture {
+        Box::pin(async move { tide_data::scrape_noaa(&station, spec).await })
+    }
+
+    #[cfg(not(feature = "net"))]
+    fn fetch(&self, _station: StationConfig, _spec: SeriesSpec) -> FetchFuture {
+        Box::pin(async { Err(TideError::Unsupported("net")) })
+    }
+}
+
+/// The offline approximation from [`crate::fallback`], with the station's model constants
+pub struct ModelProvider;
+
+impl Provider for ModelProvider {
+    fn name(&self) -> &'static str {
+        "model"
+    }
+
+    fn fetch(&self, station: StationConfig, spec: SeriesSpec) -> FetchFuture {
+        let model = station.model.unwrap_or_default();
+        Box::pin(async move { Ok(model.series_in(spec)) })
+    }
+}
+
+/// The station's harmonic constants, see [`crate::harmonics`]
+pub struct HarmonicProvider;
+
+impl Provider for HarmonicProvider {
+    fn name(&self) -> &'static str {
+        "harmonic"
+    }
+
+    fn fetch(&self, station: StationConfig, spec: SeriesSpec) -> FetchFuture {
+        Box::pin(async move {
+            let harmonics = station.harmonics.ok_or(HarmonicsError::NotConfigured)?;
+            Ok(harmonics.load()?.series_in(spec))
+        })
+    }
+}
+
+/// Predictions from the station's `data_file`, see [`crate::file_source`]
+pub struct FileProvider;
+
+impl Provider for FileProvider {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn fetch(&self, station: StationConfig, spec: SeriesSpec) -> FetchFuture {
+        Box::pin(async move {
+            let path = station
+                .data_file
+                .as_ref()
+                .ok_or(FileSourceError::NotConfigured)?;
+            file_source::series_in(path, &station, spec)
+        })
+    }
+}
+
+/// Race `providers` for `station` over `spec`; the first gets `head_start`
+/// before the rest
+///
+/// If the preferred provider fails before its head start is up, the others
+/// start right away. Returns the name of the winning provider with its series,
+/// or the error of the last provider to fail when none succeed. Losing
+/// requests are aborted.
+pub async fn race(
+    providers: &[Arc<dyn Provider>],
+    station: &StationConfig,
+    spec: SeriesSpec,
+    head_start: Duration,
+) -> Result<(&'static str, TideSeries), TideError> {
+    let (preferred_failed, failed_rx) = watch::channel(false);
+    let mut tasks = JoinSet::new();
+    for (i, provider) in providers.iter().enumerate() {
+        let provider = Arc::clone(provider);
+        let station = station.clone();
+        let mut failed_rx = failed_rx.clone();
+        tasks.spawn(async move {
+            if i > 0 {
+                tokio::select! {
+                    _ = tokio::time::sleep(head_start) => {}
+                    _ = failed_rx.wait_for(|failed| *failed) => {}
+                }
+            }
+            (i, provider.name(), provider.fetch(station, spec).await)
+        });
+    }
+
+    let mut last_error = TideError::Scrape;
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((_, name, Ok(series))) => return Ok((name, series)),
+            Ok((i, name, Err(e))) => {
+                warn!("Provider {} failed: {}", name, e);
+                if i == 0 {
+                    let _ = preferred_failed.send(true);
+                }
+                last_error = e;
+            }
+            // A panicking provider just drops out of the race
+            Err(e) => error!("Provider task failed: {}", e),
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fallback;
+
+    /// Answers after `delay` with a constant series, or fails
+    struct Fake {
+        name: &'static str,
+        delay: Duration,
+        ok: bool,
+    }
+
+    impl Provider for Fake {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn fetch(&self, _station: StationConfig, _spec: SeriesSpec) -> FetchFuture {
+            let (delay, ok) = (self.delay, self.ok);
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                if ok {
+                    Ok(fallback::approximate(None))
+                } else {
+                    Err(TideError::Scrape)
+                }
+            })
+        }
+    }
+
+    fn spec() -> SeriesSpec {
+        SeriesSpec::standard(chrono::Utc::now())
+    }
+
+    fn fake(name: &'static str, delay_ms: u64, ok: bool) -> Arc<dyn Provider> {
+        Arc::new(Fake {
+            name,
+            delay: Duration::from_millis(delay_ms),
+            ok,
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_preferred_wins_within_head_start() {
+        let station = Config::default().station;
+        let providers = [fake("slowish", 500, true), fake("instant", 0, true)];
+        let (name, _) = race(&providers, &station, spec(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(name, "slowish");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_secondary_wins_when_preferred_is_slow_or_fails() {
+        let station = Config::default().station;
+        let slow = [fake("hung", 60_000, true), fake("backup", 100, true)];
+        let (name, _) = race(&slow, &station, spec(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(name, "backup");
+
+        // A failed preferred provider cuts the head start short
+        let failing = [fake("broken", 0, false), fake("backup", 0, true)];
+        let started = tokio::time::Instant::now();
+        let (name, _) = race(&failing, &station, spec(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(name, "backup");
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let none = [fake("a", 0, false), fake("b", 0, false)];
+        assert!(race(&none, &station, spec(), Duration::from_secs(1))
+            .await
+            .is_err());
+    }
+}
@@ -1,95 +1,299 @@
+//! # Renderers
+//!
+//! A [`TideRenderer`] turns a series and the configuration into a finished
+//! [`Frame`]: text for the terminal ([`AsciiRenderer`]) or a bitmap for the
+//! panel ([`EinkTideRenderer`](crate::eink_renderer::EinkTideRenderer)).
+//! Both place the curve with the same [`ChartScale`] and take heights, times
+//! and labels from the same [`SeriesMeta`], so the two outputs agree.
+
+#[cfg(feature = "ascii")]
+use crate::display_units::{display_to_tide, format_axis_value, tide_to_display};
+use crate::epd4in2b_v2::DisplayBuffer;
+#[cfg(feature = "ascii")]
+use crate::layout::SeriesMeta;
 use crate::{config::Config, TideSeries};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "ascii")]
+use std::fmt::Write;
 
-/// Calculate the tide range and bounds for display
-/// Returns (min, max) in the appropriate coordinate system based on config
-fn calculate_display_bounds(series: &TideSeries, config: &Config) -> (f32, f32) {
-    let (min_tide_mllw, max_tide_mllw) = series
-        .samples
-        .iter()
-        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), sample| {
-            (min.min(sample.tide_ft), max.max(sample.tide_ft))
-        });
-
-    if config.station.show_msl {
-        // Convert to MSL for display (-5 to +5 feet typically)
-        (
-            min_tide_mllw - config.station.msl_offset,
-            max_tide_mllw - config.station.msl_offset,
-        )
-    } else {
-        // Use raw MLLW values (0-9 feet typically)
-        (min_tide_mllw, max_tide_mllw)
-    }
+/// Finished output of a [`TideRenderer`]
+pub enum Frame {
+    /// Lines of text for a terminal
+    Text(String),
+    /// Black and red planes for the e-ink panel
+    Bitmap(DisplayBuffer),
 }
 
-/// Convert a tide height to display value based on configuration
-fn tide_to_display(tide_ft_mllw: f32, config: &Config) -> f32 {
-    if config.station.show_msl {
-        tide_ft_mllw - config.station.msl_offset
-    } else {
-        tide_ft_mllw
-    }
+/// A backend that draws a whole tide chart from a series and the configuration
+pub trait TideRenderer {
+    /// The chart as of `now`, which times and the update stamp are given
+    /// relative to; pinned in tests, the system clock otherwise
+    fn render(&self, series: &TideSeries, config: &Config, now: DateTime<Utc>) -> Frame;
 }
 
-/// Convert tide height from MSL (Mean Sea Level) back to MLLW (Mean Lower Low Water)
-fn msl_to_mllw(tide_ft_msl: f32, msl_offset: f32) -> f32 {
-    tide_ft_msl + msl_offset
+/// Height and time ranges a chart maps onto its drawing area
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartScale {
+    /// Lowest height drawn
+    pub min_ft: f32,
+    /// Highest height drawn
+    pub max_ft: f32,
+    /// `mins_rel` of the first sample
+    pub first_mins: i16,
+    /// `mins_rel` of the last sample
+    pub last_mins: i16,
 }
 
-/// Format a tide height for display based on configuration
-fn format_display_height(tide_ft_mllw: f32, config: &Config) -> String {
-    let display_value = tide_to_display(tide_ft_mllw, config);
+impl ChartScale {
+    /// Ranges fitting `series` and every one of `overlays` (0 to 10 ft, ±12
+    /// hours when `series` is empty)
+    pub fn new<'a>(
+        series: &'a TideSeries,
+        overlays: impl IntoIterator<Item = &'a TideSeries>,
+    ) -> Self {
+        let (Some(first), Some(last)) = (series.samples.first(), series.samples.last()) else {
+            return Self {
+                min_ft: 0.0,
+                max_ft: 10.0,
+                first_mins: -(crate::grid::HALF_WIDTH_MINS as i16),
+                last_mins: crate::grid::HALF_WIDTH_MINS as i16,
+            };
+        };
+        let all: Vec<&TideSeries> = overlays
+            .into_iter()
+            .chain(std::iter::once(series))
+            .collect();
+        let min_ft = all
+            .iter()
+            .filter_map(|s| s.min_height())
+            .fold(f32::INFINITY, f32::min);
+        let max_ft = all
+            .iter()
+            .filter_map(|s| s.max_height())
+            .fold(f32::NEG_INFINITY, f32::max);
+        Self {
+            min_ft,
+            max_ft,
+            first_mins: first.mins_rel,
+            last_mins: last.mins_rel,
+        }
+    }
 
-    if config.station.show_msl {
-        // MSL format with +/- signs
-        format_tide_height(display_value)
-    } else {
-        // MLLW format without signs (always positive)
-        if display_value.fract() == 0.0 {
-            format!("{:.0}", display_value)
+    /// Span of heights, never zero so a flat series still scales
+    pub fn height_range(&self) -> f32 {
+        let range = self.max_ft - self.min_ft;
+        if range > 0.0 {
+            range
         } else {
-            format!("{:.1}", display_value)
+            1.0
         }
     }
+
+    /// Where `tide_ft` falls in the height range: 0 at the lowest, 1 at the highest
+    pub fn height_fraction(&self, tide_ft: f32) -> f32 {
+        (tide_ft - self.min_ft) / self.height_range()
+    }
+
+    /// Height at `fraction` of the way up the range
+    pub fn height_at(&self, fraction: f32) -> f32 {
+        self.min_ft + fraction * self.height_range()
+    }
+
+    /// Where `mins_rel` falls in the time range: 0 at the first sample, 1 at the last
+    pub fn time_fraction(&self, mins_rel: f32) -> f32 {
+        let range = (self.last_mins as i32 - self.first_mins as i32).max(1) as f32;
+        (mins_rel - self.first_mins as f32) / range
+    }
 }
 
-/// Format a tide height for display with appropriate precision and sign
-fn format_tide_height(tide_ft_msl: f32) -> String {
-    if tide_ft_msl == 0.0 {
-        " 0 ".to_string()
-    } else if tide_ft_msl > 0.0 {
-        if tide_ft_msl.fract() == 0.0 {
-            format!("+{:.0}", tide_ft_msl)
+/// Terminal chart: one column per sample (or every few for wide windows)
+#[cfg(feature = "ascii")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AsciiRenderer;
+
+/// How much of the terminal a chart may use
+#[cfg(feature = "ascii")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalStyle {
+    /// One dot per sample in the default colors
+    Plain,
+    /// A braille curve at 2×4 dots per cell, with ANSI colors: blue curve,
+    /// red "now" line, yellow OFFLINE MODEL
+    Enhanced,
+}
+
+#[cfg(feature = "ascii")]
+impl TerminalStyle {
+    /// [`Enhanced`](TerminalStyle::Enhanced) when stdout is a terminal that
+    /// can show it: `TERM` set and not `dumb`, no `NO_COLOR`, and a UTF-8
+    /// locale
+    pub fn detect() -> Self {
+        use std::io::IsTerminal;
+        Self::from_env(std::io::stdout().is_terminal(), |name| {
+            std::env::var(name).ok()
+        })
+    }
+
+    fn from_env(is_terminal: bool, var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        // The first of these that is set decides the character set
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .filter_map(&var)
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let utf8 = locale.contains("utf-8") || locale.contains("utf8");
+        if is_terminal && !term.is_empty() && term != "dumb" && var("NO_COLOR").is_none() && utf8 {
+            Self::Enhanced
         } else {
-            format!("+{:.1}", tide_ft_msl)
+            Self::Plain
         }
-    } else if tide_ft_msl.fract() == 0.0 {
-        format!("{:.0}", tide_ft_msl)
-    } else {
-        format!("{:.1}", tide_ft_msl)
     }
 }
 
-/// Render tide data to ASCII terminal.
-pub fn draw_ascii(series: &TideSeries) {
-    let config = Config::load();
+/// Print [`render_terminal`]'s chart to stdout
+#[cfg(feature = "ascii")]
+pub fn draw_ascii(series: &TideSeries, config: &Config, now: DateTime<Utc>, style: TerminalStyle) {
+    print!("{}", render_terminal(series, config, now, style));
+}
+
+#[cfg(feature = "ascii")]
+impl TideRenderer for AsciiRenderer {
+    fn render(&self, series: &TideSeries, config: &Config, now: DateTime<Utc>) -> Frame {
+        Frame::Text(render_terminal(series, config, now, TerminalStyle::Plain))
+    }
+}
+
+/// Terminal chart of `series` as of now, one `\n`-terminated line per row
+///
+/// The same text `--stdout` prints, for anything else that shows it (an
+/// HTTP response, an SSH banner).
+#[cfg(feature = "ascii")]
+pub fn render_ascii(series: &TideSeries, config: &Config) -> String {
+    render_terminal(series, config, Utc::now(), TerminalStyle::Plain)
+}
+
+/// [`render_ascii`] as of `now` in `style`; the enhanced chart has ANSI
+/// escapes in it
+#[cfg(feature = "ascii")]
+pub fn render_terminal(
+    series: &TideSeries,
+    config: &Config,
+    now: DateTime<Utc>,
+    style: TerminalStyle,
+) -> String {
+    ascii_chart(series, config, now, style)
+}
+
+#[cfg(feature = "ascii")]
+const BLUE: &str = "\x1b[34m";
+#[cfg(feature = "ascii")]
+const RED: &str = "\x1b[31m";
+#[cfg(feature = "ascii")]
+const YELLOW: &str = "\x1b[33m";
+#[cfg(feature = "ascii")]
+const RESET: &str = "\x1b[0m";
+
+/// Braille cells of a curve through `heights` (one per column, as fractions
+/// of the height range), `rows` cells tall, with a line down column `now`
+///
+/// Each cell holds 2×4 dots: the curve is interpolated between columns for
+/// the second dot of each cell, and consecutive dots are joined vertically
+/// so steep stretches stay unbroken.
+#[cfg(feature = "ascii")]
+fn braille_cells(heights: &[f32], rows: usize, now: usize) -> Vec<Vec<char>> {
+    // Dot (column, row) within a cell, per the Unicode braille layout
+    const BITS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+    let dot_rows = rows * 4;
+    let mut cells = vec![vec![0u8; heights.len()]; rows];
+    let mut set = |x: usize, y: usize| cells[y / 4][x / 2] |= BITS[x % 2][y % 4];
+
+    let mut previous: Option<usize> = None;
+    for x in 0..heights.len() * 2 {
+        let at = x as f32 / 2.0;
+        let left = heights[at as usize];
+        let right = heights.get(at as usize + 1).copied().unwrap_or(left);
+        let fraction = left + (right - left) * at.fract();
+        let y = ((1.0 - fraction) * (dot_rows - 1) as f32).round() as usize;
+        let y = y.min(dot_rows - 1);
+        let (top, bottom) = match previous {
+            Some(p) => (p.min(y), p.max(y)),
+            None => (y, y),
+        };
+        for dot in top..=bottom {
+            set(x, dot);
+        }
+        previous = Some(y);
+    }
+    if now < heights.len() {
+        for dot in 0..dot_rows {
+            set(now * 2, dot);
+        }
+    }
+    cells
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|bits| match bits {
+                    0 => ' ',
+                    bits => char::from_u32(0x2800 + bits as u32).unwrap_or(' '),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// `row` with the chart area past `axis` colored: blue, and red in column
+/// `now` of the chart
+#[cfg(feature = "ascii")]
+fn paint_row(row: &[char], axis: usize, now: usize) -> String {
+    let mut line: String = row[..axis].iter().collect();
+    let mut ink = None;
+    for (column, &ch) in row[axis..].iter().enumerate() {
+        let wanted = match ch {
+            ' ' => ink,
+            _ if column == now => Some(RED),
+            _ => Some(BLUE),
+        };
+        if wanted != ink {
+            line.push_str(wanted.unwrap_or(RESET));
+            ink = wanted;
+        }
+        line.push(ch);
+    }
+    if ink.is_some() {
+        line.push_str(RESET);
+    }
+    line
+}
+
+#[cfg(feature = "ascii")]
+fn ascii_chart(
+    series: &TideSeries,
+    config: &Config,
+    now: chrono::DateTime<chrono::Utc>,
+    style: TerminalStyle,
+) -> String {
+    let mut out = String::new();
     const ROWS: usize = 24;
     const Y_AXIS_WIDTH: usize = 5; // Space for Y-axis labels
-    let sample_count = series.samples.len();
+    const MAX_COLUMNS: usize = 145; // One column per sample of the standard window
 
-    // Calculate tide range using configurable display mode
-    let (min_display, max_display) = calculate_display_bounds(series, &config);
+    // Wider windows plot every 2nd (48h) or 3rd (72h) sample to stay as wide
+    let stride = series.samples.len().div_ceil(MAX_COLUMNS).max(1);
+    let columns: Vec<_> = series.samples.iter().step_by(stride).collect();
+    let sample_count = columns.len();
 
-    // Get raw MLLW bounds for row calculation (always use MLLW for internal positioning)
-    let (min_tide_mllw, max_tide_mllw) = series
-        .samples
-        .iter()
-        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), sample| {
-            (min.min(sample.tide_ft), max.max(sample.tide_ft))
-        });
+    let meta = SeriesMeta::from_config(config, now);
+
+    // Stored bounds for row calculation, and the same as shown for labels
+    let scale = ChartScale::new(series, []);
+    let min_display = tide_to_display(scale.min_ft, meta.msl_offset);
+    let max_display = tide_to_display(scale.max_ft, meta.msl_offset);
 
     let tide_to_row = |tide_ft: f32| {
-        let normalized = (tide_ft - min_tide_mllw) / (max_tide_mllw - min_tide_mllw);
+        let normalized = scale.height_fraction(tide_ft);
         ((1.0 - normalized) * (ROWS as f32 - 1.0)).round() as usize
     };
 
@@ -98,19 +302,16 @@ pub fn draw_ascii(series: &TideSeries) {
     // Add Y-axis labels using configured display mode
     let display_range = max_display - min_display;
     let tide_step = if display_range > 4.0 { 1.0 } else { 0.5 };
+    let decimals = if tide_step < 1.0 { 1 } else { 0 };
     let mut current_display = (min_display / tide_step).floor() * tide_step;
 
     while current_display <= max_display {
-        // Convert display value back to MLLW for Y positioning
-        let tide_mllw = if config.station.show_msl {
-            msl_to_mllw(current_display, config.station.msl_offset)
-        } else {
-            current_display
-        };
+        // Convert display value back to the stored height for Y positioning
+        let tide_mllw = display_to_tide(current_display, meta.msl_offset);
         let row = tide_to_row(tide_mllw);
 
         if row < ROWS {
-            let label = format_display_height(tide_mllw, &config);
+            let label = format_axis_value(current_display, meta.msl_offset.is_some(), decimals);
             // Ensure label fits in Y_AXIS_WIDTH - 1 (leave room for axis line)
             let padded_label = format!("{:<width$}", label, width = Y_AXIS_WIDTH - 1);
 
@@ -124,28 +325,98 @@ pub fn draw_ascii(series: &TideSeries) {
         current_display += tide_step;
     }
 
-    // Plot tide data with "now" marker at the center of the chart
-    // The center represents "now" regardless of exact sample timing
-    let center_index = series.samples.len() / 2;
+    // Plot tide data with the "now" marker on the column closest to it,
+    // which is only the middle one for a complete, symmetric series
+    let center_index = columns
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, s)| s.mins_rel.unsigned_abs())
+        .map_or(0, |(i, _)| i);
 
-    for (column, sample) in series.samples.iter().enumerate() {
-        let row = tide_to_row(sample.tide_ft);
-        let grid_column = column + Y_AXIS_WIDTH;
+    let enhanced = style == TerminalStyle::Enhanced;
+    if enhanced {
+        let heights: Vec<f32> = columns
+            .iter()
+            .map(|s| scale.height_fraction(s.tide_ft))
+            .collect();
+        let cells = braille_cells(&heights, ROWS, center_index);
+        for (row, cells) in grid.iter_mut().zip(cells) {
+            row[Y_AXIS_WIDTH..].copy_from_slice(&cells);
+        }
+    } else {
+        for (column, sample) in columns.iter().enumerate() {
+            let row = tide_to_row(sample.tide_ft);
+            let grid_column = column + Y_AXIS_WIDTH;
 
-        if column == center_index {
-            // Mark "now" with a prominent X (center of the time window)
-            grid[row][grid_column] = 'X';
-        } else {
-            grid[row][grid_column] = '•';
+            if column == center_index {
+                // Mark "now" with a prominent X (center of the time window)
+                grid[row][grid_column] = 'X';
+            } else if sample.modeled {
+                // Filled in from the model where NOAA's data had a gap
+                grid[row][grid_column] = '·';
+            } else {
+                grid[row][grid_column] = '•';
+            }
         }
     }
 
-    if series.offline {
-        println!("⚠ OFFLINE\n");
+    if series.is_offline() {
+        match enhanced {
+            true => writeln!(out, "{}⚠ {}{}\n", YELLOW, series.source, RESET),
+            false => writeln!(out, "⚠ {}\n", series.source),
+        }
+        .ok();
+    } else if let crate::DataSource::Cache { .. } = series.source {
+        writeln!(out, "{}\n", series.source).ok(); // "CACHED 25m"
+    }
+    if series.is_backfilled() {
+        match enhanced {
+            true => writeln!(out, "{}⚠ PARTLY MODELED{}\n", YELLOW, RESET),
+            false => writeln!(out, "⚠ PARTLY MODELED (·)\n"),
+        }
+        .ok();
+    }
+
+    if config.theme.countdown_header {
+        if let Some(next) = series.next_extreme(meta.generated_at) {
+            writeln!(out, "{}\n", next.countdown(&meta)).ok();
+        }
+    }
+    let arrow = match series.trend() {
+        crate::Trend::Rising => '▲',
+        crate::Trend::Falling => '▼',
+    };
+    let mut status = format!(
+        "{}  {} {}",
+        meta.station_name,
+        arrow,
+        series.trend().label()
+    );
+    if let Some(fraction) = meta.spring_neap {
+        status.push_str("  ");
+        status.push_str(&crate::lunar::range_label(fraction));
+    }
+    writeln!(out, "{}", status).ok();
+    let flood = meta
+        .flood_threshold_ft
+        .and_then(|threshold| series.first_above(threshold, i16::MAX));
+    if let Some(sample) = flood {
+        match sample.mins_rel {
+            m if m <= 0 => writeln!(out, "⚠ FLOOD RISK NOW"),
+            m => writeln!(out, "⚠ FLOOD RISK {}", meta.clock_at(m as i64)),
+        }
+        .ok();
     }
 
+    // Datum the Y-axis values are referenced to
+    writeln!(out, "{}", meta.axis_label()).ok();
+
     for row in grid {
-        println!("{}", row.into_iter().collect::<String>());
+        match enhanced {
+            true => writeln!(out, "{}", paint_row(&row, Y_AXIS_WIDTH, center_index)),
+            false => writeln!(out, "{}", row.into_iter().collect::<String>()),
+        }
+        .ok();
     }
 
     // Time markers below the chart
@@ -153,15 +424,271 @@ pub fn draw_ascii(series: &TideSeries) {
     let time_markers: String = (0..sample_count)
         .map(|i| if i % 6 == 0 { '|' } else { ' ' })
         .collect();
-    println!("{}{}", padding, time_markers);
+    writeln!(out, "{}{}", padding, time_markers).ok();
 
     // Time labels - properly center the "Now" label with the X marker
-    let data_center = sample_count / 2; // Center position in the data area (where X is placed)
     let now_text = "Now";
     let now_offset = now_text.len() / 2; // Offset to center the "Now" text
-    let left_width = data_center.saturating_sub(now_offset);
-    let left_part = format!("{:<width$}", "-12h", width = left_width);
-    let right_width = sample_count - data_center - now_text.len() + now_offset;
-    let right_part = format!("{:>width$}", "+12h", width = right_width);
-    println!("{}{}{}{}", padding, left_part, now_text, right_part);
+    let left_width = center_index.saturating_sub(now_offset);
+    let (start_label, end_label) = series.window_labels();
+    let left_part = format!("{:<width$}", start_label, width = left_width);
+    let right_width = (sample_count + now_offset).saturating_sub(center_index + now_text.len());
+    let right_part = format!("{:>width$}", end_label, width = right_width);
+    writeln!(out, "{}{}{}{}", padding, left_part, now_text, right_part).ok();
+
+    if config.theme.extremes_footer {
+        writeln!(out).ok();
+        for line in crate::extremes::footer_lines(series, &meta) {
+            writeln!(out, "{}{}", padding, line).ok();
+        }
+    }
+
+    if let Some(moon) = &meta.moon {
+        writeln!(out).ok();
+        writeln!(
+            out,
+            "{}{} {}, {:.0} days",
+            padding,
+            moon.symbol(),
+            moon.phase_name(),
+            moon.age_days
+        )
+        .ok();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sample;
+
+    fn series(heights: &[f32]) -> TideSeries {
+        let mut series = crate::fallback::approximate(None);
+        series.samples = heights
+            .iter()
+            .enumerate()
+            .map(|(i, &tide_ft)| Sample {
+                mins_rel: (i as i16 - heights.len() as i16 / 2) * 10,
+                tide_ft,
+                modeled: false,
+            })
+            .collect();
+        series
+    }
+
+    #[test]
+    fn test_chart_scale() {
+        let main = series(&[2.0, 5.0, 3.0]);
+        let other = series(&[1.0, 4.0, 9.0]);
+        let scale = ChartScale::new(&main, [&other]);
+        assert_eq!((scale.min_ft, scale.max_ft), (1.0, 9.0));
+        assert_eq!((scale.first_mins, scale.last_mins), (-10, 10));
+        assert_eq!(scale.height_fraction(5.0), 0.5);
+        assert_eq!(scale.height_at(0.25), 3.0);
+        assert_eq!(scale.time_fraction(0.0), 0.5);
+
+        // A flat series still maps onto the range instead of dividing by zero
+        let flat = ChartScale::new(&series(&[4.0, 4.0]), []);
+        assert_eq!(flat.height_fraction(4.0), 0.0);
+        let empty = ChartScale::new(&series(&[]), []);
+        assert_eq!((empty.min_ft, empty.max_ft), (0.0, 10.0));
+    }
+
+    /// Compare against `tests/fixtures/ascii/<name>`, or rewrite it when
+    /// `TIDE_UPDATE_SNAPSHOTS` is set
+    #[cfg(feature = "ascii")]
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/ascii")
+            .join(name);
+        if std::env::var_os("TIDE_UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            actual == expected,
+            "{} changed (TIDE_UPDATE_SNAPSHOTS=1 to accept):\n{}",
+            path.display(),
+            actual
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "ascii")]
+    fn test_ascii_snapshots() {
+        use crate::DataSource;
+        use chrono::TimeZone;
+
+        let now = chrono::Utc.with_ymd_and_hms(2024, 6, 21, 15, 0, 0).unwrap();
+        let mut config = Config::default();
+        config.station.timezone = Some(chrono_tz::America::New_York);
+        let mut series = crate::fallback::approximate(Some(now));
+        series.source = DataSource::Noaa;
+        let plain = ascii_chart(&series, &config, now, TerminalStyle::Plain);
+        assert_snapshot("default.txt", &plain);
+        let enhanced = ascii_chart(&series, &config, now, TerminalStyle::Enhanced);
+        assert_snapshot("braille.txt", &enhanced);
+
+        // Offline, relative to MSL with a flood warning, over a 48 hour window
+        config.station.show_msl = true;
+        config.station.flood_threshold_ft = Some(7.0);
+        let mut wide = crate::fallback::approximate(Some(now));
+        wide.extremes = None;
+        wide.samples = (-144..=144)
+            .map(|i| crate::Sample {
+                mins_rel: i * 10,
+                tide_ft: 5.0 + 4.5 * ((i + 20) as f32 * std::f32::consts::TAU / 74.5).cos(),
+                modeled: false,
+            })
+            .collect();
+        let annotated = ascii_chart(&wide, &config, now, TerminalStyle::Plain);
+        assert_snapshot("annotated.txt", &annotated);
+    }
+
+    #[test]
+    #[cfg(feature = "ascii")]
+    fn test_enhanced_style_needs_a_capable_terminal() {
+        use TerminalStyle::{Enhanced, Plain};
+
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let capable = env(&[("TERM", "xterm-256color"), ("LANG", "en_US.UTF-8")]);
+        assert_eq!(TerminalStyle::from_env(true, capable), Enhanced);
+        // Piped into a file or another program
+        assert_eq!(TerminalStyle::from_env(false, capable), Plain);
+
+        for vars in [
+            &[("TERM", "dumb"), ("LANG", "en_US.UTF-8")][..],
+            &[("LANG", "en_US.UTF-8")],
+            &[("TERM", "xterm"), ("LANG", "C")],
+            &[
+                ("TERM", "xterm"),
+                ("LANG", "en_US.UTF-8"),
+                ("NO_COLOR", "1"),
+            ],
+            // LC_ALL outranks LANG
+            &[
+                ("TERM", "xterm"),
+                ("LANG", "en_US.UTF-8"),
+                ("LC_ALL", "POSIX"),
+            ],
+        ] {
+            assert_eq!(TerminalStyle::from_env(true, env(vars)), Plain);
+        }
+        let lowercase = env(&[("TERM", "linux"), ("LC_CTYPE", "C.utf8"), ("LANG", "C")]);
+        assert_eq!(TerminalStyle::from_env(true, lowercase), Enhanced);
+    }
+
+    #[test]
+    #[cfg(feature = "ascii")]
+    fn test_modeled_stretch_is_drawn_lighter() {
+        use crate::DataSource;
+
+        let config = Config::default();
+        let now = chrono::Utc::now();
+        let mut series = crate::fallback::approximate(Some(now));
+        series.source = DataSource::Noaa;
+        let plain = ascii_chart(&series, &config, now, TerminalStyle::Plain);
+        assert!(!plain.contains('·') && !plain.contains("PARTLY MODELED"));
+
+        // NOAA's data ending two hours ahead, the model taking over
+        for sample in series.samples.iter_mut().filter(|s| s.mins_rel > 120) {
+            sample.modeled = true;
+        }
+        let plain = ascii_chart(&series, &config, now, TerminalStyle::Plain);
+        assert!(plain.starts_with("⚠ PARTLY MODELED (·)"), "{plain}");
+        let dots = |glyph| {
+            plain
+                .lines()
+                .map(|l| l.matches(glyph).count())
+                .sum::<usize>()
+        };
+        assert!(dots('·') > 0 && dots('•') > dots('·'), "{plain}");
+    }
+
+    #[test]
+    #[cfg(feature = "ascii")]
+    fn test_now_marker_follows_mins_rel() {
+        let config = Config::default();
+        let now = chrono::Utc::now();
+        // A fetch cut short five hours ahead: "now" is column 72 of 103
+        let mut series = crate::fallback::approximate(Some(now));
+        series.samples.retain(|s| s.mins_rel <= 300);
+        let chart = ascii_chart(&series, &config, now, TerminalStyle::Plain);
+        // In characters, the axis labels being multi-byte
+        let column =
+            |line: &str, pattern: &str| line[..line.find(pattern).unwrap()].chars().count();
+        let marker = chart.lines().find(|line| line.contains('X')).unwrap();
+        assert_eq!(column(marker, "X"), 5 + 72);
+        let labels = chart.lines().find(|line| line.contains("Now")).unwrap();
+        assert_eq!(column(labels, "Now"), 5 + 71);
+    }
+
+    #[test]
+    #[cfg(feature = "ascii")]
+    fn test_braille_curve_is_continuous() {
+        // A falling then rising curve over three rows of cells
+        let heights = [1.0, 0.0, 1.0];
+        let cells = braille_cells(&heights, 3, usize::MAX);
+        let dots = |row: usize, column: usize| (cells[row][column] as u32 - 0x2800).count_ones();
+        // Every dot row is crossed going down and again coming up
+        let total: u32 = (0..3)
+            .flat_map(|row| (0..3).map(move |column| (row, column)))
+            .filter(|&(row, column)| cells[row][column] != ' ')
+            .map(|(row, column)| dots(row, column))
+            .sum();
+        assert!(total >= 24, "{:?}", cells);
+        // Starting in the top left dot
+        assert_eq!((cells[0][0] as u32 - 0x2800) & 0x01, 0x01);
+
+        // The "now" column is a full line of dots
+        let cells = braille_cells(&[0.5, 0.5, 0.5], 2, 1);
+        assert!(cells
+            .iter()
+            .all(|row| (row[1] as u32 - 0x2800) & 0x47 == 0x47));
+    }
+
+    #[test]
+    #[cfg(feature = "ascii")]
+    fn test_backends_render_frames() {
+        let mut config = Config::default();
+        config.station.show_msl = true;
+        let series = crate::fallback::approximate(None);
+
+        let now = chrono::Utc::now();
+        let Frame::Text(text) = AsciiRenderer.render(&series, &config, now) else {
+            panic!("ASCII renders text");
+        };
+        assert!(text.contains("Now"));
+        assert!(text.lines().any(|l| l == "ft MSL"));
+        // Heights relative to MSL are signed
+        assert!(text.lines().any(|l| l.starts_with('+')));
+
+        let Frame::Bitmap(buffer) =
+            crate::eink_renderer::EinkTideRenderer::new().render(&series, &config, now)
+        else {
+            panic!("e-ink renders a bitmap");
+        };
+        let inked = |plane: &[u8], ones: bool| -> u32 {
+            plane
+                .iter()
+                .map(|b| {
+                    if ones {
+                        b.count_ones()
+                    } else {
+                        b.count_zeros()
+                    }
+                })
+                .sum()
+        };
+        assert!(inked(buffer.black_buffer(), false) > 1000);
+        assert!(inked(buffer.red_buffer(), true) > 0);
+    }
 }
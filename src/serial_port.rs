@@ -0,0 +1,37 @@
+//! Serial port for the NMEA depth sensor (`[sensor] device`), opened raw at
+//! the configured baud rate
+
+use anyhow::Context;
+use nix::sys::termios::{self, BaudRate, SetArg, SpecialCharacterIndices};
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Open `device` for reading at `baud`, 8N1 without flow control
+pub fn open(device: &Path, baud: u32) -> anyhow::Result<File> {
+    let speed = match baud {
+        4800 => BaudRate::B4800,
+        9600 => BaudRate::B9600,
+        19200 => BaudRate::B19200,
+        38400 => BaudRate::B38400,
+        57600 => BaudRate::B57600,
+        115200 => BaudRate::B115200,
+        other => anyhow::bail!("Unsupported baud rate {}", other),
+    };
+    // Not as the controlling terminal, so a hangup can't stop the daemon
+    let port = OpenOptions::new()
+        .read(true)
+        .custom_flags(nix::libc::O_NOCTTY)
+        .open(device)
+        .with_context(|| format!("Cannot open {}", device.display()))?;
+
+    let mut settings = termios::tcgetattr(&port)
+        .with_context(|| format!("{} is not a serial port", device.display()))?;
+    termios::cfmakeraw(&mut settings);
+    termios::cfsetspeed(&mut settings, speed)?;
+    // Block until at least a byte arrives
+    settings.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+    settings.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+    termios::tcsetattr(&port, SetArg::TCSANOW, &settings)?;
+    Ok(port)
+}
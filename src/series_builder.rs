@@ -0,0 +1,332 @@
+//! # Series Builder
+//!
+//! Checked construction of a [`TideSeries`]. Everything downstream assumes
+//! the samples are in time order, evenly spaced, and include "now"
+//! (`mins_rel == 0`): the chart marker, [`TideSeries::height_now`], the
+//! trend. A series produced by a provider goes through [`TideSeriesBuilder`]
+//! so a mistake there is a [`SeriesError`] at the source rather than a
+//! misdrawn chart later.
+//!
+//! ```
+//! use tide_clock_lib::{DataSource, Sample, TideSeries};
+//!
+//! let samples = (-1..=1)
+//!     .map(|i| Sample { mins_rel: i * 10, tide_ft: 2.0 + i as f32, modeled: false })
+//!     .collect();
+//! let series = TideSeries::builder(samples)
+//!     .source(DataSource::Fallback)
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(series.height_now(), Some(2.0));
+//! ```
+//!
+//! Plausibility of the heights themselves is [`validate`](crate::validate)'s
+//! job.
+
+use crate::extremes::{self, Extreme};
+use crate::grid::SeriesSpec;
+use crate::{DataSource, Sample, TideSeries};
+use alloc::vec::Vec;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+/// Why a series could not be built
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SeriesError {
+    #[error("no samples")]
+    Empty,
+    #[error("sample {0} is not after the one before it")]
+    OutOfOrder(usize),
+    #[error("sample {index} is {gap} minutes after the one before it, not {expected}")]
+    UnevenSpacing {
+        index: usize,
+        gap: i16,
+        expected: i16,
+    },
+    #[error("no sample at now (mins_rel 0)")]
+    NoNowSample,
+}
+
+/// Longest stretch between two points that [`TideSeriesBuilder::backfilled`]
+/// still interpolates rather than filling from the model
+pub const MAX_GAP_MINS: i64 = 120;
+
+/// Builder for a [`TideSeries`], see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct TideSeriesBuilder {
+    series: TideSeries,
+}
+
+impl TideSeries {
+    /// Start building a series from `samples`
+    pub fn builder(samples: Vec<Sample>) -> TideSeriesBuilder {
+        TideSeriesBuilder {
+            series: TideSeries {
+                samples,
+                source: DataSource::Noaa,
+                base_time: None,
+                extremes: None,
+                fetched_at: None,
+            },
+        }
+    }
+}
+
+impl TideSeriesBuilder {
+    /// Builder for the display grid `spec` asks for through predicted
+    /// `(time, height)` points, with the highs and lows after its center
+    ///
+    /// The points may come in any order but should be evenly spaced, at
+    /// least hourly. Returns `None` unless they cover most of the window
+    /// (20 of the 24 hours of the standard one).
+    pub fn from_points(
+        mut points: Vec<(DateTime<Utc>, f32)>,
+        spec: impl Into<SeriesSpec>,
+    ) -> Option<Self> {
+        let spec = spec.into();
+        let window = spec.window;
+        let required = (window.half_width.num_hours() * 2 * 5 / 6) as usize;
+        if hours_covered(&points, &spec) < required {
+            return None;
+        }
+
+        let samples = spec.resample(&points)?;
+        points.sort_by_key(|&(t, _)| t);
+        let extremes = extremes::find(&points)
+            .into_iter()
+            .filter(|e| e.at > window.center)
+            .collect();
+        Some(
+            TideSeries::builder(samples)
+                .base_time(window.center)
+                .extremes(extremes),
+        )
+    }
+
+    /// Builder like [`from_points`](Self::from_points) for points covering
+    /// only part of the window, with `model` filling in the rest
+    ///
+    /// `model` must be on the grid `spec` asks for, e.g. the fallback or
+    /// harmonic series for the same window. Samples before the first point,
+    /// after the last, or inside a gap of more than [`MAX_GAP_MINS`] between
+    /// two points take the model's height, shifted to meet the real data at
+    /// the edges of the gap, and are marked [`modeled`](Sample::modeled).
+    /// Highs and lows are left for the samples to give. Returns `None`
+    /// unless the points cover at least a quarter of the window (6 of the 24
+    /// hours of the standard one), or if `model` is on another grid.
+    pub fn backfilled(
+        mut points: Vec<(DateTime<Utc>, f32)>,
+        spec: impl Into<SeriesSpec>,
+        model: &TideSeries,
+    ) -> Option<Self> {
+        let spec = spec.into();
+        let window = spec.window;
+        let required = (window.half_width.num_hours() * 2 / 4).max(1) as usize;
+        if hours_covered(&points, &spec) < required {
+            return None;
+        }
+
+        let mut samples = spec.resample(&points)?;
+        let aligned = samples.len() == model.samples.len()
+            && samples
+                .iter()
+                .zip(&model.samples)
+                .all(|(s, m)| s.mins_rel == m.mins_rel);
+        if !aligned {
+            return None;
+        }
+
+        points.sort_by_key(|&(t, _)| t);
+        points.dedup_by_key(|&mut (t, _)| t);
+        let mins_of = |t: DateTime<Utc>| (t - window.center).num_seconds() as f32 / 60.0;
+        // Real minus model at each point, the shift that makes them meet
+        let anchors: Vec<(f32, f32)> = points
+            .iter()
+            .filter_map(|&(t, ft)| {
+                let mins = mins_of(t);
+                Some((mins, ft - model.height_at(mins)?))
+            })
+            .collect();
+        let (first, last) = (anchors.first()?, anchors.last()?);
+
+        for (sample, fill) in samples.iter_mut().zip(&model.samples) {
+            let mins = sample.mins_rel as f32;
+            let offset = if mins < first.0 {
+                first.1
+            } else if mins > last.0 {
+                last.1
+            } else {
+                let after = anchors.partition_point(|a| a.0 < mins);
+                let (a, b) = (anchors[after.saturating_sub(1)], anchors[after]);
+                if b.0 == mins || b.0 - a.0 <= MAX_GAP_MINS as f32 {
+                    continue;
+                }
+                a.1 + (b.1 - a.1) * (mins - a.0) / (b.0 - a.0)
+            };
+            sample.tide_ft = fill.tide_ft + offset;
+            sample.modeled = true;
+        }
+        Some(TideSeries::builder(samples).base_time(window.center))
+    }
+
+    /// Where the samples came from, [`DataSource::Noaa`] unless set
+    pub fn source(mut self, source: DataSource) -> Self {
+        self.series.source = source;
+        self
+    }
+
+    /// Instant `mins_rel == 0` refers to
+    pub fn base_time(mut self, base_time: DateTime<Utc>) -> Self {
+        self.series.base_time = Some(base_time);
+        self
+    }
+
+    /// Highs and lows the provider knows of
+    pub fn extremes(mut self, extremes: Vec<Extreme>) -> Self {
+        self.series.extremes = Some(extremes);
+        self
+    }
+
+    /// When the data was fetched
+    pub fn fetched_at(mut self, fetched_at: DateTime<Utc>) -> Self {
+        self.series.fetched_at = Some(fetched_at);
+        self
+    }
+
+    /// Check the samples and return the series
+    ///
+    /// The spacing expected is that of the first two samples.
+    pub fn build(self) -> Result<TideSeries, SeriesError> {
+        let samples = &self.series.samples;
+        if samples.is_empty() {
+            return Err(SeriesError::Empty);
+        }
+        let expected = samples
+            .get(1)
+            .map_or(0, |s| s.mins_rel - samples[0].mins_rel);
+        for (i, w) in samples.windows(2).enumerate() {
+            let gap = w[1].mins_rel - w[0].mins_rel;
+            if gap <= 0 {
+                return Err(SeriesError::OutOfOrder(i + 1));
+            }
+            if gap != expected {
+                return Err(SeriesError::UnevenSpacing {
+                    index: i + 1,
+                    gap,
+                    expected,
+                });
+            }
+        }
+        if !samples.iter().any(|s| s.mins_rel == 0) {
+            return Err(SeriesError::NoNowSample);
+        }
+        Ok(self.series)
+    }
+}
+
+/// Distinct clock hours of `spec`'s window that `points` fall in
+fn hours_covered(points: &[(DateTime<Utc>, f32)], spec: &SeriesSpec) -> usize {
+    let mut hours: Vec<i64> = points
+        .iter()
+        .filter(|(t, _)| spec.window.contains(*t))
+        .map(|(t, _)| t.timestamp().div_euclid(3600))
+        .collect();
+    hours.sort_unstable();
+    hours.dedup();
+    hours.len()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn samples(mins: &[i16]) -> Vec<Sample> {
+        mins.iter()
+            .map(|&mins_rel| Sample {
+                mins_rel,
+                tide_ft: 1.0,
+                modeled: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_checks_samples() {
+        let now = Utc::now();
+        let series = TideSeries::builder(samples(&[-10, 0, 10]))
+            .base_time(now)
+            .fetched_at(now)
+            .extremes(Vec::new())
+            .build()
+            .unwrap();
+        assert_eq!(series.base_time, Some(now));
+        assert!(!series.is_offline());
+        assert!(series.extremes.is_some());
+        assert!(TideSeries::builder(samples(&[0])).build().is_ok());
+
+        let build = |mins: &[i16]| TideSeries::builder(samples(mins)).build().unwrap_err();
+        assert_eq!(build(&[]), SeriesError::Empty);
+        assert_eq!(build(&[-10, 0, 0]), SeriesError::OutOfOrder(2));
+        assert_eq!(build(&[0, 10, 5]), SeriesError::OutOfOrder(2));
+        assert_eq!(
+            build(&[-10, 0, 20]),
+            SeriesError::UnevenSpacing {
+                index: 2,
+                gap: 20,
+                expected: 10
+            }
+        );
+        assert_eq!(build(&[-15, -5, 5]), SeriesError::NoNowSample);
+    }
+
+    #[test]
+    fn test_backfilled_fills_gaps_from_the_model() {
+        use chrono::{Duration, TimeZone};
+
+        let center = Utc.with_ymd_and_hms(2025, 7, 1, 16, 0, 0).unwrap();
+        let spec = SeriesSpec::standard(center);
+        let tide = |mins: f32| 5.0 + 3.0 * (mins / 745.2 * core::f32::consts::TAU).cos();
+        // A model a foot low, which the real data should pull back up
+        let model = TideSeries::builder(spec.generate(|m| tide(m as f32) - 1.0))
+            .build()
+            .unwrap();
+        // Hourly from 12 hours ago to 3 hours ago, then 2 to 4 hours ahead
+        let points: Vec<_> = (-12..=-3)
+            .chain(2..=4)
+            .map(|h| (center + Duration::hours(h), tide(h as f32 * 60.0)))
+            .collect();
+        assert!(TideSeriesBuilder::from_points(points.clone(), spec).is_none());
+
+        let series = TideSeriesBuilder::backfilled(points.clone(), spec, &model)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(series.samples.len(), 145);
+        assert_eq!(series.base_time, Some(center));
+        assert!(series.extremes.is_none());
+        assert!(series.is_backfilled());
+        for sample in &series.samples {
+            let modeled = (-179..120).contains(&sample.mins_rel) || sample.mins_rel > 240;
+            assert_eq!(sample.modeled, modeled, "{sample:?}");
+            // The model shifted onto the real curve, or hourly points
+            // interpolated (flattening the peaks a little, and bending next
+            // to a gap)
+            let error = (sample.tide_ft - tide(sample.mins_rel as f32)).abs();
+            assert!(error < if modeled { 0.01 } else { 0.2 }, "{sample:?}");
+        }
+        // Cutting and resampling keep the marks
+        assert!(series.window(-60, 60).samples.iter().all(|s| s.modeled));
+        assert!(series.resample(30).unwrap().samples[..10]
+            .iter()
+            .all(|s| !s.modeled));
+
+        // Too little real data, or a model on another grid
+        assert!(TideSeriesBuilder::backfilled(points[..4].to_vec(), spec, &model).is_none());
+        let coarse = TideSeries::builder(
+            SeriesSpec::new(spec.window, Duration::minutes(30)).generate(|_| 1.0),
+        )
+        .build()
+        .unwrap();
+        assert!(TideSeriesBuilder::backfilled(points, spec, &coarse).is_none());
+    }
+}
@@ -0,0 +1,118 @@
+//! # First-Run Setup Page
+//!
+//! Shown on the panel when the device boots without a `tide-config.toml`,
+//! instead of a chart for a station the user never picked. It tells the user
+//! how to reach the Pi (hostname and IP) and which panel was detected, and
+//! points them at the installer that writes a real configuration.
+
+use embedded_graphics::mono_font::ascii::{FONT_10X20, FONT_6X10, FONT_8X13};
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Line, PrimitiveStyle};
+use embedded_graphics::text::{Baseline, Text};
+use std::fs;
+use std::net::{IpAddr, UdpSocket};
+
+/// Facts about the device shown on the setup page
+#[derive(Debug, Clone)]
+pub struct SetupInfo {
+    /// Kernel hostname, e.g. "raspberrypi"
+    pub hostname: String,
+    /// Address of the interface used for the default route, if any
+    pub ip: Option<IpAddr>,
+    /// Human-readable description of the detected panel
+    pub panel: String,
+}
+
+impl SetupInfo {
+    /// Gather hostname and IP from the running system
+    pub fn detect(panel: impl Into<String>) -> Self {
+        Self {
+            hostname: detect_hostname(),
+            ip: detect_ip(),
+            panel: panel.into(),
+        }
+    }
+}
+
+fn detect_hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|h| h.trim().to_string())
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "raspberrypi".to_string())
+}
+
+/// Local address of the default route; connecting a UDP socket sends nothing
+fn detect_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("1.1.1.1:80").ok()?;
+    socket.local_addr().ok().map(|a| a.ip())
+}
+
+/// Draw the setup page into `target`
+pub fn draw_setup_page<D>(target: &mut D, info: &SetupInfo) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let width = target.bounding_box().size.width as i32;
+    let title = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+    let body = MonoTextStyle::new(&FONT_8X13, BinaryColor::On);
+    let small = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+
+    let mut y = 10;
+    let mut line = |text: &str, style: MonoTextStyle<'_, BinaryColor>, gap: i32| {
+        let r = Text::with_baseline(text, Point::new(10, y), style, Baseline::Top).draw(target);
+        y += style.font.character_size.height as i32 + gap;
+        r.map(|_| ())
+    };
+
+    line("TIDE TRACKER SETUP", title, 6)?;
+    line("No tide-config.toml found.", body, 10)?;
+
+    line("This device", body, 2)?;
+    line(&format!("  Host:  {}", info.hostname), body, 2)?;
+    let ip = info
+        .ip
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "no network".to_string());
+    line(&format!("  IP:    {}", ip), body, 2)?;
+    line(&format!("  Panel: {}", info.panel), body, 10)?;
+
+    line("Next steps", body, 2)?;
+    line(&format!("  1. ssh {}.local", info.hostname), body, 2)?;
+    line("  2. cd tide-tracker-rs && ./install.sh", body, 2)?;
+    line("  3. Edit /etc/tide-tracker/tide-config.toml", body, 2)?;
+    line("     (station id, pins), then wait for refresh", small, 0)?;
+
+    Line::new(Point::new(10, 34), Point::new(width - 10, 34))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
+        .draw(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd4in2b_v2::DisplayBuffer;
+
+    #[test]
+    fn test_setup_page_renders() {
+        let info = SetupInfo {
+            hostname: "tidepi".to_string(),
+            ip: Some("192.168.1.42".parse().unwrap()),
+            panel: "4.2in B/W/Red V2".to_string(),
+        };
+        let mut buffer = DisplayBuffer::new(400, 300);
+        draw_setup_page(&mut buffer, &info).unwrap();
+        let black: u32 = buffer.black_buffer().iter().map(|b| b.count_zeros()).sum();
+        assert!(black > 500);
+    }
+
+    #[test]
+    fn test_detect_never_panics() {
+        let info = SetupInfo::detect("test");
+        assert!(!info.hostname.is_empty());
+    }
+}
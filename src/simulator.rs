@@ -0,0 +1,92 @@
+//! # Desktop Preview
+//!
+//! With `--features simulator`, `tide-tracker --simulator` shows the panel
+//! frame in an SDL window instead of on the e-ink display, and draws it again
+//! on every refresh. It is for working on layouts without a Pi or a panel;
+//! SDL2 must be installed (`apt install libsdl2-dev` or `brew install sdl2`).
+//!
+//! The window gets exactly what [`panel::compose_frame`](crate::panel::compose_frame)
+//! would send to the hardware, red plane, rotation and all.
+
+use crate::epd4in2b_v2::DisplayBuffer;
+use crate::panel::{palette_indices, PALETTE};
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::*;
+use embedded_graphics_simulator::sdl2::Keycode;
+use embedded_graphics_simulator::{
+    OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent, Window,
+};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// What the user asked for while the window was waiting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewAction {
+    /// The refresh interval ran out, or R was pressed
+    Refresh,
+    /// The window was closed, or Q / Escape was pressed
+    Quit,
+}
+
+/// SDL window showing the latest panel frame
+pub struct PreviewWindow {
+    window: Window,
+    display: Option<SimulatorDisplay<Rgb888>>,
+}
+
+impl PreviewWindow {
+    /// Window titled `title`, each panel pixel drawn `scale` pixels wide
+    pub fn new(title: &str, scale: u32) -> Self {
+        let settings = OutputSettingsBuilder::new().scale(scale.max(1)).build();
+        Self {
+            window: Window::new(title, &settings),
+            display: None,
+        }
+    }
+
+    /// Replace the picture with `buffer`
+    pub fn show(&mut self, buffer: &DisplayBuffer) {
+        let size = buffer.size();
+        let display = self
+            .display
+            .get_or_insert_with(|| SimulatorDisplay::new(size));
+        if display.size() != size {
+            // The window keeps its first size, so a new rotation needs a restart
+            warn!("Frame size changed; restart the simulator to resize the window");
+        }
+        let pixels = palette_indices(buffer).enumerate().map(|(i, index)| {
+            let [r, g, b] = PALETTE[index as usize];
+            let point = Point::new(
+                (i as u32 % size.width) as i32,
+                (i as u32 / size.width) as i32,
+            );
+            Pixel(point, Rgb888::new(r, g, b))
+        });
+        display.draw_iter(pixels).ok();
+        self.window.update(display);
+    }
+
+    /// Keep the window responsive for up to `timeout`, returning early when
+    /// the user asks for a refresh or closes it
+    pub fn wait(&mut self, timeout: Duration) -> PreviewAction {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            for event in self.window.events() {
+                match event {
+                    SimulatorEvent::Quit
+                    | SimulatorEvent::KeyDown {
+                        keycode: Keycode::Q | Keycode::Escape,
+                        ..
+                    } => return PreviewAction::Quit,
+                    SimulatorEvent::KeyDown {
+                        keycode: Keycode::R,
+                        ..
+                    } => return PreviewAction::Refresh,
+                    _ => {}
+                }
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        PreviewAction::Refresh
+    }
+}
@@ -0,0 +1,350 @@
+//! # Shared SPI Bus
+//!
+//! The e-ink display can sit on a bus with other peripherals (SD card,
+//! second display, sensor), each with its own chip select, mode and speed:
+//!
+//! ```toml
+//! [[display.hardware.spi_devices]]
+//! name = "bme280"
+//! cs_pin = 22             # 8 = CE0, 7 = CE1, any other GPIO = manual CS
+//! spi_mode = 3
+//! spi_speed_hz = 1000000
+//! ```
+//!
+//! [`routes`] works out which spidev node each device goes through. A
+//! device on CE0 or CE1 has the kernel drive its chip select through that
+//! node. A device on any other GPIO goes through the bus's CE0 node opened
+//! with the kernel's chip select off (`SPI_NO_CS`), so CE0 stays idle while
+//! the GPIO is toggled by hand.
+//!
+//! The kernel keeps one mode and speed per node, so devices sharing a node
+//! go through a [`SharedSpiBus`]. Each [`SharedSpiDevice`] handle programs
+//! its own settings before a transfer, when they differ from the last
+//! device's, and a manual chip select is held high (deselected) whenever
+//! its device isn't transferring.
+
+use crate::config::HardwareConfig;
+use crate::epd4in2b_v2::{EpdError, GpioPin, SoftwareSpi};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// What a transfer programs into the controller first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpiSettings {
+    /// SPI mode, 0-3
+    pub mode: u8,
+    pub speed_hz: u32,
+    /// Keep the node's own chip select idle, for a device on a manual CS
+    pub no_cs: bool,
+}
+
+/// An SPI controller whose settings can change between transfers
+pub trait SpiController: SoftwareSpi {
+    fn configure(&mut self, settings: SpiSettings) -> Result<(), EpdError>;
+}
+
+/// The spidev node and settings one device's transfers go through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpiRoute {
+    /// "display", or the `spi_devices` name
+    pub name: String,
+    /// `/dev/spidev{bus}.{node}`
+    pub bus: u8,
+    pub node: u8,
+    /// GPIO toggled by hand as chip select, `None` when the kernel drives it
+    pub manual_cs: Option<u32>,
+    pub settings: SpiSettings,
+}
+
+/// The kernel-driven chip select node for `cs_pin`: CE0 (GPIO 8) or CE1
+/// (GPIO 7)
+pub fn kernel_node(cs_pin: u32) -> Option<u8> {
+    match cs_pin {
+        8 => Some(0),
+        7 => Some(1),
+        _ => None,
+    }
+}
+
+/// Routes of the display (first) and every `spi_devices` entry
+pub fn routes(hw: &HardwareConfig) -> Vec<SpiRoute> {
+    let route = |name: &str, bus, cs_pin, mode, speed_hz| {
+        let node = kernel_node(cs_pin);
+        SpiRoute {
+            name: name.to_string(),
+            bus,
+            node: node.unwrap_or(0),
+            manual_cs: node.is_none().then_some(cs_pin),
+            settings: SpiSettings {
+                mode,
+                speed_hz,
+                no_cs: node.is_none(),
+            },
+        }
+    };
+    let mut routes = vec![route(
+        "display",
+        hw.spi_bus,
+        hw.cs_pin,
+        hw.spi_mode,
+        hw.spi_speed_hz,
+    )];
+    routes.extend(hw.spi_devices.iter().map(|d| {
+        route(
+            &d.name,
+            d.spi_bus.unwrap_or(hw.spi_bus),
+            d.cs_pin,
+            d.spi_mode,
+            d.spi_speed_hz,
+        )
+    }));
+    routes
+}
+
+struct BusState<C> {
+    controller: C,
+    /// Settings currently programmed into the controller
+    active: SpiSettings,
+}
+
+/// One spidev node shared by the devices routed through it
+pub struct SharedSpiBus<C> {
+    state: Rc<RefCell<BusState<C>>>,
+}
+
+impl<C> Clone for SharedSpiBus<C> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Rc::clone(&self.state),
+        }
+    }
+}
+
+impl<C: SpiController> SharedSpiBus<C> {
+    /// Share `controller`, which was opened with `active`
+    pub fn new(controller: C, active: SpiSettings) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(BusState { controller, active })),
+        }
+    }
+
+    /// A handle for one device on this bus; `cs` is its manual chip select,
+    /// driven high (deselected) straight away
+    pub fn device(
+        &self,
+        settings: SpiSettings,
+        mut cs: Option<Box<dyn GpioPin>>,
+    ) -> Result<SharedSpiDevice<C>, EpdError> {
+        if let Some(cs) = &mut cs {
+            cs.set_high()?;
+        }
+        Ok(SharedSpiDevice {
+            state: Rc::clone(&self.state),
+            settings,
+            cs,
+        })
+    }
+}
+
+/// One device's view of a [`SharedSpiBus`]
+pub struct SharedSpiDevice<C> {
+    state: Rc<RefCell<BusState<C>>>,
+    settings: SpiSettings,
+    cs: Option<Box<dyn GpioPin>>,
+}
+
+impl<C: SpiController> SharedSpiDevice<C> {
+    fn transaction<T>(
+        &mut self,
+        op: impl FnOnce(&mut C) -> Result<T, EpdError>,
+    ) -> Result<T, EpdError> {
+        let mut bus = self.state.borrow_mut();
+        if bus.active != self.settings {
+            bus.controller.configure(self.settings)?;
+            bus.active = self.settings;
+        }
+        if let Some(cs) = &mut self.cs {
+            cs.set_low()?;
+        }
+        let r = op(&mut bus.controller);
+        if let Some(cs) = &mut self.cs {
+            cs.set_high()?;
+        }
+        r
+    }
+}
+
+impl<C: SpiController> SoftwareSpi for SharedSpiDevice<C> {
+    fn write_byte(&mut self, data: u8) -> Result<(), EpdError> {
+        self.transaction(|spi| spi.write_byte(data))
+    }
+    fn read_byte(&mut self) -> Result<u8, EpdError> {
+        self.transaction(|spi| spi.read_byte())
+    }
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), EpdError> {
+        self.transaction(|spi| spi.read_bytes(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, SpiDeviceConfig};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Event {
+        Configure(SpiSettings),
+        Write(u8),
+        Cs(&'static str, bool),
+    }
+
+    type Log = Rc<RefCell<Vec<Event>>>;
+
+    struct Controller(Log);
+
+    impl SoftwareSpi for Controller {
+        fn write_byte(&mut self, data: u8) -> Result<(), EpdError> {
+            self.0.borrow_mut().push(Event::Write(data));
+            Ok(())
+        }
+        fn read_byte(&mut self) -> Result<u8, EpdError> {
+            Ok(0)
+        }
+    }
+
+    impl SpiController for Controller {
+        fn configure(&mut self, settings: SpiSettings) -> Result<(), EpdError> {
+            self.0.borrow_mut().push(Event::Configure(settings));
+            Ok(())
+        }
+    }
+
+    struct Cs(&'static str, Log);
+
+    impl GpioPin for Cs {
+        fn set_high(&mut self) -> Result<(), EpdError> {
+            self.1.borrow_mut().push(Event::Cs(self.0, true));
+            Ok(())
+        }
+        fn set_low(&mut self) -> Result<(), EpdError> {
+            self.1.borrow_mut().push(Event::Cs(self.0, false));
+            Ok(())
+        }
+    }
+
+    fn device(name: &str, cs_pin: u32, spi_mode: u8, spi_speed_hz: u32) -> SpiDeviceConfig {
+        SpiDeviceConfig {
+            name: name.to_string(),
+            spi_bus: None,
+            cs_pin,
+            spi_mode,
+            spi_speed_hz,
+        }
+    }
+
+    #[test]
+    fn test_devices_switch_mode_and_speed() {
+        let log = Log::default();
+        let display = SpiSettings {
+            mode: 0,
+            speed_hz: 8_000_000,
+            no_cs: true,
+        };
+        let sensor = SpiSettings {
+            mode: 3,
+            speed_hz: 1_000_000,
+            no_cs: true,
+        };
+        let bus = SharedSpiBus::new(Controller(log.clone()), display);
+        let mut epd = bus
+            .device(display, Some(Box::new(Cs("display", log.clone()))))
+            .unwrap();
+        let mut bme = bus
+            .device(sensor, Some(Box::new(Cs("bme280", log.clone()))))
+            .unwrap();
+        log.borrow_mut().clear();
+
+        epd.write_byte(0x10).unwrap();
+        epd.write_byte(0x11).unwrap();
+        bme.write_byte(0xD0).unwrap();
+        epd.write_byte(0x12).unwrap();
+        assert_eq!(
+            *log.borrow(),
+            [
+                // Already in the display's settings
+                Event::Cs("display", false),
+                Event::Write(0x10),
+                Event::Cs("display", true),
+                Event::Cs("display", false),
+                Event::Write(0x11),
+                Event::Cs("display", true),
+                Event::Configure(sensor),
+                Event::Cs("bme280", false),
+                Event::Write(0xD0),
+                Event::Cs("bme280", true),
+                Event::Configure(display),
+                Event::Cs("display", false),
+                Event::Write(0x12),
+                Event::Cs("display", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chip_selects_start_deselected() {
+        let log = Log::default();
+        let settings = SpiSettings {
+            mode: 0,
+            speed_hz: 8_000_000,
+            no_cs: false,
+        };
+        let bus = SharedSpiBus::new(Controller(log.clone()), settings);
+        bus.device(settings, Some(Box::new(Cs("sd-card", log.clone()))))
+            .unwrap();
+        let mut kernel = bus.device(settings, None).unwrap();
+        kernel.write_byte(0x42).unwrap();
+        assert_eq!(
+            *log.borrow(),
+            [Event::Cs("sd-card", true), Event::Write(0x42)]
+        );
+    }
+
+    #[test]
+    fn test_routes() {
+        let mut hw = Config::default().display.hardware;
+        hw.cs_pin = 22;
+        hw.spi_devices = vec![
+            device("sd-card", 8, 0, 20_000_000),
+            device("bme280", 7, 3, 1_000_000),
+            SpiDeviceConfig {
+                spi_bus: Some(1),
+                ..device("second", 16, 0, 4_000_000)
+            },
+        ];
+        let routes = routes(&hw);
+        let summary: Vec<_> = routes
+            .iter()
+            .map(|r| {
+                (
+                    r.name.as_str(),
+                    r.bus,
+                    r.node,
+                    r.manual_cs,
+                    r.settings.no_cs,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                // A manual CS never lets the kernel assert CE0 alongside it
+                ("display", 0, 0, Some(22), true),
+                ("sd-card", 0, 0, None, false),
+                ("bme280", 0, 1, None, false),
+                ("second", 1, 0, Some(16), true),
+            ]
+        );
+        assert_eq!(routes[2].settings.mode, 3);
+        assert_eq!(routes[2].settings.speed_hz, 1_000_000);
+    }
+}
@@ -0,0 +1,126 @@
+//! # Multi-Station Display
+//!
+//! Decides which stations to fetch on a refresh when `[[stations]]` lists
+//! more than the primary `[station]`:
+//!
+//! - **Rotate** (default): one station per refresh, cycling through the list.
+//!   The position is persisted next to the cache file so it survives the
+//!   one-shot process exiting between timer runs.
+//! - **Overlay**: every station is fetched and drawn on the same chart, the
+//!   first as the main curve and the rest as lighter overlays with a legend.
+//!
+//! Each station falls back to the offline model independently, so one
+//! unreachable station never blanks the others. All fetches share the
+//! refresh budget (see [`crate::budget`]). Stations with a custom
+//! reference level get their heights shifted to it here, before any layout
+//! sees them.
+
+use crate::budget::RefreshBudget;
+use crate::config::{Config, MultiStationMode, StationConfig};
+use crate::{fallback, tide_data, TideSeries};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// A fetched series together with the station it belongs to
+#[derive(Debug, Clone)]
+pub struct StationSeries {
+    pub station: StationConfig,
+    pub series: TideSeries,
+}
+
+/// Fetch the station(s) to show on this refresh
+///
+/// Always returns at least one entry; the first is the main station, every
+/// series centered on `now`. Fetches share `budget`; a station whose fetch
+/// overruns it is shown from its stale cache when that still covers `now`,
+/// else from the offline model.
+pub async fn fetch_for_display(
+    config: &Config,
+    budget: &RefreshBudget,
+    now: DateTime<Utc>,
+) -> Vec<StationSeries> {
+    let stations = config.all_stations();
+    let selected: Vec<&StationConfig> = match config.display.multi_station {
+        _ if stations.len() == 1 => stations,
+        MultiStationMode::Overlay => stations,
+        MultiStationMode::Rotate => {
+            let index = advance_rotation(&rotation_state_path(config), stations.len());
+            vec![stations[index]]
+        }
+    };
+
+    let mut result = Vec::with_capacity(selected.len());
+    for station in selected {
+        let stage = format!("fetch {}", station.name);
+        let series = match budget
+            .run(&stage, tide_data::fetch_station_at(config, station, now))
+            .await
+        {
+            Some(Ok(series)) => series,
+            Some(Err(error)) => {
+                warn!(
+                    "Tide data fetch failed for {}, falling back to the offline model: {}",
+                    station.name, error
+                );
+                fallback::for_station(config, station, Some(now))
+            }
+            None => match tide_data::load_stale(config, station, now).await {
+                Some(series) => {
+                    info!("Showing stale cached data for {}", station.name);
+                    series
+                }
+                None => fallback::for_station(config, station, Some(now)),
+            },
+        };
+        let series = match &station.reference {
+            Some(reference) => series.relative_to(reference.elevation_ft),
+            None => series,
+        };
+        result.push(StationSeries {
+            station: station.clone(),
+            series,
+        });
+    }
+    result
+}
+
+/// File holding the index of the station shown last
+fn rotation_state_path(config: &Config) -> PathBuf {
+    config.display.cache_path.with_file_name("tide_rotation")
+}
+
+/// Read the last shown index from `path`, store and return the next one
+///
+/// A missing or unreadable state file starts the rotation at 0.
+pub fn advance_rotation(path: &Path, count: usize) -> usize {
+    if count == 0 {
+        return 0;
+    }
+    let next = fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .map(|last| (last + 1) % count)
+        .unwrap_or(0);
+    // Failure to persist just means we show the same station again next time
+    let _ = fs::write(path, next.to_string());
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_cycles_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tide_rotation");
+
+        let seen: Vec<usize> = (0..5).map(|_| advance_rotation(&path, 3)).collect();
+        assert_eq!(seen, vec![0, 1, 2, 0, 1]);
+
+        // Shrinking the list keeps the index in range
+        assert_eq!(advance_rotation(&path, 2), 0);
+    }
+}
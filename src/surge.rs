@@ -0,0 +1,317 @@
+//! # Storm Surge
+//!
+//! How far the water actually is from the predicted tide. Predictions only
+//! know the moon and sun; wind and low pressure can pile water up (or blow
+//! it out) by a few feet, and that difference, observed minus predicted, is
+//! the surge. With a threshold set for the station,
+//!
+//! ```toml
+//! [station]
+//! surge_threshold_ft = 1.0
+//! ```
+//!
+//! every refresh fetches the station's recent water levels from NOAA
+//! ([`fetch_observed`], stations with a gauge only) and draws them over the
+//! chart as an "Observed" curve. When the residual reaches the threshold
+//! either way the panel shows "SURGE +1.3ft" in the warning color, the Home
+//! Assistant JSON carries it (`surge_ft`, `surge`), and a `surge:<station>`
+//! alert goes out. There is no MQTT output to carry it.
+//!
+//! Only NOAA's observations count. The depth sounder's curve (see
+//! [`crate::nmea`]) is depth under the transducer plus a hand-set
+//! `offset_ft`, not a height in the station's datum, so it is never
+//! compared with the prediction.
+//!
+//! The residual is averaged over the last [`SMOOTHING_MINS`] of
+//! observations, so one wave or wake doesn't raise it, and observations
+//! older than [`MAX_AGE_MINS`] don't count.
+
+#[cfg(feature = "net")]
+use crate::config::StationConfig;
+#[cfg(feature = "net")]
+use crate::grid::{self, Window};
+use crate::stations::StationSeries;
+#[cfg(feature = "net")]
+use crate::tide_data::TideError;
+use crate::{DataSource, TideSeries};
+#[cfg(feature = "net")]
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+/// Minutes of observations the residual is averaged over, back from the
+/// latest one
+pub const SMOOTHING_MINS: i64 = 30;
+
+/// Latest observation older than this before "now" and there is no
+/// residual: the gauge has stopped reporting
+pub const MAX_AGE_MINS: i64 = 60;
+
+/// Legend label of the NOAA observations drawn over the chart
+pub const OBSERVED_LABEL: &str = "Observed";
+
+/// Observed minus predicted water level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Surge {
+    /// Feet above (positive) or below the prediction
+    pub residual_ft: f32,
+    /// Minutes from "now" of the latest observation
+    pub mins_rel: i64,
+}
+
+impl Surge {
+    /// Whether the water is `threshold_ft` or more above or below the
+    /// prediction
+    pub fn exceeds(&self, threshold_ft: f32) -> bool {
+        self.residual_ft.abs() >= threshold_ft
+    }
+}
+
+impl fmt::Display for Surge {
+    /// "SURGE +1.3ft"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SURGE {:+.1}ft", self.residual_ft)
+    }
+}
+
+/// The residual of `observed` against `predicted`, both anchored in time
+///
+/// `None` when either has no base time, `predicted` is the offline model
+/// (there is nothing real to compare with), or no observation from the
+/// last [`MAX_AGE_MINS`] falls on the predicted curve.
+pub fn residual(predicted: &TideSeries, observed: &TideSeries) -> Option<Surge> {
+    if predicted.is_offline() {
+        return None;
+    }
+    let now = predicted.base_time?;
+    let points: Vec<(i64, f32)> = observed
+        .samples
+        .iter()
+        .filter_map(|s| {
+            // Observations kept from an earlier fetch have their own base
+            let mins = observed.mins_rel_at(s, now)?;
+            let predicted_ft = predicted.height_at(mins as f32)?;
+            (mins <= 0).then_some((mins, s.tide_ft - predicted_ft))
+        })
+        .collect();
+    let latest = points.iter().map(|&(mins, _)| mins).max()?;
+    if latest < -MAX_AGE_MINS {
+        return None;
+    }
+    let recent: Vec<f32> = points
+        .iter()
+        .filter(|&&(mins, _)| mins >= latest - SMOOTHING_MINS)
+        .map(|&(_, residual)| residual)
+        .collect();
+    Some(Surge {
+        residual_ft: recent.iter().sum::<f32>() / recent.len() as f32,
+        mins_rel: latest,
+    })
+}
+
+/// The surge at `main`'s station from NOAA's [`OBSERVED_LABEL`] curve
+/// among `overlays`, when it reaches the station's `surge_threshold_ft`
+pub fn warning(main: &StationSeries, overlays: &[StationSeries]) -> Option<Surge> {
+    let threshold = main.station.surge_threshold_ft?;
+    overlays
+        .iter()
+        .filter(|o| is_observed(o))
+        .find_map(|o| residual(&main.series, &o.series))
+        .filter(|surge| surge.exceeds(threshold))
+}
+
+/// Whether `overlay` is the one [`fetch_observed`] makes, rather than
+/// another station's predictions or the depth sounder's curve
+fn is_observed(overlay: &StationSeries) -> bool {
+    overlay.series.source == DataSource::Sensor && overlay.station.name == OBSERVED_LABEL
+}
+
+/// NOAA's observed water levels at `main`'s station over the past half of
+/// its window, as an [`OBSERVED_LABEL`] overlay in the same reference
+///
+/// `Ok(None)` when the station has no threshold set (nothing is fetched),
+/// no gauge, or no observations in the window.
+#[cfg(feature = "net")]
+pub async fn fetch_observed(main: &StationSeries) -> Result<Option<StationSeries>, TideError> {
+    let station = &main.station;
+    let series = &main.series;
+    let (Some(_), Some(center), Some(half_width), Some(interval)) = (
+        station.surge_threshold_ft,
+        series.base_time,
+        series.half_width(),
+        series.interval(),
+    ) else {
+        return Ok(None);
+    };
+    let client = reqwest::Client::builder()
+        .timeout(station.fetch_deadline())
+        .build()?;
+    let url = format!(
+        "https://api.tidesandcurrents.noaa.gov/api/prod/datagetter?\
+        product=water_level&station={}&begin_date={}&end_date={}&\
+        datum={}&time_zone=gmt&units=english&format=json",
+        station.id,
+        (center - half_width).format("%Y%m%d %H:%M"),
+        center.format("%Y%m%d %H:%M"),
+        station.datum.api_code()
+    );
+    let response = client.get(&url).send().await?.text().await?;
+    let points: Vec<(DateTime<Utc>, f32)> = crate::accuracy::parse_points(&response)?
+        .into_iter()
+        .map(|(t, ft)| (t.and_utc(), ft))
+        .collect();
+    Ok(
+        observed_series(&points, Window::around(center, half_width), interval).map(|series| {
+            let series = match &station.reference {
+                Some(reference) => series.relative_to(reference.elevation_ft),
+                None => series,
+            };
+            StationSeries {
+                station: StationConfig {
+                    name: OBSERVED_LABEL.to_string(),
+                    ..station.clone()
+                },
+                series,
+            }
+        }),
+    )
+}
+
+/// `points` on the grid of `window`, only as far as they go; `None` with
+/// fewer than two
+#[cfg(feature = "net")]
+fn observed_series(
+    points: &[(DateTime<Utc>, f32)],
+    window: Window,
+    interval: chrono::Duration,
+) -> Option<TideSeries> {
+    let inside: Vec<_> = points
+        .iter()
+        .copied()
+        .filter(|&(t, _)| window.contains(t))
+        .collect();
+    let first = inside.iter().map(|&(t, _)| t).min()?;
+    let last = inside.iter().map(|&(t, _)| t).max()?;
+    let mut samples = grid::resample(&inside, window, interval)?;
+    let mins = |t: DateTime<Utc>| (t - window.center).num_minutes();
+    samples.retain(|s| (mins(first)..=mins(last)).contains(&(s.mins_rel as i64)));
+    Some(TideSeries {
+        samples,
+        source: DataSource::Sensor,
+        base_time: Some(window.center),
+        extremes: None,
+        fetched_at: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, StationConfig};
+    use crate::grid::SeriesSpec;
+    use chrono::{TimeZone, Utc};
+
+    fn series(height: impl Fn(i16) -> f32, keep: impl Fn(i16) -> bool) -> TideSeries {
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 16, 0, 0).unwrap();
+        let mut samples = SeriesSpec::standard(now).generate(height);
+        samples.retain(|s| keep(s.mins_rel));
+        TideSeries {
+            samples,
+            source: DataSource::Noaa,
+            base_time: Some(now),
+            extremes: None,
+            fetched_at: None,
+        }
+    }
+
+    #[test]
+    fn test_residual_averages_the_latest_observations() {
+        let predicted = series(|m| 5.0 + m as f32 / 100.0, |_| true);
+        // Running a foot high, then 1.6 ft for the last 30 minutes
+        let observed = series(
+            |m| 5.0 + m as f32 / 100.0 + if m >= -40 { 1.6 } else { 1.0 },
+            |m| m <= -10,
+        );
+        let surge = residual(&predicted, &observed).unwrap();
+        assert_eq!(surge.mins_rel, -10);
+        // -40, -30, -20 and -10
+        assert!((surge.residual_ft - 1.6).abs() < 1e-4, "{surge:?}");
+        assert_eq!(surge.to_string(), "SURGE +1.6ft");
+        assert!(surge.exceeds(1.5) && !surge.exceeds(2.0));
+        // The same observations, as fetched 10 minutes earlier
+        let earlier = observed
+            .rebased(observed.base_time.unwrap() - chrono::Duration::minutes(10))
+            .unwrap();
+        assert_eq!(residual(&predicted, &earlier), Some(surge));
+
+        let low = Surge {
+            residual_ft: -1.26,
+            mins_rel: 0,
+        };
+        assert_eq!(low.to_string(), "SURGE -1.3ft");
+        assert!(low.exceeds(1.0));
+
+        // A gauge gone quiet for over an hour, or nothing to compare with
+        let stale = series(|_| 9.0, |m| m <= -70);
+        assert_eq!(residual(&predicted, &stale), None);
+        let mut offline = predicted.clone();
+        offline.source = DataSource::Fallback;
+        assert_eq!(residual(&offline, &observed), None);
+    }
+
+    #[test]
+    fn test_warning_needs_a_threshold_and_observed_curve() {
+        let mut station = Config::default().station;
+        let main = |station: &StationConfig| StationSeries {
+            station: station.clone(),
+            series: series(|_| 3.0, |_| true),
+        };
+        let mut measured = StationSeries {
+            station: StationConfig {
+                name: OBSERVED_LABEL.to_string(),
+                ..station.clone()
+            },
+            series: series(|_| 4.2, |m| m <= 0),
+        };
+        measured.series.source = DataSource::Sensor;
+        let overlays = [measured];
+
+        assert_eq!(warning(&main(&station), &overlays), None);
+        station.surge_threshold_ft = Some(1.0);
+        let surge = warning(&main(&station), &overlays).unwrap();
+        assert!((surge.residual_ft - 1.2).abs() < 1e-4);
+        station.surge_threshold_ft = Some(1.5);
+        assert_eq!(warning(&main(&station), &overlays), None);
+
+        // Another station's predictions aren't observations
+        station.surge_threshold_ft = Some(1.0);
+        let mut other = overlays[0].clone();
+        other.series.source = DataSource::Noaa;
+        assert_eq!(warning(&main(&station), &[other]), None);
+
+        // Nor is the depth sounder's curve, 20 ft under the transducer
+        let mut sounder = overlays[0].clone();
+        sounder.station.name = "Measured".to_string();
+        sounder.series = series(|_| 20.0, |m| m <= 0);
+        sounder.series.source = DataSource::Sensor;
+        assert_eq!(warning(&main(&station), &[sounder]), None);
+    }
+
+    #[test]
+    #[cfg(feature = "net")]
+    fn test_observed_series_covers_only_the_observations() {
+        use chrono::Duration;
+
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 16, 0, 0).unwrap();
+        let points: Vec<_> = (0..=50)
+            .map(|i| (now - Duration::minutes(300 - i * 6), 2.0))
+            .collect();
+        let observed =
+            observed_series(&points, Window::standard(now), Duration::minutes(10)).unwrap();
+        assert_eq!(observed.source, DataSource::Sensor);
+        assert_eq!(observed.samples.first().unwrap().mins_rel, -300);
+        assert_eq!(observed.samples.last().unwrap().mins_rel, 0);
+        assert!(
+            observed_series(&points[..1], Window::standard(now), Duration::minutes(10)).is_none()
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! # Rendering Theme
+//!
+//! Visual parameters shared by every layout and output backend: stroke widths,
+//! line and fill patterns, per-element colors and font sizes. Layouts read
+//! these instead of hardcoding their own constants so that a custom layout and
+//! the built-in chart stay visually consistent, and users can restyle the
+//! display from the `[theme]` section of `tide-config.toml`:
+//!
+//! ```toml
+//! [theme]
+//! curve_stroke = 3
+//! curve_fill = "hatch"
+//! now_marker_color = "red"
+//! label_font = "small"
+//! ```
+
+use crate::epd4in2b_v2::Gray;
+use embedded_graphics::mono_font::ascii::{FONT_10X20, FONT_6X10, FONT_8X13};
+use embedded_graphics::mono_font::MonoFont;
+use embedded_graphics::pixelcolor::BinaryColor;
+use serde::{Deserialize, Serialize};
+
+/// Ink a themed element is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InkColor {
+    Black,
+    White,
+    /// Accent color on tri-color panels; drawn black on monochrome targets
+    Red,
+}
+
+impl InkColor {
+    /// Color to use on a monochrome draw target
+    pub fn binary(self) -> BinaryColor {
+        match self {
+            InkColor::White => BinaryColor::Off,
+            InkColor::Black | InkColor::Red => BinaryColor::On,
+        }
+    }
+}
+
+/// Monospace font sizes available to layouts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontSize {
+    /// 6x10 pixels
+    Small,
+    /// 8x13 pixels
+    Medium,
+    /// 10x20 pixels
+    Large,
+}
+
+impl FontSize {
+    /// The embedded-graphics font for this size
+    pub fn font(self) -> &'static MonoFont<'static> {
+        match self {
+            FontSize::Small => &FONT_6X10,
+            FontSize::Medium => &FONT_8X13,
+            FontSize::Large => &FONT_10X20,
+        }
+    }
+}
+
+/// Dash pattern for straight lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinePattern {
+    Solid,
+    /// 4 pixels on, 4 off
+    Dashed,
+    /// 2 pixels on, 2 off
+    Dotted,
+    /// Not drawn at all
+    Hidden,
+}
+
+impl LinePattern {
+    /// `(on, off)` run lengths in pixels, `None` when the line is hidden
+    pub fn runs(self) -> Option<(u32, u32)> {
+        match self {
+            LinePattern::Solid => Some((1, 0)),
+            LinePattern::Dashed => Some((4, 4)),
+            LinePattern::Dotted => Some((2, 2)),
+            LinePattern::Hidden => None,
+        }
+    }
+}
+
+/// Pattern used to fill areas on a 1-bit panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillPattern {
+    None,
+    Solid,
+    /// Every other pixel, ~50% coverage
+    Checker,
+    /// Diagonal lines every 4 pixels, ~25% coverage
+    Hatch,
+    /// One pixel in four, ~25% coverage
+    Dots,
+    /// Dithered light gray, 25% coverage
+    Gray25,
+    /// Dithered mid gray, 50% coverage
+    Gray50,
+    /// Dithered dark gray, 75% coverage
+    Gray75,
+}
+
+impl FillPattern {
+    /// Whether the pixel at (x, y) is inked by this pattern
+    pub fn covers(self, x: i32, y: i32) -> bool {
+        match self {
+            FillPattern::None => false,
+            FillPattern::Solid => true,
+            FillPattern::Checker => (x + y).rem_euclid(2) == 0,
+            FillPattern::Hatch => (x + y).rem_euclid(4) == 0,
+            FillPattern::Dots => x.rem_euclid(2) == 0 && y.rem_euclid(2) == 0,
+            FillPattern::Gray25 => Gray::LIGHT.covers(x, y),
+            FillPattern::Gray50 => Gray::MEDIUM.covers(x, y),
+            FillPattern::Gray75 => Gray::DARK.covers(x, y),
+        }
+    }
+}
+
+/// Appearance settings passed to every [`Layout`](crate::layout::Layout)
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Stroke width of the chart axes and tick marks in pixels
+    pub axis_stroke: u32,
+    /// Stroke width of the tide curve in pixels
+    pub curve_stroke: u32,
+    /// Diameter of the dot drawn at each sample in pixels, 0 for a plain line
+    pub point_diameter: u32,
+    /// Diameter of the ring marking the current time in pixels
+    pub now_marker_diameter: u32,
+    /// Dash pattern of the vertical "now" line
+    pub now_line: LinePattern,
+    /// Fill between the tide curve and the bottom of the plot
+    pub curve_fill: FillPattern,
+    /// Color of axes, ticks and axis labels
+    pub axis_color: InkColor,
+    /// Color of the tide curve and its fill
+    pub curve_color: InkColor,
+    /// Color of the "now" line and marker
+    pub now_marker_color: InkColor,
+    /// Color of overlay text (timestamp, status banners)
+    pub text_color: InkColor,
+    /// Color of warnings such as the OFFLINE banner
+    pub warning_color: InkColor,
+    /// Font for axis labels
+    pub label_font: FontSize,
+    /// Font for overlays such as the last-updated time and OFFLINE banner
+    pub overlay_font: FontSize,
+    /// Two-line footer under the chart with today's remaining and
+    /// tomorrow's first highs and lows
+    pub extremes_footer: bool,
+    /// Countdown to the next high or low in large text above the chart,
+    /// e.g. "High in 2h 15m (10.8 ft)"
+    pub countdown_header: bool,
+    /// Current height in extra-large digits at the top left, with the
+    /// trend arrow beside it
+    pub current_height: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            axis_stroke: 2,
+            curve_stroke: 2,
+            point_diameter: 0,
+            now_marker_diameter: 8,
+            now_line: LinePattern::Dashed,
+            curve_fill: FillPattern::None,
+            axis_color: InkColor::Black,
+            curve_color: InkColor::Black,
+            now_marker_color: InkColor::Red,
+            text_color: InkColor::Black,
+            warning_color: InkColor::Red,
+            label_font: FontSize::Large,
+            overlay_font: FontSize::Large,
+            extremes_footer: true,
+            countdown_header: true,
+            current_height: true,
+        }
+    }
+}
+
+impl Theme {
+    /// Copy of the theme with every red element drawn in `ink` instead
+    pub fn with_red_as(&self, ink: InkColor) -> Theme {
+        let swap = |color: InkColor| if color == InkColor::Red { ink } else { color };
+        Theme {
+            axis_color: swap(self.axis_color),
+            curve_color: swap(self.curve_color),
+            now_marker_color: swap(self.now_marker_color),
+            text_color: swap(self.text_color),
+            warning_color: swap(self.warning_color),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_theme_uses_defaults() {
+        let theme: Theme = toml::from_str(
+            r#"
+            curve_stroke = 4
+            curve_fill = "hatch"
+            label_font = "small"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(theme.curve_stroke, 4);
+        assert_eq!(theme.curve_fill, FillPattern::Hatch);
+        assert_eq!(theme.label_font, FontSize::Small);
+        assert_eq!(theme.axis_stroke, Theme::default().axis_stroke);
+    }
+
+    #[test]
+    fn test_fill_pattern_coverage() {
+        let covered = |p: FillPattern| {
+            (0..8)
+                .flat_map(|x| (0..8).map(move |y| (x, y)))
+                .filter(|&(x, y)| p.covers(x, y))
+                .count()
+        };
+        assert_eq!(covered(FillPattern::None), 0);
+        assert_eq!(covered(FillPattern::Solid), 64);
+        assert_eq!(covered(FillPattern::Checker), 32);
+        assert_eq!(covered(FillPattern::Hatch), 16);
+        assert_eq!(covered(FillPattern::Dots), 16);
+        assert_eq!(covered(FillPattern::Gray25), 16);
+        assert_eq!(covered(FillPattern::Gray50), 32);
+        assert_eq!(covered(FillPattern::Gray75), 48);
+    }
+}
@@ -15,17 +15,23 @@
 //! ### Data Processing Pipeline
 //! 1. **Fetch**: HTTP GET request to NOAA CO-OPS API
 //! 2. **Parse**: Deserialize JSON response containing tide predictions
-//! 3. **Filter**: Extract 24-hour window (-12h to +12h from current time)
-//! 4. **Interpolate**: Convert 6-minute data to 10-minute samples using linear interpolation
-//! 5. **Cache**: Store processed data with timestamp for 30-minute TTL
-//! 6. **Return**: 145 samples ready for visualization
+//! 3. **Filter**: Extract the display window (-12h to +12h from current time
+//!    by default, see `time_window_hours`)
+//! 4. **Interpolate**: Convert 6-minute data to 10-minute samples using monotone
+//!    cubic interpolation (see [`crate::interp`])
+//! 5. **Cache**: Store processed data with timestamp for the configured TTL
+//! 6. **Return**: `grid::sample_count` samples (145 for ±12h, 433 for ±36h)
+//!    ready for visualization
 //!
 //! ## Caching Strategy
 //!
 //! ### Memory-Efficient Caching
-//! - **Location**: `/tmp/tide_cache.json` (cleared on reboot)
-//! - **Format**: Binary JSON serialization for compact storage
-//! - **TTL**: 30 minutes (balances freshness vs. network load)
+//! - **Location**: `display.cache_path` with the station ID appended, default
+//!   `/tmp/tide_cache_8418150.bin` (cleared on reboot), one file per station
+//!   and provider
+//! - **Format**: `TIDE` magic + schema version byte + postcard payload (~0.9 KB vs ~4 KB JSON)
+//!   that starts with the station ID and provider, checked on every read
+//! - **TTL**: `display.cache_ttl_minutes`, default 30 (balances freshness vs. network load)
 //! - **Validation**: File modification time checked before loading
 //!
 //! ### Cache Benefits
@@ -34,21 +40,62 @@
 //! - **Network resilience**: Recent data available during temporary outages
 //! - **Pi Zero friendly**: Minimizes CPU time spent on network I/O
 //!
+//! ## Deadlines and Cancellation
+//!
+//! The whole pipeline is async, so a refresh can be abandoned at any await point:
+//! - [`fetch()`] enforces `station.fetch_timeout_secs` as an overall deadline
+//! - [`fetch_with_deadline()`] lets the caller pick the deadline explicitly
+//! - [`fetch_cancellable()`] stops as soon as an arbitrary future completes
+//!   (shutdown signal, button press, render budget...)
+//!
+//! Dropping the in-flight request closes its connection, so a hung TLS
+//! handshake never holds up the display update past the deadline.
+//!
 //! ## Error Handling
 //!
 //! The module handles multiple failure modes gracefully:
-//! - **Network timeouts**: HTTP client configured with reasonable timeouts
+//! - **Network timeouts**: Overall deadline plus an HTTP client timeout
 //! - **Server errors**: 5xx responses handled as fetch failures
 //! - **Parse failures**: Malformed JSON or unexpected API response structure
-//! - **Cache corruption**: Invalid JSON falls back to fresh network fetch
+//! - **Bad data**: Implausible samples are repaired, structurally broken series
+//!   rejected, by [`TideSeries::validate`] before caching
+//! - **Cache corruption**: Bad magic, unknown schema version or undecodable payload
+//!   invalidates the file and falls back to fresh network fetch
 //! - **File system issues**: Permissions or disk space problems
 //!
 //! All errors propagate through `TideError` enum for consistent handling.
+//!
+//! ## Testing
+//!
+//! The request goes through a small `HttpClient` trait, so the tests replay
+//! recorded responses from `tests/fixtures/noaa` (an ordinary day, hourly
+//! data with a gap, broken and error responses, both DST changes) instead of
+//! calling the live API.
 
-use crate::{config::Config, Sample, TideSeries};
-use chrono::{Duration, Local};
-use std::{fs, io, time::SystemTime};
+use crate::config::{Config, StationConfig};
+use crate::file_source::FileSourceError;
+#[cfg(feature = "net")]
+use crate::grid;
+use crate::harmonics::HarmonicsError;
+use crate::provider::{self, ProviderKind};
+use crate::series_builder::SeriesError;
+#[cfg(feature = "net")]
+use crate::series_builder::TideSeriesBuilder;
+use crate::validate::ValidationError;
+use crate::{DataSource, TideSeries};
+#[cfg(feature = "net")]
+use chrono::Duration;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::io;
+use std::path::Path;
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "cache")]
+use std::time::SystemTime;
 use thiserror::Error;
+#[cfg(feature = "net")]
+use tracing::info;
 
 /// Errors that can occur during tide data fetching and processing.
 ///
@@ -58,6 +105,7 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum TideError {
     /// HTTP request failed (network, server, or protocol error)
+    #[cfg(feature = "net")]
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
 
@@ -65,30 +113,72 @@ pub enum TideError {
     #[error("API parse failed")]
     Scrape,
 
+    /// NOAA answered with an `error` object instead of data
+    #[error("NOAA: {0}")]
+    Api(String),
+
     /// Cache file operations failed (permissions, disk space, corruption)
     #[error("cache IO: {0}")]
     Cache(#[from] io::Error),
+
+    /// The overall fetch deadline elapsed before data was obtained
+    #[error("fetch timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
+    /// The caller cancelled the fetch
+    #[error("fetch cancelled")]
+    Cancelled,
+
+    /// The response parsed but the resulting series failed validation
+    #[error("invalid data: {0}")]
+    Invalid(#[from] ValidationError),
+
+    /// The resampled samples don't form a usable series
+    #[error("malformed series: {0}")]
+    Series(#[from] SeriesError),
+
+    /// Harmonic constants missing or unreadable
+    #[error("harmonics: {0}")]
+    Harmonics(#[from] HarmonicsError),
+
+    /// The `file` provider's data file is missing or unusable
+    #[error("data file: {0}")]
+    File(#[from] FileSourceError),
+
+    /// The library was built without the named feature this source needs
+    #[error("built without the {0} feature")]
+    Unsupported(&'static str),
 }
 
-/// Cache file location on filesystem
+/// Magic bytes at the start of every cache file
+#[cfg(feature = "cache")]
+const CACHE_MAGIC: &[u8; 4] = b"TIDE";
+
+/// Schema version of the cached `TideSeries` payload
 ///
-/// Using /tmp ensures the cache is cleared on reboot and doesn't consume
-/// permanent storage on the Pi Zero 2 W's limited SD card space.
-const CACHE: &str = "/tmp/tide_cache.json";
+/// Bump this whenever `TideSeries`, `Sample` or [`CacheKey`] change shape.
+/// Files of any other version are a cache miss, removed and refetched.
+#[cfg(feature = "cache")]
+const CACHE_VERSION: u8 = 6;
 
-/// Cache time-to-live in seconds (30 minutes)
+/// Which station and provider a cache file holds
 ///
-/// This balances data freshness with network efficiency:
-/// - Short enough: Tide predictions stay reasonably current
-/// - Long enough: Reduces network load during active development
-/// - Pi Zero friendly: Minimizes cellular/WiFi radio usage
-const TTL: u64 = 1800; // 30 minutes
+/// Written ahead of the series and checked on every read, so a file that
+/// was copied, renamed or shared between configs can't pass for another
+/// station's data.
+#[cfg(feature = "cache")]
+#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct CacheKey {
+    station_id: String,
+    provider: ProviderKind,
+}
 
-/// Fetch current tide series from NOAA or cache.
+/// Fetch `config`'s main station from NOAA or cache.
 ///
 /// This is the main entry point for obtaining tide data. It implements
 /// a cache-first strategy: check for valid cached data, and only fetch
-/// from the network if the cache is stale or missing.
+/// from the network if the cache is stale or missing. The whole operation is
+/// bounded by `station.fetch_timeout_secs` from the configuration.
 ///
 /// # Memory Usage
 /// - Cache check: ~100 bytes for file metadata
@@ -106,33 +196,222 @@ const TTL: u64 = 1800; // 30 minutes
 ///
 /// # Example
 /// ```no_run
-/// use tide_clock_lib::tide_data::fetch;
-/// use tide_clock_lib::fallback;
+/// use tide_clock_lib::{config::Config, fallback, tide_data::fetch};
 ///
 /// # async fn example() {
-/// let series = fetch().await.unwrap_or_else(|err| {
+/// let config = Config::load();
+/// let series = fetch(&config).await.unwrap_or_else(|err| {
 ///     eprintln!("Failed to fetch tide data: {}", err);
 ///     fallback::approximate(None)
 /// });
 /// # }
 /// ```
-pub async fn fetch() -> Result<TideSeries, TideError> {
-    let config = Config::load();
+pub async fn fetch(config: &Config) -> Result<TideSeries, TideError> {
+    fetch_with_deadline(config, config.station.fetch_deadline()).await
+}
+
+/// Fetch tide series, giving up once `deadline` has elapsed.
+///
+/// The in-flight request is dropped on timeout and `TideError::Timeout` is
+/// returned, so the caller can render from the fallback model immediately.
+pub async fn fetch_with_deadline(
+    config: &Config,
+    deadline: std::time::Duration,
+) -> Result<TideSeries, TideError> {
+    match tokio::time::timeout(deadline, fetch_from(config, &config.station, Utc::now())).await {
+        Ok(result) => result,
+        Err(_) => Err(TideError::Timeout(deadline)),
+    }
+}
+
+/// Fetch tide series for any configured station, not just the primary one.
+///
+/// Each station gets its own cache file per provider (see
+/// [`Config::cache_path_for`]) and is bounded by its own `fetch_timeout_secs`.
+pub async fn fetch_station(
+    config: &Config,
+    station: &StationConfig,
+) -> Result<TideSeries, TideError> {
+    fetch_station_at(config, station, Utc::now()).await
+}
+
+/// [`fetch_station`] with the window centered on `now` rather than the
+/// system clock, for tests and replays pinned to a moment
+///
+/// The cache still expires by the age of its file; a cached series that
+/// doesn't reach `now` is fetched again.
+pub async fn fetch_station_at(
+    config: &Config,
+    station: &StationConfig,
+    now: DateTime<Utc>,
+) -> Result<TideSeries, TideError> {
+    let deadline = station.fetch_deadline();
+    match tokio::time::timeout(deadline, fetch_from(config, station, now)).await {
+        Ok(result) => result,
+        Err(_) => Err(TideError::Timeout(deadline)),
+    }
+}
+
+/// Fetch tide series until `cancel` completes, whichever comes first.
+///
+/// # Example
+/// ```no_run
+/// use tide_clock_lib::{config::Config, tide_data::fetch_cancellable};
+///
+/// # async fn example() {
+/// let config = Config::default();
+/// let shutdown = tokio::time::sleep(std::time::Duration::from_secs(5));
+/// let result = fetch_cancellable(&config, shutdown).await;
+/// # }
+/// ```
+pub async fn fetch_cancellable<C>(config: &Config, cancel: C) -> Result<TideSeries, TideError>
+where
+    C: Future<Output = ()>,
+{
+    tokio::select! {
+        biased;
+        _ = cancel => Err(TideError::Cancelled),
+        result = fetch_from(config, &config.station, Utc::now()) => result,
+    }
+}
 
-    // Try cache first - much faster than network fetch
-    if let Ok(series) = load_cache() {
-        return Ok(series);
+/// Cache-first fetch without any deadline of its own.
+async fn fetch_from(
+    config: &Config,
+    station: &StationConfig,
+    now: DateTime<Utc>,
+) -> Result<TideSeries, TideError> {
+    // Try cache first - much faster than network fetch - in the order the
+    // providers are preferred. Re-anchor cached samples to the current time
+    // so the "now" marker stays accurate. A cache saved for a different
+    // `time_window_hours` or `sample_interval_minutes` doesn't count.
+    let spec = config.display.series_spec(now);
+    for &provider in &station.providers {
+        let cache_path = &config.cache_path_for(&station.id, provider);
+        let ttl = config.display.cache_ttl();
+        let Ok(series) = load_cache(cache_path, ttl, &station.id, provider).await else {
+            continue;
+        };
+        if fits_spec(&series, spec) {
+            // Caches from before base times were stored are taken as they are
+            let rebased = match series.base_time {
+                Some(_) => series.rebased(now),
+                None => Some(series),
+            };
+            if let Some(series) = rebased.filter(|s| s.height_now().is_some()) {
+                return Ok(from_cache(series, now));
+            }
+        }
     }
 
-    // Cache miss or stale - fetch fresh data from NOAA
-    let series = scrape_noaa(&config).await?;
+    // Cache miss or stale - fetch fresh data, racing providers if several are configured
+    let station = &StationConfig {
+        harmonics: config.harmonics_for(station),
+        model: Some(config.model_for(station)),
+        ..station.clone()
+    };
+    let providers: Vec<_> = station.providers.iter().map(|p| p.build()).collect();
+    let (name, series) =
+        provider::race(&providers, station, spec, station.race_head_start()).await?;
 
-    // Save for future requests (ignore cache write failures)
-    let _ = save_cache(&series);
+    // Save real data for future requests under the provider that produced it
+    // (ignore cache write failures); an offline model result, or real data
+    // partly backfilled from the model, must not keep a complete NOAA
+    // response out for a whole TTL
+    let winner = station.providers.iter().find(|p| p.name() == name);
+    let partly_modeled = series.is_offline() || series.is_backfilled();
+    if let (Some(&provider), false) = (winner, partly_modeled) {
+        let cache_path = &config.cache_path_for(&station.id, provider);
+        let _ = save_cache(cache_path, &station.id, provider, &series).await;
+    }
 
     Ok(series)
 }
 
+/// Whether a cached `series` has the window and step `spec` asks for, i.e.
+/// was saved under the same `time_window_hours` and `sample_interval_minutes`
+fn fits_spec(series: &TideSeries, spec: crate::grid::SeriesSpec) -> bool {
+    series.half_width() == Some(spec.window.half_width) && series.interval() == Some(spec.interval)
+}
+
+/// Last cached series for `station` regardless of age, re-anchored to `now`
+///
+/// For rendering something real when a refresh runs out of time. Returns
+/// `None` when there is no readable cache, it was saved for another window
+/// or step, or it no longer covers `now`, and always without the `cache`
+/// feature.
+#[cfg(feature = "cache")]
+pub async fn load_stale(
+    config: &Config,
+    station: &StationConfig,
+    now: DateTime<Utc>,
+) -> Option<TideSeries> {
+    let spec = config.display.series_spec(now);
+    for &provider in &station.providers {
+        let path = config.cache_path_for(&station.id, provider);
+        let Ok(data) = tokio::fs::read(&path).await else {
+            continue;
+        };
+        let Some(series) = decode_cache_for(&data, &station.id, provider)
+            .ok()
+            .filter(|s| fits_spec(s, spec))
+            .and_then(|s| s.rebased(now))
+        else {
+            continue;
+        };
+        let covers_now = series.samples.first().is_some_and(|s| s.mins_rel <= 0)
+            && series.samples.last().is_some_and(|s| s.mins_rel >= 0);
+        if covers_now {
+            return Some(from_cache(series, now));
+        }
+    }
+    None
+}
+
+/// `series` as read back from the cache at `now`, marked with how long ago
+/// it was fetched (0 for caches from before fetch times were kept)
+///
+/// Offline model data, which only very old versions cached, stays marked
+/// as such.
+fn from_cache(series: TideSeries, now: DateTime<Utc>) -> TideSeries {
+    if series.is_offline() {
+        return series;
+    }
+    let age_mins = series.fetched_at.map_or(0, |at| {
+        (now - at).num_minutes().clamp(0, u32::MAX as i64) as u32
+    });
+    TideSeries {
+        source: DataSource::Cache { age_mins },
+        ..series
+    }
+}
+
+/// Last cached series for `station` as stored, however old
+///
+/// What the offline model [calibrates](crate::fallback::Calibration) itself
+/// against. Blocking, it's a single small file.
+#[cfg(feature = "cache")]
+pub fn last_fetched(config: &Config, station: &StationConfig) -> Option<TideSeries> {
+    station.providers.iter().find_map(|&provider| {
+        let data = std::fs::read(config.cache_path_for(&station.id, provider)).ok()?;
+        decode_cache_for(&data, &station.id, provider).ok()
+    })
+}
+
+#[cfg(not(feature = "cache"))]
+pub async fn load_stale(
+    _config: &Config,
+    _station: &StationConfig,
+    _now: DateTime<Utc>,
+) -> Option<TideSeries> {
+    None
+}
+
+#[cfg(not(feature = "cache"))]
+pub fn last_fetched(_config: &Config, _station: &StationConfig) -> Option<TideSeries> {
+    None
+}
+
 // -- Private Implementation --
 
 /// Fetch tide predictions from NOAA API and convert to TideSeries.
@@ -140,15 +419,17 @@ pub async fn fetch() -> Result<TideSeries, TideError> {
 /// This function uses NOAA's official CO-OPS API instead of HTML scraping:
 /// 1. Downloads JSON data from NOAA API
 /// 2. Parses the structured tide predictions
-/// 3. Converts hourly data points to 10-minute interpolated samples
-/// 4. Returns a complete 24-hour TideSeries
+/// 3. Converts hourly data points to interpolated samples every
+///    `sample_interval_minutes`
+/// 4. Returns a TideSeries covering the whole window
 ///
 /// # API Configuration
 /// Uses NOAA CO-OPS API v1 with the following parameters:
 /// - Station: 8418150 (Portland, ME)
 /// - Product: predictions (tide predictions)
-/// - Datum: MLLW (Mean Lower Low Water)
-/// - Time zone: lst_ldt (Local Standard/Daylight Time)
+/// - Datum: the station's configured datum (MLLW by default)
+/// - Time zone: gmt, so the times don't depend on `station.timezone` or
+///   the Pi's own
 /// - Units: english (feet)
 /// - Format: json
 ///
@@ -156,20 +437,64 @@ pub async fn fetch() -> Result<TideSeries, TideError> {
 /// ```text
 /// https://api.tidesandcurrents.noaa.gov/api/prod/datagetter?
 /// product=predictions&station=8418150&begin_date=20240616&end_date=20240617&
-/// datum=MLLW&time_zone=lst_ldt&units=english&format=json
+/// datum=MLLW&time_zone=gmt&units=english&format=json
 /// ```
 ///
-/// # Interpolation Algorithm
-/// Linear interpolation between adjacent hourly points:
-/// ```text
-/// tide_height = h1 + (h2 - h1) * (t - t1) / (t2 - t1)
-/// ```
-/// This provides smooth 10-minute samples suitable for curve visualization.
-async fn scrape_noaa(config: &Config) -> Result<TideSeries, TideError> {
-    // Calculate date range: yesterday to tomorrow (ensures we have enough data)
-    let now = Local::now();
-    let yesterday = now - Duration::days(1);
-    let tomorrow = now + Duration::days(1);
+/// # Interpolation
+/// Hourly points are interpolated onto the display grid with
+/// [`grid::resample`], a monotone cubic that rounds highs and lows without
+/// overshooting them.
+#[cfg(feature = "net")]
+pub(crate) async fn scrape_noaa(
+    station: &StationConfig,
+    spec: grid::SeriesSpec,
+) -> Result<TideSeries, TideError> {
+    // Fetch JSON data from API with rustls TLS backend
+    let client = reqwest::Client::builder()
+        .timeout(station.fetch_deadline())
+        .build()?;
+    scrape_noaa_with(&client, station, spec, Utc::now()).await
+}
+
+/// Boxed future returned by [`HttpClient::get_text`]
+#[cfg(feature = "net")]
+pub(crate) type GetFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<String, TideError>> + Send + 'a>>;
+
+/// The HTTP layer under [`scrape_noaa`], so tests can answer with recorded
+/// responses (`tests/fixtures/noaa`) instead of the live API
+#[cfg(feature = "net")]
+pub(crate) trait HttpClient: Send + Sync {
+    /// Body of the response to a GET of `url`
+    fn get_text<'a>(&'a self, url: &'a str) -> GetFuture<'a>;
+}
+
+#[cfg(feature = "net")]
+impl HttpClient for reqwest::Client {
+    fn get_text<'a>(&'a self, url: &'a str) -> GetFuture<'a> {
+        Box::pin(async move { Ok(self.get(url).send().await?.text().await?) })
+    }
+}
+
+/// [`scrape_noaa`] through `http`, recording the series as fetched at
+/// `fetched_at`
+///
+/// That's the moment of the request, not the window's center, which a
+/// pinned `--at` moves.
+#[cfg(feature = "net")]
+pub(crate) async fn scrape_noaa_with(
+    http: &dyn HttpClient,
+    station: &StationConfig,
+    spec: grid::SeriesSpec,
+    fetched_at: DateTime<Utc>,
+) -> Result<TideSeries, TideError> {
+    // Calculate date range: yesterday to tomorrow in UTC, the zone the
+    // dates are read in with `time_zone=gmt`, or further for a wider window
+    // (ensures we have enough data)
+    let center = spec.window.center;
+    let reach = spec.window.half_width.max(Duration::days(1));
+    let yesterday = center - reach;
+    let tomorrow = center + reach;
 
     // Format dates for API (YYYYMMDD)
     let begin_date = yesterday.format("%Y%m%d").to_string();
@@ -179,143 +504,292 @@ async fn scrape_noaa(config: &Config) -> Result<TideSeries, TideError> {
     let url = format!(
         "https://api.tidesandcurrents.noaa.gov/api/prod/datagetter?\
         product=predictions&station={}&begin_date={}&end_date={}&\
-        datum=MLLW&time_zone=lst_ldt&units=english&format=json",
-        config.station.id, begin_date, end_date
+        datum={}&time_zone=gmt&units=english&format=json",
+        station.id,
+        begin_date,
+        end_date,
+        station.datum.api_code()
     );
 
-    // Fetch JSON data from API with rustls TLS backend
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
+    let response = http.get_text(&url).await?;
+    let hourly = parse_gmt_predictions(&response)?;
+
+    // Interpolate hourly data to the display grid when the window is well
+    // covered by real data, highs and lows through tomorrow going along for
+    // the footer; otherwise fill what's missing from the offline model
+    let builder = match TideSeriesBuilder::from_points(hourly.clone(), spec) {
+        Some(builder) => builder,
+        None => {
+            let model = offline_model(station, spec);
+            let builder =
+                TideSeriesBuilder::backfilled(hourly, spec, &model).ok_or(TideError::Scrape)?;
+            info!(
+                "NOAA response covers only part of the window for {}, backfilling from the model",
+                station.name
+            );
+            builder
+        }
+    };
+    let mut series = builder.fetched_at(fetched_at).build()?;
 
-    let response = client.get(&url).send().await?.text().await?;
+    // Reject or repair bad data before it can be cached
+    let repaired = series.validate()?;
+    if repaired > 0 {
+        info!("Repaired {} implausible samples in NOAA response", repaired);
+    }
+    Ok(series)
+}
+
+/// The station's offline prediction for `spec`, to backfill a partial
+/// response with: its harmonic constants when they load, the simple model
+/// otherwise
+#[cfg(feature = "net")]
+fn offline_model(station: &StationConfig, spec: grid::SeriesSpec) -> TideSeries {
+    match station.harmonics.as_ref().map(|h| h.load()) {
+        Some(Ok(harmonics)) => harmonics.series_in(spec),
+        _ => station.model.unwrap_or_default().series_in(spec),
+    }
+}
 
+/// `(time, height)` points of a NOAA predictions response requested with
+/// `time_zone=gmt`, as [`scrape_noaa`] does
+///
+/// The response is `{"predictions": [{"t": "2024-06-16 15:00", "v": "3.2"}, ...]}`
+/// with heights in feet.
+#[cfg(feature = "net")]
+pub(crate) fn parse_gmt_predictions(
+    response: &str,
+) -> Result<Vec<(chrono::DateTime<Utc>, f32)>, TideError> {
+    parse_predictions_with(response, |naive, _| Some(naive.and_utc()))
+}
+
+/// [`parse_gmt_predictions`] for a response with station-local times
+/// (`time_zone=lst_ldt`), such as one saved for the file provider
+///
+/// Times the clocks skip when they go forward aren't expected, and one
+/// missing hour is harmless; ones repeated when they go back come in order.
+pub(crate) fn parse_predictions(
+    response: &str,
+    station: &StationConfig,
+) -> Result<Vec<(chrono::DateTime<Utc>, f32)>, TideError> {
+    parse_predictions_with(response, |naive, previous| {
+        station.from_local_after(naive, previous)
+    })
+}
+
+/// Points of a predictions response, each time turned into an instant by
+/// `resolve` given the one before; times it drops are skipped
+///
+/// NOAA's `error` object, e.g. for a datum the station doesn't support, is
+/// a [`TideError::Api`] with its message.
+fn parse_predictions_with(
+    response: &str,
+    resolve: impl Fn(
+        chrono::NaiveDateTime,
+        Option<chrono::DateTime<Utc>>,
+    ) -> Option<chrono::DateTime<Utc>>,
+) -> Result<Vec<(chrono::DateTime<Utc>, f32)>, TideError> {
     // Parse JSON response
-    let json: serde_json::Value = serde_json::from_str(&response).map_err(|_| TideError::Scrape)?;
+    let json: serde_json::Value = serde_json::from_str(response).map_err(|_| TideError::Scrape)?;
+    if let Some(message) = json["error"]["message"].as_str() {
+        return Err(TideError::Api(message.trim().to_string()));
+    }
 
     // Extract predictions array
     let predictions = json["predictions"].as_array().ok_or(TideError::Scrape)?;
 
     // Parse predictions into (datetime, height) pairs
-    let mut hourly = Vec::<(chrono::DateTime<Local>, f32)>::new();
+    let mut points = Vec::with_capacity(predictions.len());
     for prediction in predictions {
         let time_str = prediction["t"].as_str().ok_or(TideError::Scrape)?;
         let height_str = prediction["v"].as_str().ok_or(TideError::Scrape)?;
 
         // Parse datetime (format: "2024-06-16 15:00")
-        let dt = chrono::NaiveDateTime::parse_from_str(time_str, "%Y-%m-%d %H:%M")
-            .map_err(|_| TideError::Scrape)?
-            .and_local_timezone(Local)
-            .single()
-            .ok_or(TideError::Scrape)?;
+        let naive = chrono::NaiveDateTime::parse_from_str(time_str, "%Y-%m-%d %H:%M")
+            .map_err(|_| TideError::Scrape)?;
+        let previous = points.last().map(|&(t, _)| t);
+        let Some(dt) = resolve(naive, previous) else {
+            continue;
+        };
 
         // Parse tide height
         let ft: f32 = height_str.parse().map_err(|_| TideError::Scrape)?;
 
-        hourly.push((dt, ft));
+        points.push((dt, ft));
     }
+    Ok(points)
+}
 
-    // Verify we got enough data (should have ~48 hours worth)
-    if hourly.len() < 24 {
-        return Err(TideError::Scrape);
-    }
-
-    // Sort by time to ensure chronological order
-    hourly.sort_by_key(|&(dt, _)| dt);
-
-    // Find data closest to our 24-hour window (-12h to +12h from now)
-    let start_time = now - Duration::hours(12);
-    let end_time = now + Duration::hours(12);
+/// Load `station_id`'s series from `provider` from cache file if still valid.
+///
+/// Checks file modification time against TTL before decoding. Returns error
+/// for stale, missing, or corrupted cache files; files written by an unknown
+/// schema version or holding another station's data are removed so the next
+/// fetch replaces them.
+#[cfg(feature = "cache")]
+async fn load_cache(
+    path: &Path,
+    ttl: std::time::Duration,
+    station_id: &str,
+    provider: ProviderKind,
+) -> Result<TideSeries, io::Error> {
+    let meta = tokio::fs::metadata(path).await?;
 
-    // Filter to our time window and ensure we have enough points
-    let filtered: Vec<_> = hourly
-        .into_iter()
-        .filter(|(dt, _)| *dt >= start_time && *dt <= end_time)
-        .collect();
+    // Check if cache has expired based on file modification time
+    let age = SystemTime::now()
+        .duration_since(meta.modified()?)
+        .map_err(|_| io::Error::other("time error"))?;
 
-    if filtered.len() < 20 {
-        return Err(TideError::Scrape);
+    if age > ttl {
+        return Err(io::Error::other("stale"));
     }
 
-    // Interpolate hourly data to 10-minute grid
-    let start = now - Duration::hours(12);
-    let mut samples = Vec::with_capacity(145);
-
-    // Generate 145 samples: 0, 10, 20, ..., 1440 minutes (24 hours)
-    for step in 0..=144 {
-        let ts = start + Duration::minutes(step * 10);
-
-        // Find the hourly interval containing this timestamp
-        let (p0, p1) = filtered
-            .windows(2)
-            .find(|w| w[0].0 <= ts && ts <= w[1].0)
-            .map(|w| (&w[0], &w[1]))
-            .unwrap_or((&filtered[0], &filtered[filtered.len() - 1]));
+    let data = tokio::fs::read(path).await?;
+    match decode_cache_for(&data, station_id, provider) {
+        Ok(series) => Ok(series),
+        Err(e) => {
+            // Incompatible or corrupt: drop it rather than failing on every run
+            let _ = tokio::fs::remove_file(path).await;
+            Err(e)
+        }
+    }
+}
 
-        // Linear interpolation: alpha = 0.0 at p0, 1.0 at p1
-        let duration_secs = (p1.0 - p0.0).num_seconds();
-        let alpha = if duration_secs > 0 {
-            (ts - p0.0).num_seconds() as f32 / duration_secs as f32
-        } else {
-            0.0
-        };
-        let alpha = alpha.clamp(0.0, 1.0);
-        let ft = p0.1 + alpha * (p1.1 - p0.1);
+/// Save `station_id`'s series from `provider` to cache file for future use.
+///
+/// Writes the versioned binary format. Creates the parent directory if
+/// needed so persistent locations work out of the box. Failure to write
+/// cache is non-fatal - the application continues with fresh data.
+#[cfg(feature = "cache")]
+async fn save_cache(
+    path: &Path,
+    station_id: &str,
+    provider: ProviderKind,
+    series: &TideSeries,
+) -> Result<(), io::Error> {
+    let key = CacheKey {
+        station_id: station_id.to_string(),
+        provider,
+    };
+    let data = encode_cache(&key, series)?;
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(path, data).await?;
+    Ok(())
+}
 
-        // Calculate minutes relative to "now" for display positioning
-        let mins_rel = (ts - now).num_minutes() as i16;
+/// Serialize a series as `MAGIC | VERSION | postcard((key, series))`.
+#[cfg(feature = "cache")]
+fn encode_cache(key: &CacheKey, series: &TideSeries) -> Result<Vec<u8>, io::Error> {
+    let mut data = Vec::with_capacity(1024);
+    data.extend_from_slice(CACHE_MAGIC);
+    data.push(CACHE_VERSION);
+    let payload = postcard::to_allocvec(&(key, series)).map_err(io::Error::other)?;
+    data.extend_from_slice(&payload);
+    Ok(data)
+}
 
-        samples.push(Sample {
-            mins_rel,
-            tide_ft: ft,
-        });
+/// [`decode_cache`], failing with `InvalidData` when the file holds another
+/// station's or provider's series
+#[cfg(feature = "cache")]
+fn decode_cache_for(
+    data: &[u8],
+    station_id: &str,
+    provider: ProviderKind,
+) -> Result<TideSeries, io::Error> {
+    let (key, series) = decode_cache(data)?;
+    if key.station_id != station_id || key.provider != provider {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "cache holds {} data for station {}",
+                key.provider.name(),
+                key.station_id
+            ),
+        ));
     }
-
-    Ok(TideSeries {
-        samples,
-        offline: false,
-    })
+    Ok(series)
 }
 
-/// Load tide series from cache file if still valid.
+/// Decode a cache file and the key it was saved under
 ///
-/// Checks file modification time against TTL before deserializing.
-/// Returns error for stale, missing, or corrupted cache files.
-fn load_cache() -> Result<TideSeries, io::Error> {
-    let meta = fs::metadata(CACHE)?;
-
-    // Check if cache has expired based on file modification time
-    let age = SystemTime::now()
-        .duration_since(meta.modified()?)
-        .map_err(|_| io::Error::other("time error"))?
-        .as_secs();
-
-    if age > TTL {
-        return Err(io::Error::other("stale"));
+/// Anything but the current [`CACHE_VERSION`] is `InvalidData`.
+#[cfg(feature = "cache")]
+fn decode_cache(data: &[u8]) -> Result<(CacheKey, TideSeries), io::Error> {
+    let header_len = CACHE_MAGIC.len() + 1;
+    if data.len() < header_len || &data[..CACHE_MAGIC.len()] != CACHE_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a tide cache file",
+        ));
     }
 
-    // Deserialize cached data (binary JSON format)
-    let data = fs::read(CACHE)?;
-    let series = serde_json::from_slice(&data)?;
+    match data[CACHE_MAGIC.len()] {
+        CACHE_VERSION => postcard::from_bytes(&data[header_len..])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        other => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported cache version {other}"),
+        )),
+    }
+}
 
-    Ok(series)
+/// Without the `cache` feature nothing is written, so every cache is a miss
+#[cfg(not(feature = "cache"))]
+async fn load_cache(
+    _path: &Path,
+    _ttl: std::time::Duration,
+    _station_id: &str,
+    _provider: ProviderKind,
+) -> Result<TideSeries, io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "built without the cache feature",
+    ))
 }
 
-/// Save tide series to cache file for future use.
-///
-/// Uses binary JSON serialization for compact storage. Failure to write
-/// cache is non-fatal - the application continues with fresh data.
-fn save_cache(series: &TideSeries) -> Result<(), io::Error> {
-    let data = serde_json::to_vec(series)?;
-    fs::write(CACHE, data)?;
+#[cfg(not(feature = "cache"))]
+async fn save_cache(
+    _path: &Path,
+    _station_id: &str,
+    _provider: ProviderKind,
+    _series: &TideSeries,
+) -> Result<(), io::Error> {
     Ok(())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "cache"))]
 mod tests {
     use super::*;
+    use crate::Sample;
+    use chrono::Duration;
+    use std::fs;
     use tempfile::NamedTempFile;
 
+    const ID: &str = "8418150";
+
+    /// Test helper: the key [`save_cache`] writes for station `ID` from NOAA
+    fn key() -> CacheKey {
+        CacheKey {
+            station_id: ID.to_string(),
+            provider: ProviderKind::Noaa,
+        }
+    }
+
+    /// Test helper: cache `series` as `station`'s data from `provider`
+    async fn save_for(
+        config: &Config,
+        station: &StationConfig,
+        provider: ProviderKind,
+        series: &TideSeries,
+    ) {
+        let path = config.cache_path_for(&station.id, provider);
+        save_cache(&path, &station.id, provider, series)
+            .await
+            .unwrap();
+    }
+
     /// Test helper: create a sample TideSeries for testing
     fn sample_series() -> TideSeries {
         TideSeries {
@@ -323,17 +797,23 @@ mod tests {
                 Sample {
                     mins_rel: -10,
                     tide_ft: 2.0,
+                    modeled: false,
                 },
                 Sample {
                     mins_rel: 0,
                     tide_ft: 3.0,
+                    modeled: false,
                 },
                 Sample {
                     mins_rel: 10,
                     tide_ft: 4.0,
+                    modeled: false,
                 },
             ],
-            offline: false,
+            source: DataSource::Noaa,
+            base_time: None,
+            extremes: None,
+            fetched_at: None,
         }
     }
 
@@ -352,6 +832,488 @@ mod tests {
         let loaded = serde_json::from_slice::<TideSeries>(&fs::read(cache_path).unwrap()).unwrap();
 
         assert_eq!(loaded.samples.len(), series.samples.len());
-        assert_eq!(loaded.offline, series.offline);
+        assert_eq!(loaded.source, series.source);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cancellable_stops_immediately() {
+        let config = Config::default();
+        let result = fetch_cancellable(&config, async {}).await;
+        assert!(matches!(result, Err(TideError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn test_cache_in_persistent_subdirectory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("var/cache/tide-tracker/tide_cache.bin");
+
+        save_cache(&path, ID, ProviderKind::Noaa, &sample_series())
+            .await
+            .unwrap();
+        let loaded = load_cache(
+            &path,
+            std::time::Duration::from_secs(60),
+            ID,
+            ProviderKind::Noaa,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(loaded.samples.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_missing_cache_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.json");
+        assert!(load_cache(
+            &path,
+            std::time::Duration::from_secs(60),
+            ID,
+            ProviderKind::Noaa
+        )
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn test_binary_cache_roundtrip() {
+        let series = sample_series();
+        let data = encode_cache(&key(), &series).unwrap();
+        assert_eq!(&data[..4], CACHE_MAGIC);
+        assert_eq!(data[4], CACHE_VERSION);
+        assert!(data.len() < serde_json::to_vec(&series).unwrap().len());
+
+        let (saved_for, decoded) = decode_cache(&data).unwrap();
+        assert_eq!(saved_for, key());
+        assert_eq!(decoded.samples.len(), series.samples.len());
+        assert_eq!(decoded.samples[1].tide_ft, 3.0);
+        assert_eq!(decoded.source, DataSource::Noaa);
+
+        // Anything without the magic, JSON included, is refetched
+        let json = serde_json::to_vec(&series).unwrap();
+        let err = decode_cache(&json).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_cached_series_shows_its_age() {
+        let now = Utc::now();
+        let fetched = TideSeries {
+            fetched_at: Some(now - Duration::minutes(25)),
+            ..sample_series()
+        };
+        let cached = from_cache(fetched, now);
+        assert_eq!(cached.source.to_string(), "CACHED 25m");
+        assert!(!cached.is_offline());
+        // Caches from before fetch times were kept are taken as fresh
+        assert_eq!(
+            from_cache(sample_series(), now).source.to_string(),
+            "CACHED 0m"
+        );
+        // Offline data stays offline
+        let model = crate::fallback::approximate(Some(now));
+        assert!(from_cache(model, now).is_offline());
+    }
+
+    #[test]
+    fn test_unknown_cache_version_rejected() {
+        let mut data = encode_cache(&key(), &sample_series()).unwrap();
+        data[4] = CACHE_VERSION + 1;
+        assert!(decode_cache(&data).is_err());
+        assert!(decode_cache(b"junk").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_cache_is_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tide_cache.bin");
+        fs::write(&path, b"TIDE\xFFgarbage").unwrap();
+
+        assert!(load_cache(
+            &path,
+            std::time::Duration::from_secs(60),
+            ID,
+            ProviderKind::Noaa
+        )
+        .await
+        .is_err());
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_cache_for_another_station_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.display.cache_path = dir.path().join("tide_cache.bin");
+        let portland = config.station.clone();
+        let mut boothbay = portland.clone();
+        boothbay.id = "8414612".to_string();
+
+        // Portland's data copied over Boothbay's file
+        save_for(&config, &portland, ProviderKind::Noaa, &sample_series()).await;
+        let path = config.cache_path_for(&boothbay.id, ProviderKind::Noaa);
+        fs::copy(
+            config.cache_path_for(&portland.id, ProviderKind::Noaa),
+            &path,
+        )
+        .unwrap();
+
+        let data = fs::read(&path).unwrap();
+        let error = decode_cache_for(&data, &boothbay.id, ProviderKind::Noaa).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cache holds noaa data for station 8418150"
+        );
+        assert!(decode_cache_for(&data, &portland.id, ProviderKind::File).is_err());
+        assert!(decode_cache_for(&data, &portland.id, ProviderKind::Noaa).is_ok());
+        assert!(last_fetched(&config, &boothbay).is_none());
+
+        let ttl = std::time::Duration::from_secs(60);
+        assert!(load_cache(&path, ttl, &boothbay.id, ProviderKind::Noaa)
+            .await
+            .is_err());
+        assert!(!path.exists());
+        // Portland's own file is untouched
+        assert!(last_fetched(&config, &portland).is_some());
+    }
+
+    #[test]
+    fn test_earlier_cache_versions_are_misses() {
+        // A keyless series under an older version byte isn't trusted for
+        // any station
+        let mut data = CACHE_MAGIC.to_vec();
+        data.push(CACHE_VERSION - 1);
+        data.extend(postcard::to_allocvec(&sample_series()).unwrap());
+        assert!(decode_cache(&data).is_err());
+        assert!(decode_cache_for(&data, ID, ProviderKind::Noaa).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_stale_ignores_ttl_but_needs_now_covered() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.display.cache_path = dir.path().join("tide_cache.bin");
+        let station = config.station.clone();
+        let now = Utc::now();
+        assert!(load_stale(&config, &station, now).await.is_none());
+
+        // Saved two hours ago: past any TTL, but still spans now
+        let series = TideSeries {
+            base_time: Some(now - Duration::hours(2)),
+            ..crate::fallback::approximate(None)
+        };
+        save_for(&config, &station, ProviderKind::Noaa, &series).await;
+        let stale = load_stale(&config, &station, now).await.unwrap();
+        assert_eq!(stale.samples[0].mins_rel, -840);
+        assert_eq!(stale.samples[144].mins_rel, 600);
+
+        let ancient = TideSeries {
+            base_time: Some(now - Duration::hours(13)),
+            ..series
+        };
+        save_for(&config, &station, ProviderKind::Noaa, &ancient).await;
+        assert!(load_stale(&config, &station, now).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_load_stale_needs_the_configured_window_and_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.display.cache_path = dir.path().join("tide_cache.bin");
+        let station = config.station.clone();
+        let now = Utc::now();
+
+        // 12 hours either side every 10 minutes, saved an hour ago
+        let series = TideSeries {
+            base_time: Some(now - Duration::hours(1)),
+            ..crate::fallback::approximate(None)
+        };
+        save_for(&config, &station, ProviderKind::Noaa, &series).await;
+        assert!(load_stale(&config, &station, now).await.is_some());
+
+        config.display.sample_interval_minutes = 5;
+        assert!(load_stale(&config, &station, now).await.is_none());
+        config.display.sample_interval_minutes = 10;
+        config.display.time_window_hours = 6;
+        assert!(load_stale(&config, &station, now).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_pinned_to_a_moment() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.display.cache_path = dir.path().join("tide_cache.bin");
+        let mut station = config.station.clone();
+        station.providers = vec![crate::provider::ProviderKind::Model];
+        let pinned = chrono::TimeZone::with_ymd_and_hms(&Utc, 2025, 7, 1, 12, 0, 0).unwrap();
+
+        let series = fetch_station_at(&config, &station, pinned).await.unwrap();
+        assert_eq!(series.base_time, Some(pinned));
+        let model = crate::fallback::ModelConstants::default().series(pinned);
+        assert_eq!(series.samples.len(), model.samples.len());
+        assert!(series
+            .samples
+            .iter()
+            .zip(&model.samples)
+            .all(|(a, b)| a.mins_rel == b.mins_rel && a.tide_ft == b.tide_ft));
+
+        // A fresh cache of today's data doesn't reach back to the pinned day
+        let today = TideSeries {
+            source: DataSource::Noaa,
+            ..crate::fallback::approximate(None)
+        };
+        save_for(&config, &station, ProviderKind::Model, &today).await;
+        let series = fetch_station_at(&config, &station, pinned).await.unwrap();
+        assert!(series.is_offline());
+        assert_eq!(series.base_time, Some(pinned));
+    }
+
+    #[tokio::test]
+    async fn test_offline_model_calibrates_to_last_fetch() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.display.cache_path = dir.path().join("tide_cache.bin");
+        let station = config.station.clone();
+
+        // Fetched a day ago: too old to show, recent enough to calibrate from
+        let now = Utc::now();
+        let fetched_at = now - Duration::hours(24);
+        let real = TideSeries {
+            samples: crate::grid::generate(
+                crate::grid::Window::standard(fetched_at),
+                Duration::minutes(crate::grid::INTERVAL_MINS),
+                |m| 2.0 + 1.5 * (m as f32 / 60.0 * std::f32::consts::TAU / 12.42).cos(),
+            ),
+            source: DataSource::Noaa,
+            base_time: Some(fetched_at),
+            extremes: None,
+            fetched_at: None,
+        };
+        save_for(&config, &station, ProviderKind::Noaa, &real).await;
+        assert!(load_stale(&config, &station, now).await.is_none());
+
+        let offline = crate::fallback::for_station(&config, &station, Some(now));
+        assert!(offline.is_offline());
+        let (low, high) = (offline.min_height().unwrap(), offline.max_height().unwrap());
+        assert!(
+            (low - 0.5).abs() < 0.1 && (high - 3.5).abs() < 0.1,
+            "{low}..{high}"
+        );
+
+        // Too old to trust: back to the generic model
+        let much_later = now + Duration::hours(72);
+        let generic = crate::fallback::for_station(&config, &station, Some(much_later));
+        let approximate = crate::fallback::approximate(Some(much_later));
+        let heights = |s: &TideSeries| s.samples.iter().map(|s| s.tide_ft).collect::<Vec<_>>();
+        assert_eq!(heights(&generic), heights(&approximate));
+    }
+}
+
+/// `scrape_noaa` against recorded NOAA responses in `tests/fixtures/noaa`
+#[cfg(all(test, feature = "net"))]
+mod fixture_tests {
+    use super::*;
+    use crate::grid::SeriesSpec;
+    use chrono::{DateTime, TimeZone};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    const NORMAL: &str = include_str!("../tests/fixtures/noaa/normal.json");
+    const SPARSE: &str = include_str!("../tests/fixtures/noaa/sparse.json");
+    const MALFORMED: &str = include_str!("../tests/fixtures/noaa/malformed.json");
+    const NO_DATA: &str = include_str!("../tests/fixtures/noaa/no_data.json");
+    const DST_SPRING: &str = include_str!("../tests/fixtures/noaa/dst_spring.json");
+    const DST_FALL: &str = include_str!("../tests/fixtures/noaa/dst_fall.json");
+
+    /// Answers every request with one recorded response, keeping the URLs
+    struct Recorded {
+        body: &'static str,
+        urls: Mutex<Vec<String>>,
+    }
+
+    impl HttpClient for Recorded {
+        fn get_text<'a>(&'a self, url: &'a str) -> GetFuture<'a> {
+            self.urls.lock().unwrap().push(url.to_string());
+            Box::pin(async move { Ok(self.body.to_string()) })
+        }
+    }
+
+    fn portland() -> StationConfig {
+        let mut station = Config::default().station;
+        station.timezone = Some(chrono_tz::America::New_York);
+        station
+    }
+
+    /// Scrape `body` for the standard window around `center`, returning the
+    /// requested URL too
+    async fn scrape(
+        body: &'static str,
+        center: DateTime<Utc>,
+    ) -> (Result<TideSeries, TideError>, String) {
+        let http = Recorded {
+            body,
+            urls: Mutex::new(Vec::new()),
+        };
+        let spec = SeriesSpec::standard(center);
+        let result = scrape_noaa_with(&http, &portland(), spec, fetched_at()).await;
+        let url = http.urls.into_inner().unwrap().swap_remove(0);
+        (result, url)
+    }
+
+    /// When the fixtures are "fetched", months after the days they cover
+    fn fetched_at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 12, 1, 9, 30, 0).unwrap()
+    }
+
+    /// Every sample landing on a point of `body` has that point's height,
+    /// and no two neighbouring samples are further apart than the tide can
+    /// move in 10 minutes
+    fn assert_follows(series: &TideSeries, body: &str) {
+        let points: HashMap<_, _> = parse_gmt_predictions(body).unwrap().into_iter().collect();
+        let mut matched = 0;
+        for sample in &series.samples {
+            if let Some(ft) = points.get(&series.time_of(sample).unwrap()) {
+                assert!((sample.tide_ft - ft).abs() < 0.001, "{sample:?} vs {ft}");
+                matched += 1;
+            }
+        }
+        assert!(matched >= 20, "only {matched} samples on response points");
+        for pair in series.samples.windows(2) {
+            assert!((pair[1].tide_ft - pair[0].tide_ft).abs() < 0.5, "{pair:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_normal_day() {
+        // Noon EDT
+        let center = Utc.with_ymd_and_hms(2025, 7, 1, 16, 0, 0).unwrap();
+        let (series, url) = scrape(NORMAL, center).await;
+        let series = series.unwrap();
+        assert!(url.contains("station=8418150"), "{url}");
+        assert!(
+            url.contains("begin_date=20250630&end_date=20250702"),
+            "{url}"
+        );
+        assert!(url.contains("datum=MLLW"), "{url}");
+        assert!(url.contains("time_zone=gmt"), "{url}");
+
+        assert_eq!(series.samples.len(), 145);
+        assert_eq!(series.base_time, Some(center));
+        assert!(!series.is_offline());
+        // The moment of the request, not the pinned window center
+        assert_eq!(series.fetched_at, Some(fetched_at()));
+        assert_follows(&series, NORMAL);
+        let extremes = series.extremes.as_deref().unwrap();
+        assert!(extremes.len() >= 3);
+        assert!(extremes.iter().all(|e| e.at > center));
+    }
+
+    #[tokio::test]
+    async fn test_sparse_hourly_response() {
+        // Hourly points with 09:00-11:00 EDT missing, still most of the window
+        let center = Utc.with_ymd_and_hms(2025, 7, 1, 16, 0, 0).unwrap();
+        let (series, _) = scrape(SPARSE, center).await;
+        let series = series.unwrap();
+        assert_eq!(series.samples.len(), 145);
+        assert_follows(&series, SPARSE);
+
+        assert!(!series.is_backfilled());
+
+        // A window running past the end of the response (03:00 UTC on July
+        // 3) takes the rest from the model
+        let late = Utc.with_ymd_and_hms(2025, 7, 3, 2, 0, 0).unwrap();
+        let series = scrape(SPARSE, late).await.0.unwrap();
+        assert_eq!(series.samples.len(), 145);
+        assert!(!series.is_offline());
+        let end = Utc.with_ymd_and_hms(2025, 7, 3, 3, 0, 0).unwrap();
+        for sample in &series.samples {
+            assert_eq!(sample.modeled, series.time_of(sample).unwrap() > end);
+        }
+        // Meeting the real data without a step
+        for pair in series.samples.windows(2) {
+            assert!((pair[1].tide_ft - pair[0].tide_ft).abs() < 0.5, "{pair:?}");
+        }
+
+        // One with none of it at all
+        let later = Utc.with_ymd_and_hms(2025, 7, 3, 20, 0, 0).unwrap();
+        assert!(matches!(
+            scrape(SPARSE, later).await.0,
+            Err(TideError::Scrape)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_and_error_responses() {
+        let center = Utc.with_ymd_and_hms(2025, 7, 1, 16, 0, 0).unwrap();
+        // Cut off mid-array
+        assert!(matches!(
+            scrape(MALFORMED, center).await.0,
+            Err(TideError::Scrape)
+        ));
+        // NOAA's answer for a datum the station doesn't have says so
+        let err = scrape(NO_DATA, center).await.0.unwrap_err();
+        assert!(
+            matches!(&err, TideError::Api(m) if m.contains("Datum input is valid")),
+            "{err:?}"
+        );
+        assert_eq!(
+            err.to_string(),
+            "NOAA: No Predictions data was found. Please make sure the Datum input is valid."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dst_transition_days() {
+        // Spring forward: 02:00-02:54 on March 9 don't exist locally
+        let center = Utc.with_ymd_and_hms(2025, 3, 9, 16, 0, 0).unwrap();
+        let (series, url) = scrape(DST_SPRING, center).await;
+        assert!(
+            url.contains("begin_date=20250308&end_date=20250310"),
+            "{url}"
+        );
+        let series = series.unwrap();
+        assert_eq!(series.samples.len(), 145);
+        assert_follows(&series, DST_SPRING);
+
+        // Fall back: 01:00-01:54 on November 2 come twice
+        let center = Utc.with_ymd_and_hms(2025, 11, 2, 17, 0, 0).unwrap();
+        let (series, url) = scrape(DST_FALL, center).await;
+        assert!(
+            url.contains("begin_date=20251101&end_date=20251103"),
+            "{url}"
+        );
+        let series = series.unwrap();
+        assert_eq!(series.samples.len(), 145);
+        assert_follows(&series, DST_FALL);
+
+        // The same days saved in station time parse to one point every 6
+        // minutes straight through the change: no hour missing in spring,
+        // none landing twice in the fall
+        for body in [DST_SPRING, DST_FALL] {
+            let local: Vec<String> = parse_gmt_predictions(body)
+                .unwrap()
+                .into_iter()
+                .map(|(t, ft)| {
+                    let t = t.with_timezone(&chrono_tz::America::New_York);
+                    format!(r#"{{"t":"{}","v":"{ft}"}}"#, t.format("%Y-%m-%d %H:%M"))
+                })
+                .collect();
+            let body = format!(r#"{{"predictions":[{}]}}"#, local.join(","));
+            let points = parse_predictions(&body, &portland()).unwrap();
+            assert_eq!(points.len(), local.len());
+            assert!(points
+                .windows(2)
+                .all(|w| w[1].0 - w[0].0 == Duration::minutes(6)));
+        }
+
+        // The dates asked for are UTC ones: 22:00 EDT on June 30 is already
+        // July 1, whatever the station's or the Pi's timezone
+        let center = Utc.with_ymd_and_hms(2025, 7, 1, 2, 0, 0).unwrap();
+        let (_, url) = scrape(NORMAL, center).await;
+        assert!(
+            url.contains("begin_date=20250630&end_date=20250702"),
+            "{url}"
+        );
     }
 }
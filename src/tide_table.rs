@@ -0,0 +1,244 @@
+//! # Tide Table Layout
+//!
+//! The classic printed tide table: today's and tomorrow's highs and lows with
+//! their times and heights, and a marker on the next one.
+//!
+//! ```text
+//! ┌──────────────────────────┐
+//! │ Portland, ME             │
+//! │ TODAY     Fri Oct 17     │
+//! │   LOW    4:53AM   1.1 ft │
+//! │ > HIGH  10:38AM   8.9 ft │
+//! │ TOMORROW  Sat Oct 18     │
+//! │   LOW    5:41AM   0.8 ft │
+//! └──────────────────────────┘
+//! ```
+//!
+//! `layout = "table"` fills the panel with it; `layout = "split"` draws the
+//! chart on the left and a compact table on the right.
+
+use crate::eink_renderer::{ChartStyle, EinkTideRenderer};
+use crate::extremes::{self, Extreme};
+use crate::layout::{Layout, LayoutContext};
+use embedded_graphics::mono_font::ascii::{FONT_10X20, FONT_8X13};
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle};
+use embedded_graphics::text::{Baseline, Text};
+
+/// Outer margin around the table in pixels
+const MARGIN: u32 = 8;
+
+/// Narrowest table that gets the full-size font and wording
+const FULL_WIDTH: u32 = 300;
+
+/// Share of the panel width given to the chart in split mode, in percent
+const SPLIT_CHART_PERCENT: u32 = 60;
+
+/// Built-in tide table layout (`layout = "table"`)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TideTableLayout;
+
+impl TideTableLayout {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<D> Layout<D> for TideTableLayout
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    fn name(&self) -> &'static str {
+        "table"
+    }
+
+    fn draw(&self, ctx: &LayoutContext<'_>, target: &mut D) -> Result<(), D::Error> {
+        let compact = ctx.size.width < FULL_WIDTH;
+        let font: &MonoFont<'_> = if compact { &FONT_8X13 } else { &FONT_10X20 };
+        let style = MonoTextStyle::new(font, BinaryColor::On);
+        let line_height = font.character_size.height as i32 + 2;
+        let left = MARGIN as i32;
+        let mut y = MARGIN as i32;
+
+        let text = |target: &mut D, s: &str, y: i32| {
+            Text::with_baseline(s, Point::new(left, y), style, Baseline::Top)
+                .draw(target)
+                .map(|_| ())
+        };
+
+        text(target, &ctx.meta.station_name, y)?;
+        y += line_height + 2;
+        Line::new(
+            Point::new(left, y - 3),
+            Point::new(ctx.size.width as i32 - left, y - 3),
+        )
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(target)?;
+
+        let next = ctx.series.upcoming_extremes().first().map(|e| e.at);
+        let days = extremes::day_extremes(ctx.series, ctx.meta);
+        for (offset, day) in days.iter().enumerate() {
+            let date = ctx.meta.local_time(offset as i64 * 24 * 60);
+            let heading = match (offset, compact) {
+                (0, false) => format!("TODAY     {}", date.format("%a %b %-d")),
+                (_, false) => format!("TOMORROW  {}", date.format("%a %b %-d")),
+                (0, true) => format!("Today {}", date.format("%a %-d")),
+                (_, true) => format!("Tmrw  {}", date.format("%a %-d")),
+            };
+            text(target, &heading, y)?;
+            y += line_height;
+
+            if day.is_empty() {
+                text(target, "  --", y)?;
+                y += line_height;
+            }
+            for extreme in day {
+                let marker = if Some(extreme.at) == next { '>' } else { ' ' };
+                let row = table_row(ctx, extreme, marker, compact);
+                text(target, &row, y)?;
+                y += line_height;
+            }
+            y += line_height / 2;
+        }
+
+        // Rows past the bottom are clipped by the target; note the render time
+        // if there's room left for it
+        if y + line_height <= ctx.size.height as i32 {
+            let updated = format!("Updated {}", ctx.meta.clock_at(0));
+            let bottom = ctx.size.height as i32 - MARGIN as i32 - line_height;
+            text(target, &updated, bottom.max(y))?;
+        }
+        Ok(())
+    }
+}
+
+/// One table row, e.g. "> HIGH  10:38AM   8.9 ft" or compact ">H 10:38AM 8.9"
+fn table_row(ctx: &LayoutContext<'_>, extreme: &Extreme, marker: char, compact: bool) -> String {
+    let mins_rel = (extreme.at - ctx.meta.generated_at).num_minutes();
+    let time = ctx.meta.clock_at(mins_rel);
+    let height = ctx.meta.format_height(extreme.tide_ft);
+    if compact {
+        let kind = if extreme.high { 'H' } else { 'L' };
+        let height = height.trim_end_matches(" ft");
+        format!("{}{} {:>7} {:>5}", marker, kind, time, height)
+    } else {
+        let kind = if extreme.high { "HIGH" } else { "LOW" };
+        format!("{} {:<4} {:>7}  {:>7}", marker, kind, time, height)
+    }
+}
+
+/// Built-in chart-plus-table layout (`layout = "split"`)
+///
+/// The chart keeps the left part of the panel without its footer and
+/// countdown, which the table already covers.
+#[derive(Debug, Default, Clone)]
+pub struct SplitLayout {
+    chart_style: ChartStyle,
+}
+
+impl SplitLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Split layout whose chart uses `style`
+    pub fn with_chart_style(chart_style: ChartStyle) -> Self {
+        Self { chart_style }
+    }
+}
+
+impl<D> Layout<D> for SplitLayout
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    fn name(&self) -> &'static str {
+        "split"
+    }
+
+    fn draw(&self, ctx: &LayoutContext<'_>, target: &mut D) -> Result<(), D::Error> {
+        let chart_width = ctx.size.width * SPLIT_CHART_PERCENT / 100;
+        let table_width = ctx.size.width - chart_width;
+
+        let mut theme = ctx.theme.clone();
+        theme.extremes_footer = false;
+        theme.countdown_header = false;
+        let chart_size = Size::new(chart_width, ctx.size.height);
+        let chart_ctx = LayoutContext {
+            theme: &theme,
+            size: chart_size,
+            ..*ctx
+        };
+        let mut chart_area = target.cropped(&Rectangle::new(Point::zero(), chart_size));
+        EinkTideRenderer::with_style(self.chart_style.clone()).draw(&chart_ctx, &mut chart_area)?;
+
+        Line::new(
+            Point::new(chart_width as i32, MARGIN as i32),
+            Point::new(chart_width as i32, (ctx.size.height - MARGIN) as i32),
+        )
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(target)?;
+
+        let table_size = Size::new(table_width, ctx.size.height);
+        let table_ctx = LayoutContext {
+            size: table_size,
+            ..*ctx
+        };
+        let mut table_area = target.cropped(&Rectangle::new(
+            Point::new(chart_width as i32, 0),
+            table_size,
+        ));
+        TideTableLayout::new().draw(&table_ctx, &mut table_area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, TimeFormat};
+    use crate::layout::SeriesMeta;
+    use crate::theme::Theme;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn ctx_parts() -> (SeriesMeta, Theme) {
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 18, 0, 0).unwrap();
+        let mut meta = SeriesMeta::from_config(&Config::default(), now);
+        meta.timezone = Some(chrono_tz::UTC);
+        meta.time_format = TimeFormat::H24;
+        (meta, Theme::default())
+    }
+
+    #[test]
+    fn test_table_rows() {
+        let (meta, theme) = ctx_parts();
+        let series = crate::fallback::approximate(None);
+        let ctx = LayoutContext {
+            series: &series,
+            meta: &meta,
+            theme: &theme,
+            size: Size::new(400, 300),
+            overlays: &[],
+        };
+        let high = Extreme {
+            at: meta.generated_at + Duration::minutes(158),
+            tide_ft: 8.94,
+            high: true,
+        };
+        assert_eq!(
+            table_row(&ctx, &high, '>', false),
+            "> HIGH   20:38   8.9 ft"
+        );
+        assert_eq!(table_row(&ctx, &high, ' ', true), " H   20:38   8.9");
+
+        let mut msl = meta.clone();
+        msl.msl_offset = Some(5.0);
+        let ctx = LayoutContext { meta: &msl, ..ctx };
+        let low = Extreme {
+            tide_ft: 0.4,
+            high: false,
+            ..high
+        };
+        assert_eq!(table_row(&ctx, &low, ' ', true), " L   20:38  -4.6");
+    }
+}
@@ -0,0 +1,456 @@
+//! # Terminal Dashboard
+//!
+//! With `--features tui`, `tide-tracker tui` keeps a live dashboard in the
+//! terminal: the chart, the next high and low, the station and how its data
+//! was fetched. It needs nothing but a terminal, so it is the way to check
+//! the tides over SSH to the Pi.
+//!
+//! | Key                | Action                                 |
+//! |--------------------|----------------------------------------|
+//! | `Tab` / `→`        | Next configured station                |
+//! | `Shift-Tab` / `←`  | Previous station                       |
+//! | `+` / `-`          | Wider or narrower time window          |
+//! | `r`                | Fetch again now                        |
+//! | `q` / `Esc`        | Quit                                   |
+//!
+//! [`Dashboard`] holds what is shown and reacts to keys; fetching happens
+//! elsewhere (`main.rs` runs it on a thread) and comes back as a
+//! [`Fetched`], so the screen keeps redrawing while NOAA is slow.
+
+use crate::config::{local_time, Config};
+use crate::display_units::tide_to_display;
+use crate::layout::SeriesMeta;
+use crate::{DataSource, TideSeries};
+use chrono::{DateTime, Utc};
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::symbols::Marker;
+use ratatui::text::Line;
+use ratatui::widgets::{Axis, Block, Chart, Dataset, GraphType, Paragraph};
+use ratatui::Frame;
+
+/// Time windows `+` and `-` step through, in hours either side of now
+pub const WINDOWS: [i64; 4] = [6, 12, 24, 36];
+
+/// What the dashboard wants after a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Nothing changed
+    Nothing,
+    /// Fetch for the (possibly new) station and window
+    Fetch,
+    /// Leave the dashboard
+    Quit,
+}
+
+/// Where the shown series came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum FetchStatus {
+    /// A fetch is running; the previous series (if any) stays up
+    Fetching,
+    /// The last fetch succeeded
+    Fetched,
+    /// The last fetch failed with this error; the series is the offline model
+    Failed(String),
+}
+
+/// One fetch to run: which station, over which window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchRequest {
+    /// Matches the [`Fetched`] that answers it
+    pub generation: u64,
+    /// Index into [`Config::all_stations`]
+    pub station: usize,
+    /// Hours either side of now
+    pub window_hours: i64,
+}
+
+/// The answer to a [`FetchRequest`]
+#[derive(Debug)]
+pub struct Fetched {
+    /// The request's generation
+    pub generation: u64,
+    /// Configuration the fetch read, so edits show up on the next fetch
+    pub config: Config,
+    /// The series, or the offline model when the fetch failed
+    pub series: TideSeries,
+    /// Why the fetch failed, if it did
+    pub error: Option<String>,
+}
+
+/// State of the dashboard between redraws
+pub struct Dashboard {
+    config: Config,
+    station: usize,
+    window_hours: i64,
+    series: Option<TideSeries>,
+    status: FetchStatus,
+    generation: u64,
+}
+
+impl Dashboard {
+    /// Dashboard on the main station and the configured window, with no
+    /// data yet
+    pub fn new(config: Config) -> Self {
+        let window_hours = config.display.time_window_hours;
+        Self {
+            config,
+            station: 0,
+            window_hours,
+            series: None,
+            status: FetchStatus::Fetching,
+            generation: 0,
+        }
+    }
+
+    /// Start a fetch for the current station and window
+    ///
+    /// Results of earlier requests are ignored from now on.
+    pub fn fetch_request(&mut self) -> FetchRequest {
+        self.generation += 1;
+        self.status = FetchStatus::Fetching;
+        FetchRequest {
+            generation: self.generation,
+            station: self.station,
+            window_hours: self.window_hours,
+        }
+    }
+
+    /// Take in a finished fetch, unless a newer one has been requested
+    pub fn receive(&mut self, fetched: Fetched) {
+        if fetched.generation != self.generation {
+            return;
+        }
+        self.config = fetched.config;
+        // The station list may have shrunk with the reloaded config
+        self.station = self.station.min(self.config.all_stations().len() - 1);
+        self.series = Some(fetched.series);
+        self.status = match fetched.error {
+            Some(error) => FetchStatus::Failed(error),
+            None => FetchStatus::Fetched,
+        };
+    }
+
+    /// React to a key press
+    pub fn handle_key(&mut self, key: KeyCode) -> Action {
+        let stations = self.config.all_stations().len();
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('r') => return Action::Fetch,
+            KeyCode::Tab | KeyCode::Right if stations > 1 => {
+                self.station = (self.station + 1) % stations;
+            }
+            KeyCode::BackTab | KeyCode::Left if stations > 1 => {
+                self.station = (self.station + stations - 1) % stations;
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                match WINDOWS.iter().find(|&&hours| hours > self.window_hours) {
+                    Some(&wider) => self.window_hours = wider,
+                    None => return Action::Nothing,
+                }
+            }
+            KeyCode::Char('-') => {
+                match WINDOWS
+                    .iter()
+                    .rev()
+                    .find(|&&hours| hours < self.window_hours)
+                {
+                    Some(&narrower) => self.window_hours = narrower,
+                    None => return Action::Nothing,
+                }
+            }
+            _ => return Action::Nothing,
+        }
+        // A series for another station or window would be misleading
+        self.series = None;
+        Action::Fetch
+    }
+
+    /// Draw the whole dashboard as of `now`
+    pub fn draw(&self, frame: &mut Frame, now: DateTime<Utc>) {
+        let [main, keys] =
+            Layout::vertical([Constraint::Min(10), Constraint::Length(1)]).areas(frame.area());
+        let [chart, side] =
+            Layout::horizontal([Constraint::Min(40), Constraint::Length(34)]).areas(main);
+
+        let stations = self.config.all_stations();
+        let station = stations[self.station.min(stations.len() - 1)];
+        let meta = SeriesMeta::for_station(&self.config, station, now);
+        // Samples counted from now rather than from the fetch
+        let series = self
+            .series
+            .as_ref()
+            .map(|series| series.rebased(now).unwrap_or_else(|| series.clone()));
+
+        let title = format!(
+            " {} ({}/{}) ",
+            station.name,
+            self.station + 1,
+            stations.len()
+        );
+        match &series {
+            Some(series) => self.draw_chart(frame, chart, &title, series, &meta),
+            None => frame.render_widget(
+                Paragraph::new("Fetching…").block(Block::bordered().title(title)),
+                chart,
+            ),
+        }
+        frame.render_widget(self.side_panel(series.as_ref(), &meta, now), side);
+        frame.render_widget(
+            Line::from(" ←/→ station   +/- window   r refresh   q quit").dim(),
+            keys,
+        );
+    }
+
+    fn draw_chart(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        title: &str,
+        series: &TideSeries,
+        meta: &SeriesMeta,
+    ) {
+        let points: Vec<(f64, f64)> = series
+            .samples
+            .iter()
+            .map(|s| {
+                let height = tide_to_display(s.tide_ft, meta.msl_offset);
+                (s.mins_rel as f64 / 60.0, height as f64)
+            })
+            .collect();
+        let (min, max) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &(_, y)| {
+                (min.min(y), max.max(y))
+            });
+        let (min, max) = match (min.floor(), max.ceil()) {
+            (min, max) if min < max => (min, max),
+            (min, _) if min.is_finite() => (min, min + 1.0),
+            _ => (0.0, 10.0),
+        };
+        let hours = self.window_hours as f64;
+        let now_line = [(0.0, min), (0.0, max)];
+
+        let chart = Chart::new(vec![
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(Color::Blue))
+                .data(&points),
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(Color::Red))
+                .data(&now_line),
+        ])
+        .block(Block::bordered().title(title.to_string()))
+        .x_axis(Axis::default().bounds([-hours, hours]).labels([
+            format!("-{}h", self.window_hours),
+            "Now".into(),
+            format!("+{}h", self.window_hours),
+        ]))
+        .y_axis(
+            Axis::default()
+                .title(meta.axis_label())
+                .bounds([min, max])
+                .labels([
+                    format!("{:.0}", min),
+                    format!("{:.0}", (min + max) / 2.0),
+                    format!("{:.0}", max),
+                ]),
+        );
+        frame.render_widget(chart, area);
+    }
+
+    fn side_panel(
+        &self,
+        series: Option<&TideSeries>,
+        meta: &SeriesMeta,
+        now: DateTime<Utc>,
+    ) -> Paragraph<'static> {
+        let mut lines = vec![
+            Line::from(meta.station_name.clone()).bold(),
+            Line::from(format!("Station {}", meta.station_id)),
+            Line::from(meta.axis_label()),
+            Line::from(format!("Window ±{}h", self.window_hours)),
+            Line::default(),
+        ];
+
+        if let Some(series) = series {
+            if let Some(height) = series.height_now() {
+                lines.push(Line::from(format!(
+                    "Now {} {}",
+                    meta.format_height(height),
+                    series.trend().label()
+                )));
+            }
+            let upcoming: Vec<_> = series
+                .upcoming_extremes()
+                .into_iter()
+                .filter(|e| e.at > now)
+                .collect();
+            for high in [true, false] {
+                let kind = if high { "High" } else { "Low" };
+                let line = match upcoming.iter().find(|e| e.high == high) {
+                    Some(e) => format!(
+                        "{} {} {}",
+                        kind,
+                        meta.clock_at((e.at - now).num_minutes()),
+                        meta.format_height(e.tide_ft)
+                    ),
+                    None => format!("{} --", kind),
+                };
+                lines.push(Line::from(line));
+            }
+            if let Some(next) = upcoming.first() {
+                lines.push(Line::from(next.countdown(meta)).dim());
+            }
+            lines.push(Line::default());
+        }
+
+        let clock = |t: DateTime<Utc>| {
+            local_time(meta.timezone, t)
+                .format(meta.time_format.clock())
+                .to_string()
+        };
+        match (&self.status, series) {
+            (FetchStatus::Fetching, _) => lines.push(Line::from("Fetching…").yellow()),
+            (FetchStatus::Failed(error), _) => {
+                lines.push(Line::from("OFFLINE: fetch failed").yellow());
+                lines.push(Line::from(error.clone()).dim());
+            }
+            (FetchStatus::Fetched, Some(s)) if s.is_offline() => {
+                lines.push(Line::from(s.source.to_string()).yellow())
+            }
+            (FetchStatus::Fetched, Some(s)) => lines.push(match (s.source, s.fetched_at) {
+                (DataSource::Cache { .. }, Some(at)) => {
+                    Line::from(format!("{}, fetched {}", s.source, clock(at)))
+                }
+                (_, Some(at)) => Line::from(format!("Fetched {}", clock(at))),
+                (_, None) => Line::from("Predicted"),
+            }),
+            (FetchStatus::Fetched, None) => {}
+        }
+        Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: true })
+            .block(Block::bordered())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn two_stations() -> Config {
+        let mut config = Config::default();
+        let mut other = config.station.clone();
+        other.id = "8443970".into();
+        other.name = "Boston".into();
+        config.stations.push(other);
+        config
+    }
+
+    fn fetched(generation: u64, error: Option<&str>) -> Fetched {
+        let now = Utc.with_ymd_and_hms(2024, 6, 21, 15, 0, 0).unwrap();
+        Fetched {
+            generation,
+            config: two_stations(),
+            series: crate::fallback::approximate(Some(now)),
+            error: error.map(String::from),
+        }
+    }
+
+    fn screen(dashboard: &Dashboard, now: DateTime<Utc>) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| dashboard.draw(frame, now)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_keys_switch_stations_and_windows() {
+        let mut dashboard = Dashboard::new(two_stations());
+        assert_eq!(dashboard.window_hours, 12);
+
+        assert_eq!(dashboard.handle_key(KeyCode::Tab), Action::Fetch);
+        assert_eq!(dashboard.station, 1);
+        assert_eq!(dashboard.handle_key(KeyCode::Right), Action::Fetch);
+        assert_eq!(dashboard.station, 0);
+        dashboard.handle_key(KeyCode::Left);
+        assert_eq!(dashboard.station, 1);
+
+        assert_eq!(dashboard.handle_key(KeyCode::Char('+')), Action::Fetch);
+        assert_eq!(dashboard.window_hours, 24);
+        dashboard.handle_key(KeyCode::Char('+'));
+        assert_eq!(dashboard.handle_key(KeyCode::Char('+')), Action::Nothing);
+        assert_eq!(dashboard.window_hours, 36);
+        for _ in 0..4 {
+            dashboard.handle_key(KeyCode::Char('-'));
+        }
+        assert_eq!(dashboard.window_hours, 6);
+
+        assert_eq!(dashboard.handle_key(KeyCode::Char('r')), Action::Fetch);
+        assert_eq!(dashboard.handle_key(KeyCode::Char('x')), Action::Nothing);
+        assert_eq!(dashboard.handle_key(KeyCode::Esc), Action::Quit);
+
+        // One station: nothing to switch to
+        let mut single = Dashboard::new(Config::default());
+        assert_eq!(single.handle_key(KeyCode::Tab), Action::Nothing);
+    }
+
+    #[test]
+    fn test_only_the_latest_fetch_is_shown() {
+        let mut dashboard = Dashboard::new(two_stations());
+        let first = dashboard.fetch_request();
+        dashboard.handle_key(KeyCode::Tab);
+        let second = dashboard.fetch_request();
+        assert_eq!((second.station, second.window_hours), (1, 12));
+
+        dashboard.receive(fetched(first.generation, None));
+        assert!(dashboard.series.is_none());
+        assert_eq!(dashboard.status, FetchStatus::Fetching);
+
+        dashboard.receive(fetched(second.generation, Some("timed out")));
+        assert!(dashboard.series.is_some());
+        assert_eq!(dashboard.status, FetchStatus::Failed("timed out".into()));
+    }
+
+    #[test]
+    fn test_dashboard_draws_chart_extremes_and_status() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 21, 15, 0, 0).unwrap();
+        let mut config = two_stations();
+        config.station.timezone = Some(chrono_tz::America::New_York);
+        let mut dashboard = Dashboard::new(config);
+        assert!(screen(&dashboard, now).contains("Fetching…"));
+
+        let request = dashboard.fetch_request();
+        let mut answer = fetched(request.generation, None);
+        answer.config.station.timezone = Some(chrono_tz::America::New_York);
+        dashboard.receive(answer);
+        let screen = screen(&dashboard, now);
+        assert!(screen.contains("Portland, ME (1/2)"), "{}", screen);
+        assert!(screen.contains("High 2:47PM 10.2 ft"), "{}", screen);
+        assert!(screen.contains("Low 8:58PM -0.2 ft"), "{}", screen);
+        assert!(screen.contains("OFFLINE MODEL"), "{}", screen);
+        assert!(
+            screen.contains("-12h") && screen.contains("+12h"),
+            "{}",
+            screen
+        );
+        // The curve is drawn in braille
+        assert!(screen
+            .chars()
+            .any(|c| ('\u{2801}'..='\u{28FF}').contains(&c)));
+    }
+}
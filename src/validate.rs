@@ -0,0 +1,211 @@
+//! # Series Validation
+//!
+//! Sanity checks run on freshly fetched data before it is cached or drawn.
+//! A malformed or partial API response should either be repaired (a few bad
+//! samples) or rejected outright, never silently rendered as a distorted
+//! curve that then sticks around for a whole cache TTL.
+//!
+//! Checks, in order:
+//! 1. **Sample count**: exactly the grid the series starts on at the step of
+//!    its first two samples, e.g. 145 samples for the standard ±12h
+//! 2. **Timestamps**: `mins_rel` strictly increasing by that step
+//! 3. **Plausible heights**: finite and within [`MIN_HEIGHT_FT`]..=[`MAX_HEIGHT_FT`]
+//! 4. **Spikes**: a single sample jumping away from both neighbors faster
+//!    than [`MAX_RATE_FT_PER_HOUR`]
+//!
+//! Samples failing 3 or 4 are replaced by interpolating their good neighbors,
+//! as long as no more than [`MAX_REPAIRED_FRACTION`] of the series is bad.
+//! After repair no step may exceed the rate limit either.
+
+use crate::{grid, TideSeries};
+use thiserror::Error;
+
+/// Lowest height accepted, in feet (generous for STND/NAVD88 datums)
+pub const MIN_HEIGHT_FT: f32 = -50.0;
+
+/// Highest height accepted, in feet
+pub const MAX_HEIGHT_FT: f32 = 100.0;
+
+/// Fastest believable rise or fall, in feet per hour (Bay of Fundy peaks ~8)
+pub const MAX_RATE_FT_PER_HOUR: f32 = 12.0;
+
+/// Largest share of samples that may be repaired before the series is rejected
+pub const MAX_REPAIRED_FRACTION: f32 = 0.1;
+
+/// Why a series was rejected
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    #[error("expected {expected} samples, got {actual}")]
+    SampleCount { expected: usize, actual: usize },
+    #[error("timestamps out of order at sample {0}")]
+    Timestamps(usize),
+    #[error("{0} implausible samples, too many to repair")]
+    TooManyBadSamples(usize),
+    #[error("height changes {rate:.1} ft/h at sample {index}")]
+    RateOfChange { index: usize, rate: f32 },
+}
+
+impl TideSeries {
+    /// Check the series and repair isolated bad samples in place
+    ///
+    /// Returns how many samples were repaired.
+    pub fn validate(&mut self) -> Result<usize, ValidationError> {
+        let half_width = self
+            .samples
+            .first()
+            .map_or(grid::HALF_WIDTH_MINS, |s| -(s.mins_rel as i64))
+            .max(0);
+        let interval = self
+            .interval()
+            .filter(|i| i.num_minutes() > 0)
+            .unwrap_or(chrono::Duration::minutes(grid::INTERVAL_MINS));
+        let step = interval.num_minutes();
+        let expected = grid::sample_count(chrono::Duration::minutes(half_width), interval);
+        if self.samples.len() != expected {
+            return Err(ValidationError::SampleCount {
+                expected,
+                actual: self.samples.len(),
+            });
+        }
+        if let Some(i) = self
+            .samples
+            .windows(2)
+            .position(|w| (w[1].mins_rel - w[0].mins_rel) as i64 != step)
+        {
+            return Err(ValidationError::Timestamps(i + 1));
+        }
+
+        let max_step = MAX_RATE_FT_PER_HOUR * step as f32 / 60.0;
+        let heights: Vec<f32> = self.samples.iter().map(|s| s.tide_ft).collect();
+        let plausible = |h: f32| h.is_finite() && (MIN_HEIGHT_FT..=MAX_HEIGHT_FT).contains(&h);
+        let mut bad: Vec<bool> = heights.iter().map(|&h| !plausible(h)).collect();
+
+        // Single-sample spikes between two good neighbors
+        for i in 1..heights.len() - 1 {
+            if bad[i - 1] || bad[i] || bad[i + 1] {
+                continue;
+            }
+            let up = heights[i] - heights[i - 1];
+            let down = heights[i + 1] - heights[i];
+            if up.abs() > max_step && down.abs() > max_step && up.signum() != down.signum() {
+                bad[i] = true;
+            }
+        }
+
+        let bad_count = bad.iter().filter(|&&b| b).count();
+        if bad_count as f32 > heights.len() as f32 * MAX_REPAIRED_FRACTION {
+            return Err(ValidationError::TooManyBadSamples(bad_count));
+        }
+
+        // Interpolate between the nearest good samples, holding at the edges
+        for i in (0..heights.len()).filter(|&i| bad[i]) {
+            let before = (0..i).rev().find(|&j| !bad[j]);
+            let after = (i + 1..heights.len()).find(|&j| !bad[j]);
+            self.samples[i].tide_ft = match (before, after) {
+                (Some(a), Some(b)) => {
+                    let t = (i - a) as f32 / (b - a) as f32;
+                    heights[a] + t * (heights[b] - heights[a])
+                }
+                (Some(a), None) => heights[a],
+                (None, Some(b)) => heights[b],
+                (None, None) => return Err(ValidationError::TooManyBadSamples(bad_count)),
+            };
+        }
+
+        if let Some(i) = self
+            .samples
+            .windows(2)
+            .position(|w| (w[1].tide_ft - w[0].tide_ft).abs() > max_step)
+        {
+            let change = self.samples[i + 1].tide_ft - self.samples[i].tide_ft;
+            return Err(ValidationError::RateOfChange {
+                index: i + 1,
+                rate: change.abs() * 60.0 / step as f32,
+            });
+        }
+
+        Ok(bad_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fallback;
+
+    #[test]
+    fn test_good_series_passes_untouched() {
+        let mut series = fallback::approximate(None);
+        let before: Vec<f32> = series.samples.iter().map(|s| s.tide_ft).collect();
+        assert_eq!(series.validate(), Ok(0));
+        let after: Vec<f32> = series.samples.iter().map(|s| s.tide_ft).collect();
+        assert_eq!(before, after);
+
+        // Any window width, as long as the grid is complete
+        let mut wide = crate::fallback::ModelConstants::default()
+            .series_in(crate::grid::Window::hours(chrono::Utc::now(), 24));
+        assert_eq!(wide.validate(), Ok(0));
+        wide.samples.pop();
+        assert!(matches!(
+            wide.validate(),
+            Err(ValidationError::SampleCount {
+                expected: 289,
+                actual: 288
+            })
+        ));
+
+        // And any sample interval
+        for interval in [5, 15] {
+            let spec = crate::grid::SeriesSpec::hours(chrono::Utc::now(), 12, interval);
+            let mut series = crate::fallback::ModelConstants::default().series_in(spec);
+            assert_eq!(series.validate(), Ok(0));
+        }
+    }
+
+    #[test]
+    fn test_isolated_bad_samples_are_repaired() {
+        let mut series = fallback::approximate(None);
+        let expected = series.samples[50].tide_ft;
+        series.samples[50].tide_ft = f32::NAN;
+        series.samples[90].tide_ft += 30.0; // spike
+        series.samples[144].tide_ft = 999.0; // edge
+
+        assert_eq!(series.validate(), Ok(3));
+        assert!((series.samples[50].tide_ft - expected).abs() < 0.1);
+        assert!(series.samples.iter().all(|s| s.tide_ft.is_finite()));
+        assert_eq!(series.samples[144].tide_ft, series.samples[143].tide_ft);
+    }
+
+    #[test]
+    fn test_structural_problems_are_rejected() {
+        let mut short = fallback::approximate(None);
+        short.samples.truncate(100);
+        assert!(matches!(
+            short.validate(),
+            Err(ValidationError::SampleCount { actual: 100, .. })
+        ));
+
+        let mut shuffled = fallback::approximate(None);
+        shuffled.samples.swap(10, 11);
+        assert_eq!(shuffled.validate(), Err(ValidationError::Timestamps(10)));
+
+        let mut garbage = fallback::approximate(None);
+        for s in garbage.samples.iter_mut().step_by(3) {
+            s.tide_ft = f32::INFINITY;
+        }
+        assert!(matches!(
+            garbage.validate(),
+            Err(ValidationError::TooManyBadSamples(_))
+        ));
+
+        // A step (not a spike) in the curve can't be repaired
+        let mut step = fallback::approximate(None);
+        for s in &mut step.samples[80..] {
+            s.tide_ft += 20.0;
+        }
+        assert!(matches!(
+            step.validate(),
+            Err(ValidationError::RateOfChange { index: 80, .. })
+        ));
+    }
+}
@@ -0,0 +1,377 @@
+//! # XTide Harmonics Files
+//!
+//! Reads the legacy XTide text format (`harmonics-*.txt`) so an existing
+//! XTide install's harbors can drive the offline predictions:
+//!
+//! ```toml
+//! [fallback]
+//! harmonics_file = "/usr/share/xtide/harmonics-dwf-20220109-free.txt"
+//!
+//! [station.harmonics]            # optional, when the names differ
+//! xtide_station = "Portland, Casco Bay, Maine"
+//! ```
+//!
+//! The file lists every constituent's speed, then per-year equilibrium
+//! arguments and node factors, then the stations with their amplitudes and
+//! epochs. Those tables are used as-is, so the result matches what XTide
+//! itself predicts for the years they cover. Epochs are given relative to
+//! each station's time meridian and converted to Greenwich here.
+//!
+//! A station is picked by name: an exact (case-insensitive) match, else the
+//! first name containing the query. Binary `.tcd` databases aren't read;
+//! `restore_tide_db` from tcd-utils converts one to this text format.
+//! Current (knots) stations are rejected.
+
+use crate::harmonics::{Harmonics, HarmonicsError, TabulatedConstituent, YearTable};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Feet per meter, for stations given in meters
+const FEET_PER_METER: f32 = 3.280_84;
+
+/// Load the station matching `station` from the XTide file at `path`
+pub fn load(path: &Path, station: &str) -> Result<Harmonics, HarmonicsError> {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tcd"))
+    {
+        return Err(HarmonicsError::Unsupported(
+            "binary .tcd database; convert it to text with restore_tide_db".to_string(),
+        ));
+    }
+    let text = std::fs::read_to_string(path)?;
+    parse(path, &text, station)
+}
+
+/// Non-comment, non-blank lines with their 1-based line numbers
+struct Reader<'a> {
+    path: &'a Path,
+    lines: Box<dyn Iterator<Item = (usize, &'a str)> + 'a>,
+    last_line: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(path: &'a Path, text: &'a str) -> Self {
+        let lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        Self {
+            path,
+            lines: Box::new(lines),
+            last_line: 0,
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> HarmonicsError {
+        HarmonicsError::Parse {
+            path: self.path.to_path_buf(),
+            line: self.last_line,
+            message: message.into(),
+        }
+    }
+
+    fn try_next(&mut self) -> Option<&'a str> {
+        let (number, line) = self.lines.next()?;
+        self.last_line = number;
+        Some(line)
+    }
+
+    fn next(&mut self) -> Result<&'a str, HarmonicsError> {
+        self.try_next()
+            .ok_or_else(|| self.error("unexpected end of file"))
+    }
+
+    fn number<T: std::str::FromStr>(&self, field: &str) -> Result<T, HarmonicsError> {
+        field
+            .parse()
+            .map_err(|_| self.error(format!("expected a number, got \"{}\"", field)))
+    }
+
+    /// A line holding a single number
+    fn count<T: std::str::FromStr>(&mut self) -> Result<T, HarmonicsError> {
+        let line = self.next()?;
+        self.number(line)
+    }
+
+    /// One `NAME` line plus `years` numbers per constituent, then `*END*`
+    fn tables(&mut self, names: &[&str], years: usize) -> Result<Vec<Vec<f32>>, HarmonicsError> {
+        let mut tables = Vec::with_capacity(names.len());
+        for name in names {
+            let line = self.next()?;
+            if !line.eq_ignore_ascii_case(name) {
+                return Err(self.error(format!("expected table for {}, got \"{}\"", name, line)));
+            }
+            let mut values = Vec::with_capacity(years);
+            while values.len() < years {
+                for field in self.next()?.split_whitespace() {
+                    values.push(self.number(field)?);
+                }
+            }
+            if values.len() != years {
+                return Err(self.error(format!("{} needs {} values", name, years)));
+            }
+            tables.push(values);
+        }
+        match self.next()? {
+            "*END*" => Ok(tables),
+            other => Err(self.error(format!("expected *END*, got \"{}\"", other))),
+        }
+    }
+}
+
+fn parse(path: &Path, text: &str, station: &str) -> Result<Harmonics, HarmonicsError> {
+    let mut reader = Reader::new(path, text);
+
+    let count: usize = reader.count()?;
+    let mut speeds = Vec::with_capacity(count);
+    for _ in 0..count {
+        match reader.next()?.split_whitespace().collect::<Vec<_>>()[..] {
+            [name, speed] => speeds.push((name, reader.number::<f64>(speed)?)),
+            _ => return Err(reader.error("expected NAME speed")),
+        }
+    }
+    let names: Vec<&str> = speeds.iter().map(|(name, _)| *name).collect();
+
+    let first_year: i32 = reader.count()?;
+    let years: usize = reader.count()?;
+    let equilibrium = reader.tables(&names, years)?;
+    if reader.count::<usize>()? != years {
+        return Err(reader.error("node factor years differ from equilibrium years"));
+    }
+    let nodes = reader.tables(&names, years)?;
+    let tables: Vec<Arc<YearTable>> = equilibrium
+        .into_iter()
+        .zip(nodes)
+        .map(|(equilibrium_deg, node_factor)| {
+            Arc::new(YearTable {
+                first_year,
+                equilibrium_deg,
+                node_factor,
+            })
+        })
+        .collect();
+
+    let query = station.to_lowercase();
+    let mut partial = None;
+    while let Some(name) = reader.try_next() {
+        let meridian = reader.next()?;
+        let meridian_hours = parse_meridian(&reader, meridian)?;
+        let datum = reader.next()?;
+        let mut constants = Vec::with_capacity(count);
+        for _ in 0..count {
+            match reader.next()?.split_whitespace().collect::<Vec<_>>()[..] {
+                [_, amplitude, epoch] => constants.push((
+                    reader.number::<f32>(amplitude)?,
+                    reader.number::<f32>(epoch)?,
+                )),
+                _ => return Err(reader.error("expected NAME amplitude epoch")),
+            }
+        }
+
+        let lower = name.to_lowercase();
+        let exact = lower == query;
+        if !exact && (partial.is_some() || !lower.contains(&query)) {
+            continue;
+        }
+        let to_feet = match datum.split_whitespace().collect::<Vec<_>>()[..] {
+            [_, "feet" | "ft"] => 1.0,
+            [_, "meters" | "m"] => FEET_PER_METER,
+            [_, units] => {
+                return Err(HarmonicsError::Unsupported(format!(
+                    "{} is in {} (current stations aren't supported)",
+                    name, units
+                )))
+            }
+            _ => return Err(reader.error("expected datum and units")),
+        };
+        let mean_level: f32 = reader.number(datum.split_whitespace().next().unwrap_or(""))?;
+
+        let constituents = speeds
+            .iter()
+            .zip(&constants)
+            .zip(&tables)
+            .filter(|((_, (amplitude, _)), _)| *amplitude != 0.0)
+            .map(|(((name, speed), (amplitude, epoch)), table)| {
+                // Epochs are relative to the station's time meridian
+                let greenwich = (*epoch as f64 - speed * meridian_hours).rem_euclid(360.0);
+                TabulatedConstituent {
+                    name: name.to_string(),
+                    speed_deg_per_hour: *speed,
+                    amplitude_ft: amplitude * to_feet,
+                    phase_deg: greenwich as f32,
+                    table: Arc::clone(table),
+                }
+            })
+            .collect();
+        let harmonics = Harmonics::tabulated(mean_level * to_feet, constituents);
+        if exact {
+            return Ok(harmonics);
+        }
+        partial = Some(harmonics);
+    }
+    partial.ok_or_else(|| HarmonicsError::StationNotFound(station.to_string()))
+}
+
+/// Offset of the station's time meridian from UTC in hours, from `-5:00`
+/// (optionally followed by a `:Zone/Name`)
+fn parse_meridian(reader: &Reader<'_>, line: &str) -> Result<f64, HarmonicsError> {
+    let field = line.split_whitespace().next().unwrap_or_default();
+    let (sign, rest) = match field.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, field.trim_start_matches('+')),
+    };
+    let (hours, minutes) = rest
+        .split_once(':')
+        .ok_or_else(|| reader.error(format!("expected meridian like -5:00, got \"{}\"", line)))?;
+    let hours: f64 = reader.number(hours)?;
+    let minutes: f64 = reader.number(minutes)?;
+    Ok(sign * (hours + minutes / 60.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harmonics::{self, Constituent};
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use std::fmt::Write;
+    use std::path::PathBuf;
+
+    /// (name, speed, amplitude ft, Greenwich phase)
+    const CONSTANTS: [(&str, f64, f32, f32); 3] = [
+        ("M2", 28.984_104_2, 4.5, 103.9),
+        ("S2", 30.0, 0.7, 140.1),
+        ("K1", 15.041_068_6, 0.45, 200.0),
+    ];
+
+    /// A two-year XTide file whose tables come from our own astronomy
+    fn xtide_file(stations: &[(&str, &str, &str)]) -> String {
+        let mut text = String::from("# test harmonics\n3\n");
+        for (name, speed, _, _) in CONSTANTS {
+            writeln!(text, "{} {}", name, speed).unwrap();
+        }
+        text.push_str("2025\n2\n");
+        for (name, ..) in CONSTANTS {
+            writeln!(text, "{}", name).unwrap();
+            for year in [2025, 2026] {
+                let start = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+                let (v0u, _) = harmonics::equilibrium(name, start).unwrap();
+                writeln!(text, " {:.2}", v0u.rem_euclid(360.0)).unwrap();
+            }
+        }
+        text.push_str("*END*\n2\n");
+        for (name, ..) in CONSTANTS {
+            writeln!(text, "{}", name).unwrap();
+            let mid = |year| Utc.with_ymd_and_hms(year, 7, 2, 0, 0, 0).unwrap();
+            let f = |year| harmonics::equilibrium(name, mid(year)).unwrap().1;
+            writeln!(text, " {:.4} {:.4}", f(2025), f(2026)).unwrap();
+        }
+        text.push_str("*END*\n");
+        for (name, meridian, datum) in stations {
+            writeln!(text, "# station\n{}\n{}\n{}", name, meridian, datum).unwrap();
+            for (constituent, speed, amplitude, phase) in CONSTANTS {
+                // Local epoch: Greenwich phase shifted to the -5:00 meridian
+                let epoch = (phase as f64 - 5.0 * speed).rem_euclid(360.0);
+                writeln!(text, "{} {:.3} {:.2}", constituent, amplitude, epoch).unwrap();
+            }
+        }
+        text
+    }
+
+    fn inline() -> Harmonics {
+        let constituents: Vec<Constituent> = CONSTANTS
+            .iter()
+            .map(|&(name, _, amplitude_ft, phase_deg)| Constituent {
+                name: name.to_string(),
+                amplitude_ft,
+                phase_deg,
+                speed_deg_per_hour: None,
+            })
+            .collect();
+        Harmonics::new(5.0, &constituents).unwrap()
+    }
+
+    fn at(month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, month, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_tables_agree_with_astronomical_prediction() {
+        let text = xtide_file(&[(
+            "Portland, Casco Bay, Maine",
+            "-5:00 :America/New_York",
+            "5.0 feet",
+        )]);
+        let path = PathBuf::from("harmonics.txt");
+        let xtide = parse(&path, &text, "portland").unwrap();
+        let inline = inline();
+        for t in [at(1, 1, 3), at(3, 15, 12), at(7, 2, 18), at(12, 30, 23)] {
+            for h in 0..12 {
+                let t = t + Duration::hours(h);
+                let (a, b) = (xtide.predict(t), inline.predict(t));
+                assert!((a - b).abs() < 0.1, "{}: xtide {} vs inline {}", t, a, b);
+            }
+        }
+        // Past the tables, standard constituents fall back to astronomy
+        // (the file's epochs are rounded, so not bit-for-bit)
+        let later = Utc.with_ymd_and_hms(2030, 5, 1, 0, 0, 0).unwrap();
+        assert!((xtide.predict(later) - inline.predict(later)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_station_selection_and_units() {
+        let text = xtide_file(&[
+            ("Portland Harbor, Maine", "-5:00", "5.0 feet"),
+            ("Portland", "-5:00", "1.5 meters"),
+            ("Boston, Massachusetts", "-5:00", "5.0 feet"),
+        ]);
+        let path = PathBuf::from("harmonics.txt");
+
+        // Exact match wins over an earlier partial one; meters become feet
+        let exact = parse(&path, &text, "PORTLAND").unwrap();
+        let partial = parse(&path, &text, "portland harbor").unwrap();
+        for hour in [0, 5, 11] {
+            let t = at(7, 2, hour);
+            let feet = partial.predict(t) - 5.0;
+            let meters = exact.predict(t) - 1.5 * FEET_PER_METER;
+            assert!(
+                (meters - feet * FEET_PER_METER).abs() < 1e-2,
+                "{} vs {}",
+                meters,
+                feet
+            );
+        }
+
+        assert!(matches!(
+            parse(&path, &text, "Bar Harbor"),
+            Err(HarmonicsError::StationNotFound(name)) if name == "Bar Harbor"
+        ));
+        assert!(matches!(
+            load(Path::new("/usr/share/xtide/harmonics.tcd"), "Portland"),
+            Err(HarmonicsError::Unsupported(_))
+        ));
+
+        let knots = xtide_file(&[("Portland Channel", "-5:00", "0.0 knots")]);
+        assert!(matches!(
+            parse(&path, &knots, "Portland"),
+            Err(HarmonicsError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_malformed_files_report_line() {
+        let path = PathBuf::from("harmonics.txt");
+        let truncated = "# header\n2\nM2 28.98\nS2 thirty\n";
+        assert!(matches!(
+            parse(&path, truncated, "x"),
+            Err(HarmonicsError::Parse { line: 4, .. })
+        ));
+        let mut missing_end = xtide_file(&[]);
+        missing_end = missing_end.replacen("*END*", "END", 1);
+        assert!(matches!(
+            parse(&path, &missing_end, "x"),
+            Err(HarmonicsError::Parse { .. })
+        ));
+    }
+}
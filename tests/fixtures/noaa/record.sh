@@ -0,0 +1,27 @@
+#!/bin/sh
+
+# Records the NOAA fixtures from the live CO-OPS API; see README.md
+set -eu
+cd "$(dirname "$0")"
+
+api='https://api.tidesandcurrents.noaa.gov/api/prod/datagetter?product=predictions&station=8418150&time_zone=gmt&units=english&format=json'
+
+fetch() {
+    echo "$1: $api&$2"
+    curl -fsS -o "$1" "$api&$2"
+}
+
+fetch normal.json 'datum=MLLW&begin_date=20250630&end_date=20250702'
+fetch sparse.json 'datum=MLLW&begin_date=20250630&end_date=20250702&interval=h'
+fetch dst_spring.json 'datum=MLLW&begin_date=20250308&end_date=20250310'
+fetch dst_fall.json 'datum=MLLW&begin_date=20251101&end_date=20251103'
+# Portland has no NAVD88 predictions, so NOAA answers with its error object
+fetch no_data.json 'datum=NAVD&begin_date=20250630&end_date=20250702'
+
+# Hourly with 13:00-15:00 UTC on July 1 dropped, as a gap in the data
+grep -v '"2025-07-01 1[345]:00"' sparse.json > sparse.json.tmp
+mv sparse.json.tmp sparse.json
+# The normal response cut off mid-array
+head -c 10000 normal.json > malformed.json
+
+echo "Recorded $(date -u +%Y-%m-%d)"